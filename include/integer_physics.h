@@ -0,0 +1,121 @@
+#ifndef INTEGER_PHYSICS_H
+#define INTEGER_PHYSICS_H
+
+/* C interface to the integer physics world, built with `--features ffi`. On
+ * wasm32 the same functions are always exported from the cdylib, so a browser
+ * can call them through the module's exports.
+ *
+ * Positions, sizes and velocities are raw sub-pixel integers (256 per pixel).
+ * Every function accepts a null world and does nothing. Functions returning
+ * bool report false if the world is null, the entity is missing, or the call
+ * panicked inside Rust. After a failed physics_world_step the world may be
+ * partially updated and should be freed. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct PhysicsWorld PhysicsWorld;
+
+#define PHYSICS_INPUT_LEN 4
+#define PHYSICS_INVALID_ENTITY UINT32_MAX
+
+typedef struct {
+    uint32_t entity_id;
+    float x;
+    float y;
+    float width;
+    float height;
+    float colour[4];
+} PhysicsRenderRect;
+
+typedef struct {
+    int64_t x;
+    int64_t y;
+    int64_t width;
+    int64_t height;
+    int64_t velocity_x;
+    int64_t velocity_y;
+    bool dynamic;
+} PhysicsBody;
+
+typedef struct {
+    uint32_t entity_id;
+    int64_t x;
+    int64_t y;
+    int64_t surface_start_x;
+    int64_t surface_start_y;
+    int64_t surface_end_x;
+    int64_t surface_end_y;
+} PhysicsRaycastHit;
+
+/* Heap buffers for callers such as wasm hosts that cannot allocate inside the
+ * module. A buffer of n floats may also hold n entity ids. */
+float *physics_buffer_new(size_t len);
+void physics_buffer_free(float *buffer, size_t len);
+
+PhysicsWorld *physics_world_new(float width, float height);
+/* Returns NULL if the demo entities can't be spawned. */
+PhysicsWorld *physics_world_new_demo(float width, float height);
+void physics_world_free(PhysicsWorld *world);
+
+/* input is null or points to PHYSICS_INPUT_LEN floats: left, right, up, down. */
+bool physics_world_step(PhysicsWorld *world, const float *input);
+
+/* Returns PHYSICS_INVALID_ENTITY if the body could not be spawned, including
+ * when its width or height is not positive. */
+uint32_t physics_world_spawn(PhysicsWorld *world, const PhysicsBody *body);
+bool physics_world_remove(PhysicsWorld *world, uint32_t id);
+
+bool physics_world_position(const PhysicsWorld *world, uint32_t id, int64_t *x, int64_t *y);
+bool physics_world_velocity(const PhysicsWorld *world, uint32_t id, int64_t *x, int64_t *y);
+bool physics_world_set_velocity(PhysicsWorld *world, uint32_t id, int64_t x, int64_t y);
+uint64_t physics_world_state_hash(const PhysicsWorld *world);
+
+/* Casts the ray from start along ray against bodies on any layer in mask.
+ * Returns false if nothing was hit; otherwise fills hit unless it is null. */
+bool physics_world_raycast(
+    const PhysicsWorld *world,
+    int64_t start_x,
+    int64_t start_y,
+    int64_t ray_x,
+    int64_t ray_y,
+    uint32_t mask,
+    PhysicsRaycastHit *hit);
+
+/* Ids of bodies on any layer in mask overlapping the rect with its top left at
+ * (x, y). Writes up to capacity ids and returns the total number overlapping,
+ * or 0 if width or height is not positive. */
+size_t physics_world_overlap_shape(
+    const PhysicsWorld *world,
+    int64_t x,
+    int64_t y,
+    int64_t width,
+    int64_t height,
+    uint32_t mask,
+    uint32_t *out,
+    size_t capacity);
+
+/* Writes up to capacity rects to out and returns the total number available. */
+size_t physics_world_render_rects(
+    const PhysicsWorld *world,
+    PhysicsRenderRect *out,
+    size_t capacity);
+
+/* Flat per-entity arrays in render order. Each writes up to capacity values
+ * and returns the total number available: one id, two position floats, four
+ * rect floats (x, y, width, height) or four colour floats per entity. */
+size_t physics_world_entity_ids(const PhysicsWorld *world, uint32_t *out, size_t capacity);
+size_t physics_world_positions(const PhysicsWorld *world, float *out, size_t capacity);
+size_t physics_world_rects(const PhysicsWorld *world, float *out, size_t capacity);
+size_t physics_world_colours(const PhysicsWorld *world, float *out, size_t capacity);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
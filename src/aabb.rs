@@ -1,7 +1,7 @@
 use cgmath::{Vector2, vec2};
 use physics_num::PhysicsNum;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
 pub struct Aabb<N> {
     top_left: Vector2<N>,
     size: Vector2<N>,
@@ -23,7 +23,10 @@ impl<N: PhysicsNum> Aabb<N> {
         let size = half_size * PhysicsNum::two();
         Self::new(top_left, size)
     }
-    fn bottom_right_coord(&self) -> Vector2<N> {
+    pub fn top_left(&self) -> Vector2<N> {
+        self.top_left
+    }
+    pub fn bottom_right_coord(&self) -> Vector2<N> {
         self.top_left + self.size
     }
     pub fn from_union(a: &Aabb<N>, b: &Aabb<N>) -> Self {
@@ -52,6 +55,17 @@ impl<N: PhysicsNum> Aabb<N> {
             && self.top_left.y + self.size.y >= other.top_left.y
             && other.top_left.y + other.size.y >= self.top_left.y
     }
+    pub fn contains(&self, other: &Aabb<N>) -> bool {
+        let bottom_right_coord = self.bottom_right_coord();
+        let other_bottom_right_coord = other.bottom_right_coord();
+        self.top_left.x <= other.top_left.x && self.top_left.y <= other.top_left.y
+            && bottom_right_coord.x >= other_bottom_right_coord.x
+            && bottom_right_coord.y >= other_bottom_right_coord.y
+    }
+    pub fn expand(&self, margin: N) -> Self {
+        let margin = vec2(margin, margin);
+        Self::new(self.top_left - margin, self.size + margin * PhysicsNum::two())
+    }
     pub fn centre(&self) -> Vector2<N> {
         self.top_left + self.size / PhysicsNum::two()
     }
@@ -67,4 +81,51 @@ impl<N: PhysicsNum> Aabb<N> {
     pub fn double_about_centre(&self) -> Self {
         Self::from_centre_and_half_size(self.centre(), self.size)
     }
+    pub fn translate(&self, offset: Vector2<N>) -> Self {
+        Self::new(self.top_left + offset, self.size)
+    }
+    pub fn wrap_point(&self, point: Vector2<N>) -> Vector2<N> {
+        let wrap = |value: N, size: N| {
+            let value = value % size;
+            if value < N::zero() {
+                value + size
+            } else {
+                value
+            }
+        };
+        let relative = point - self.top_left;
+        self.top_left + vec2(wrap(relative.x, self.size.x), wrap(relative.y, self.size.y))
+    }
+    pub fn seam_offsets(&self, other: &Aabb<N>) -> impl Iterator<Item = Vector2<N>> {
+        let (bounds, other) = (*self, *other);
+        let zero = N::zero();
+        let xs = [-self.size.x, zero, self.size.x];
+        let ys = [-self.size.y, zero, self.size.y];
+        (0..9)
+            .map(move |i| vec2(xs[i % 3], ys[i / 3]))
+            .filter(move |&offset| {
+                let is_seam = offset.x != zero || offset.y != zero;
+                is_seam && bounds.translate(offset).is_intersecting(&other)
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wrapping() {
+        let bounds = Aabb::new(vec2(-10, 0), vec2(100, 50));
+        assert_eq!(bounds.wrap_point(vec2(95, -5)), vec2(-5, 45));
+        assert_eq!(bounds.wrap_point(vec2(-11, 120)), vec2(89, 20));
+        let inside = Aabb::new(vec2(0, 10), vec2(5, 5));
+        assert_eq!(bounds.seam_offsets(&inside).count(), 0);
+        assert_eq!(
+            bounds
+                .seam_offsets(&Aabb::new(vec2(85, 45), vec2(10, 10)))
+                .collect::<Vec<_>>(),
+            vec![vec2(100, 0), vec2(0, 50), vec2(100, 50)]
+        );
+    }
 }
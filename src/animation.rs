@@ -0,0 +1,160 @@
+use pixel_num::SubPixelI64;
+use shape::Shape;
+use sprite::Sprite;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LoopMode {
+    Once,
+    Loop,
+    PingPong,
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct AnimationFrame {
+    pub sprite: Sprite,
+    pub duration: u32,
+    pub hitbox: Option<Shape<SubPixelI64>>,
+}
+
+impl AnimationFrame {
+    pub fn new(sprite: Sprite, duration: u32) -> Self {
+        assert!(duration > 0, "frame duration must be positive");
+        Self {
+            sprite,
+            duration,
+            hitbox: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Animation {
+    frames: Vec<AnimationFrame>,
+    loop_mode: LoopMode,
+}
+
+impl Animation {
+    pub fn new(frames: Vec<AnimationFrame>, loop_mode: LoopMode) -> Self {
+        assert!(!frames.is_empty(), "animation needs at least one frame");
+        Self { frames, loop_mode }
+    }
+    pub fn frames(&self) -> &[AnimationFrame] {
+        &self.frames
+    }
+    pub fn loop_mode(&self) -> LoopMode {
+        self.loop_mode
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct AnimationState {
+    animation: Animation,
+    frame: usize,
+    elapsed: u32,
+    forward: bool,
+    finished: bool,
+}
+
+impl AnimationState {
+    pub fn new(animation: Animation) -> Self {
+        Self {
+            animation,
+            frame: 0,
+            elapsed: 0,
+            forward: true,
+            finished: false,
+        }
+    }
+    pub fn animation(&self) -> &Animation {
+        &self.animation
+    }
+    pub fn frame_index(&self) -> usize {
+        self.frame
+    }
+    pub fn current_frame(&self) -> &AnimationFrame {
+        &self.animation.frames[self.frame]
+    }
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+    pub fn tick(&mut self) -> bool {
+        if self.finished {
+            return false;
+        }
+        self.elapsed += 1;
+        if self.elapsed < self.current_frame().duration {
+            return false;
+        }
+        self.elapsed = 0;
+        let last = self.animation.frames.len() - 1;
+        let previous = self.frame;
+        match self.animation.loop_mode {
+            LoopMode::Once => {
+                if self.frame < last {
+                    self.frame += 1;
+                } else {
+                    self.finished = true;
+                }
+            }
+            LoopMode::Loop => {
+                self.frame = if self.frame < last { self.frame + 1 } else { 0 };
+            }
+            LoopMode::PingPong => {
+                let at_end = if self.forward {
+                    self.frame == last
+                } else {
+                    self.frame == 0
+                };
+                if at_end {
+                    self.forward = !self.forward;
+                }
+                if self.forward {
+                    self.frame = (self.frame + 1).min(last);
+                } else {
+                    self.frame = self.frame.saturating_sub(1);
+                }
+            }
+        }
+        self.frame != previous
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use sprite::SourceRect;
+
+    fn frames(durations: &[u32]) -> Vec<AnimationFrame> {
+        let source = SourceRect {
+            x: 0,
+            y: 0,
+            width: 1,
+            height: 1,
+        };
+        durations
+            .iter()
+            .map(|&duration| AnimationFrame::new(Sprite::new(0, source), duration))
+            .collect()
+    }
+
+    fn sequence(loop_mode: LoopMode, durations: &[u32], ticks: usize) -> Vec<usize> {
+        let mut state = AnimationState::new(Animation::new(frames(durations), loop_mode));
+        (0..ticks)
+            .map(|_| {
+                state.tick();
+                state.frame_index()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn loop_modes() {
+        assert_eq!(sequence(LoopMode::Once, &[1, 2], 5), vec![1, 1, 1, 1, 1]);
+        assert_eq!(sequence(LoopMode::Loop, &[2, 1, 1], 6), vec![0, 1, 2, 0, 0, 1]);
+        assert_eq!(
+            sequence(LoopMode::PingPong, &[1, 1, 1], 6),
+            vec![1, 2, 1, 0, 1, 2]
+        );
+        assert_eq!(sequence(LoopMode::PingPong, &[1], 3), vec![0, 0, 0]);
+    }
+}
@@ -0,0 +1,110 @@
+use cgmath::{Vector2, vec2};
+use num::Zero;
+use physics_num;
+use pixel_num::SubPixelI64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AttractorFalloff {
+    Linear,
+    InverseSquare { core_radius: SubPixelI64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Attractor {
+    pub strength: SubPixelI64,
+    pub radius: SubPixelI64,
+    pub falloff: AttractorFalloff,
+}
+
+impl Attractor {
+    pub fn new(
+        strength: SubPixelI64,
+        radius: SubPixelI64,
+        falloff: AttractorFalloff,
+    ) -> Self {
+        assert!(radius > Zero::zero(), "attractor radius must be positive");
+        if let AttractorFalloff::InverseSquare { core_radius } = falloff {
+            assert!(core_radius > Zero::zero(), "attractor core radius must be positive");
+        }
+        Self {
+            strength,
+            radius,
+            falloff,
+        }
+    }
+    pub fn linear(strength: SubPixelI64, radius: SubPixelI64) -> Self {
+        Self::new(strength, radius, AttractorFalloff::Linear)
+    }
+    pub fn inverse_square(
+        strength: SubPixelI64,
+        radius: SubPixelI64,
+        core_radius: SubPixelI64,
+    ) -> Self {
+        Self::new(
+            strength,
+            radius,
+            AttractorFalloff::InverseSquare { core_radius },
+        )
+    }
+    pub fn acceleration(
+        &self,
+        offset: Vector2<SubPixelI64>,
+    ) -> Option<Vector2<SubPixelI64>> {
+        let radius = physics_num::widen(self.radius);
+        let distance = physics_num::isqrt_wide(physics_num::magnitude2_wide(offset));
+        if distance > radius {
+            return None;
+        }
+        if distance == 0 {
+            return Some(vec2(Zero::zero(), Zero::zero()));
+        }
+        let strength = physics_num::widen(self.strength);
+        let (numerator, denominator) = match self.falloff {
+            AttractorFalloff::Linear => (strength * (radius - distance), radius),
+            AttractorFalloff::InverseSquare { core_radius } => {
+                let core_radius = physics_num::widen(core_radius);
+                let clamped = distance.max(core_radius);
+                (strength * core_radius * core_radius, clamped * clamped)
+            }
+        };
+        Some(vec2(
+            physics_num::saturating_narrow(
+                physics_num::widen(offset.x) * numerator / (denominator * distance),
+            ),
+            physics_num::saturating_narrow(
+                physics_num::widen(offset.y) * numerator / (denominator * distance),
+            ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(x: i64, y: i64) -> Vector2<SubPixelI64> {
+        vec2(SubPixelI64::new(x), SubPixelI64::new(y))
+    }
+
+    #[test]
+    fn linear_attraction_weakens_towards_edge() {
+        let attractor = Attractor::linear(SubPixelI64::new(400), SubPixelI64::new(100));
+        assert_eq!(attractor.acceleration(v(30, 40)), Some(v(120, 160)));
+        assert_eq!(attractor.acceleration(v(-60, -80)), Some(v(0, 0)));
+        assert_eq!(attractor.acceleration(v(70, 80)), None);
+        assert_eq!(attractor.acceleration(v(0, 0)), Some(v(0, 0)));
+    }
+
+    #[test]
+    fn inverse_square_attraction_is_capped_inside_core() {
+        let attractor = Attractor::inverse_square(
+            SubPixelI64::new(400),
+            SubPixelI64::new(1000),
+            SubPixelI64::new(25),
+        );
+        assert_eq!(attractor.acceleration(v(30, 40)), Some(v(60, 80)));
+        assert_eq!(attractor.acceleration(v(0, -100)), Some(v(0, -25)));
+        assert_eq!(attractor.acceleration(v(12, 16)), Some(v(240, 320)));
+        assert_eq!(attractor.acceleration(v(0, 1001)), None);
+    }
+}
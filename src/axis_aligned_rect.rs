@@ -5,7 +5,7 @@ use line_segment::LineSegment;
 use num::Zero;
 use shape::Collide;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub struct AxisAlignedRect<N: PhysicsNum> {
     dimensions: Vector2<N>,
 }
@@ -26,16 +26,16 @@ impl<N: PhysicsNum> AxisAlignedRect<N> {
     fn bottom_right(&self) -> Vector2<N> {
         self.dimensions
     }
-    fn top(&self) -> LineSegment<N> {
+    pub fn top(&self) -> LineSegment<N> {
         LineSegment::new(self.top_left(), self.top_right())
     }
-    fn right(&self) -> LineSegment<N> {
+    pub fn right(&self) -> LineSegment<N> {
         LineSegment::new(self.top_right(), self.bottom_right())
     }
-    fn bottom(&self) -> LineSegment<N> {
+    pub fn bottom(&self) -> LineSegment<N> {
         LineSegment::new(self.bottom_right(), self.bottom_left())
     }
-    fn left(&self) -> LineSegment<N> {
+    pub fn left(&self) -> LineSegment<N> {
         LineSegment::new(self.bottom_left(), self.top_left())
     }
     pub fn dimensions(&self) -> Vector2<N> {
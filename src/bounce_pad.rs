@@ -0,0 +1,72 @@
+use cgmath::{Vector2, vec2};
+use game::EntityId;
+use num::Zero;
+use physics_num;
+use pixel_num::sub_pixel_i64::{self, SubPixelI64};
+use stacking;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BouncePad {
+    pub launch_speed: SubPixelI64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LaunchEvent {
+    pub entity_id: EntityId,
+    pub pad_id: EntityId,
+    pub velocity: Vector2<SubPixelI64>,
+}
+
+impl BouncePad {
+    pub fn new(launch_speed: SubPixelI64) -> Self {
+        assert!(launch_speed > Zero::zero(), "launch speed must be positive");
+        Self { launch_speed }
+    }
+    pub fn launch(
+        &self,
+        velocity: Vector2<SubPixelI64>,
+        normal: Vector2<SubPixelI64>,
+        gravity: Vector2<SubPixelI64>,
+    ) -> Option<Vector2<SubPixelI64>> {
+        let normal_len2 = physics_num::magnitude2_wide(normal);
+        if normal_len2 == 0 {
+            return None;
+        }
+        let from_above = stacking::supports(normal, gravity);
+        if !sub_pixel_i64::vector_is_zero(gravity) && !from_above {
+            return None;
+        }
+        let approach = physics_num::widen(velocity.x) * physics_num::widen(normal.x)
+            + physics_num::widen(velocity.y) * physics_num::widen(normal.y);
+        if approach < 0 {
+            return None;
+        }
+        let scale = |value| {
+            physics_num::saturating_narrow(physics_num::widen(value) * approach / normal_len2)
+        };
+        let tangential = velocity - vec2(scale(normal.x), scale(normal.y));
+        Some(tangential - physics_num::normalize_to_length(normal, self.launch_speed))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(x: i64, y: i64) -> Vector2<SubPixelI64> {
+        vec2(SubPixelI64::new(x), SubPixelI64::new(y))
+    }
+
+    #[test]
+    fn pad_replaces_normal_velocity_from_above_only() {
+        let pad = BouncePad::new(SubPixelI64::new(1000));
+        let gravity = v(0, 24);
+        let down = v(0, 512);
+        assert_eq!(pad.launch(v(30, 200), down, gravity), Some(v(30, -1000)));
+        assert_eq!(pad.launch(v(30, 0), down, gravity), Some(v(30, -1000)));
+        assert_eq!(pad.launch(v(30, -200), down, gravity), None);
+        assert_eq!(pad.launch(v(30, -200), -down, gravity), None);
+        assert_eq!(pad.launch(v(200, 30), v(512, 0), gravity), None);
+        assert_eq!(pad.launch(v(200, 30), v(512, 0), v(0, 0)), Some(v(-1000, 30)));
+    }
+}
@@ -0,0 +1,173 @@
+use aabb::Aabb;
+use chunked_index::ChunkedIndex;
+use loose_quad_tree::{LooseQuadTree, NodeInfo, QuadTreeParams, QuadTreeStats};
+use physics_num::PhysicsNum;
+use sweep_and_prune::SweepAndPrune;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BroadphaseKind {
+    #[default]
+    LooseQuadTree,
+    SweepAndPrune,
+    Chunked,
+}
+
+#[derive(Debug, Clone)]
+pub enum Broadphase<T, N: PhysicsNum> {
+    LooseQuadTree(LooseQuadTree<T, N>),
+    SweepAndPrune(SweepAndPrune<T, N>),
+    Chunked(ChunkedIndex<T, N>),
+}
+
+impl<T, N: PhysicsNum> Broadphase<T, N> {
+    pub fn new(
+        kind: BroadphaseKind,
+        bounds: &Aabb<N>,
+        chunk_size: N,
+        quad_tree_params: QuadTreeParams,
+    ) -> Self {
+        match kind {
+            BroadphaseKind::LooseQuadTree => Broadphase::LooseQuadTree(
+                LooseQuadTree::with_params(bounds, quad_tree_params),
+            ),
+            BroadphaseKind::SweepAndPrune => {
+                Broadphase::SweepAndPrune(SweepAndPrune::new())
+            }
+            BroadphaseKind::Chunked => Broadphase::Chunked(ChunkedIndex::new(chunk_size)),
+        }
+    }
+    pub fn kind(&self) -> BroadphaseKind {
+        match *self {
+            Broadphase::LooseQuadTree(_) => BroadphaseKind::LooseQuadTree,
+            Broadphase::SweepAndPrune(_) => BroadphaseKind::SweepAndPrune,
+            Broadphase::Chunked(_) => BroadphaseKind::Chunked,
+        }
+    }
+    pub fn quad_tree_stats(&self) -> Option<QuadTreeStats> {
+        match self {
+            Broadphase::LooseQuadTree(quad_tree) => Some(quad_tree.stats()),
+            _ => None,
+        }
+    }
+    pub fn quad_tree_nodes(&self) -> Vec<NodeInfo<N>> {
+        match self {
+            Broadphase::LooseQuadTree(quad_tree) => quad_tree.nodes().collect(),
+            _ => Vec::new(),
+        }
+    }
+    pub fn clear(&mut self) {
+        match *self {
+            Broadphase::LooseQuadTree(ref mut quad_tree) => quad_tree.clear(),
+            Broadphase::SweepAndPrune(ref mut sweep_and_prune) => {
+                sweep_and_prune.clear()
+            }
+            Broadphase::Chunked(ref mut chunked) => chunked.clear(),
+        }
+    }
+    pub fn insert(&mut self, aabb: Aabb<N>, t: T) {
+        match *self {
+            Broadphase::LooseQuadTree(ref mut quad_tree) => {
+                quad_tree.insert(aabb, t)
+            }
+            Broadphase::SweepAndPrune(ref mut sweep_and_prune) => {
+                sweep_and_prune.insert(aabb, t)
+            }
+            Broadphase::Chunked(ref mut chunked) => chunked.insert(aabb, t),
+        }
+    }
+    pub fn remove(&mut self, aabb: &Aabb<N>, t: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        match *self {
+            Broadphase::LooseQuadTree(ref mut quad_tree) => quad_tree.remove(aabb, t),
+            Broadphase::SweepAndPrune(ref mut sweep_and_prune) => {
+                sweep_and_prune.remove(aabb, t)
+            }
+            Broadphase::Chunked(ref mut chunked) => chunked.remove(aabb, t),
+        }
+    }
+    /// Moves an item's proxy, returning false (and leaving the broadphase
+    /// unchanged) if no item matched `old_aabb`.
+    pub fn update(&mut self, old_aabb: &Aabb<N>, new_aabb: Aabb<N>, t: T) -> bool
+    where
+        T: PartialEq,
+    {
+        if !self.remove(old_aabb, &t) {
+            return false;
+        }
+        self.insert(new_aabb, t);
+        true
+    }
+    pub fn bulk_load<I: IntoIterator<Item = (Aabb<N>, T)>>(&mut self, items: I) {
+        match self {
+            &mut Broadphase::LooseQuadTree(ref mut quad_tree) => {
+                quad_tree.bulk_load(items)
+            }
+            other => {
+                other.clear();
+                for (aabb, t) in items {
+                    other.insert(aabb, t);
+                }
+            }
+        }
+    }
+    pub fn for_each_intersection<F: FnMut(&Aabb<N>, &T)>(
+        &self,
+        aabb: &Aabb<N>,
+        f: F,
+    ) -> usize {
+        match self {
+            Broadphase::LooseQuadTree(quad_tree) => {
+                quad_tree.for_each_intersection(aabb, f)
+            }
+            Broadphase::SweepAndPrune(sweep_and_prune) => {
+                sweep_and_prune.for_each_intersection(aabb, f)
+            }
+            Broadphase::Chunked(chunked) => chunked.for_each_intersection(aabb, f),
+        }
+    }
+    pub fn for_each_item<F: FnMut(&Aabb<N>, &T)>(&self, f: F) {
+        match self {
+            Broadphase::LooseQuadTree(quad_tree) => quad_tree.for_each_item(f),
+            Broadphase::SweepAndPrune(sweep_and_prune) => {
+                sweep_and_prune.for_each_item(f)
+            }
+            Broadphase::Chunked(chunked) => chunked.for_each_item(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cgmath::vec2;
+
+    #[test]
+    fn update_moves_a_single_proxy_in_every_kind() {
+        let kinds = [
+            BroadphaseKind::LooseQuadTree,
+            BroadphaseKind::SweepAndPrune,
+            BroadphaseKind::Chunked,
+        ];
+        let bounds = Aabb::new(vec2(0i64, 0), vec2(256, 256));
+        for &kind in kinds.iter() {
+            let mut broadphase = Broadphase::new(kind, &bounds, 32, Default::default());
+            let start = Aabb::new(vec2(10, 10), vec2(8, 8));
+            let moved = Aabb::new(vec2(200, 150), vec2(8, 8));
+            broadphase.insert(start, 1);
+            broadphase.insert(Aabb::new(vec2(12, 12), vec2(8, 8)), 2);
+            assert!(broadphase.update(&start, moved, 1));
+            assert!(!broadphase.update(&start, moved, 1));
+            let query = |broadphase: &Broadphase<i32, i64>, aabb: &Aabb<i64>| {
+                let mut found = Vec::new();
+                broadphase.for_each_intersection(aabb, |_, &t| found.push(t));
+                found
+            };
+            assert_eq!(query(&broadphase, &start), vec![2]);
+            assert_eq!(query(&broadphase, &moved), vec![1]);
+            assert!(broadphase.remove(&moved, &1));
+            assert!(query(&broadphase, &moved).is_empty());
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use cgmath::{Vector2, vec2};
+use num::Zero;
+use physics_num::PhysicsNum;
+use pixel_num::sub_pixel_i64::{self, SubPixelI64};
+use render::View;
+use trig::Angle;
+
+pub const CAMERA_SCALE: i64 = 1 << 16;
+const SHAKE_ANGLE_STEP: u16 = 40503;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Camera {
+    position: Vector2<SubPixelI64>,
+    viewport: Vector2<SubPixelI64>,
+    deadzone: Vector2<SubPixelI64>,
+    follow_rate: i64,
+    shake_amplitude: SubPixelI64,
+    shake_decay: i64,
+    tick: u16,
+}
+
+fn follow_axis(
+    position: SubPixelI64,
+    target: SubPixelI64,
+    deadzone: SubPixelI64,
+    rate: i64,
+) -> SubPixelI64 {
+    let offset = target - position;
+    let excess = if offset > deadzone {
+        offset - deadzone
+    } else if offset < -deadzone {
+        offset + deadzone
+    } else {
+        return position;
+    };
+    let step = (excess.raw() as i128 * rate as i128 / CAMERA_SCALE as i128) as i64;
+    let step = if step == 0 { excess.raw().signum() } else { step };
+    position + SubPixelI64::new(step)
+}
+
+impl Camera {
+    pub fn new(viewport: Vector2<SubPixelI64>) -> Self {
+        Self {
+            position: vec2(Zero::zero(), Zero::zero()),
+            viewport,
+            deadzone: vec2(Zero::zero(), Zero::zero()),
+            follow_rate: CAMERA_SCALE,
+            shake_amplitude: Zero::zero(),
+            shake_decay: CAMERA_SCALE * 7 / 8,
+            tick: 0,
+        }
+    }
+    pub fn with_deadzone(mut self, half_size: Vector2<SubPixelI64>) -> Self {
+        self.deadzone = half_size;
+        self
+    }
+    pub fn with_follow_rate(mut self, follow_rate: i64) -> Self {
+        assert!(
+            follow_rate > 0 && follow_rate <= CAMERA_SCALE,
+            "follow rate must be in (0, CAMERA_SCALE]"
+        );
+        self.follow_rate = follow_rate;
+        self
+    }
+    pub fn with_shake_decay(mut self, shake_decay: i64) -> Self {
+        assert!(
+            (0..CAMERA_SCALE).contains(&shake_decay),
+            "shake decay must be in [0, CAMERA_SCALE)"
+        );
+        self.shake_decay = shake_decay;
+        self
+    }
+    pub fn position(&self) -> Vector2<SubPixelI64> {
+        self.position
+    }
+    pub fn viewport(&self) -> Vector2<SubPixelI64> {
+        self.viewport
+    }
+    pub fn centre(&self) -> Vector2<SubPixelI64> {
+        self.position + self.viewport / PhysicsNum::two()
+    }
+    pub fn centre_on(&mut self, point: Vector2<SubPixelI64>) {
+        self.position = point - self.viewport / PhysicsNum::two();
+    }
+    pub fn shake(&mut self, amplitude: SubPixelI64) {
+        if amplitude > Zero::zero() {
+            self.shake_amplitude += amplitude;
+        }
+    }
+    pub fn shake_amplitude(&self) -> SubPixelI64 {
+        self.shake_amplitude
+    }
+    pub fn shake_offset(&self) -> Vector2<SubPixelI64> {
+        if self.shake_amplitude.is_zero() {
+            return vec2(Zero::zero(), Zero::zero());
+        }
+        let angle = Angle::from_raw(self.tick.wrapping_mul(SHAKE_ANGLE_STEP));
+        angle.unit_vector(self.shake_amplitude)
+    }
+    pub fn reset(&mut self) {
+        self.position = vec2(Zero::zero(), Zero::zero());
+        self.shake_amplitude = Zero::zero();
+        self.tick = 0;
+    }
+    pub fn update(&mut self, target: Option<Vector2<SubPixelI64>>) {
+        self.tick = self.tick.wrapping_add(1);
+        if let Some(target) = target {
+            let centre = self.centre();
+            let centre = vec2(
+                follow_axis(centre.x, target.x, self.deadzone.x, self.follow_rate),
+                follow_axis(centre.y, target.y, self.deadzone.y, self.follow_rate),
+            );
+            self.centre_on(centre);
+        }
+        let amplitude = self.shake_amplitude.raw() as i128 * self.shake_decay as i128
+            / CAMERA_SCALE as i128;
+        self.shake_amplitude = SubPixelI64::new(amplitude as i64);
+    }
+    pub fn view(&self) -> View {
+        let top_left = self.position + self.shake_offset();
+        View {
+            top_left: sub_pixel_i64::vector_to_f32_pixel(top_left),
+            scale: 1.,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(x: i64, y: i64) -> Vector2<SubPixelI64> {
+        vec2(SubPixelI64::new(x), SubPixelI64::new(y))
+    }
+
+    #[test]
+    fn follows_outside_deadzone() {
+        let mut camera = Camera::new(v(200, 100))
+            .with_deadzone(v(20, 10))
+            .with_follow_rate(CAMERA_SCALE / 2);
+        camera.update(Some(v(110, 55)));
+        assert_eq!(camera.centre(), v(100, 50));
+        camera.update(Some(v(160, 20)));
+        assert_eq!(camera.centre(), v(120, 40));
+        camera.update(Some(v(160, 20)));
+        assert_eq!(camera.centre(), v(130, 35));
+        for _ in 0..20 {
+            camera.update(Some(v(160, 20)));
+        }
+        assert_eq!(camera.centre(), v(140, 30));
+    }
+
+    #[test]
+    fn shake_decays_to_rest() {
+        let mut camera = Camera::new(v(200, 100)).with_shake_decay(CAMERA_SCALE / 2);
+        camera.shake(SubPixelI64::new(64));
+        assert!(!sub_pixel_i64::vector_is_zero(camera.shake_offset()));
+        camera.update(None);
+        assert_eq!(camera.shake_amplitude(), SubPixelI64::new(32));
+        for _ in 0..6 {
+            camera.update(None);
+        }
+        assert!(camera.shake_amplitude().is_zero());
+        assert!(sub_pixel_i64::vector_is_zero(camera.shake_offset()));
+    }
+}
@@ -0,0 +1,164 @@
+use aabb::Aabb;
+use alloc::vec::Vec;
+use cgmath::{Vector2, vec2};
+use collision;
+use line_segment::LineSegment;
+use num::Zero;
+use physics_num::{self, PhysicsNum};
+use shape::Collide;
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Chain<N: PhysicsNum> {
+    points: Vec<Vector2<N>>,
+}
+
+fn outward_normal<N: PhysicsNum>(edge: LineSegment<N>) -> Vector2<N> {
+    let vector = edge.vector();
+    vec2(vector.y, -vector.x)
+}
+
+impl<N: PhysicsNum> Chain<N> {
+    pub fn new(points: Vec<Vector2<N>>) -> Self {
+        assert!(points.len() >= 2, "chain needs at least two points");
+        Self { points }
+    }
+    pub fn points(&self) -> &[Vector2<N>] {
+        &self.points
+    }
+    pub fn num_edges(&self) -> usize {
+        self.points.len() - 1
+    }
+    pub fn edge(&self, index: usize) -> LineSegment<N> {
+        LineSegment::new(self.points[index], self.points[index + 1])
+    }
+    pub fn as_slice(&self) -> ChainSlice<'_, N> {
+        ChainSlice {
+            points: &self.points,
+        }
+    }
+    pub fn slice(&self, first_edge: usize, end_edge: usize) -> ChainSlice<'_, N> {
+        assert!(first_edge < end_edge, "chain slice needs at least one edge");
+        ChainSlice {
+            points: &self.points[first_edge..(end_edge + 1)],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ChainSlice<'a, N: 'a + PhysicsNum> {
+    points: &'a [Vector2<N>],
+}
+
+impl<'a, N: PhysicsNum> ChainSlice<'a, N> {
+    fn edge(&self, index: usize) -> LineSegment<N> {
+        LineSegment::new(self.points[index], self.points[index + 1])
+    }
+    fn edge_faces(&self, index: usize, direction: Vector2<N>) -> bool {
+        physics_num::dot(outward_normal(self.edge(index)), direction) >= Zero::zero()
+    }
+    fn is_convex_joint(&self, index: usize) -> bool {
+        let before = self.edge(index - 1).vector();
+        let after = self.edge(index).vector();
+        collision::vector2_cross_product(before, after) > Zero::zero()
+    }
+}
+
+impl<'a, N: PhysicsNum> Collide<N> for ChainSlice<'a, N> {
+    fn aabb(&self, top_left: Vector2<N>) -> Aabb<N> {
+        let first = self.points[0];
+        let (min, max) = self.points.iter().fold((first, first), |(min, max), v| {
+            (vec2(min.x.min(v.x), min.y.min(v.y)), vec2(max.x.max(v.x), max.y.max(v.y)))
+        });
+        Aabb::new(min + top_left, max - min)
+    }
+    fn for_each_edge_facing<F>(&self, direction: Vector2<N>, mut f: F)
+    where
+        F: FnMut(LineSegment<N>),
+    {
+        for i in 0..(self.points.len() - 1) {
+            if self.edge_faces(i, direction) {
+                f(self.edge(i));
+            }
+        }
+    }
+    fn for_each_vertex_facing<F>(&self, direction: Vector2<N>, mut f: F)
+    where
+        F: FnMut(Vector2<N>),
+    {
+        let last = self.points.len() - 1;
+        if self.edge_faces(0, direction) {
+            f(self.points[0]);
+        }
+        for i in 1..last {
+            let facing =
+                self.edge_faces(i - 1, direction) || self.edge_faces(i, direction);
+            if facing && self.is_convex_joint(i) {
+                f(self.points[i]);
+            }
+        }
+        if self.edge_faces(last - 1, direction) {
+            f(self.points[last]);
+        }
+    }
+}
+
+impl<N: PhysicsNum> Collide<N> for Chain<N> {
+    fn aabb(&self, top_left: Vector2<N>) -> Aabb<N> {
+        self.as_slice().aabb(top_left)
+    }
+    fn for_each_edge_facing<F>(&self, direction: Vector2<N>, f: F)
+    where
+        F: FnMut(LineSegment<N>),
+    {
+        self.as_slice().for_each_edge_facing(direction, f)
+    }
+    fn for_each_vertex_facing<F>(&self, direction: Vector2<N>, f: F)
+    where
+        F: FnMut(Vector2<N>),
+    {
+        self.as_slice().for_each_vertex_facing(direction, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axis_aligned_rect::AxisAlignedRect;
+    use shape::StartInsideEdgePolicy;
+
+    #[test]
+    fn flat_and_concave_joints_are_not_vertices() {
+        let chain = Chain::new(vec![
+            vec2(0, 10),
+            vec2(10, 10),
+            vec2(20, 10),
+            vec2(30, 0),
+            vec2(40, 10),
+        ]);
+        let mut vertices = Vec::new();
+        chain.for_each_vertex_facing(vec2(0, -1), |v| vertices.push(v));
+        assert_eq!(vertices, vec![vec2(0, 10), vec2(30, 0), vec2(40, 10)]);
+    }
+
+    #[test]
+    fn one_sided() {
+        let chain = Chain::new(vec![vec2(0, 10), vec2(30, 10)]);
+        let rect = AxisAlignedRect::new(vec2(4, 4));
+        let from_above = rect.movement_collision_test(
+            vec2(10, 0),
+            &chain,
+            vec2(0, 0),
+            vec2(0, 20),
+            StartInsideEdgePolicy::BlockAll,
+        );
+        assert_eq!(from_above.unwrap().allowed_movement, vec2(0, 5));
+        let from_below = rect.movement_collision_test(
+            vec2(10, 20),
+            &chain,
+            vec2(0, 0),
+            vec2(0, -20),
+            StartInsideEdgePolicy::BlockAll,
+        );
+        assert!(from_below.is_none());
+    }
+}
@@ -0,0 +1,106 @@
+use fnv::FnvHashMap;
+use game::EntityId;
+
+#[derive(Debug, Clone)]
+pub struct ChangeTracker<T> {
+    last: FnvHashMap<EntityId, (T, bool)>,
+    changed: Vec<EntityId>,
+    generation: bool,
+}
+
+impl<T> Default for ChangeTracker<T> {
+    fn default() -> Self {
+        Self {
+            last: Default::default(),
+            changed: Vec::new(),
+            generation: false,
+        }
+    }
+}
+
+impl<T: Copy + PartialEq> ChangeTracker<T> {
+    pub fn track<'a, I>(&mut self, current: I)
+    where
+        T: 'a,
+        I: IntoIterator<Item = (&'a EntityId, &'a T)>,
+    {
+        self.changed.clear();
+        self.generation = !self.generation;
+        let generation = self.generation;
+        let mut count = 0;
+        for (&id, &value) in current {
+            count += 1;
+            match self.last.get_mut(&id) {
+                Some(entry) => {
+                    if entry.0 != value {
+                        self.changed.push(id);
+                    }
+                    *entry = (value, generation);
+                    continue;
+                }
+                None => self.changed.push(id),
+            }
+            self.last.insert(id, (value, generation));
+        }
+        if self.last.len() > count {
+            self.last.retain(|_, &mut (_, seen)| seen == generation);
+        }
+        self.changed.sort_unstable();
+    }
+    pub fn changed(&self) -> &[EntityId] {
+        &self.changed
+    }
+    pub fn is_changed(&self, id: EntityId) -> bool {
+        self.changed.binary_search(&id).is_ok()
+    }
+    /// Forgets `id`, so an entity later created with the same id is reported as
+    /// new rather than compared with the removed one.
+    pub fn remove(&mut self, id: EntityId) {
+        self.last.remove(&id);
+        if let Ok(index) = self.changed.binary_search(&id) {
+            self.changed.remove(index);
+        }
+    }
+    pub fn clear(&mut self) {
+        self.last.clear();
+        self.changed.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reports_inserted_and_modified_entries() {
+        let mut tracker = ChangeTracker::default();
+        let mut values = FnvHashMap::default();
+        values.insert(3, 30);
+        values.insert(1, 10);
+        tracker.track(&values);
+        assert_eq!(tracker.changed(), &[1, 3]);
+        tracker.track(&values);
+        assert!(tracker.changed().is_empty());
+        values.insert(3, 31);
+        values.remove(&1);
+        tracker.track(&values);
+        assert_eq!(tracker.changed(), &[3]);
+        values.insert(1, 10);
+        tracker.track(&values);
+        assert_eq!(tracker.changed(), &[1]);
+        assert!(tracker.is_changed(1) && !tracker.is_changed(3));
+    }
+
+    #[test]
+    fn removed_entries_are_reported_as_new() {
+        let mut tracker = ChangeTracker::default();
+        let mut values = FnvHashMap::default();
+        values.insert(1, 10);
+        values.insert(2, 20);
+        tracker.track(&values);
+        tracker.remove(2);
+        assert_eq!(tracker.changed(), &[1]);
+        tracker.track(&values);
+        assert_eq!(tracker.changed(), &[2]);
+    }
+}
@@ -0,0 +1,185 @@
+use aabb::Aabb;
+use cgmath::Vector2;
+use fnv::FnvHashMap;
+use num::Zero;
+use physics_num::{self, PhysicsNum};
+
+pub type ChunkCoord = (i64, i64);
+
+pub fn chunk_containing<N: PhysicsNum>(point: Vector2<N>, chunk_size: N) -> ChunkCoord {
+    let chunk_size = physics_num::widen(chunk_size);
+    (
+        physics_num::widen(point.x).div_euclid(chunk_size) as i64,
+        physics_num::widen(point.y).div_euclid(chunk_size) as i64,
+    )
+}
+
+#[derive(Debug, Clone)]
+pub struct ChunkedIndex<T, N: PhysicsNum> {
+    chunk_size: N,
+    chunks: FnvHashMap<ChunkCoord, Vec<(Aabb<N>, T)>>,
+    /// Emptied chunk buffers, kept so repopulating chunks doesn't reallocate.
+    spare: Vec<Vec<(Aabb<N>, T)>>,
+    max_size: Vector2<N>,
+}
+
+impl<T, N: PhysicsNum> ChunkedIndex<T, N> {
+    pub fn new(chunk_size: N) -> Self {
+        assert!(chunk_size > Zero::zero(), "chunk size must be positive");
+        Self {
+            chunk_size,
+            chunks: FnvHashMap::default(),
+            spare: Vec::new(),
+            max_size: Vector2::new(Zero::zero(), Zero::zero()),
+        }
+    }
+
+    pub fn chunk_size(&self) -> N {
+        self.chunk_size
+    }
+
+    pub fn clear(&mut self) {
+        for (_, mut items) in self.chunks.drain() {
+            items.clear();
+            self.spare.push(items);
+        }
+        self.max_size = Vector2::new(Zero::zero(), Zero::zero());
+    }
+
+    pub fn insert(&mut self, aabb: Aabb<N>, t: T) {
+        let size = aabb.size();
+        self.max_size.x = self.max_size.x.max(size.x);
+        self.max_size.y = self.max_size.y.max(size.y);
+        let chunk = chunk_containing(aabb.top_left(), self.chunk_size);
+        let spare = &mut self.spare;
+        self.chunks
+            .entry(chunk)
+            .or_insert_with(|| spare.pop().unwrap_or_default())
+            .push((aabb, t));
+    }
+
+    pub fn remove(&mut self, aabb: &Aabb<N>, t: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        let chunk = chunk_containing(aabb.top_left(), self.chunk_size);
+        let items = match self.chunks.get_mut(&chunk) {
+            Some(items) => items,
+            None => return false,
+        };
+        let position = items
+            .iter()
+            .position(|(item_aabb, item)| item_aabb == aabb && item == t);
+        let position = match position {
+            Some(position) => position,
+            None => return false,
+        };
+        items.swap_remove(position);
+        if items.is_empty() {
+            if let Some(items) = self.chunks.remove(&chunk) {
+                self.spare.push(items);
+            }
+        }
+        true
+    }
+
+    pub fn for_each_intersection<F: FnMut(&Aabb<N>, &T)>(
+        &self,
+        aabb: &Aabb<N>,
+        mut f: F,
+    ) -> usize {
+        let (min_x, min_y) =
+            chunk_containing(aabb.top_left() - self.max_size, self.chunk_size);
+        let (max_x, max_y) = chunk_containing(aabb.bottom_right_coord(), self.chunk_size);
+        let mut visits = 0;
+        if (max_x - min_x + 1) * (max_y - min_y + 1) > self.chunks.len() as i64 {
+            for items in self.chunks.values() {
+                visits += 1;
+                for (item_aabb, t) in items.iter() {
+                    if item_aabb.is_intersecting(aabb) {
+                        f(item_aabb, t);
+                    }
+                }
+            }
+            return visits;
+        }
+        for y in min_y..(max_y + 1) {
+            for x in min_x..(max_x + 1) {
+                if let Some(items) = self.chunks.get(&(x, y)) {
+                    visits += 1;
+                    for (item_aabb, t) in items.iter() {
+                        if item_aabb.is_intersecting(aabb) {
+                            f(item_aabb, t);
+                        }
+                    }
+                }
+            }
+        }
+        visits
+    }
+
+    pub fn for_each_item<F: FnMut(&Aabb<N>, &T)>(&self, mut f: F) {
+        for items in self.chunks.values() {
+            for (aabb, t) in items.iter() {
+                f(aabb, t);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cgmath::vec2;
+
+    #[test]
+    fn finds_intersections_across_chunks() {
+        let mut index = ChunkedIndex::new(16i64);
+        index.insert(Aabb::new(vec2(-40, -40), vec2(100, 10)), 0);
+        index.insert(Aabb::new(vec2(1000, 1000), vec2(4, 4)), 1);
+        index.insert(Aabb::new(vec2(-1000, 5), vec2(4, 4)), 2);
+        let query = |top_left, size| {
+            let mut found = Vec::new();
+            let aabb = Aabb::new(top_left, size);
+            index.for_each_intersection(&aabb, |_, &t| found.push(t));
+            found.sort();
+            found
+        };
+        assert_eq!(query(vec2(50, -35), vec2(2, 2)), vec![0]);
+        assert_eq!(query(vec2(1002, 1002), vec2(20, 20)), vec![1]);
+        assert_eq!(query(vec2(-998, 0), vec2(1, 10)), vec![2]);
+        assert_eq!(query(vec2(500, 500), vec2(1, 1)), Vec::<i32>::new());
+        assert_eq!(chunk_containing(vec2(-1i64, 16), 16), (-1, 1));
+    }
+
+    #[test]
+    fn remove_takes_items_out_of_their_chunk() {
+        let mut index = ChunkedIndex::new(16i64);
+        let aabb = Aabb::new(vec2(-40, -40), vec2(100, 10));
+        index.insert(aabb, 0);
+        index.insert(aabb, 1);
+        assert!(index.remove(&aabb, &0));
+        assert!(!index.remove(&aabb, &0));
+        let mut found = Vec::new();
+        index.for_each_intersection(&Aabb::new(vec2(0, -35), vec2(2, 2)), |_, &t| {
+            found.push(t)
+        });
+        assert_eq!(found, vec![1]);
+    }
+
+    #[test]
+    fn empty_chunks_are_dropped() {
+        let mut index = ChunkedIndex::new(16i64);
+        let aabb = Aabb::new(vec2(0, 0), vec2(4, 4));
+        index.insert(aabb, 0);
+        index.insert(Aabb::new(vec2(100, 100), vec2(4, 4)), 1);
+        assert!(index.remove(&aabb, &0));
+        assert_eq!(index.chunks.len(), 1);
+        assert!(!index.chunks.contains_key(&(0, 0)));
+        index.clear();
+        assert!(index.chunks.is_empty());
+        assert_eq!(index.spare.len(), 2);
+        index.insert(aabb, 2);
+        assert_eq!((index.chunks.len(), index.spare.len()), (1, 1));
+    }
+}
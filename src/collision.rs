@@ -1,16 +1,109 @@
+use core::cmp::Ordering;
+use aabb::Aabb;
 use physics_num::{self, PhysicsNum};
 use cgmath::{Vector2, vec2};
 use line_segment::LineSegment;
 use num::{One, Zero};
 
-fn vector2_cross_product<N: PhysicsNum>(v: Vector2<N>, w: Vector2<N>) -> N {
+pub fn vector2_cross_product<N: PhysicsNum>(v: Vector2<N>, w: Vector2<N>) -> N {
     v.x * w.y - v.y * w.x
 }
 
+/// A fraction of the movement. Equality and ordering compare the rational
+/// values, so 1/2 and 2/4 are equal.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeOfImpact<N> {
+    pub numerator: N,
+    pub denominator: N,
+}
+
+fn gcd_wide(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        let remainder = a % b;
+        a = b;
+        b = remainder;
+    }
+    a
+}
+
+impl<N: PhysicsNum> TimeOfImpact<N> {
+    pub fn zero() -> Self {
+        Self {
+            numerator: Zero::zero(),
+            denominator: One::one(),
+        }
+    }
+    /// Reduces the fraction, dropping low bits from both halves when the
+    /// reduced form still doesn't fit in `N`. Returns `None` when the
+    /// denominator is zero.
+    pub fn from_wide(numerator: i128, denominator: i128) -> Option<Self> {
+        if denominator == 0 {
+            return None;
+        }
+        let sign = denominator.signum();
+        let divisor = gcd_wide(numerator, denominator).max(1);
+        let (mut numerator, mut denominator) =
+            (sign * (numerator / divisor), sign * (denominator / divisor));
+        loop {
+            match (
+                physics_num::checked_narrow(numerator),
+                physics_num::checked_narrow(denominator),
+            ) {
+                (Some(numerator), Some(denominator)) => {
+                    return Some(Self {
+                        numerator,
+                        denominator,
+                    })
+                }
+                _ => {
+                    numerator >>= 1;
+                    denominator = (denominator >> 1).max(1);
+                }
+            }
+        }
+    }
+    pub fn is_before(&self, other: &Self) -> bool {
+        physics_num::widen(self.numerator) * physics_num::widen(other.denominator)
+            < physics_num::widen(other.numerator) * physics_num::widen(self.denominator)
+    }
+    pub fn scale_vector(&self, v: Vector2<N>) -> Vector2<N> {
+        let numerator = physics_num::widen(self.numerator);
+        let denominator = physics_num::widen(self.denominator);
+        narrow_vector(
+            physics_num::widen(v.x) * numerator / denominator,
+            physics_num::widen(v.y) * numerator / denominator,
+        )
+    }
+}
+
+impl<N: PhysicsNum> Ord for TimeOfImpact<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (numerator, denominator) = (self.numerator, self.denominator);
+        let lhs = physics_num::widen(numerator) * physics_num::widen(other.denominator);
+        let rhs = physics_num::widen(other.numerator) * physics_num::widen(denominator);
+        lhs.cmp(&rhs)
+    }
+}
+
+impl<N: PhysicsNum> PartialEq for TimeOfImpact<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<N: PhysicsNum> Eq for TimeOfImpact<N> {}
+
+impl<N: PhysicsNum> PartialOrd for TimeOfImpact<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Collision<N> {
+pub enum Collision<N: PhysicsNum> {
     StartInsideEdge,
-    CollidesWithEdgeAfter(Vector2<N>),
+    CollidesWithEdgeAfter(Vector2<N>, TimeOfImpact<N>),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -20,10 +113,26 @@ pub enum NoCollision {
     NonParallelNonIntersecting,
 }
 
-fn reduce_one<N: PhysicsNum>(v: N) -> N {
-    let sign = v.signum();
-    let abs = v.abs();
-    sign * (abs - One::one())
+type Wide = (i128, i128);
+
+fn widen_vector<N: PhysicsNum>(v: Vector2<N>) -> Wide {
+    (physics_num::widen(v.x), physics_num::widen(v.y))
+}
+
+fn cross_wide(v: Wide, w: Wide) -> i128 {
+    v.0 * w.1 - v.1 * w.0
+}
+
+fn dot_wide(v: Wide, w: Wide) -> i128 {
+    v.0 * w.0 + v.1 * w.1
+}
+
+fn narrow_vector<N: PhysicsNum>(x: i128, y: i128) -> Vector2<N> {
+    vec2(physics_num::saturating_narrow(x), physics_num::saturating_narrow(y))
+}
+
+fn reduce_one(v: i128) -> i128 {
+    v.signum() * (v.abs() - 1)
 }
 
 pub fn vertex_moving_towards_edge<N: PhysicsNum>(
@@ -32,89 +141,241 @@ pub fn vertex_moving_towards_edge<N: PhysicsNum>(
     edge: LineSegment<N>,
     sign: N,
 ) -> Result<Collision<N>, NoCollision> {
-    let edge_vector = edge.vector();
-    let cross = vector2_cross_product(vertex_movement, edge_vector);
-    let vertex_to_edge_start = edge.start - vertex;
-    if cross.is_zero() {
-        if vector2_cross_product(vertex_to_edge_start, vertex_movement).is_zero() {
-            let mult_a_x_movement_len2 =
-                physics_num::dot(vertex_to_edge_start, vertex_movement);
-            let mult_b_x_movement_len2 =
-                physics_num::dot(vertex_to_edge_start + edge_vector, vertex_movement);
+    let edge_vector = widen_vector(edge.vector());
+    let movement = widen_vector(vertex_movement);
+    let cross = cross_wide(movement, edge_vector);
+    let vertex_to_edge_start = widen_vector(edge.start - vertex);
+    if cross == 0 {
+        if cross_wide(vertex_to_edge_start, movement) == 0 {
+            let mult_a_x_movement_len2 = dot_wide(vertex_to_edge_start, movement);
+            let mult_b_x_movement_len2 = dot_wide(
+                (
+                    vertex_to_edge_start.0 + edge_vector.0,
+                    vertex_to_edge_start.1 + edge_vector.1,
+                ),
+                movement,
+            );
             let (mult_min_x_movement_len2, mult_max_x_movement_len2) =
                 if mult_a_x_movement_len2 < mult_b_x_movement_len2 {
                     (mult_a_x_movement_len2, mult_b_x_movement_len2)
                 } else {
                     (mult_b_x_movement_len2, mult_a_x_movement_len2)
                 };
-            let movement_len2 = physics_num::magnitude2(vertex_movement);
-            if mult_max_x_movement_len2 < Zero::zero()
-                || mult_min_x_movement_len2 > movement_len2
-            {
-                return Err(NoCollision::ColinearNonOverlapping);;
+            let movement_len2 = dot_wide(movement, movement);
+            if mult_max_x_movement_len2 < 0 || mult_min_x_movement_len2 > movement_len2 {
+                return Err(NoCollision::ColinearNonOverlapping);
             }
-            if mult_min_x_movement_len2 <= Zero::zero() {
+            if mult_min_x_movement_len2 <= 0 {
                 return Ok(Collision::StartInsideEdge);
             }
             if mult_min_x_movement_len2 <= movement_len2 {
-                let allowed_vertex_movement = {
-                    let allowed_movement_x_movement_len2 =
-                        vertex_movement * mult_min_x_movement_len2;
-                    let x =
-                        (allowed_movement_x_movement_len2.x - One::one()) / movement_len2;
-                    let y =
-                        (allowed_movement_x_movement_len2.y - One::one()) / movement_len2;
-                    vec2(x, y)
-                };
-                return Ok(Collision::CollidesWithEdgeAfter(
-                    allowed_vertex_movement * sign,
-                ));
+                let x = (movement.0 * mult_min_x_movement_len2 - 1) / movement_len2;
+                let y = (movement.1 * mult_min_x_movement_len2 - 1) / movement_len2;
+                if let Some(time) =
+                    TimeOfImpact::from_wide(mult_min_x_movement_len2, movement_len2)
+                {
+                    return Ok(Collision::CollidesWithEdgeAfter(
+                        narrow_vector::<N>(x, y) * sign,
+                        time,
+                    ));
+                }
             }
         }
         Err(NoCollision::ParallelNonColinear)
     } else {
         let cross_abs = cross.abs();
         let cross_sign = cross.signum();
-        let vertex_multiplier_x_cross =
-            vector2_cross_product(vertex_to_edge_start, edge_vector);
+        let vertex_multiplier_x_cross = cross_wide(vertex_to_edge_start, edge_vector);
         let vertex_multiplier_x_cross_abs = vertex_multiplier_x_cross * cross_sign;
-        if vertex_multiplier_x_cross_abs < Zero::zero() {
+        if vertex_multiplier_x_cross_abs < 0 {
             return Err(NoCollision::NonParallelNonIntersecting);
         }
         if vertex_multiplier_x_cross_abs > cross_abs {
             return Err(NoCollision::NonParallelNonIntersecting);
         }
-        let edge_multiplier_x_cross =
-            vector2_cross_product(vertex_to_edge_start, vertex_movement);
+        let edge_multiplier_x_cross = cross_wide(vertex_to_edge_start, movement);
         let edge_multiplier_x_cross_abs = edge_multiplier_x_cross * cross_sign;
-        if edge_multiplier_x_cross_abs < Zero::zero() {
+        if edge_multiplier_x_cross_abs < 0 {
             return Err(NoCollision::NonParallelNonIntersecting);
         }
         if edge_multiplier_x_cross_abs > cross_abs {
             return Err(NoCollision::NonParallelNonIntersecting);
         }
-        if vertex_multiplier_x_cross.is_zero() {
+        if vertex_multiplier_x_cross == 0 {
             return Ok(Collision::StartInsideEdge);
         }
-        let movement_to_intersection_point_x_cross =
-            vertex_movement * vertex_multiplier_x_cross;
-        let allowed_vertex_movement = {
-            let x = if movement_to_intersection_point_x_cross.x.is_zero() {
-                Zero::zero()
-            } else {
-                reduce_one(movement_to_intersection_point_x_cross.x) / cross
-            };
-            let y = if movement_to_intersection_point_x_cross.y.is_zero() {
-                Zero::zero()
-            } else {
-                reduce_one(movement_to_intersection_point_x_cross.y) / cross
-            };
-            vec2(x, y)
-        };
-        Ok(Collision::CollidesWithEdgeAfter(
-            allowed_vertex_movement * sign,
-        ))
+        let x = reduce_one(movement.0 * vertex_multiplier_x_cross) / cross;
+        let y = reduce_one(movement.1 * vertex_multiplier_x_cross) / cross;
+        TimeOfImpact::from_wide(vertex_multiplier_x_cross_abs, cross_abs)
+            .map(|time| Collision::CollidesWithEdgeAfter(narrow_vector::<N>(x, y) * sign, time))
+            .ok_or(NoCollision::NonParallelNonIntersecting)
+    }
+}
+
+fn isqrt_ceil(v: i128) -> i128 {
+    let root = physics_num::isqrt_wide(v);
+    if root * root < v {
+        root + 1
+    } else {
+        root
+    }
+}
+
+fn distance2_to_segment_at_most(offset: Wide, edge: Wide, distance2: i128) -> bool {
+    let edge_len2 = dot_wide(edge, edge);
+    let along = dot_wide(offset, edge);
+    if edge_len2 == 0 || along <= 0 {
+        dot_wide(offset, offset) <= distance2
+    } else if along >= edge_len2 {
+        let from_end = (offset.0 - edge.0, offset.1 - edge.1);
+        dot_wide(from_end, from_end) <= distance2
+    } else {
+        let cross = cross_wide(offset, edge);
+        cross * cross <= distance2 * edge_len2
+    }
+}
+
+fn circle_time_of_impact_with_point(
+    offset: Wide,
+    movement: Wide,
+    radius2: i128,
+) -> Option<Wide> {
+    let a = dot_wide(movement, movement);
+    let half_b = dot_wide(offset, movement);
+    let c = dot_wide(offset, offset) - radius2;
+    if half_b >= 0 || a == 0 {
+        return None;
+    }
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0 {
+        return None;
+    }
+    let numerator = (-half_b - isqrt_ceil(discriminant)).max(0);
+    if numerator > a {
+        return None;
+    }
+    Some((numerator, a))
+}
+
+fn circle_time_of_impact_with_line(
+    offset: Wide,
+    movement: Wide,
+    radius2: i128,
+    edge: Wide,
+) -> Option<Wide> {
+    let edge_len2 = dot_wide(edge, edge);
+    if edge_len2 == 0 {
+        return None;
+    }
+    let normal = (edge.1, -edge.0);
+    let distance = dot_wide(normal, offset);
+    let approach = dot_wide(normal, movement);
+    if distance == 0 || approach == 0 || (distance > 0) == (approach > 0) {
+        return None;
+    }
+    let contact_distance = isqrt_ceil(radius2 * edge_len2);
+    let numerator = (distance.abs() - contact_distance).max(0);
+    let denominator = approach.abs();
+    if numerator > denominator {
+        return None;
     }
+    let along =
+        dot_wide(edge, offset) * denominator + dot_wide(edge, movement) * numerator;
+    if along < 0 || along > edge_len2 * denominator {
+        return None;
+    }
+    Some((numerator, denominator))
+}
+
+fn scale_movement_reduced<N: PhysicsNum>(movement: Wide, time: Wide) -> Vector2<N> {
+    let (numerator, denominator) = time;
+    let x = reduce_one(movement.0 * numerator) / denominator;
+    let y = reduce_one(movement.1 * numerator) / denominator;
+    narrow_vector(x, y)
+}
+
+pub fn circle_moving_towards_edge<N: PhysicsNum>(
+    centre: Vector2<N>,
+    radius: N,
+    movement: Vector2<N>,
+    edge: LineSegment<N>,
+) -> Result<Collision<N>, NoCollision> {
+    let offset = widen_vector(centre - edge.start);
+    let edge_vector = widen_vector(edge.vector());
+    let movement_wide = widen_vector(movement);
+    let radius = physics_num::widen(radius);
+    let radius2 = radius * radius;
+    if distance2_to_segment_at_most(offset, edge_vector, radius2) {
+        return Ok(Collision::StartInsideEdge);
+    }
+    let from_end = (offset.0 - edge_vector.0, offset.1 - edge_vector.1);
+    let times = [
+        circle_time_of_impact_with_line(offset, movement_wide, radius2, edge_vector),
+        circle_time_of_impact_with_point(offset, movement_wide, radius2),
+        circle_time_of_impact_with_point(from_end, movement_wide, radius2),
+    ];
+    let earliest = times.iter().filter_map(|&time| time).fold(None, |best, time| {
+        match best {
+            Some((numerator, denominator))
+                if numerator * time.1 <= time.0 * denominator =>
+            {
+                best
+            }
+            _ => Some(time),
+        }
+    });
+    earliest
+        .and_then(|time| {
+            TimeOfImpact::from_wide(time.0, time.1).map(|time_of_impact| {
+                Collision::CollidesWithEdgeAfter(
+                    scale_movement_reduced(movement_wide, time),
+                    time_of_impact,
+                )
+            })
+        })
+        .ok_or(NoCollision::NonParallelNonIntersecting)
+}
+
+pub fn circle_moving_towards_rect<N: PhysicsNum>(
+    centre: Vector2<N>,
+    radius: N,
+    movement: Vector2<N>,
+    rect: &Aabb<N>,
+) -> Result<Collision<N>, NoCollision> {
+    let top_left = rect.top_left();
+    let bottom_right = rect.bottom_right_coord();
+    if centre.x >= top_left.x && centre.x <= bottom_right.x && centre.y >= top_left.y
+        && centre.y <= bottom_right.y
+    {
+        return Ok(Collision::StartInsideEdge);
+    }
+    let top_right = vec2(bottom_right.x, top_left.y);
+    let bottom_left = vec2(top_left.x, bottom_right.y);
+    let edges = [
+        LineSegment::new(top_left, top_right),
+        LineSegment::new(top_right, bottom_right),
+        LineSegment::new(bottom_right, bottom_left),
+        LineSegment::new(bottom_left, top_left),
+    ];
+    let mut closest = Err(NoCollision::NonParallelNonIntersecting);
+    for edge in edges.iter() {
+        match circle_moving_towards_edge(centre, radius, movement, *edge) {
+            Ok(Collision::StartInsideEdge) => return Ok(Collision::StartInsideEdge),
+            Ok(Collision::CollidesWithEdgeAfter(allowed, time)) => {
+                let closer = match closest {
+                    Ok(Collision::CollidesWithEdgeAfter(_, current)) => {
+                        time.is_before(&current)
+                    }
+                    _ => true,
+                };
+                if closer {
+                    closest = Ok(Collision::CollidesWithEdgeAfter(allowed, time));
+                }
+            }
+            Err(_) => (),
+        }
+    }
+    closest
 }
 
 #[cfg(test)]
@@ -128,16 +389,27 @@ mod test {
     fn ls(start: Vector2<i64>, end: Vector2<i64>) -> LineSegment<i64> {
         LineSegment::new(start, end)
     }
+    fn after(
+        x: i64,
+        y: i64,
+        numerator: i128,
+        denominator: i128,
+    ) -> Result<Collision<i64>, NoCollision> {
+        Ok(Collision::CollidesWithEdgeAfter(
+            v(x, y),
+            TimeOfImpact::from_wide(numerator, denominator).unwrap(),
+        ))
+    }
 
     #[test]
     fn basic() {
         assert_eq!(
             vertex_moving_towards_edge(v(0, 0), v(3, 3), ls(v(0, 4), v(4, 0)), 1),
-            Ok(Collision::CollidesWithEdgeAfter(v(1, 1)))
+            after(1, 1, 2, 3)
         );
         assert_eq!(
             vertex_moving_towards_edge(v(0, 0), v(3, 3), ls(v(0, 5), v(5, 0)), 1),
-            Ok(Collision::CollidesWithEdgeAfter(v(2, 2)))
+            after(2, 2, 5, 6)
         );
         assert_eq!(
             vertex_moving_towards_edge(v(0, 0), v(2, 2), ls(v(0, 5), v(5, 0)), 1),
@@ -157,7 +429,7 @@ mod test {
         );
         assert_eq!(
             vertex_moving_towards_edge(v(0, 0), v(2, 1), ls(v(2, 1), v(8, 4)), 1),
-            Ok(Collision::CollidesWithEdgeAfter(v(1, 0)))
+            after(1, 0, 1, 1)
         );
         assert_eq!(
             vertex_moving_towards_edge(v(2, 1), v(2, 1), ls(v(0, 0), v(8, 4)), 1),
@@ -169,11 +441,114 @@ mod test {
     fn perpendicular() {
         assert_eq!(
             vertex_moving_towards_edge(v(0, 0), v(10, 0), ls(v(5, 5), v(5, -5)), 1),
-            Ok(Collision::CollidesWithEdgeAfter(v(4, 0)))
+            after(4, 0, 1, 2)
         );
         assert_eq!(
             vertex_moving_towards_edge(v(0, 2), v(0, -1), ls(v(-1, 1), v(1, 1)), 1),
-            Ok(Collision::CollidesWithEdgeAfter(v(0, 0)))
+            after(0, 0, 1, 1)
+        );
+    }
+
+    #[test]
+    fn large_coordinates() {
+        let far = 1 << 40;
+        assert_eq!(
+            vertex_moving_towards_edge(
+                v(far, far),
+                v(far, 0),
+                ls(v(far + far / 2, far + 5), v(far + far / 2, far - 5)),
+                1
+            ),
+            after(far / 2 - 1, 0, 1, 2)
+        );
+    }
+
+    #[test]
+    fn time_of_impact_orders_exactly() {
+        let third = TimeOfImpact::<i64>::from_wide(1, 3).unwrap();
+        let close = TimeOfImpact::<i64>::from_wide(333_333, 1_000_000).unwrap();
+        assert!(close < third);
+        assert_eq!(TimeOfImpact::<i64>::from_wide(2, 6), Some(third));
+        assert_eq!(third.scale_vector(v(9, -10)), v(3, -3));
+    }
+
+    #[test]
+    fn equal_time_of_impact_fractions_compare_equal() {
+        let half = TimeOfImpact::<i64>::from_wide(1, 2).unwrap();
+        let unreduced = TimeOfImpact {
+            numerator: 2,
+            denominator: 4,
+        };
+        assert_eq!(half, unreduced);
+        assert_eq!(half.cmp(&unreduced), Ordering::Equal);
+        let mut times = vec![unreduced, half, TimeOfImpact::zero()];
+        times.sort();
+        times.dedup();
+        assert_eq!(times, [TimeOfImpact::zero(), half]);
+    }
+
+    #[test]
+    fn time_of_impact_from_wide_never_panics() {
+        assert_eq!(TimeOfImpact::<i64>::from_wide(1, 0), None);
+        let half = TimeOfImpact::<i64>::from_wide(1, 2).unwrap();
+        assert_eq!(TimeOfImpact::<i64>::from_wide(-1, -2), Some(half));
+        let huge = 1i128 << 100;
+        let almost_half = TimeOfImpact::<i64>::from_wide(huge - 1, 2 * huge).unwrap();
+        assert!(almost_half.denominator > 0);
+        assert!(almost_half <= half);
+        assert!(TimeOfImpact::from_wide(huge / 2 - 1, huge).unwrap() < half);
+    }
+
+    #[test]
+    fn circle_head_on() {
+        assert_eq!(
+            circle_moving_towards_edge(v(0, 0), 2, v(10, 0), ls(v(5, -5), v(5, 5))),
+            after(2, 0, 3, 10)
+        );
+        assert_eq!(
+            circle_moving_towards_edge(v(0, 0), 2, v(-10, 0), ls(v(5, -5), v(5, 5))),
+            Err(NoCollision::NonParallelNonIntersecting)
+        );
+    }
+
+    #[test]
+    fn circle_grazing_endpoint() {
+        assert_eq!(
+            circle_moving_towards_edge(v(0, 0), 3, v(10, 0), ls(v(5, 3), v(5, 10))),
+            after(4, 0, 1, 2)
+        );
+        assert_eq!(
+            circle_moving_towards_edge(v(0, 0), 3, v(10, 0), ls(v(5, 4), v(5, 10))),
+            Err(NoCollision::NonParallelNonIntersecting)
+        );
+    }
+
+    #[test]
+    fn circle_tangential() {
+        assert_eq!(
+            circle_moving_towards_edge(v(0, 0), 2, v(3, 0), ls(v(-5, 2), v(5, 2))),
+            Ok(Collision::StartInsideEdge)
+        );
+        assert_eq!(
+            circle_moving_towards_edge(v(0, 0), 2, v(3, 0), ls(v(-5, 3), v(5, 3))),
+            Err(NoCollision::NonParallelNonIntersecting)
+        );
+    }
+
+    #[test]
+    fn circle_rect_corner() {
+        let rect = Aabb::new(v(6, 6), v(4, 4));
+        assert_eq!(
+            circle_moving_towards_rect(v(0, 0), 2, v(10, 10), &rect),
+            after(4, 4, 91, 200)
+        );
+        assert_eq!(
+            circle_moving_towards_rect(v(0, 8), 2, v(10, 0), &rect),
+            after(3, 0, 2, 5)
+        );
+        assert_eq!(
+            circle_moving_towards_rect(v(7, 7), 2, v(10, 0), &rect),
+            Ok(Collision::StartInsideEdge)
         );
     }
 }
@@ -0,0 +1,84 @@
+use std::hash::{Hash, Hasher};
+
+pub const WHITE: [f32; 4] = [1., 1., 1., 1.];
+
+/// Hashes a colour by its bit pattern, for use in state hashes.
+pub fn hash_colour<H: Hasher>(colour: &[f32; 4], state: &mut H) {
+    for channel in colour.iter() {
+        state.write_u32(channel.to_bits());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColourTween {
+    from: [f32; 4],
+    to: [f32; 4],
+    duration: u32,
+    elapsed: u32,
+}
+
+impl ColourTween {
+    pub fn new(from: [f32; 4], to: [f32; 4], duration: u32) -> Self {
+        assert!(duration > 0, "tween duration must be positive");
+        Self {
+            from,
+            to,
+            duration,
+            elapsed: 0,
+        }
+    }
+    pub fn current(&self) -> [f32; 4] {
+        let t = self.elapsed as f32 / self.duration as f32;
+        let mut colour = self.from;
+        for ((c, from), to) in colour.iter_mut().zip(self.from.iter()).zip(self.to.iter()) {
+            *c += (to - from) * t;
+        }
+        colour
+    }
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+    pub fn tick(&mut self) -> [f32; 4] {
+        self.elapsed = (self.elapsed + 1).min(self.duration);
+        self.current()
+    }
+}
+
+impl Hash for ColourTween {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_colour(&self.from, state);
+        hash_colour(&self.to, state);
+        self.duration.hash(state);
+        self.elapsed.hash(state);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Flash {
+    pub colour: [f32; 4],
+    pub remaining: u32,
+}
+
+impl Hash for Flash {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_colour(&self.colour, state);
+        self.remaining.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tween_reaches_target() {
+        let mut tween = ColourTween::new([0., 0., 1., 1.], [1., 0., 0., 0.], 4);
+        assert_eq!(tween.tick(), [0.25, 0., 0.75, 0.75]);
+        assert!(!tween.is_finished());
+        tween.tick();
+        tween.tick();
+        assert_eq!(tween.tick(), [1., 0., 0., 0.]);
+        assert!(tween.is_finished());
+        assert_eq!(tween.tick(), [1., 0., 0., 0.]);
+    }
+}
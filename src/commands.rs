@@ -0,0 +1,119 @@
+use cgmath::Vector2;
+use error::PhysicsError;
+use game::EntityId;
+use pixel_num::SubPixelI64;
+use shape::Shape;
+use std::mem;
+use std::vec;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Spawn {
+        prefab: String,
+        position: Vector2<SubPixelI64>,
+    },
+    Despawn(EntityId),
+    SetPosition(EntityId, Vector2<SubPixelI64>),
+    SetVelocity(EntityId, Vector2<SubPixelI64>),
+    SetShape(EntityId, Shape<SubPixelI64>),
+    SetColour(EntityId, [f32; 4]),
+    ApplyImpulse(EntityId, Vector2<SubPixelI64>),
+}
+
+/// A queued command that could not be applied, with the reason.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandError {
+    pub command: Command,
+    pub error: PhysicsError,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Commands {
+    commands: Vec<Command>,
+}
+
+impl Commands {
+    pub fn push(&mut self, command: Command) {
+        self.commands.push(command);
+    }
+    pub fn spawn(&mut self, prefab: &str, position: Vector2<SubPixelI64>) {
+        self.push(Command::Spawn {
+            prefab: prefab.to_string(),
+            position,
+        });
+    }
+    pub fn despawn(&mut self, id: EntityId) {
+        self.push(Command::Despawn(id));
+    }
+    pub fn set_position(&mut self, id: EntityId, position: Vector2<SubPixelI64>) {
+        self.push(Command::SetPosition(id, position));
+    }
+    pub fn set_velocity(&mut self, id: EntityId, velocity: Vector2<SubPixelI64>) {
+        self.push(Command::SetVelocity(id, velocity));
+    }
+    pub fn set_shape(&mut self, id: EntityId, shape: Shape<SubPixelI64>) {
+        self.push(Command::SetShape(id, shape));
+    }
+    pub fn set_colour(&mut self, id: EntityId, colour: [f32; 4]) {
+        self.push(Command::SetColour(id, colour));
+    }
+    pub fn apply_impulse(&mut self, id: EntityId, impulse: Vector2<SubPixelI64>) {
+        self.push(Command::ApplyImpulse(id, impulse));
+    }
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+    pub fn drain(&mut self) -> vec::Drain<'_, Command> {
+        self.commands.drain(..)
+    }
+    pub fn swap_buffer(&mut self, buffer: &mut Vec<Command>) {
+        mem::swap(&mut self.commands, buffer);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cgmath::vec2;
+
+    #[test]
+    fn commands_are_drained_in_order() {
+        let mut commands = Commands::default();
+        let position = vec2(SubPixelI64::new(1), SubPixelI64::new(2));
+        commands.spawn("crate", position);
+        commands.set_position(3, position);
+        commands.despawn(3);
+        assert_eq!(commands.len(), 3);
+        let drained = commands.drain().collect::<Vec<_>>();
+        assert!(commands.is_empty());
+        assert_eq!(
+            drained,
+            [
+                Command::Spawn {
+                    prefab: "crate".to_string(),
+                    position,
+                },
+                Command::SetPosition(3, position),
+                Command::Despawn(3),
+            ]
+        );
+    }
+
+    #[test]
+    fn swap_buffer_exchanges_queues() {
+        let mut commands = Commands::default();
+        commands.despawn(1);
+        let mut buffer = vec![Command::Despawn(2), Command::Despawn(3)];
+        commands.swap_buffer(&mut buffer);
+        assert_eq!(buffer, [Command::Despawn(1)]);
+        assert_eq!(commands.len(), 2);
+        commands.clear();
+        assert!(commands.is_empty());
+    }
+}
@@ -0,0 +1,278 @@
+use aabb::Aabb;
+use alloc::vec::Vec;
+use cgmath::{Vector2, vec2};
+use collision::{self, TimeOfImpact};
+use line_segment::LineSegment;
+use num::Zero;
+use physics_num::{self, PhysicsNum};
+use shape::Collide;
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct ConvexPolygon<N: PhysicsNum> {
+    vertices: Vec<Vector2<N>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SweptPolygonCollision<N> {
+    pub time_of_impact: TimeOfImpact<N>,
+    pub allowed_movement: Vector2<N>,
+    pub edge: LineSegment<N>,
+    pub start_inside: bool,
+}
+
+fn outward_normal<N: PhysicsNum>(edge: LineSegment<N>) -> Vector2<N> {
+    let vector = edge.vector();
+    vec2(vector.y, -vector.x)
+}
+
+fn project<N: PhysicsNum>(
+    vertices: &[Vector2<N>],
+    offset: Vector2<N>,
+    axis: Vector2<N>,
+) -> (i128, i128) {
+    let mut min = None;
+    let mut max = None;
+    for &vertex in vertices.iter() {
+        let projection = physics_num::widen(physics_num::dot(vertex + offset, axis));
+        min = Some(min.map_or(projection, |min: i128| min.min(projection)));
+        max = Some(max.map_or(projection, |max: i128| max.max(projection)));
+    }
+    (min.expect("empty polygon"), max.expect("empty polygon"))
+}
+
+pub fn vertices_overlap<N: PhysicsNum>(
+    a: &[Vector2<N>],
+    a_position: Vector2<N>,
+    b: &[Vector2<N>],
+    b_position: Vector2<N>,
+    tolerance: N,
+) -> bool {
+    let tolerance = physics_num::widen(tolerance);
+    let edges = (0..a.len())
+        .map(|i| a[(i + 1) % a.len()] - a[i])
+        .chain((0..b.len()).map(|i| b[(i + 1) % b.len()] - b[i]));
+    for edge in edges {
+        let axis = vec2(edge.y, -edge.x);
+        if axis.x.is_zero() && axis.y.is_zero() {
+            continue;
+        }
+        let (a_min, a_max) = project(a, a_position, axis);
+        let (b_min, b_max) = project(b, b_position, axis);
+        let overlap = a_max.min(b_max) - a_min.max(b_min);
+        let axis_length2 = physics_num::magnitude2_wide(axis);
+        let within_tolerance =
+            tolerance > 0 && overlap * overlap <= tolerance * tolerance * axis_length2;
+        if overlap <= 0 || within_tolerance {
+            return false;
+        }
+    }
+    true
+}
+
+impl<N: PhysicsNum> ConvexPolygon<N> {
+    pub fn new(mut vertices: Vec<Vector2<N>>) -> Self {
+        assert!(vertices.len() >= 3, "polygon needs at least three vertices");
+        let mut area2 = 0;
+        for i in 0..vertices.len() {
+            let next = vertices[(i + 1) % vertices.len()];
+            let cross = collision::vector2_cross_product(vertices[i], next);
+            area2 += physics_num::widen(cross);
+        }
+        if area2 < 0 {
+            vertices.reverse();
+        }
+        Self { vertices }
+    }
+    pub fn vertices(&self) -> &[Vector2<N>] {
+        &self.vertices
+    }
+    pub fn edge(&self, index: usize) -> LineSegment<N> {
+        LineSegment::new(
+            self.vertices[index],
+            self.vertices[(index + 1) % self.vertices.len()],
+        )
+    }
+    pub fn num_edges(&self) -> usize {
+        self.vertices.len()
+    }
+    pub fn swept_collision_test(
+        &self,
+        position: Vector2<N>,
+        stationary: &ConvexPolygon<N>,
+        stationary_position: Vector2<N>,
+        movement: Vector2<N>,
+    ) -> Option<SweptPolygonCollision<N>> {
+        let mut entry: Option<(i128, i128)> = None;
+        let mut exit = (1i128, 1i128);
+        let mut contact_edge = None;
+        let edges = (0..self.num_edges())
+            .map(|i| self.edge(i).add_vector(position))
+            .chain(
+                (0..stationary.num_edges())
+                    .map(|i| stationary.edge(i).add_vector(stationary_position)),
+            );
+        for edge in edges {
+            let axis = outward_normal(edge);
+            if axis.x.is_zero() && axis.y.is_zero() {
+                continue;
+            }
+            let (moving_min, moving_max) = project(&self.vertices, position, axis);
+            let (stationary_min, stationary_max) =
+                project(&stationary.vertices, stationary_position, axis);
+            let speed = physics_num::widen(physics_num::dot(movement, axis));
+            let (axis_entry, axis_exit) = if speed > 0 {
+                (
+                    (stationary_min - moving_max, speed),
+                    (stationary_max - moving_min, speed),
+                )
+            } else if speed < 0 {
+                (
+                    (moving_min - stationary_max, -speed),
+                    (moving_max - stationary_min, -speed),
+                )
+            } else if moving_max <= stationary_min || stationary_max <= moving_min {
+                return None;
+            } else {
+                continue;
+            };
+            let later = entry.is_none_or(|entry| {
+                axis_entry.0 * entry.1 > entry.0 * axis_entry.1
+            });
+            if later {
+                entry = Some(axis_entry);
+                contact_edge = Some(edge);
+            }
+            if axis_exit.0 * exit.1 < exit.0 * axis_exit.1 {
+                exit = axis_exit;
+            }
+        }
+        let (entry, edge) = (entry?, contact_edge?);
+        if entry.0 < 0 {
+            let zero = Zero::zero();
+            return Some(SweptPolygonCollision {
+                time_of_impact: TimeOfImpact::zero(),
+                allowed_movement: vec2(zero, zero),
+                edge,
+                start_inside: true,
+            });
+        }
+        if entry.0 > entry.1 || entry.0 * exit.1 > exit.0 * entry.1 {
+            return None;
+        }
+        let scale = |value: N| {
+            let product = physics_num::widen(value) * entry.0;
+            physics_num::saturating_narrow((product - product.signum()) / entry.1)
+        };
+        Some(SweptPolygonCollision {
+            time_of_impact: TimeOfImpact::from_wide(entry.0, entry.1)?,
+            allowed_movement: vec2(scale(movement.x), scale(movement.y)),
+            edge,
+            start_inside: false,
+        })
+    }
+}
+
+impl<N: PhysicsNum> Collide<N> for ConvexPolygon<N> {
+    fn aabb(&self, top_left: Vector2<N>) -> Aabb<N> {
+        let first = self.vertices[0];
+        let (min, max) = self.vertices.iter().fold((first, first), |(min, max), v| {
+            (vec2(min.x.min(v.x), min.y.min(v.y)), vec2(max.x.max(v.x), max.y.max(v.y)))
+        });
+        Aabb::new(min + top_left, max - min)
+    }
+    fn for_each_edge_facing<F>(&self, direction: Vector2<N>, mut f: F)
+    where
+        F: FnMut(LineSegment<N>),
+    {
+        for i in 0..self.num_edges() {
+            let edge = self.edge(i);
+            if physics_num::dot(outward_normal(edge), direction) >= Zero::zero() {
+                f(edge);
+            }
+        }
+    }
+    fn for_each_vertex_facing<F>(&self, direction: Vector2<N>, mut f: F)
+    where
+        F: FnMut(Vector2<N>),
+    {
+        let num_edges = self.num_edges();
+        let facing = |i: usize| {
+            physics_num::dot(outward_normal(self.edge(i)), direction) >= Zero::zero()
+        };
+        for i in 0..num_edges {
+            if facing(i) || facing((i + num_edges - 1) % num_edges) {
+                f(self.vertices[i]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn square(size: i64) -> ConvexPolygon<i64> {
+        ConvexPolygon::new(vec![
+            vec2(0, 0),
+            vec2(0, size),
+            vec2(size, size),
+            vec2(size, 0),
+        ])
+    }
+
+    fn triangle() -> ConvexPolygon<i64> {
+        ConvexPolygon::new(vec![vec2(0, 0), vec2(4, 0), vec2(0, 4)])
+    }
+
+    #[test]
+    fn winding_is_normalised() {
+        let polygon = square(2);
+        for i in 0..polygon.num_edges() {
+            let next = (i + 1) % polygon.num_edges();
+            let cross = collision::vector2_cross_product(
+                polygon.edge(i).vector(),
+                polygon.edge(next).vector(),
+            );
+            assert!(cross > 0);
+        }
+    }
+
+    #[test]
+    fn square_hits_square() {
+        let collision = square(4)
+            .swept_collision_test(vec2(0, 0), &square(4), vec2(10, 0), vec2(12, 0))
+            .unwrap();
+        assert_eq!(collision.time_of_impact, TimeOfImpact::from_wide(1, 2).unwrap());
+        assert_eq!(collision.allowed_movement, vec2(5, 0));
+        assert_eq!(collision.edge.vector().x, 0);
+    }
+
+    #[test]
+    fn triangle_hypotenuse() {
+        let collision = square(2)
+            .swept_collision_test(vec2(4, 4), &triangle(), vec2(0, 0), vec2(-4, -4))
+            .unwrap();
+        assert_eq!(collision.edge.vector(), vec2(-4, 4));
+        assert_eq!(collision.allowed_movement, vec2(-1, -1));
+        assert!(
+            square(2)
+                .swept_collision_test(vec2(5, 0), &triangle(), vec2(0, 0), vec2(0, 8))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn overlap_at_start_is_reported() {
+        let collision = square(4)
+            .swept_collision_test(vec2(0, 0), &square(4), vec2(3, 1), vec2(2, 0))
+            .unwrap();
+        assert!(collision.start_inside);
+        assert_eq!(collision.allowed_movement, vec2(0, 0));
+        assert_eq!(collision.edge.vector().x, 0);
+        assert!(
+            square(4)
+                .swept_collision_test(vec2(0, 0), &square(4), vec2(4, 0), vec2(0, 8))
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,369 @@
+use std::ops::Index;
+use std::rc::Rc;
+use std::slice;
+use game::EntityId;
+
+const CHUNK_BITS: u32 = 6;
+const CHUNK_SIZE: usize = 1 << CHUNK_BITS;
+
+#[derive(Debug, Clone)]
+struct Chunk<V> {
+    slots: Vec<Option<(EntityId, V)>>,
+    len: usize,
+}
+
+impl<V> Chunk<V> {
+    fn new() -> Self {
+        Self {
+            slots: (0..CHUNK_SIZE).map(|_| None).collect(),
+            len: 0,
+        }
+    }
+}
+
+fn split(id: EntityId) -> (usize, usize) {
+    ((id >> CHUNK_BITS) as usize, id as usize & (CHUNK_SIZE - 1))
+}
+
+#[derive(Debug, Clone)]
+pub struct CowTable<V> {
+    chunks: Vec<Option<Rc<Chunk<V>>>>,
+    len: usize,
+}
+
+impl<V> Default for CowTable<V> {
+    fn default() -> Self {
+        Self {
+            chunks: Vec::new(),
+            len: 0,
+        }
+    }
+}
+
+impl<V: Clone> CowTable<V> {
+    fn slot(&self, id: EntityId) -> Option<&(EntityId, V)> {
+        let (chunk, slot) = split(id);
+        self.chunks
+            .get(chunk)
+            .and_then(Option::as_ref)
+            .and_then(|chunk| chunk.slots[slot].as_ref())
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    pub fn contains_key(&self, id: &EntityId) -> bool {
+        self.slot(*id).is_some()
+    }
+    pub fn get(&self, id: &EntityId) -> Option<&V> {
+        self.slot(*id).map(|(_, value)| value)
+    }
+    pub fn get_mut(&mut self, id: &EntityId) -> Option<&mut V> {
+        let (chunk, slot) = split(*id);
+        match self.chunks.get_mut(chunk) {
+            Some(Some(chunk)) if chunk.slots[slot].is_some() => {
+                Rc::make_mut(chunk).slots[slot].as_mut().map(|(_, value)| value)
+            }
+            _ => None,
+        }
+    }
+    pub fn insert(&mut self, id: EntityId, value: V) -> Option<V> {
+        let (chunk, slot) = split(id);
+        if self.chunks.len() <= chunk {
+            self.chunks.resize(chunk + 1, None);
+        }
+        let chunk = self.chunks[chunk].get_or_insert_with(|| Rc::new(Chunk::new()));
+        let chunk = Rc::make_mut(chunk);
+        let previous = chunk.slots[slot].take().map(|(_, value)| value);
+        chunk.slots[slot] = Some((id, value));
+        if previous.is_none() {
+            chunk.len += 1;
+            self.len += 1;
+        }
+        previous
+    }
+    pub fn remove(&mut self, id: &EntityId) -> Option<V> {
+        if !self.contains_key(id) {
+            return None;
+        }
+        let (chunk_index, slot) = split(*id);
+        let (previous, empty) = {
+            let chunk = Rc::make_mut(self.chunks[chunk_index].as_mut()?);
+            let previous = chunk.slots[slot].take().map(|(_, value)| value);
+            chunk.len -= 1;
+            (previous, chunk.len == 0)
+        };
+        if empty {
+            self.chunks[chunk_index] = None;
+        }
+        self.len -= 1;
+        previous
+    }
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+        self.len = 0;
+    }
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter {
+            chunks: self.chunks.iter(),
+            slots: [].iter(),
+        }
+    }
+    pub fn keys(&self) -> impl Iterator<Item = &EntityId> {
+        self.iter().map(|(id, _)| id)
+    }
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+    pub fn get_or_insert_with<F>(&mut self, id: EntityId, f: F) -> &mut V
+    where
+        F: FnOnce() -> V,
+    {
+        if !self.contains_key(&id) {
+            self.insert(id, f());
+        }
+        self.get_mut(&id).expect("entry was just inserted")
+    }
+    /// Mutable access to the entries matching `predicate`. A chunk shared with a
+    /// clone is only copied if it holds a matching entry.
+    pub fn iter_mut_where<P>(&mut self, predicate: P) -> IterMut<'_, V, P>
+    where
+        P: FnMut(&EntityId, &V) -> bool,
+    {
+        IterMut {
+            chunks: self.chunks.iter_mut(),
+            slots: [].iter_mut(),
+            predicate,
+        }
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&EntityId, &mut V)> {
+        self.iter_mut_where(|_, _| true)
+    }
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.iter_mut().map(|(_, value)| value)
+    }
+    /// Removes the entries `keep` rejects, copying only the shared chunks that
+    /// lose an entry.
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&EntityId, &V) -> bool,
+    {
+        for slot in self.chunks.iter_mut() {
+            let removed = {
+                let chunk = match *slot {
+                    Some(ref mut chunk) => chunk,
+                    None => continue,
+                };
+                let any_removed = chunk
+                    .slots
+                    .iter()
+                    .flatten()
+                    .any(|(id, value)| !keep(id, value));
+                if !any_removed {
+                    continue;
+                }
+                let chunk = Rc::make_mut(chunk);
+                let mut removed = 0;
+                for entry in chunk.slots.iter_mut() {
+                    let discard = match *entry {
+                        Some((ref id, ref value)) => !keep(id, value),
+                        None => false,
+                    };
+                    if discard {
+                        *entry = None;
+                        removed += 1;
+                    }
+                }
+                chunk.len -= removed;
+                if chunk.len == 0 {
+                    *slot = None;
+                }
+                removed
+            };
+            self.len -= removed;
+        }
+    }
+    pub fn shared_chunks(&self, other: &Self) -> usize {
+        self.chunks
+            .iter()
+            .zip(other.chunks.iter())
+            .filter(|&(a, b)| match (a, b) {
+                (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+                _ => false,
+            })
+            .count()
+    }
+}
+
+pub struct Iter<'a, V: 'a> {
+    chunks: slice::Iter<'a, Option<Rc<Chunk<V>>>>,
+    slots: slice::Iter<'a, Option<(EntityId, V)>>,
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = (&'a EntityId, &'a V);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(slot) = self.slots.next() {
+                if let Some((ref id, ref value)) = *slot {
+                    return Some((id, value));
+                }
+                continue;
+            }
+            match self.chunks.next() {
+                Some(Some(chunk)) => self.slots = chunk.slots.iter(),
+                Some(None) => (),
+                None => return None,
+            }
+        }
+    }
+}
+
+pub struct IterMut<'a, V: 'a, P> {
+    chunks: slice::IterMut<'a, Option<Rc<Chunk<V>>>>,
+    slots: slice::IterMut<'a, Option<(EntityId, V)>>,
+    predicate: P,
+}
+
+impl<'a, V, P> Iterator for IterMut<'a, V, P>
+where
+    V: Clone,
+    P: FnMut(&EntityId, &V) -> bool,
+{
+    type Item = (&'a EntityId, &'a mut V);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            for slot in self.slots.by_ref() {
+                if let Some((ref id, ref mut value)) = *slot {
+                    if (self.predicate)(id, value) {
+                        return Some((id, value));
+                    }
+                }
+            }
+            if let Some(chunk) = self.chunks.next()?.as_mut() {
+                let predicate = &mut self.predicate;
+                let matched = chunk
+                    .slots
+                    .iter()
+                    .flatten()
+                    .any(|(id, value)| predicate(id, value));
+                if matched {
+                    self.slots = Rc::make_mut(chunk).slots.iter_mut();
+                }
+            }
+        }
+    }
+}
+
+impl<'a, V: Clone> IntoIterator for &'a CowTable<V> {
+    type Item = (&'a EntityId, &'a V);
+    type IntoIter = Iter<'a, V>;
+    fn into_iter(self) -> Iter<'a, V> {
+        self.iter()
+    }
+}
+
+impl<V: Clone> Index<&EntityId> for CowTable<V> {
+    type Output = V;
+    fn index(&self, id: &EntityId) -> &V {
+        self.get(id).expect("no entry for entity")
+    }
+}
+
+/// A set of entities whose chunks are shared between clones like `CowTable`'s.
+#[derive(Debug, Clone, Default)]
+pub struct CowSet {
+    table: CowTable<()>,
+}
+
+impl CowSet {
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+    pub fn contains(&self, id: &EntityId) -> bool {
+        self.table.contains_key(id)
+    }
+    pub fn insert(&mut self, id: EntityId) -> bool {
+        self.table.insert(id, ()).is_none()
+    }
+    pub fn remove(&mut self, id: &EntityId) -> bool {
+        self.table.remove(id).is_some()
+    }
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &EntityId> {
+        self.table.keys()
+    }
+    pub fn retain<F>(&mut self, mut keep: F)
+    where
+        F: FnMut(&EntityId) -> bool,
+    {
+        self.table.retain(|id, _| keep(id));
+    }
+}
+
+impl Extend<EntityId> for CowSet {
+    fn extend<I: IntoIterator<Item = EntityId>>(&mut self, ids: I) {
+        for id in ids {
+            self.insert(id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn clones_share_untouched_chunks() {
+        let mut table = CowTable::default();
+        for id in 0..200 {
+            table.insert(id, id * 10);
+        }
+        let mut branch = table.clone();
+        assert_eq!(branch.shared_chunks(&table), 4);
+        *branch.get_mut(&70).unwrap() += 1;
+        branch.remove(&199);
+        assert_eq!(branch.shared_chunks(&table), 2);
+        assert_eq!((table[&70], branch[&70]), (700, 701));
+        assert_eq!((table.len(), branch.len()), (200, 199));
+        assert_eq!(branch.get(&199), None);
+        let ids = branch.keys().cloned().collect::<Vec<_>>();
+        assert_eq!(ids, (0..199).collect::<Vec<_>>());
+        for value in branch.values_mut() {
+            *value = 0;
+        }
+        assert_eq!(branch.shared_chunks(&table), 0);
+        assert_eq!(table.values().sum::<u32>(), 199_000);
+    }
+
+    #[test]
+    fn only_written_chunks_stop_being_shared() {
+        let mut table = CowTable::default();
+        for id in 0..256 {
+            table.insert(id, id);
+        }
+        let mut branch = table.clone();
+        for (_, value) in branch.iter_mut_where(|&id, _| id == 100) {
+            *value += 1;
+        }
+        assert_eq!(branch.shared_chunks(&table), 3);
+        branch.retain(|&id, _| id != 200);
+        assert_eq!(branch.shared_chunks(&table), 2);
+        branch.retain(|_, _| true);
+        assert_eq!(branch.shared_chunks(&table), 2);
+        assert_eq!((branch[&100], branch.get(&200), branch.len()), (101, None, 255));
+        let mut set = CowSet::default();
+        set.extend(0..128);
+        let mut set_branch = set.clone();
+        set_branch.retain(|&id| id < 64);
+        assert_eq!((set.len(), set_branch.len()), (128, 64));
+        assert!(set_branch.contains(&63) && !set_branch.contains(&64));
+    }
+}
@@ -0,0 +1,192 @@
+use game::{EntityId, GameState, InputModel};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DivergentComponent {
+    PlayerId,
+    Existence,
+    /// A replicated component table, named as in `ComponentDeltas`.
+    Component(&'static str),
+    Rng,
+    Scheduler,
+    /// State outside the entity tables, named as in `SimulationDelta`.
+    Simulation(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Divergence {
+    pub frame: usize,
+    pub component: DivergentComponent,
+    pub entity_id: Option<EntityId>,
+}
+
+pub fn first_divergence(
+    a: &GameState,
+    b: &GameState,
+) -> Option<(DivergentComponent, Option<EntityId>)> {
+    let delta = a.diff(b);
+    if a.player_id() != b.player_id() {
+        return Some((DivergentComponent::PlayerId, None));
+    }
+    let existence = delta
+        .despawns
+        .iter()
+        .cloned()
+        .chain(delta.spawns.iter().map(|spawn| spawn.id))
+        .min();
+    if let Some(id) = existence {
+        return Some((DivergentComponent::Existence, Some(id)));
+    }
+    if let Some((table, id)) = delta.components.first_change() {
+        return Some((DivergentComponent::Component(table), Some(id)));
+    }
+    if delta.rng_state.is_some() {
+        return Some((DivergentComponent::Rng, None));
+    }
+    if delta.scheduler.is_some() {
+        return Some((DivergentComponent::Scheduler, None));
+    }
+    if let Some(field) = delta.simulation.first_change() {
+        return Some((DivergentComponent::Simulation(field), None));
+    }
+    None
+}
+
+pub fn run_lockstep(
+    a: &mut GameState,
+    b: &mut GameState,
+    inputs: &[InputModel],
+) -> Result<(), Divergence> {
+    let check = |frame: usize, a: &GameState, b: &GameState| {
+        first_divergence(a, b).map_or(Ok(()), |(component, entity_id)| {
+            Err(Divergence {
+                frame,
+                component,
+                entity_id,
+            })
+        })
+    };
+    check(0, a, b)?;
+    for (index, input) in inputs.iter().enumerate() {
+        a.update(input);
+        b.update(input);
+        check(index + 1, a, b)?;
+    }
+    Ok(())
+}
+
+/// Runs two branches of `source` in lockstep, after letting `configure`
+/// adjust the second one (for example to swap its broadphase).
+pub fn verify_determinism<F>(
+    source: &GameState,
+    configure: F,
+    inputs: &[InputModel],
+) -> Result<(), Divergence>
+where
+    F: FnOnce(&mut GameState),
+{
+    let mut a = source.branch();
+    let mut b = source.branch();
+    configure(&mut b);
+    run_lockstep(&mut a, &mut b, inputs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axis_aligned_rect::AxisAlignedRect;
+    use broadphase::BroadphaseKind;
+    use cgmath::vec2;
+    use game::BodyType;
+    use health::Health;
+    use physics_config::PhysicsConfig;
+    use pixel_num::SubPixelI64;
+    use shape::Shape;
+
+    fn world() -> (GameState, EntityId) {
+        let mut game_state = GameState::new(vec2(320., 240.), PhysicsConfig::default());
+        let size = SubPixelI64::new_pixels(8);
+        let block = Shape::AxisAlignedRect(AxisAlignedRect::new(vec2(size, size)));
+        game_state
+            .spawn()
+            .position_pixels(vec2(0., 200.))
+            .shape(Shape::AxisAlignedRect(AxisAlignedRect::new(vec2(
+                SubPixelI64::new_pixels(320),
+                size,
+            ))))
+            .body_type(BodyType::Static)
+            .build()
+            .unwrap();
+        let id = game_state
+            .spawn()
+            .position_pixels(vec2(100., 100.))
+            .shape(block)
+            .velocity(vec2(SubPixelI64::new(40), SubPixelI64::new(0)))
+            .build()
+            .unwrap();
+        (game_state, id)
+    }
+
+    #[test]
+    fn non_positional_divergence_is_found() {
+        let (a, id) = world();
+        let mut b = a.branch();
+        assert_eq!(first_divergence(&a, &b), None);
+        assert_eq!(a.state_hash(), b.state_hash());
+        b.set_mass(id, Some(5)).unwrap();
+        assert_eq!(
+            first_divergence(&a, &b),
+            Some((DivergentComponent::Component("masses"), Some(id)))
+        );
+        assert_ne!(a.state_hash(), b.state_hash());
+        let mut c = a.branch();
+        c.set_health(id, Some(Health::new(3, 0)));
+        assert_ne!(a.state_hash(), c.state_hash());
+        let inputs = (0..3).map(|_| InputModel::default()).collect::<Vec<_>>();
+        let divergence = run_lockstep(&mut a.branch(), &mut c, &inputs).unwrap_err();
+        assert_eq!(divergence.frame, 0);
+        assert_eq!(divergence.component, DivergentComponent::Component("healths"));
+    }
+
+    #[test]
+    fn divergence_outside_entity_tables_is_found() {
+        let (a, id) = world();
+        let mut b = a.branch();
+        b.signal(id, "jump");
+        assert_eq!(
+            first_divergence(&a, &b),
+            Some((DivergentComponent::Simulation("signals"), None))
+        );
+        assert_ne!(a.state_hash(), b.state_hash());
+        let mut c = a.branch();
+        let origin = vec2(SubPixelI64::new(0), SubPixelI64::new(0));
+        c.particles_mut().spawn(origin, origin, 10, [1.; 4]);
+        assert_eq!(
+            first_divergence(&a, &c),
+            Some((DivergentComponent::Simulation("particles"), None))
+        );
+        assert_ne!(a.state_hash(), c.state_hash());
+    }
+
+    #[test]
+    fn branches_stay_in_lockstep() {
+        let (source, _) = world();
+        let inputs = (0..30).map(|_| InputModel::default()).collect::<Vec<_>>();
+        let swap_broadphase = |b: &mut GameState| {
+            let config = *b.config();
+            b.set_config(PhysicsConfig {
+                broadphase: BroadphaseKind::SweepAndPrune,
+                ..config
+            })
+        };
+        assert_eq!(verify_determinism(&source, swap_broadphase, &inputs), Ok(()));
+        let heavier_gravity = |b: &mut GameState| {
+            let config = *b.config();
+            b.set_config(PhysicsConfig {
+                gravity: vec2(SubPixelI64::new(0), SubPixelI64::new(64)),
+                ..config
+            })
+        };
+        let divergence = verify_determinism(&source, heavier_gravity, &inputs);
+        assert_eq!(divergence.unwrap_err().frame, 1);
+    }
+}
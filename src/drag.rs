@@ -0,0 +1,76 @@
+use cgmath::{Vector2, vec2};
+use physics_num;
+use pixel_num::sub_pixel_i64::{self, SubPixelI64};
+
+pub const DRAG_SCALE: i64 = 1 << 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Drag {
+    pub linear: i64,
+    pub quadratic: i64,
+}
+
+impl Drag {
+    pub fn new(linear: i64, quadratic: i64) -> Self {
+        assert!(
+            (0..=DRAG_SCALE).contains(&linear),
+            "linear drag must be between zero and DRAG_SCALE"
+        );
+        assert!(quadratic >= 0, "quadratic drag must not be negative");
+        Self { linear, quadratic }
+    }
+    pub fn linear(linear: i64) -> Self {
+        Self::new(linear, 0)
+    }
+    pub fn quadratic(quadratic: i64) -> Self {
+        Self::new(0, quadratic)
+    }
+    pub fn apply(&self, velocity: Vector2<SubPixelI64>) -> Vector2<SubPixelI64> {
+        let speed = physics_num::isqrt_wide(physics_num::magnitude2_wide(velocity));
+        if speed == 0 {
+            return velocity;
+        }
+        let scale = DRAG_SCALE as i128;
+        let loss = speed * self.linear as i128 / scale
+            + speed * speed * self.quadratic as i128
+                / (scale * sub_pixel_i64::SUB_PIXELS_PER_PIXEL as i128);
+        let loss = loss.min(speed);
+        velocity
+            - vec2(
+                physics_num::saturating_narrow(physics_num::widen(velocity.x) * loss / speed),
+                physics_num::saturating_narrow(physics_num::widen(velocity.y) * loss / speed),
+            )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(x: i64, y: i64) -> Vector2<SubPixelI64> {
+        vec2(SubPixelI64::new(x), SubPixelI64::new(y))
+    }
+
+    #[test]
+    fn linear_drag_removes_a_fraction_of_velocity() {
+        assert_eq!(Drag::linear(DRAG_SCALE / 2).apply(v(300, -400)), v(150, -200));
+        assert_eq!(Drag::linear(DRAG_SCALE).apply(v(300, -400)), v(0, 0));
+        assert_eq!(Drag::default().apply(v(300, -400)), v(300, -400));
+        assert_eq!(Drag::new(DRAG_SCALE, 1).apply(v(0, 0)), v(0, 0));
+    }
+
+    #[test]
+    fn quadratic_drag_reaches_terminal_velocity() {
+        let drag = Drag::quadratic(DRAG_SCALE / 16);
+        assert_eq!(drag.apply(v(0, 256)), v(0, 240));
+        assert_eq!(drag.apply(v(0, 512)), v(0, 448));
+        let gravity = v(0, 24);
+        let mut velocity = v(0, 0);
+        for _ in 0..200 {
+            velocity = drag.apply(velocity + gravity);
+        }
+        let terminal = velocity;
+        assert_eq!(drag.apply(terminal + gravity), terminal);
+        assert!(terminal.y > SubPixelI64::new(0) && terminal.y < SubPixelI64::new(320));
+    }
+}
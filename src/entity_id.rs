@@ -0,0 +1 @@
+pub type EntityId = u32;
@@ -0,0 +1,27 @@
+use entity_id::EntityId;
+use validation::ValidationError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComponentKind {
+    Shape,
+    Velocity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhysicsError {
+    MissingEntity(EntityId),
+    MissingComponent(EntityId, ComponentKind),
+    MissingShape,
+    MissingPrefab,
+    WorldFull,
+    Overflow,
+    InputLength(usize),
+    InvalidTimeScale,
+    InvalidMass,
+    InvalidSkin,
+    InvalidScale,
+    ImmovableShape,
+    Validation(ValidationError),
+}
+
+pub type PhysicsResult<T> = Result<T, PhysicsError>;
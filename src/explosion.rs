@@ -0,0 +1,66 @@
+use cgmath::{Vector2, vec2};
+use game::EntityId;
+use physics_num;
+use pixel_num::sub_pixel_i64::SubPixelI64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Falloff {
+    Constant,
+    Linear,
+    Quadratic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExplosionEvent {
+    pub entity_id: EntityId,
+    pub impulse: Vector2<SubPixelI64>,
+}
+
+pub fn impulse(
+    offset: Vector2<SubPixelI64>,
+    radius: SubPixelI64,
+    strength: SubPixelI64,
+    falloff: Falloff,
+) -> Option<Vector2<SubPixelI64>> {
+    let radius = physics_num::widen(radius);
+    let distance = physics_num::isqrt_wide(physics_num::magnitude2_wide(offset));
+    if radius <= 0 || distance > radius {
+        return None;
+    }
+    let strength = physics_num::widen(strength);
+    let remaining = radius - distance;
+    let magnitude = match falloff {
+        Falloff::Constant => strength,
+        Falloff::Linear => strength * remaining / radius,
+        Falloff::Quadratic => strength * remaining * remaining / (radius * radius),
+    };
+    if distance == 0 || magnitude == 0 {
+        return Some(vec2(SubPixelI64::new(0), SubPixelI64::new(0)));
+    }
+    Some(vec2(
+        physics_num::saturating_narrow(physics_num::widen(offset.x) * magnitude / distance),
+        physics_num::saturating_narrow(physics_num::widen(offset.y) * magnitude / distance),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(x: i64, y: i64) -> Vector2<SubPixelI64> {
+        vec2(SubPixelI64::new(x), SubPixelI64::new(y))
+    }
+
+    #[test]
+    fn impulse_falls_off_with_distance() {
+        let radius = SubPixelI64::new(100);
+        let strength = SubPixelI64::new(400);
+        let at = |x, y, falloff| impulse(v(x, y), radius, strength, falloff);
+        assert_eq!(at(30, 40, Falloff::Constant), Some(v(240, 320)));
+        assert_eq!(at(30, 40, Falloff::Linear), Some(v(120, 160)));
+        assert_eq!(at(0, -50, Falloff::Quadratic), Some(v(0, -100)));
+        assert_eq!(at(60, 80, Falloff::Linear), Some(v(0, 0)));
+        assert_eq!(at(70, 80, Falloff::Constant), None);
+        assert_eq!(at(0, 0, Falloff::Constant), Some(v(0, 0)));
+    }
+}
@@ -0,0 +1,527 @@
+//! C ABI over `flat::Simulation`.
+//!
+//! Every `unsafe` entry point expects handles returned by `physics_world_new`
+//! (or null), and output pointers that are either null or valid for writes of
+//! `capacity` elements.
+#![allow(clippy::missing_safety_doc)]
+
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+use axis_aligned_rect::AxisAlignedRect;
+use cgmath::{Vector2, vec2};
+use flat::{Simulation, INPUT_LEN};
+use game::{BodyType, EntityId};
+use pixel_num::sub_pixel_i64::{self, SubPixelI64};
+use shape::Shape;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhysicsRenderRect {
+    pub entity_id: u32,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub colour: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhysicsBody {
+    pub x: i64,
+    pub y: i64,
+    pub width: i64,
+    pub height: i64,
+    pub velocity_x: i64,
+    pub velocity_y: i64,
+    pub dynamic: bool,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhysicsRaycastHit {
+    pub entity_id: u32,
+    pub x: i64,
+    pub y: i64,
+    pub surface_start_x: i64,
+    pub surface_start_y: i64,
+    pub surface_end_x: i64,
+    pub surface_end_y: i64,
+}
+
+fn guard<T, F: FnOnce() -> T>(fallback: T, f: F) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(fallback)
+}
+
+unsafe fn write_vector(
+    vector: Option<Vector2<SubPixelI64>>,
+    x: *mut i64,
+    y: *mut i64,
+) -> bool {
+    let vector = match vector {
+        Some(vector) => vector,
+        None => return false,
+    };
+    if !x.is_null() {
+        *x = vector.x.raw();
+    }
+    if !y.is_null() {
+        *y = vector.y.raw();
+    }
+    true
+}
+
+fn rect(
+    x: i64,
+    y: i64,
+    width: i64,
+    height: i64,
+) -> Option<(Vector2<SubPixelI64>, Shape<SubPixelI64>)> {
+    if width <= 0 || height <= 0 {
+        return None;
+    }
+    let dimensions = vec2(SubPixelI64::new(width), SubPixelI64::new(height));
+    let shape = Shape::AxisAlignedRect(AxisAlignedRect::new(dimensions));
+    Some((vec2(SubPixelI64::new(x), SubPixelI64::new(y)), shape))
+}
+
+unsafe fn out_slice<'a, T>(out: *mut T, capacity: usize) -> &'a mut [T] {
+    if out.is_null() {
+        &mut []
+    } else {
+        slice::from_raw_parts_mut(out, capacity)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn physics_buffer_new(len: usize) -> *mut f32 {
+    guard(ptr::null_mut(), || {
+        let mut buffer = vec![0.; len].into_boxed_slice();
+        let pointer = buffer.as_mut_ptr();
+        mem::forget(buffer);
+        pointer
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn physics_buffer_free(buffer: *mut f32, len: usize) {
+    if !buffer.is_null() {
+        let buffer = ptr::slice_from_raw_parts_mut(buffer, len);
+        guard((), || drop(Box::from_raw(buffer)));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn physics_world_new(width: f32, height: f32) -> *mut Simulation {
+    guard(ptr::null_mut(), || {
+        Box::into_raw(Box::new(Simulation::new(width, height)))
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn physics_world_new_demo(width: f32, height: f32) -> *mut Simulation {
+    guard(ptr::null_mut(), || {
+        match Simulation::demo(width, height) {
+            Ok(simulation) => Box::into_raw(Box::new(simulation)),
+            Err(_) => ptr::null_mut(),
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn physics_world_free(world: *mut Simulation) {
+    if !world.is_null() {
+        guard((), || drop(Box::from_raw(world)));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn physics_world_step(
+    world: *mut Simulation,
+    input: *const f32,
+) -> bool {
+    let world = match world.as_mut() {
+        Some(world) => world,
+        None => return false,
+    };
+    let input = if input.is_null() {
+        &[0.; INPUT_LEN]
+    } else {
+        slice::from_raw_parts(input, INPUT_LEN)
+    };
+    guard(false, || world.step(input).is_ok())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn physics_world_spawn(
+    world: *mut Simulation,
+    body: *const PhysicsBody,
+) -> u32 {
+    let (world, body) = match (world.as_mut(), body.as_ref()) {
+        (Some(world), Some(body)) => (world, body),
+        _ => return EntityId::MAX,
+    };
+    let (position, shape) = match rect(body.x, body.y, body.width, body.height) {
+        Some(rect) => rect,
+        None => return EntityId::MAX,
+    };
+    guard(EntityId::MAX, || {
+        let builder = world
+            .game_state_mut()
+            .spawn()
+            .position(position)
+            .shape(shape);
+        let built = if body.dynamic {
+            builder
+                .body_type(BodyType::Dynamic)
+                .velocity(vec2(
+                    SubPixelI64::new(body.velocity_x),
+                    SubPixelI64::new(body.velocity_y),
+                ))
+                .build()
+        } else {
+            builder.body_type(BodyType::Static).build()
+        };
+        built.unwrap_or(EntityId::MAX)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn physics_world_remove(world: *mut Simulation, id: u32) -> bool {
+    world.as_mut().is_some_and(|world| {
+        guard(false, || world.game_state_mut().try_remove_entity(id).is_ok())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn physics_world_position(
+    world: *const Simulation,
+    id: u32,
+    x: *mut i64,
+    y: *mut i64,
+) -> bool {
+    let position = world
+        .as_ref()
+        .and_then(|world| guard(None, || world.game_state().position(id)));
+    write_vector(position, x, y)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn physics_world_velocity(
+    world: *const Simulation,
+    id: u32,
+    x: *mut i64,
+    y: *mut i64,
+) -> bool {
+    let velocity = world
+        .as_ref()
+        .and_then(|world| guard(None, || world.game_state().velocity(id)));
+    write_vector(velocity, x, y)
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn physics_world_set_velocity(
+    world: *mut Simulation,
+    id: u32,
+    x: i64,
+    y: i64,
+) -> bool {
+    let velocity = vec2(SubPixelI64::new(x), SubPixelI64::new(y));
+    world.as_mut().is_some_and(|world| {
+        guard(false, || {
+            world.game_state_mut().try_set_velocity(id, velocity).is_ok()
+        })
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn physics_world_raycast(
+    world: *const Simulation,
+    start_x: i64,
+    start_y: i64,
+    ray_x: i64,
+    ray_y: i64,
+    mask: u32,
+    hit: *mut PhysicsRaycastHit,
+) -> bool {
+    let start = vec2(SubPixelI64::new(start_x), SubPixelI64::new(start_y));
+    let ray = vec2(SubPixelI64::new(ray_x), SubPixelI64::new(ray_y));
+    let found = world
+        .as_ref()
+        .and_then(|world| guard(None, || world.game_state().raycast(start, ray, mask)));
+    let found = match found {
+        Some(found) => found,
+        None => return false,
+    };
+    if !hit.is_null() {
+        *hit = PhysicsRaycastHit {
+            entity_id: found.entity_id,
+            x: found.point.x.raw(),
+            y: found.point.y.raw(),
+            surface_start_x: found.surface.start.x.raw(),
+            surface_start_y: found.surface.start.y.raw(),
+            surface_end_x: found.surface.end.x.raw(),
+            surface_end_y: found.surface.end.y.raw(),
+        };
+    }
+    true
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn physics_world_overlap_shape(
+    world: *const Simulation,
+    x: i64,
+    y: i64,
+    width: i64,
+    height: i64,
+    mask: u32,
+    out: *mut u32,
+    capacity: usize,
+) -> usize {
+    let (world, (position, shape)) = match (world.as_ref(), rect(x, y, width, height)) {
+        (Some(world), Some(rect)) => (world, rect),
+        _ => return 0,
+    };
+    guard(0, || {
+        let mut ids = Vec::new();
+        world.game_state().overlap_shape(&shape, position, mask, &mut ids);
+        let out = out_slice(out, capacity);
+        let count = ids.len().min(out.len());
+        out[..count].copy_from_slice(&ids[..count]);
+        ids.len()
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn physics_world_state_hash(world: *const Simulation) -> u64 {
+    world
+        .as_ref()
+        .map_or(0, |world| guard(0, || world.state_hash()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn physics_world_render_rects(
+    world: *const Simulation,
+    out: *mut PhysicsRenderRect,
+    capacity: usize,
+) -> usize {
+    let world = match world.as_ref() {
+        Some(world) => world,
+        None => return 0,
+    };
+    guard(0, || {
+        let mut count = 0;
+        for update in world.game_state().render_updates() {
+            if count < capacity && !out.is_null() {
+                let aabb = update.shape.aabb(update.position);
+                let top_left = sub_pixel_i64::vector_to_f32_pixel(aabb.top_left());
+                let size = sub_pixel_i64::vector_to_f32_pixel(aabb.size());
+                ptr::write(
+                    out.add(count),
+                    PhysicsRenderRect {
+                        entity_id: update.entity_id,
+                        x: top_left.x,
+                        y: top_left.y,
+                        width: size.x,
+                        height: size.y,
+                        colour: update.colour,
+                    },
+                );
+            }
+            count += 1;
+        }
+        count
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn physics_world_entity_ids(
+    world: *const Simulation,
+    out: *mut u32,
+    capacity: usize,
+) -> usize {
+    world.as_ref().map_or(0, |world| {
+        guard(0, || world.write_entity_ids(out_slice(out, capacity)))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn physics_world_positions(
+    world: *const Simulation,
+    out: *mut f32,
+    capacity: usize,
+) -> usize {
+    world.as_ref().map_or(0, |world| {
+        guard(0, || world.write_positions(out_slice(out, capacity)))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn physics_world_rects(
+    world: *const Simulation,
+    out: *mut f32,
+    capacity: usize,
+) -> usize {
+    world.as_ref().map_or(0, |world| {
+        guard(0, || world.write_rects(out_slice(out, capacity)))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn physics_world_colours(
+    world: *const Simulation,
+    out: *mut f32,
+    capacity: usize,
+) -> usize {
+    world.as_ref().map_or(0, |world| {
+        guard(0, || world.write_colours(out_slice(out, capacity)))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use error::PhysicsError;
+
+    fn body(x: i64, y: i64, width: i64, height: i64, velocity_y: i64) -> PhysicsBody {
+        PhysicsBody {
+            x,
+            y,
+            width,
+            height,
+            velocity_x: 0,
+            velocity_y,
+            dynamic: velocity_y != 0,
+        }
+    }
+
+    #[test]
+    fn steps_and_reads_back_through_handle() {
+        unsafe {
+            let world = physics_world_new(320., 240.);
+            let floor = physics_world_spawn(world, &body(0, 4096, 8192, 256, 0));
+            let block = physics_world_spawn(world, &body(0, 0, 512, 512, 256));
+            assert!(floor != EntityId::MAX && block != EntityId::MAX);
+            for _ in 0..30 {
+                assert!(physics_world_step(world, ptr::null()));
+            }
+            let (mut x, mut y) = (0, 0);
+            assert!(physics_world_position(world, block, &mut x, &mut y));
+            assert_eq!(x, 0);
+            assert!(y > 3000 && y <= 4096 - 512);
+            let mut rects = [PhysicsRenderRect {
+                entity_id: 0,
+                x: 0.,
+                y: 0.,
+                width: 0.,
+                height: 0.,
+                colour: [0.; 4],
+            }; 4];
+            assert_eq!(physics_world_render_rects(world, rects.as_mut_ptr(), 4), 2);
+            assert!(physics_world_remove(world, block));
+            assert!(!physics_world_position(world, block, &mut x, &mut y));
+            assert!(!physics_world_remove(world, block));
+            physics_world_free(world);
+        }
+    }
+
+    #[test]
+    fn flat_arrays_fill_caller_buffers() {
+        unsafe {
+            let world = physics_world_new_demo(320., 240.);
+            let count = physics_world_entity_ids(world, ptr::null_mut(), 0);
+            assert!(count > 0);
+            let len = count * 4;
+            let buffer = physics_buffer_new(len);
+            assert_eq!(physics_world_rects(world, buffer, len), len);
+            assert!(*buffer.add(2) > 0. && *buffer.add(3) > 0.);
+            assert_eq!(physics_world_positions(world, buffer, 2), count * 2);
+            assert_eq!(physics_world_colours(world, buffer, len), len);
+            physics_buffer_free(buffer, len);
+            physics_world_free(world);
+        }
+        let mut simulation = Simulation::new(320., 240.);
+        assert_eq!(simulation.step(&[0.; 3]), Err(PhysicsError::InputLength(3)));
+        assert_eq!(simulation.frame(), 0);
+    }
+
+    #[test]
+    fn queries_report_hits_and_overlaps() {
+        unsafe {
+            let world = physics_world_new(320., 240.);
+            let floor = physics_world_spawn(world, &body(0, 4096, 8192, 256, 0));
+            let block = physics_world_spawn(world, &body(1024, 0, 512, 512, 0));
+            let mut hit = PhysicsRaycastHit {
+                entity_id: 0,
+                x: 0,
+                y: 0,
+                surface_start_x: 0,
+                surface_start_y: 0,
+                surface_end_x: 0,
+                surface_end_y: 0,
+            };
+            assert!(physics_world_raycast(world, 256, 0, 0, 8192, !0, &mut hit));
+            assert_eq!((hit.entity_id, hit.x), (floor, 256));
+            assert!(hit.y > 4000 && hit.y <= 4096);
+            assert_eq!((hit.surface_start_y, hit.surface_end_y), (4096, 4096));
+            assert!(!physics_world_raycast(world, 256, 0, 0, 8192, 0, &mut hit));
+            assert!(!physics_world_raycast(world, 256, 0, 0, 1024, !0, &mut hit));
+            let mut ids = [0; 1];
+            let overlapping = physics_world_overlap_shape(
+                world,
+                0,
+                256,
+                2048,
+                4096,
+                !0,
+                ids.as_mut_ptr(),
+                1,
+            );
+            assert_eq!(overlapping, 2);
+            assert!(ids[0] == floor || ids[0] == block);
+            assert_eq!(
+                physics_world_overlap_shape(world, 0, 0, 0, 4096, !0, ptr::null_mut(), 0),
+                0
+            );
+            physics_world_free(world);
+        }
+    }
+
+    #[test]
+    fn spawn_rejects_non_positive_sizes() {
+        unsafe {
+            let world = physics_world_new(320., 240.);
+            let flat = physics_world_spawn(world, &body(0, 0, 0, 512, 0));
+            let inverted = physics_world_spawn(world, &body(0, 0, 512, -1, 256));
+            assert_eq!((flat, inverted), (EntityId::MAX, EntityId::MAX));
+            assert_eq!(physics_world_entity_ids(world, ptr::null_mut(), 0), 0);
+            physics_world_free(world);
+        }
+    }
+
+    #[test]
+    fn null_handles_do_nothing() {
+        unsafe {
+            let world = ptr::null_mut();
+            assert!(!physics_world_step(world, ptr::null()));
+            assert_eq!(physics_world_state_hash(world), 0);
+            assert_eq!(physics_world_spawn(world, ptr::null()), EntityId::MAX);
+            assert!(!physics_world_set_velocity(world, 0, 1, 1));
+            physics_world_free(world);
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn panics_do_not_cross_the_boundary() {
+        unsafe {
+            let world = physics_world_new(320., 240.);
+            let block = physics_world_spawn(world, &body(0, 0, 512, 512, 256));
+            assert!(physics_world_set_velocity(world, block, i64::MAX, 0));
+            assert!(!physics_world_step(world, ptr::null()));
+            physics_world_free(world);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use cgmath::vec2;
+use error::{PhysicsError, PhysicsResult};
+use game::{GameState, InputModel, RenderUpdate};
+
+use pixel_num::sub_pixel_i64;
+
+pub const INPUT_LEN: usize = 4;
+
+pub struct Simulation {
+    game_state: GameState,
+    input_model: InputModel,
+}
+
+impl Simulation {
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            game_state: GameState::new(vec2(width, height), Default::default()),
+            input_model: InputModel::default(),
+        }
+    }
+    pub fn demo(width: f32, height: f32) -> PhysicsResult<Self> {
+        let mut simulation = Self::new(width, height);
+        simulation.game_state.init_demo()?;
+        Ok(simulation)
+    }
+    pub fn game_state(&self) -> &GameState {
+        &self.game_state
+    }
+    pub fn game_state_mut(&mut self) -> &mut GameState {
+        &mut self.game_state
+    }
+    pub fn step(&mut self, input: &[f32]) -> PhysicsResult<()> {
+        if input.len() != INPUT_LEN {
+            return Err(PhysicsError::InputLength(input.len()));
+        }
+        self.input_model.set_left(input[0]);
+        self.input_model.set_right(input[1]);
+        self.input_model.set_up(input[2]);
+        self.input_model.set_down(input[3]);
+        self.game_state.update(&self.input_model);
+        Ok(())
+    }
+    pub fn frame(&self) -> u32 {
+        self.game_state.frame_count() as u32
+    }
+    pub fn state_hash(&self) -> u64 {
+        self.game_state.state_hash()
+    }
+    /// Writes one id per rendered entity into `out`, stopping when it is full,
+    /// and returns the number of values available.
+    pub fn write_entity_ids(&self, out: &mut [u32]) -> usize {
+        self.write_per_entity(out, |update| [update.entity_id])
+    }
+    /// Two floats (x, y) per rendered entity.
+    pub fn write_positions(&self, out: &mut [f32]) -> usize {
+        self.write_per_entity(out, |update| {
+            let position = sub_pixel_i64::vector_to_f32_pixel(update.position);
+            [position.x, position.y]
+        })
+    }
+    /// Four floats (x, y, width, height) per rendered entity.
+    pub fn write_rects(&self, out: &mut [f32]) -> usize {
+        self.write_per_entity(out, |update| {
+            let aabb = update.shape.aabb(update.position);
+            let top_left = sub_pixel_i64::vector_to_f32_pixel(aabb.top_left());
+            let size = sub_pixel_i64::vector_to_f32_pixel(aabb.size());
+            [top_left.x, top_left.y, size.x, size.y]
+        })
+    }
+    /// Four floats (r, g, b, a) per rendered entity.
+    pub fn write_colours(&self, out: &mut [f32]) -> usize {
+        self.write_per_entity(out, |update| update.colour)
+    }
+    fn write_per_entity<T, A, F>(&self, out: &mut [T], mut f: F) -> usize
+    where
+        T: Copy,
+        A: AsRef<[T]>,
+        F: FnMut(&RenderUpdate) -> A,
+    {
+        let mut count = 0;
+        for update in self.game_state.render_updates() {
+            let values = f(&update);
+            let values = values.as_ref();
+            if let Some(out) = out.get_mut(count..) {
+                let len = values.len().min(out.len());
+                out[..len].copy_from_slice(&values[..len]);
+            }
+            count += values.len();
+        }
+        count
+    }
+}
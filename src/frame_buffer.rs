@@ -0,0 +1,197 @@
+use std::mem;
+use std::sync::{Arc, Mutex};
+use cgmath::Vector2;
+use game::{EntityId, RenderUpdate};
+use pixel_num::SubPixelI64;
+use render::{BlendHint, View};
+use shape::Shape;
+use sprite::Sprite;
+
+#[derive(Debug, Clone)]
+pub struct FrameEntry {
+    pub entity_id: EntityId,
+    pub z_index: i32,
+    pub position: Vector2<SubPixelI64>,
+    pub shape: Shape<SubPixelI64>,
+    pub colour: [f32; 4],
+    pub sprite: Option<Sprite>,
+    pub animation_frame: Option<usize>,
+    pub blend: BlendHint,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Frame {
+    frame: u64,
+    view: View,
+    entries: Vec<FrameEntry>,
+}
+
+impl FrameEntry {
+    fn new(update: RenderUpdate) -> Self {
+        Self {
+            entity_id: update.entity_id,
+            z_index: update.z_index,
+            position: update.position,
+            shape: update.shape.clone(),
+            colour: update.colour,
+            sprite: update.sprite.cloned(),
+            animation_frame: update.animation_frame,
+            blend: update.blend,
+        }
+    }
+    /// Overwrites this entry in place, only cloning the shape if it differs, so
+    /// a steady frame reuses the previous frame's chains and heightfields.
+    fn assign(&mut self, update: RenderUpdate) {
+        self.entity_id = update.entity_id;
+        self.z_index = update.z_index;
+        self.position = update.position;
+        if self.shape != *update.shape {
+            self.shape = update.shape.clone();
+        }
+        self.colour = update.colour;
+        self.sprite = update.sprite.cloned();
+        self.animation_frame = update.animation_frame;
+        self.blend = update.blend;
+    }
+}
+
+impl Frame {
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+    pub fn view(&self) -> View {
+        self.view
+    }
+    pub fn entries(&self) -> &[FrameEntry] {
+        &self.entries
+    }
+    pub fn updates(&self) -> impl Iterator<Item = RenderUpdate<'_>> {
+        self.entries.iter().map(|entry| RenderUpdate {
+            entity_id: entry.entity_id,
+            z_index: entry.z_index,
+            position: entry.position,
+            shape: &entry.shape,
+            colour: entry.colour,
+            sprite: entry.sprite.as_ref(),
+            animation_frame: entry.animation_frame,
+            blend: entry.blend,
+        })
+    }
+}
+
+type FrontBuffer = Arc<Mutex<Arc<Frame>>>;
+
+#[derive(Debug, Default)]
+pub struct FramePublisher {
+    back: Arc<Frame>,
+    front: FrontBuffer,
+    len: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct FrameSubscriber {
+    front: FrontBuffer,
+}
+
+impl FramePublisher {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    pub fn subscribe(&self) -> FrameSubscriber {
+        FrameSubscriber {
+            front: self.front.clone(),
+        }
+    }
+    fn back_mut(&mut self) -> &mut Frame {
+        if Arc::get_mut(&mut self.back).is_none() {
+            self.back = Arc::new(Frame::default());
+        }
+        Arc::get_mut(&mut self.back).expect("back buffer is shared")
+    }
+    /// Starts a frame. Entries left in the back buffer from two frames ago are
+    /// overwritten by `push` rather than dropped, so publishing allocates
+    /// nothing once frames stop growing, as long as subscribers don't keep
+    /// hold of old frames.
+    pub fn begin(&mut self, frame: u64, view: View) {
+        let back = self.back_mut();
+        back.frame = frame;
+        back.view = view;
+        self.len = 0;
+    }
+    pub fn push(&mut self, update: RenderUpdate) {
+        let index = self.len;
+        self.len += 1;
+        let entries = &mut self.back_mut().entries;
+        match entries.get_mut(index) {
+            Some(entry) => entry.assign(update),
+            None => entries.push(FrameEntry::new(update)),
+        }
+    }
+    pub fn write<'a, I>(&mut self, frame: u64, view: View, updates: I)
+    where
+        I: IntoIterator<Item = RenderUpdate<'a>>,
+    {
+        self.begin(frame, view);
+        for update in updates {
+            self.push(update);
+        }
+    }
+    pub fn publish(&mut self) {
+        let len = self.len;
+        let entries = &mut self.back_mut().entries;
+        entries.truncate(len);
+        entries.sort_unstable_by_key(|entry| (entry.z_index, entry.entity_id));
+        let mut front = self.front.lock().expect("front buffer poisoned");
+        mem::swap(&mut *front, &mut self.back);
+    }
+}
+
+impl FrameSubscriber {
+    pub fn latest(&self) -> Arc<Frame> {
+        self.front.lock().expect("front buffer poisoned").clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axis_aligned_rect::AxisAlignedRect;
+    use cgmath::vec2;
+    use std::thread;
+
+    fn update(entity_id: EntityId, shape: &Shape<SubPixelI64>) -> RenderUpdate<'_> {
+        RenderUpdate {
+            entity_id,
+            z_index: 0,
+            position: vec2(SubPixelI64::new(entity_id as i64), SubPixelI64::new(0)),
+            shape,
+            colour: [1., 1., 1., 1.],
+            sprite: None,
+            animation_frame: None,
+            blend: BlendHint::Alpha,
+        }
+    }
+
+    #[test]
+    fn subscribers_see_only_published_frames() {
+        let shape = Shape::AxisAlignedRect(AxisAlignedRect::new(vec2(
+            SubPixelI64::new(4),
+            SubPixelI64::new(4),
+        )));
+        let mut publisher = FramePublisher::new();
+        let subscriber = publisher.subscribe();
+        publisher.write(1, View::default(), vec![update(1, &shape), update(2, &shape)]);
+        assert_eq!(subscriber.latest().entries().len(), 0);
+        publisher.publish();
+        let held = subscriber.latest();
+        publisher.write(2, View::default(), vec![update(3, &shape)]);
+        publisher.publish();
+        assert_eq!((held.frame(), held.entries().len()), (1, 2));
+        let ids = thread::spawn(move || {
+            let frame = subscriber.latest();
+            frame.updates().map(|update| update.entity_id).collect::<Vec<_>>()
+        }).join()
+            .unwrap();
+        assert_eq!(ids, vec![3]);
+    }
+}
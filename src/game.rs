@@ -1,12 +1,87 @@
-use fnv::FnvHashMap;
-use cgmath::{InnerSpace, Vector2, vec2};
+use fnv::{FnvHashMap, FnvHashSet, FnvHasher};
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+use aabb::Aabb;
+use chunked_index::{self, ChunkCoord};
+use cgmath::{Vector2, vec2};
 use pixel_num::sub_pixel_i64::{self, SubPixelI64};
-use shape::Shape;
+use shape::{Shape, StartInsideEdgePolicy};
 use axis_aligned_rect::AxisAlignedRect;
-use loose_quad_tree::LooseQuadTree;
+use broadphase::{Broadphase, BroadphaseKind};
+use loose_quad_tree::{NodeInfo, QuadTreeStats};
 use line_segment::LineSegment;
+use collision::TimeOfImpact;
 use best::BestMap;
 use num::Zero;
+use user_data::UserData;
+use resources::{FrameCount, Resources};
+use change_tracker::ChangeTracker;
+use cow_table::{CowSet, CowTable};
+use hooks::{Hook, UpdateStage};
+use commands::{Command, CommandError, Commands};
+use physics_config::{CollisionResponse, MovementMode, NarrowphaseRounding, PhysicsConfig};
+use pool::EntityPool;
+use prefab::{Prefab, PrefabRegistry};
+use rng::Rng;
+use scratch::IdBuffers;
+use sprite::Sprite;
+use animation::{Animation, AnimationState};
+use colour_tween::{self, ColourTween, Flash};
+use render::BlendHint;
+use gizmo::{self, Gizmo};
+use out_of_bounds::{self, OutOfBoundsEvent, OutOfBoundsPolicy};
+use physics_num;
+use pair_cache::PairCache;
+use island::IslandBuilder;
+use impulse::{self, Restitution};
+use stacking;
+use rope::{Rope, RopeBlocked};
+use verlet::VerletSystem;
+use particles::ParticleSystem;
+use explosion::{self, ExplosionEvent, Falloff};
+use attractor::Attractor;
+use gravity::GravityOverride;
+use drag::Drag;
+use material::{ContactEvent, MaterialId, MaterialRegistry};
+use bounce_pad::{BouncePad, LaunchEvent};
+use portal::{PortalPair, PortalSide, TeleportEvent};
+use hazard::{DamageEvent, Hazard, HazardCooldowns};
+use health::{DamageOutcome, DeathEvent, Health};
+use pickup::{Pickup, PickupEvent};
+use lifetime::Lifetime;
+use scheduler::{Scheduler, TimerEvent};
+use state_machine::{StateChangeEvent, StateMachine, Trigger};
+use path_follower::PathFollower;
+use homing::{self, Homing, TargetLostEvent};
+use trig::Angle;
+use camera::Camera;
+use error::{ComponentKind, PhysicsError, PhysicsResult};
+use frame_buffer::{FramePublisher, FrameSubscriber};
+use systems::{Plugin, Schedule, System};
+use std::mem;
+use stats::UpdateStats;
+#[cfg(feature = "alloc-audit")]
+use alloc_audit;
+use state_delta::{ComponentDeltas, SimulationDelta, Spawn, StateDelta};
+use static_bake::{self, StaticGeometry};
+use validation::ValidationError;
+use walkability::WalkabilityGrid;
+
+#[cfg(feature = "tracing")]
+macro_rules! profile {
+    ($($span:tt)*) => {
+        let _span = ::tracing::trace_span!($($span)*).entered();
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! profile {
+    ($($span:tt)*) => {};
+}
 
 #[derive(Default, Debug)]
 pub struct InputModel {
@@ -50,50 +125,571 @@ impl InputModel {
 fn update_player_velocity(
     _current_velocity: Vector2<SubPixelI64>,
     input_model: &InputModel,
+    config: &PhysicsConfig,
 ) -> Vector2<SubPixelI64> {
-    const MULTIPLIER: i64 = 4;
-    input_model.movement() * SubPixelI64::new(MULTIPLIER)
+    input_model.movement() * SubPixelI64::new(config.player_velocity_multiplier)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeScale {
+    numerator: i64,
+    denominator: i64,
+}
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self {
+            numerator: 1,
+            denominator: 1,
+        }
+    }
+}
+
+impl TimeScale {
+    /// Both halves must be positive. Hitstop is expressed with the paused flag
+    /// rather than a zero scale.
+    pub fn new(numerator: i64, denominator: i64) -> PhysicsResult<Self> {
+        if denominator <= 0 || numerator <= 0 {
+            return Err(PhysicsError::InvalidTimeScale);
+        }
+        Ok(Self {
+            numerator,
+            denominator,
+        })
+    }
+    pub fn numerator(&self) -> i64 {
+        self.numerator
+    }
+    pub fn denominator(&self) -> i64 {
+        self.denominator
+    }
+    fn is_identity(&self) -> bool {
+        self.numerator == self.denominator
+    }
+    /// Scales in i128 and saturates, carrying the remainder towards the next call.
+    fn scale(&self, value: i128, remainder: i128) -> (i128, i128) {
+        let total = value * i128::from(self.numerator) + remainder;
+        let denominator = i128::from(self.denominator);
+        (total / denominator, total % denominator)
+    }
+    fn scale_vector(
+        &self,
+        v: Vector2<SubPixelI64>,
+        remainder: &mut Vector2<SubPixelI64>,
+    ) -> Vector2<SubPixelI64> {
+        let scale_axis = |v: SubPixelI64, remainder: &mut SubPixelI64| {
+            let (scaled, left) =
+                self.scale(physics_num::widen(v), physics_num::widen(*remainder));
+            *remainder = SubPixelI64::new(left as i64);
+            physics_num::saturating_narrow(scaled)
+        };
+        vec2(scale_axis(v.x, &mut remainder.x), scale_axis(v.y, &mut remainder.y))
+    }
+    fn ticks(&self, remainder: &mut i64) -> u32 {
+        let (ticks, left) = self.scale(1, i128::from(*remainder));
+        *remainder = left as i64;
+        u32::try_from(ticks).unwrap_or(u32::MAX)
+    }
+}
+
+/// What time scaling has carried over for an entity, below the size of a
+/// sub-pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScaleRemainder {
+    velocity: Vector2<SubPixelI64>,
+    movement: Vector2<SubPixelI64>,
+}
+
+impl Default for ScaleRemainder {
+    fn default() -> Self {
+        let zero = vec2(Zero::zero(), Zero::zero());
+        Self {
+            velocity: zero,
+            movement: zero,
+        }
+    }
 }
 
-pub type EntityId = u32;
+pub use entity_id::EntityId;
 
-#[derive(Default)]
+pub const ALL_COLLISION_LAYERS: u32 = !0;
+const WORLD_BOUNDS_THICKNESS_PIXELS: i64 = 8;
+const WORLD_BOUNDS_COLOUR: [f32; 4] = [0.5, 0.5, 0.5, 1.];
+
+#[derive(Clone, Default, PartialEq, Hash)]
 struct EntityIdAllocator {
     next: u32,
 }
 
 impl EntityIdAllocator {
-    fn allocate(&mut self) -> EntityId {
+    fn allocate(&mut self) -> PhysicsResult<EntityId> {
         let id = self.next;
-        self.next += 1;
-        id
+        self.next = id.checked_add(1).ok_or(PhysicsError::WorldFull)?;
+        Ok(id)
     }
     fn reset(&mut self) {
         self.next = 0;
     }
+    fn reserve(&mut self, id: EntityId) {
+        self.next = self.next.max(id.saturating_add(1));
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 struct SpatialInfo {
     entity_id: EntityId,
 }
 
-type SpatialLooseQuadTree = LooseQuadTree<SpatialInfo, SubPixelI64>;
+type SpatialBroadphase = Broadphase<SpatialInfo, SubPixelI64>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Contact {
+    pub other_id: EntityId,
+    pub surface: LineSegment<SubPixelI64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RaycastHit {
+    pub entity_id: EntityId,
+    pub point: Vector2<SubPixelI64>,
+    pub surface: LineSegment<SubPixelI64>,
+    pub material: Option<MaterialId>,
+}
+
+pub type ShapeQueryItem<'a> =
+    (EntityId, &'a Vector2<SubPixelI64>, &'a Shape<SubPixelI64>);
+pub type VectorQueryItem<'a> =
+    (EntityId, &'a mut Vector2<SubPixelI64>, &'a Vector2<SubPixelI64>);
 
+#[derive(Clone, Copy)]
 pub struct RenderUpdate<'a> {
+    pub entity_id: EntityId,
+    pub z_index: i32,
     pub position: Vector2<SubPixelI64>,
     pub shape: &'a Shape<SubPixelI64>,
-    pub colour: [f32; 3],
+    pub colour: [f32; 4],
+    pub sprite: Option<&'a Sprite>,
+    pub animation_frame: Option<usize>,
+    pub blend: BlendHint,
 }
 
-pub struct GameState {
+/// Z index, position in `for_each_render_update` order, entity and position.
+type RenderKey = (i32, usize, EntityId, Vector2<SubPixelI64>);
+
+struct RenderUpdates<'a> {
+    world: &'a World,
+    order: Vec<RenderKey>,
+    next: usize,
+}
+
+impl<'a> Iterator for RenderUpdates<'a> {
+    type Item = RenderUpdate<'a>;
+    fn next(&mut self) -> Option<RenderUpdate<'a>> {
+        let &(_, _, entity_id, position) = self.order.get(self.next)?;
+        self.next += 1;
+        self.world.render_update(entity_id, position)
+    }
+}
+
+impl<'a> Drop for RenderUpdates<'a> {
+    fn drop(&mut self) {
+        self.world.render_order.set(mem::take(&mut self.order));
+    }
+}
+
+/// Component tables, the spatial index and everything else the systems read
+/// and write. `GameState` owns one and runs its schedule against it.
+pub struct World {
     player_id: Option<EntityId>,
     entity_id_allocator: EntityIdAllocator,
-    position: FnvHashMap<EntityId, Vector2<SubPixelI64>>,
-    shape: FnvHashMap<EntityId, Shape<SubPixelI64>>,
-    colour: FnvHashMap<EntityId, [f32; 3]>,
-    velocity: FnvHashMap<EntityId, Vector2<SubPixelI64>>,
-    quad_tree: SpatialLooseQuadTree,
+    position: CowTable<Vector2<SubPixelI64>>,
+    shape: CowTable<Shape<SubPixelI64>>,
+    colour: CowTable<[f32; 4]>,
+    z_index: CowTable<i32>,
+    sprite: CowTable<Sprite>,
+    animation: CowTable<AnimationState>,
+    colour_tween: CowTable<ColourTween>,
+    flash: CowTable<Flash>,
+    hidden: CowSet,
+    pushable: CowSet,
+    blend: CowTable<BlendHint>,
+    shape_dirty: CowSet,
+    shape_changed: CowSet,
+    changed_shapes: Vec<EntityId>,
+    position_changes: ChangeTracker<Vector2<SubPixelI64>>,
+    colour_changes: ChangeTracker<[f32; 4]>,
+    rendered: CowTable<(Vector2<SubPixelI64>, [f32; 4])>,
+    render_changed: CowSet,
+    render_removals: Vec<EntityId>,
+    unreported_removals: Vec<EntityId>,
+    render_order: Cell<Vec<RenderKey>>,
+    gizmos_enabled: bool,
+    contact_gizmos: Vec<Gizmo>,
+    velocity: CowTable<Vector2<SubPixelI64>>,
+    mass: CowTable<i64>,
+    restitution: CowTable<Restitution>,
+    rope: CowTable<Rope>,
+    attractor: CowTable<Attractor>,
+    force: CowTable<Vector2<SubPixelI64>>,
+    gravity: CowTable<GravityOverride>,
+    drag: CowTable<Drag>,
+    materials: MaterialRegistry,
+    material: CowTable<MaterialId>,
+    contact_events: Vec<ContactEvent>,
+    impact_events: Vec<ContactEvent>,
+    touching: FnvHashSet<(EntityId, EntityId)>,
+    previously_touching: FnvHashSet<(EntityId, EntityId)>,
+    bounce_pad: CowTable<BouncePad>,
+    launch_events: Vec<LaunchEvent>,
+    portals: Vec<PortalPair>,
+    portal_suppressed: CowSet,
+    teleport_events: Vec<TeleportEvent>,
+    hazard: CowTable<Hazard>,
+    hazard_cooldowns: HazardCooldowns,
+    damage_events: Vec<DamageEvent>,
+    health: CowTable<Health>,
+    death_events: Vec<DeathEvent>,
+    sensor: CowSet,
+    pickup: CowTable<Pickup>,
+    collector: CowSet,
+    pickup_events: Vec<PickupEvent>,
+    lifetime: CowTable<Lifetime>,
+    scheduler: Scheduler,
+    timer_events: Vec<TimerEvent>,
+    timer_targets: Vec<(EntityId, Rc<str>)>,
+    state_machine: CowTable<StateMachine>,
+    signals: Vec<(EntityId, String)>,
+    state_change_events: Vec<StateChangeEvent>,
+    path_follower: CowTable<PathFollower>,
+    homing: CowTable<Homing>,
+    target_lost_events: Vec<TargetLostEvent>,
+    frame_publisher: Option<FramePublisher>,
+    verlet: Vec<VerletSystem>,
+    particles: ParticleSystem,
+    movement_remainder: CowTable<ScaleRemainder>,
+    time_remainder: i64,
+    time_ticks: u32,
+    start_inside_edge_policy: CowTable<StartInsideEdgePolicy>,
+    skin: CowTable<SubPixelI64>,
+    movement_mode: CowTable<MovementMode>,
+    collision_layers: CowTable<u32>,
+    out_of_bounds_policy: CowTable<OutOfBoundsPolicy>,
+    out_of_bounds: CowSet,
+    out_of_bounds_events: Vec<OutOfBoundsEvent>,
+    explosion_events: Vec<ExplosionEvent>,
+    kill_zone: Option<Aabb<SubPixelI64>>,
+    world_bounds: Aabb<SubPixelI64>,
+    active_chunks: Option<FnvHashSet<ChunkCoord>>,
+    broadphase: SpatialBroadphase,
+    baked: Option<Rc<StaticGeometry<SubPixelI64>>>,
+    pair_cache: PairCache,
+    low_velocity_streak: CowTable<u32>,
+    resting: CowSet,
+    asleep: CowSet,
+    touched: Vec<Contact>,
+    id_buffers: IdBuffers,
+    command_buffer: Vec<Command>,
+    command_errors: Vec<CommandError>,
+    #[cfg(feature = "validation")]
+    validation_error: Option<PhysicsError>,
+    island_builder: IslandBuilder<SubPixelI64>,
+    islands: Vec<Vec<EntityId>>,
+    user_data: UserData,
+    resources: Resources,
+    prefabs: PrefabRegistry,
+    pool: EntityPool,
+    commands: Commands,
+    paused: bool,
+    stats: UpdateStats,
+}
+
+/// Copies the whole state. Scratch buffers start empty, frame publishing is not
+/// carried over, and only resources inserted as persistent are kept. Entity
+/// tables, user data included, share unchanged chunks until either side writes
+/// to them.
+impl Clone for World {
+    fn clone(&self) -> Self {
+        World {
+            player_id: self.player_id,
+            entity_id_allocator: self.entity_id_allocator.clone(),
+            position: self.position.clone(),
+            shape: self.shape.clone(),
+            colour: self.colour.clone(),
+            z_index: self.z_index.clone(),
+            sprite: self.sprite.clone(),
+            animation: self.animation.clone(),
+            colour_tween: self.colour_tween.clone(),
+            flash: self.flash.clone(),
+            hidden: self.hidden.clone(),
+            pushable: self.pushable.clone(),
+            blend: self.blend.clone(),
+            shape_dirty: self.shape_dirty.clone(),
+            shape_changed: self.shape_changed.clone(),
+            changed_shapes: self.changed_shapes.clone(),
+            position_changes: self.position_changes.clone(),
+            colour_changes: self.colour_changes.clone(),
+            rendered: self.rendered.clone(),
+            render_changed: self.render_changed.clone(),
+            render_removals: self.render_removals.clone(),
+            unreported_removals: self.unreported_removals.clone(),
+            render_order: Cell::default(),
+            gizmos_enabled: self.gizmos_enabled,
+            contact_gizmos: self.contact_gizmos.clone(),
+            velocity: self.velocity.clone(),
+            mass: self.mass.clone(),
+            restitution: self.restitution.clone(),
+            rope: self.rope.clone(),
+            attractor: self.attractor.clone(),
+            force: self.force.clone(),
+            gravity: self.gravity.clone(),
+            drag: self.drag.clone(),
+            materials: self.materials.clone(),
+            material: self.material.clone(),
+            contact_events: self.contact_events.clone(),
+            impact_events: self.impact_events.clone(),
+            touching: self.touching.clone(),
+            previously_touching: self.previously_touching.clone(),
+            bounce_pad: self.bounce_pad.clone(),
+            launch_events: self.launch_events.clone(),
+            portals: self.portals.clone(),
+            portal_suppressed: self.portal_suppressed.clone(),
+            teleport_events: self.teleport_events.clone(),
+            hazard: self.hazard.clone(),
+            hazard_cooldowns: self.hazard_cooldowns.clone(),
+            damage_events: self.damage_events.clone(),
+            health: self.health.clone(),
+            death_events: self.death_events.clone(),
+            sensor: self.sensor.clone(),
+            pickup: self.pickup.clone(),
+            collector: self.collector.clone(),
+            pickup_events: self.pickup_events.clone(),
+            lifetime: self.lifetime.clone(),
+            scheduler: self.scheduler.clone(),
+            timer_events: self.timer_events.clone(),
+            timer_targets: Vec::new(),
+            state_machine: self.state_machine.clone(),
+            signals: self.signals.clone(),
+            state_change_events: self.state_change_events.clone(),
+            path_follower: self.path_follower.clone(),
+            homing: self.homing.clone(),
+            target_lost_events: self.target_lost_events.clone(),
+            frame_publisher: None,
+            verlet: self.verlet.clone(),
+            particles: self.particles.clone(),
+            movement_remainder: self.movement_remainder.clone(),
+            time_remainder: self.time_remainder,
+            time_ticks: self.time_ticks,
+            start_inside_edge_policy: self.start_inside_edge_policy.clone(),
+            skin: self.skin.clone(),
+            movement_mode: self.movement_mode.clone(),
+            collision_layers: self.collision_layers.clone(),
+            out_of_bounds_policy: self.out_of_bounds_policy.clone(),
+            out_of_bounds: self.out_of_bounds.clone(),
+            out_of_bounds_events: self.out_of_bounds_events.clone(),
+            explosion_events: self.explosion_events.clone(),
+            kill_zone: self.kill_zone,
+            world_bounds: self.world_bounds,
+            active_chunks: self.active_chunks.clone(),
+            broadphase: self.broadphase.clone(),
+            baked: self.baked.clone(),
+            pair_cache: self.pair_cache.clone(),
+            low_velocity_streak: self.low_velocity_streak.clone(),
+            resting: self.resting.clone(),
+            asleep: self.asleep.clone(),
+            touched: self.touched.clone(),
+            id_buffers: Default::default(),
+            command_buffer: self.command_buffer.clone(),
+            command_errors: self.command_errors.clone(),
+            #[cfg(feature = "validation")]
+            validation_error: self.validation_error,
+            island_builder: Default::default(),
+            islands: self.islands.clone(),
+            user_data: self.user_data.clone(),
+            resources: self.resources.clone(),
+            prefabs: self.prefabs.clone(),
+            pool: self.pool.clone(),
+            commands: self.commands.clone(),
+            paused: self.paused,
+            stats: self.stats,
+        }
+    }
+}
+
+pub struct WorldView<'a> {
+    player_id: Option<EntityId>,
+    position: &'a mut CowTable<Vector2<SubPixelI64>>,
+    shape: &'a CowTable<Shape<SubPixelI64>>,
+    velocity: &'a mut CowTable<Vector2<SubPixelI64>>,
+    user_data: &'a mut UserData,
+    resources: &'a mut Resources,
+    commands: &'a mut Commands,
+}
+
+impl<'a> WorldView<'a> {
+    pub fn player_id(&self) -> Option<EntityId> {
+        self.player_id
+    }
+    pub fn position(&self, id: EntityId) -> Option<Vector2<SubPixelI64>> {
+        self.position.get(&id).cloned()
+    }
+    pub fn shape(&self, id: EntityId) -> Option<&Shape<SubPixelI64>> {
+        self.shape.get(&id)
+    }
+    pub fn velocity(&self, id: EntityId) -> Option<Vector2<SubPixelI64>> {
+        self.velocity.get(&id).cloned()
+    }
+    pub fn set_velocity(&mut self, id: EntityId, velocity: Vector2<SubPixelI64>) {
+        if let Some(current) = self.velocity.get_mut(&id) {
+            *current = velocity;
+        }
+    }
+    pub fn entities_with_velocity<'b>(&'b self) -> impl Iterator<Item = EntityId> + 'b {
+        self.velocity.keys().cloned()
+    }
+    pub fn user_data(&self) -> &UserData {
+        self.user_data
+    }
+    pub fn user_data_mut(&mut self) -> &mut UserData {
+        self.user_data
+    }
+    pub fn resources(&self) -> &Resources {
+        self.resources
+    }
+    pub fn resources_mut(&mut self) -> &mut Resources {
+        self.resources
+    }
+    pub fn commands(&mut self) -> &mut Commands {
+        self.commands
+    }
+    pub fn rng(&mut self) -> &mut Rng {
+        self.resources.rng()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyType {
+    Static,
+    Dynamic,
+}
+
+pub struct EntityBuilder<'a> {
+    world: &'a mut World,
+    position: Vector2<SubPixelI64>,
+    shape: Option<Shape<SubPixelI64>>,
+    colour: [f32; 4],
+    velocity: Option<Vector2<SubPixelI64>>,
+    body_type: Option<BodyType>,
+    layers: u32,
+    mass: Option<i64>,
+    restitution: Option<Restitution>,
+    material: Option<MaterialId>,
+    bounce_pad: Option<BouncePad>,
+    lifetime: Option<Lifetime>,
+    z_index: Option<i32>,
+}
+
+impl<'a> EntityBuilder<'a> {
+    pub fn position(mut self, position: Vector2<SubPixelI64>) -> Self {
+        self.position = position;
+        self
+    }
+    pub fn position_pixels(self, position: Vector2<f32>) -> Self {
+        self.position(vec2(
+            SubPixelI64::new_pixels_f32(position.x),
+            SubPixelI64::new_pixels_f32(position.y),
+        ))
+    }
+    pub fn shape(mut self, shape: Shape<SubPixelI64>) -> Self {
+        self.shape = Some(shape);
+        self
+    }
+    pub fn colour(mut self, colour: [f32; 4]) -> Self {
+        self.colour = colour;
+        self
+    }
+    pub fn velocity(mut self, velocity: Vector2<SubPixelI64>) -> Self {
+        self.velocity = Some(velocity);
+        self
+    }
+    pub fn body_type(mut self, body_type: BodyType) -> Self {
+        self.body_type = Some(body_type);
+        self
+    }
+    pub fn layer(mut self, layers: u32) -> Self {
+        self.layers = layers;
+        self
+    }
+    pub fn mass(mut self, mass: i64) -> Self {
+        self.mass = Some(mass);
+        self
+    }
+    pub fn restitution(mut self, restitution: Restitution) -> Self {
+        self.restitution = Some(restitution);
+        self
+    }
+    pub fn material(mut self, material: MaterialId) -> Self {
+        self.material = Some(material);
+        self
+    }
+    pub fn bounce_pad(mut self, bounce_pad: BouncePad) -> Self {
+        self.bounce_pad = Some(bounce_pad);
+        self
+    }
+    pub fn lifetime(mut self, lifetime: Lifetime) -> Self {
+        self.lifetime = Some(lifetime);
+        self
+    }
+    pub fn z_index(mut self, z_index: i32) -> Self {
+        self.z_index = Some(z_index);
+        self
+    }
+    pub fn build(self) -> PhysicsResult<EntityId> {
+        let shape = self.shape.ok_or(PhysicsError::MissingShape)?;
+        let body_type = self.body_type.unwrap_or(if self.velocity.is_some() {
+            BodyType::Dynamic
+        } else {
+            BodyType::Static
+        });
+        if self.mass.is_some_and(|mass| mass <= 0) {
+            return Err(PhysicsError::InvalidMass);
+        }
+        if body_type == BodyType::Dynamic && !shape.can_move() {
+            return Err(PhysicsError::ImmovableShape);
+        }
+        let world = self.world;
+        let id = world.entity_id_allocator.allocate()?;
+        let aabb = shape.aabb(self.position);
+        world.position.insert(id, self.position);
+        world.shape.insert(id, shape);
+        world.shape_dirty.insert(id);
+        world.shape_changed.insert(id);
+        if world.grow_world_to_fit(&aabb) {
+            world.rebuild_broadphase();
+        } else {
+            if body_type == BodyType::Static {
+                world
+                    .broadphase
+                    .insert(aabb, SpatialInfo { entity_id: id });
+            }
+            world.pair_cache.clear();
+        }
+        world.colour.insert(id, self.colour);
+        if body_type == BodyType::Dynamic {
+            let velocity = self.velocity
+                .unwrap_or_else(|| vec2(Zero::zero(), Zero::zero()));
+            world.velocity.insert(id, velocity);
+        }
+        if self.layers != ALL_COLLISION_LAYERS {
+            world.collision_layers.insert(id, self.layers);
+        }
+        world.set_mass(id, self.mass)?;
+        world.set_restitution(id, self.restitution);
+        world.set_material(id, self.material);
+        world.set_bounce_pad(id, self.bounce_pad);
+        world.set_lifetime(id, self.lifetime);
+        world.set_z_index(id, self.z_index);
+        Ok(id)
+    }
 }
 
 enum MovementStep {
@@ -105,231 +701,5713 @@ enum MovementStep {
         allowed_movement: Vector2<SubPixelI64>,
         destination: Vector2<SubPixelI64>,
         line_segment: LineSegment<SubPixelI64>,
+        start_inside_normal: Option<Vector2<SubPixelI64>>,
+        other_id: EntityId,
     },
 }
 
+struct CollisionWorld<'a> {
+    position: &'a CowTable<Vector2<SubPixelI64>>,
+    shape: &'a CowTable<Shape<SubPixelI64>>,
+    velocity: &'a CowTable<Vector2<SubPixelI64>>,
+    sensor: &'a CowSet,
+    skin: &'a CowTable<SubPixelI64>,
+    island: &'a [EntityId],
+    broadphase: &'a SpatialBroadphase,
+    baked: Option<&'a StaticGeometry<SubPixelI64>>,
+    config: &'a PhysicsConfig,
+    wrap_bounds: Option<Aabb<SubPixelI64>>,
+}
+
+/// The run of `events` addressed to `id`, given events sorted by target.
+fn targeted<T>(events: &[(EntityId, T)], id: EntityId) -> &[(EntityId, T)] {
+    let start = events.partition_point(|&(target, _)| target < id);
+    let end = events.partition_point(|&(target, _)| target <= id);
+    &events[start..end]
+}
+
+fn for_each_baked_candidate<F>(
+    world: &CollisionWorld,
+    aabb: &Aabb<SubPixelI64>,
+    offset: Vector2<SubPixelI64>,
+    stats: &mut UpdateStats,
+    mut f: F,
+) where
+    F: FnMut(EntityId, Vector2<SubPixelI64>, &mut UpdateStats),
+{
+    if let Some(baked) = world.baked {
+        let visits = baked.for_each_intersection(aabb, |collider| {
+            if !world.velocity.contains_key(&collider.entity_id) {
+                f(collider.entity_id, offset, stats);
+            }
+        });
+        stats.broadphase_visits += visits;
+    }
+}
+
+fn for_each_wrapped_candidate<F>(
+    id: EntityId,
+    aabb: &Aabb<SubPixelI64>,
+    world: &CollisionWorld,
+    stats: &mut UpdateStats,
+    mut f: F,
+) where
+    F: FnMut(EntityId, Vector2<SubPixelI64>, &mut UpdateStats),
+{
+    let bounds = match world.wrap_bounds {
+        Some(bounds) => bounds,
+        None => return,
+    };
+    for offset in bounds.seam_offsets(aabb) {
+        let query_aabb = aabb.translate(-offset);
+        let visits =
+            world.broadphase.for_each_intersection(&query_aabb, |_aabb, spatial_info| {
+                let other_id = spatial_info.entity_id;
+                if other_id != id && !world.velocity.contains_key(&other_id) {
+                    f(other_id, offset, stats);
+                }
+            });
+        stats.broadphase_visits += visits;
+        for_each_baked_candidate(world, &query_aabb, offset, stats, &mut f);
+        for &other_id in world.island.iter() {
+            if other_id != id {
+                f(other_id, offset, stats);
+            }
+        }
+    }
+}
+
+fn exact_allowed_movement(
+    id: EntityId,
+    world: &CollisionWorld,
+    movement: Vector2<SubPixelI64>,
+    time_of_impact: TimeOfImpact<SubPixelI64>,
+) -> Vector2<SubPixelI64> {
+    let allowed_movement = time_of_impact.scale_vector(movement);
+    if world.config.narrowphase_rounding != NarrowphaseRounding::ExactWithSkin {
+        return allowed_movement;
+    }
+    let skin = world
+        .skin
+        .get(&id)
+        .cloned()
+        .unwrap_or(world.config.default_skin);
+    let back_off = physics_num::normalize_to_length(movement, skin);
+    let remaining = physics_num::magnitude2_wide(allowed_movement);
+    if physics_num::magnitude2_wide(back_off) >= remaining {
+        vec2(Zero::zero(), Zero::zero())
+    } else {
+        allowed_movement - back_off
+    }
+}
+
 fn movement_step(
     id: EntityId,
     position: Vector2<SubPixelI64>,
-    position_table: &FnvHashMap<EntityId, Vector2<SubPixelI64>>,
-    shape_table: &FnvHashMap<EntityId, Shape<SubPixelI64>>,
-    quad_tree: &SpatialLooseQuadTree,
+    world: &CollisionWorld,
+    pair_cache: &mut PairCache,
     movement: Vector2<SubPixelI64>,
+    start_inside_edge_policy: StartInsideEdgePolicy,
+    stats: &mut UpdateStats,
 ) -> MovementStep {
     if movement.x.is_zero() && movement.y.is_zero() {
         return MovementStep::NoMovement;
     }
-    if let Some(shape) = shape_table.get(&id) {
+    if let Some(shape) = world.shape.get(&id) {
+        if !shape.can_move() {
+            return MovementStep::NoMovement;
+        }
         let mut closest_collision = BestMap::new();
         let start_aabb = shape.aabb(position);
         let end_aabb = shape.aabb(position + movement);
         let aabb = start_aabb.union(&end_aabb);
-        quad_tree.for_each_intersection(
-            &aabb,
-            |_other_aabb,
-             SpatialInfo {
-                 entity_id: other_id,
-             }| {
-                if *other_id != id {
-                    if let Some(stationary_position) = position_table.get(other_id) {
-                        if let Some(stationary_shape) = shape_table.get(other_id) {
-                            if let Some(collision_info) = shape.movement_collision_test(
-                                position,
-                                stationary_shape,
-                                *stationary_position,
-                                movement,
-                            ) {
-                                closest_collision.insert_le(
-                                    collision_info.magnitude2,
-                                    (
-                                        collision_info.allowed_movement,
-                                        collision_info.line_segment,
-                                    ),
-                                );
+        {
+            let mut test = |other_id: EntityId,
+                            offset: Vector2<SubPixelI64>,
+                            stats: &mut UpdateStats| {
+                let dynamic_other = world.velocity.contains_key(&other_id);
+                if world.sensor.contains(&other_id)
+                    || (world.sensor.contains(&id) && dynamic_other)
+                {
+                    return;
+                }
+                stats.broadphase_candidates += 1;
+                if let (Some(stationary_position), Some(stationary_shape)) =
+                    (world.position.get(&other_id), world.shape.get(&other_id))
+                {
+                    stats.narrowphase_tests += 1;
+                    if let Some(collision_info) = shape.movement_collision_test(
+                        position,
+                        stationary_shape,
+                        *stationary_position + offset,
+                        movement,
+                        start_inside_edge_policy,
+                    ) {
+                        let exact_time = match world.config.narrowphase_rounding {
+                            NarrowphaseRounding::RoundTowardStart => None,
+                            NarrowphaseRounding::ExactFloor
+                            | NarrowphaseRounding::ExactWithSkin => {
+                                Some(collision_info.time_of_impact)
                             }
-                        }
+                        };
+                        closest_collision.insert_le(
+                            (exact_time, collision_info.magnitude2),
+                            (
+                                collision_info.allowed_movement,
+                                collision_info.line_segment,
+                                collision_info.start_inside_normal,
+                                other_id,
+                            ),
+                        );
                     }
                 }
-            },
-        );
-        return match closest_collision.into_value() {
+            };
+            let zero = vec2(Zero::zero(), Zero::zero());
+            let candidates = pair_cache.candidates(
+                id,
+                &aabb,
+                world.config.broadphase_margin,
+                world.broadphase,
+                |spatial_info| spatial_info.entity_id,
+                stats,
+            );
+            for &other_id in candidates.iter() {
+                if !world.velocity.contains_key(&other_id) {
+                    test(other_id, zero, stats);
+                }
+            }
+            let query_aabb = aabb.expand(world.config.broadphase_margin);
+            for_each_baked_candidate(world, &query_aabb, zero, stats, &mut test);
+            for &other_id in world.island.iter() {
+                if other_id != id {
+                    test(other_id, zero, stats);
+                }
+            }
+            for_each_wrapped_candidate(id, &aabb, world, stats, &mut test);
+        }
+        return match closest_collision.into_key_and_value() {
             None => MovementStep::NoCollision {
                 destination: position + movement,
             },
-            Some((allowed_movement, line_segment)) => MovementStep::Collision {
-                allowed_movement,
-                destination: position + allowed_movement,
-                line_segment,
-            },
+            Some(((exact_time, _), value)) => {
+                let (allowed_movement, line_segment, start_inside_normal, other_id) =
+                    value;
+                let allowed_movement = match exact_time {
+                    Some(time_of_impact) => exact_allowed_movement(
+                        id,
+                        world,
+                        movement,
+                        time_of_impact,
+                    ),
+                    None => allowed_movement,
+                };
+                MovementStep::Collision {
+                    allowed_movement,
+                    destination: position + allowed_movement,
+                    line_segment,
+                    start_inside_normal,
+                    other_id,
+                }
+            }
         };
     }
     MovementStep::NoMovement
 }
 
+#[allow(clippy::too_many_arguments)]
 fn position_after_movement(
     id: EntityId,
-    position_table: &FnvHashMap<EntityId, Vector2<SubPixelI64>>,
-    shape_table: &FnvHashMap<EntityId, Shape<SubPixelI64>>,
-    quad_tree: &SpatialLooseQuadTree,
+    world: &CollisionWorld,
+    pair_cache: &mut PairCache,
     mut movement: Vector2<SubPixelI64>,
+    start_inside_edge_policy: StartInsideEdgePolicy,
+    skin: SubPixelI64,
+    touched: &mut Vec<Contact>,
+    stats: &mut UpdateStats,
 ) -> Option<Vector2<SubPixelI64>> {
-    let mut position = if let Some(position) = position_table.get(&id) {
+    let mut position = {
+        let position = world.position.get(&id)?;
         *position
-    } else {
-        return None;
     };
-    const MAX_ITERATIONS: usize = 16;
-    for _ in 0..MAX_ITERATIONS {
+    let config = world.config;
+    for _ in 0..config.max_slide_iterations {
+        profile!("slide", entity_id = id);
+        stats.slide_iterations += 1;
         match movement_step(
             id,
             position,
-            position_table,
-            shape_table,
-            quad_tree,
+            world,
+            pair_cache,
             movement,
+            start_inside_edge_policy,
+            stats,
         ) {
             MovementStep::NoMovement => return Some(position),
             MovementStep::NoCollision { destination } => return Some(destination),
+            MovementStep::Collision {
+                start_inside_normal: Some(_),
+                line_segment,
+                other_id,
+                ..
+            } if start_inside_edge_policy == StartInsideEdgePolicy::BlockAll =>
+            {
+                touched.push(Contact {
+                    other_id,
+                    surface: line_segment,
+                });
+                return Some(position);
+            }
+            MovementStep::Collision {
+                start_inside_normal: Some(normal),
+                line_segment,
+                other_id,
+                ..
+            } if start_inside_edge_policy == StartInsideEdgePolicy::PushOut
+                && !sub_pixel_i64::vector_is_zero(normal) =>
+            {
+                touched.push(Contact {
+                    other_id,
+                    surface: line_segment,
+                });
+                let push_out =
+                    physics_num::normalize_to_length(normal, skin);
+                position = match movement_step(
+                    id,
+                    position,
+                    world,
+                    pair_cache,
+                    push_out,
+                    StartInsideEdgePolicy::AllowSeparating,
+                    stats,
+                ) {
+                    MovementStep::NoMovement => position,
+                    MovementStep::NoCollision { destination } => destination,
+                    MovementStep::Collision { destination, .. } => destination,
+                };
+            }
             MovementStep::Collision {
                 allowed_movement,
                 destination,
                 line_segment,
+                other_id,
+                ..
             } => {
+                touched.push(Contact {
+                    other_id,
+                    surface: line_segment,
+                });
                 position = destination;
-                let remaining_movement =
-                    sub_pixel_i64::vector_to_f32_sub_pixel(movement - allowed_movement);
-                let collision_surface_direction =
-                    sub_pixel_i64::vector_to_f32_sub_pixel(line_segment.vector())
-                        .normalize();
-                let slide_movement_float =
-                    remaining_movement.project_on(collision_surface_direction);
-                let remaining_to_slide_direction =
-                    (slide_movement_float - remaining_movement).normalize();
-                let padding = remaining_to_slide_direction * 0.1
-                    * sub_pixel_i64::SUB_PIXELS_PER_PIXEL as f32;
-                let slide_movement = sub_pixel_i64::vector_from_f32_sub_pixel(
-                    slide_movement_float + padding,
+                let slide_movement = sub_pixel_i64::slide_movement(
+                    movement - allowed_movement,
+                    line_segment.vector(),
+                    skin,
                 );
                 if sub_pixel_i64::vector_is_zero(slide_movement) {
-                    break;
+                    return Some(position);
                 }
                 movement = slide_movement
             }
         }
     }
+    stats.max_slide_iterations_hit += 1;
     Some(position)
 }
 
-impl GameState {
-    pub fn new(size_hint: Vector2<f32>) -> Self {
+fn position_after_axis_separated_movement(
+    id: EntityId,
+    world: &CollisionWorld,
+    pair_cache: &mut PairCache,
+    movement: Vector2<SubPixelI64>,
+    start_inside_edge_policy: StartInsideEdgePolicy,
+    touched: &mut Vec<Contact>,
+    stats: &mut UpdateStats,
+) -> Option<Vector2<SubPixelI64>> {
+    let mut position = {
+        let position = world.position.get(&id)?;
+        *position
+    };
+    let zero = Zero::zero();
+    let axis_movements = [vec2(movement.x, zero), vec2(zero, movement.y)];
+    for &axis_movement in axis_movements.iter() {
+        if sub_pixel_i64::vector_is_zero(axis_movement) {
+            continue;
+        }
+        profile!("axis_step", entity_id = id);
+        stats.slide_iterations += 1;
+        match movement_step(
+            id,
+            position,
+            world,
+            pair_cache,
+            axis_movement,
+            start_inside_edge_policy,
+            stats,
+        ) {
+            MovementStep::NoMovement => return Some(position),
+            MovementStep::NoCollision { destination } => position = destination,
+            MovementStep::Collision {
+                destination,
+                line_segment,
+                other_id,
+                ..
+            } => {
+                touched.push(Contact {
+                    other_id,
+                    surface: line_segment,
+                });
+                position = destination;
+            }
+        }
+    }
+    Some(position)
+}
+
+impl World {
+    pub fn new(size_hint: Vector2<f32>, config: PhysicsConfig) -> Self {
+        let world_bounds = Aabb::new(
+            vec2(Zero::zero(), Zero::zero()),
+            vec2(
+                SubPixelI64::new_pixels_f32(size_hint.x),
+                SubPixelI64::new_pixels_f32(size_hint.y),
+            ),
+        );
         Self {
             player_id: None,
             entity_id_allocator: Default::default(),
             position: Default::default(),
             shape: Default::default(),
             colour: Default::default(),
+            z_index: Default::default(),
+            sprite: Default::default(),
+            animation: Default::default(),
+            colour_tween: Default::default(),
+            flash: Default::default(),
+            hidden: Default::default(),
+            pushable: Default::default(),
+            blend: Default::default(),
+            shape_dirty: Default::default(),
+            shape_changed: Default::default(),
+            changed_shapes: Vec::new(),
+            position_changes: Default::default(),
+            colour_changes: Default::default(),
+            rendered: Default::default(),
+            render_changed: Default::default(),
+            render_removals: Vec::new(),
+            unreported_removals: Vec::new(),
+            render_order: Cell::default(),
+            gizmos_enabled: false,
+            contact_gizmos: Vec::new(),
             velocity: Default::default(),
-            quad_tree: LooseQuadTree::new(vec2(
-                SubPixelI64::new_pixels_f32(size_hint.x),
-                SubPixelI64::new_pixels_f32(size_hint.y),
-            )),
+            mass: Default::default(),
+            restitution: Default::default(),
+            rope: Default::default(),
+            attractor: Default::default(),
+            force: Default::default(),
+            gravity: Default::default(),
+            drag: Default::default(),
+            materials: Default::default(),
+            material: Default::default(),
+            contact_events: Vec::new(),
+            impact_events: Vec::new(),
+            touching: Default::default(),
+            previously_touching: Default::default(),
+            bounce_pad: Default::default(),
+            launch_events: Vec::new(),
+            portals: Vec::new(),
+            portal_suppressed: Default::default(),
+            teleport_events: Vec::new(),
+            hazard: Default::default(),
+            hazard_cooldowns: Default::default(),
+            damage_events: Vec::new(),
+            health: Default::default(),
+            death_events: Vec::new(),
+            sensor: Default::default(),
+            pickup: Default::default(),
+            collector: Default::default(),
+            pickup_events: Vec::new(),
+            lifetime: Default::default(),
+            scheduler: Default::default(),
+            timer_events: Vec::new(),
+            timer_targets: Vec::new(),
+            state_machine: Default::default(),
+            signals: Vec::new(),
+            state_change_events: Vec::new(),
+            path_follower: Default::default(),
+            homing: Default::default(),
+            target_lost_events: Vec::new(),
+            frame_publisher: None,
+            verlet: Vec::new(),
+            particles: ParticleSystem::new(config.gravity, config.max_particles),
+            movement_remainder: Default::default(),
+            time_remainder: 0,
+            time_ticks: 1,
+            start_inside_edge_policy: Default::default(),
+            skin: Default::default(),
+            movement_mode: Default::default(),
+            collision_layers: Default::default(),
+            out_of_bounds_policy: Default::default(),
+            out_of_bounds: Default::default(),
+            out_of_bounds_events: Vec::new(),
+            explosion_events: Vec::new(),
+            kill_zone: None,
+            world_bounds,
+            active_chunks: None,
+            broadphase: Broadphase::new(
+                config.broadphase,
+                &world_bounds,
+                config.chunk_size,
+                config.quad_tree,
+            ),
+            baked: None,
+            pair_cache: Default::default(),
+            low_velocity_streak: Default::default(),
+            resting: Default::default(),
+            asleep: Default::default(),
+            touched: Vec::new(),
+            id_buffers: Default::default(),
+            command_buffer: Vec::new(),
+            command_errors: Vec::new(),
+            #[cfg(feature = "validation")]
+            validation_error: None,
+            island_builder: Default::default(),
+            islands: Vec::new(),
+            user_data: Default::default(),
+            resources: Resources::new(config, Camera::new(world_bounds.size())),
+            prefabs: Default::default(),
+            pool: Default::default(),
+            commands: Default::default(),
+            paused: false,
+            stats: Default::default(),
         }
     }
-    fn clear(&mut self) {
-        self.player_id = None;
-        self.entity_id_allocator.reset();
-        self.position.clear();
-        self.shape.clear();
-        self.colour.clear();
-        self.velocity.clear();
+    pub fn player_id(&self) -> Option<EntityId> {
+        self.player_id
     }
-    fn add_entity(
-        &mut self,
-        position: Vector2<f32>,
-        shape: Shape<SubPixelI64>,
-        colour: [f32; 3],
-    ) -> EntityId {
-        let id = self.entity_id_allocator.allocate();
-        let position = vec2(
-            SubPixelI64::new_pixels_f32(position.x),
-            SubPixelI64::new_pixels_f32(position.y),
-        );
-        self.position.insert(id, position);
-
-        self.quad_tree
-            .insert(shape.aabb(position), SpatialInfo { entity_id: id });
-        self.shape.insert(id, shape);
-        self.colour.insert(id, colour);
-        id
+    pub fn set_player_id(&mut self, id: Option<EntityId>) {
+        self.player_id = id;
     }
-    pub fn init_demo(&mut self) {
-        self.clear();
-        let player_id = self.add_entity(
-            vec2(200., 50.),
-            Shape::AxisAlignedRect(AxisAlignedRect::new(vec2(
-                SubPixelI64::new_pixels_f32(32.),
-                SubPixelI64::new_pixels_f32(64.),
-            ))),
-            [1., 0., 0.],
-        );
-
-        self.player_id = Some(player_id);
-        self.velocity.insert(
-            player_id,
-            vec2(
-                SubPixelI64::new_pixels_f32(0.),
-                SubPixelI64::new_pixels_f32(0.),
-            ),
-        );
-
-        self.add_entity(
-            vec2(50., 200.),
-            Shape::AxisAlignedRect(AxisAlignedRect::new(vec2(
-                SubPixelI64::new_pixels_f32(400.),
-                SubPixelI64::new_pixels_f32(20.),
-            ))),
-            [1., 1., 0.],
-        );
-
-        self.add_entity(
-            vec2(20., 20.),
-            Shape::LineSegment(LineSegment::new(
-                vec2(Zero::zero(), Zero::zero()),
-                vec2(
-                    SubPixelI64::new_pixels_f32(50.),
-                    SubPixelI64::new_pixels_f32(100.),
-                ),
-            )),
-            [0., 1., 0.],
-        );
+    pub fn position(&self, id: EntityId) -> Option<Vector2<SubPixelI64>> {
+        self.position.get(&id).cloned()
     }
-    pub fn render_updates(&self) -> impl Iterator<Item = RenderUpdate> {
-        let position = &self.position;
-        position.iter().filter_map(move |(id, &position)| {
-            self.shape.get(id).and_then(|shape| {
-                self.colour.get(id).map(|&colour| RenderUpdate {
+    pub fn velocity(&self, id: EntityId) -> Option<Vector2<SubPixelI64>> {
+        self.velocity.get(&id).cloned()
+    }
+    pub fn config(&self) -> &PhysicsConfig {
+        &self.resources.config
+    }
+    pub fn set_config(&mut self, config: PhysicsConfig) {
+        let previous = mem::replace(&mut self.resources.config, config);
+        self.reconfigure(previous);
+    }
+    fn reconfigure(&mut self, previous: PhysicsConfig) {
+        let config = self.resources.config;
+        if self.broadphase.kind() != config.broadphase
+            || previous.quad_tree != config.quad_tree
+            || previous.chunk_size != config.chunk_size
+        {
+            self.broadphase = Broadphase::new(
+                config.broadphase,
+                &self.world_bounds,
+                config.chunk_size,
+                config.quad_tree,
+            );
+            self.rebuild_broadphase();
+        }
+    }
+    pub fn world_bounds(&self) -> Aabb<SubPixelI64> {
+        self.world_bounds
+    }
+    pub fn quad_tree_stats(&self) -> Option<QuadTreeStats> {
+        self.broadphase.quad_tree_stats()
+    }
+    pub fn quad_tree_nodes(&self) -> Vec<NodeInfo<SubPixelI64>> {
+        self.broadphase.quad_tree_nodes()
+    }
+    fn wrap_bounds(&self) -> Option<Aabb<SubPixelI64>> {
+        if self.resources.config.wrap_world {
+            Some(self.world_bounds)
+        } else {
+            None
+        }
+    }
+    pub fn set_active_chunks<I>(&mut self, chunks: I)
+    where
+        I: IntoIterator<Item = ChunkCoord>,
+    {
+        self.active_chunks = Some(chunks.into_iter().collect());
+    }
+    pub fn activate_all_chunks(&mut self) {
+        self.active_chunks = None;
+    }
+    pub fn is_chunk_active(&self, chunk: ChunkCoord) -> bool {
+        self.active_chunks
+            .as_ref()
+            .map(|active| active.contains(&chunk))
+            .unwrap_or(true)
+    }
+    fn is_body_active(&self, id: EntityId) -> bool {
+        self.position
+            .get(&id)
+            .map(|&position| {
+                self.is_chunk_active(chunked_index::chunk_containing(
                     position,
-                    shape,
-                    colour,
-                })
+                    self.resources.config.chunk_size,
+                ))
             })
-        })
+            .unwrap_or(false)
     }
-    pub fn update(&mut self, input_model: &InputModel) {
-        let player_id = self.player_id.expect("No player id");
-        if let Some(velocity) = self.velocity.get_mut(&player_id) {
-            *velocity = update_player_velocity(*velocity, input_model);
+    fn is_island_active(&self, island_index: usize) -> bool {
+        if self.active_chunks.is_none() {
+            return true;
         }
-        for (id, velocity) in self.velocity.iter() {
-            if let Some(position) = position_after_movement(
-                *id,
-                &self.position,
-                &self.shape,
-                &self.quad_tree,
-                *velocity,
-            ) {
-                self.position.insert(*id, position);
-            }
+        self.islands[island_index].iter().any(|&id| self.is_body_active(id))
+    }
+    pub fn resize_world(&mut self, bounds: Aabb<SubPixelI64>) {
+        self.world_bounds = bounds;
+        self.broadphase = Broadphase::new(
+            self.resources.config.broadphase,
+            &bounds,
+            self.resources.config.chunk_size,
+            self.resources.config.quad_tree,
+        );
+        self.rebuild_broadphase();
+    }
+    fn grow_world_to_fit(&mut self, aabb: &Aabb<SubPixelI64>) -> bool {
+        if !self.resources.config.auto_grow_world || self.resources.config.wrap_world
+            || self.world_bounds.contains(aabb)
+        {
+            return false;
         }
+        let bounds = Aabb::from_union(&self.world_bounds, aabb).double_about_centre();
+        self.world_bounds = bounds;
+        if self.broadphase.kind() != BroadphaseKind::LooseQuadTree {
+            return false;
+        }
+        self.broadphase = Broadphase::new(
+            self.resources.config.broadphase,
+            &bounds,
+            self.resources.config.chunk_size,
+            self.resources.config.quad_tree,
+        );
+        true
+    }
+    pub fn set_time_scale(
+        &mut self,
+        numerator: i64,
+        denominator: i64,
+    ) -> PhysicsResult<()> {
+        self.resources.time_scale = TimeScale::new(numerator, denominator)?;
+        self.movement_remainder.clear();
+        self.time_remainder = 0;
+        Ok(())
+    }
+    pub fn time_scale(&self) -> TimeScale {
+        self.resources.time_scale
+    }
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    pub fn stats(&self) -> UpdateStats {
+        self.stats
+    }
+    pub fn run_hook(&mut self, hook: &mut dyn Hook) {
+        let mut world_view = WorldView {
+            player_id: self.player_id,
+            position: &mut self.position,
+            shape: &self.shape,
+            velocity: &mut self.velocity,
+            user_data: &mut self.user_data,
+            resources: &mut self.resources,
+            commands: &mut self.commands,
+        };
+        hook(&mut world_view);
+    }
+    pub fn commands(&mut self) -> &mut Commands {
+        &mut self.commands
+    }
+    /// Commands from the most recent update that could not be applied.
+    pub fn command_errors(&self) -> &[CommandError] {
+        &self.command_errors
+    }
+    /// The first invariant the last update broke, when validation is enabled
+    /// through `PhysicsConfig::validation_tolerance`.
+    #[cfg(feature = "validation")]
+    pub fn validation_error(&self) -> Option<PhysicsError> {
+        self.validation_error
+    }
+    pub fn rng(&mut self) -> &mut Rng {
+        &mut self.resources.rng
+    }
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.resources.rng = Rng::new(seed);
+    }
+    pub fn apply_commands(&mut self) {
+        let mut commands = std::mem::take(&mut self.command_buffer);
+        self.commands.swap_buffer(&mut commands);
+        for command in commands.drain(..) {
+            let result = match command {
+                Command::Spawn {
+                    ref prefab,
+                    position,
+                } => self.instantiate(prefab, position).map(|_| ()),
+                Command::Despawn(id) => self.try_remove_entity(id),
+                Command::SetPosition(id, position) => self.try_set_position(id, position),
+                Command::SetVelocity(id, velocity) => self.try_set_velocity(id, velocity),
+                Command::SetShape(id, ref shape) => self.try_set_shape(id, shape.clone()),
+                Command::SetColour(id, colour) => match self.colour.get_mut(&id) {
+                    Some(current) => {
+                        *current = colour;
+                        Ok(())
+                    }
+                    None => Err(PhysicsError::MissingEntity(id)),
+                },
+                Command::ApplyImpulse(id, impulse) => {
+                    self.try_apply_impulse(id, impulse).map(|_| ())
+                }
+            };
+            if let Err(error) = result {
+                self.command_errors.push(CommandError { command, error });
+            }
+        }
+        self.command_buffer = commands;
+    }
+    pub fn apply_impulse(&mut self, id: EntityId, impulse: Vector2<SubPixelI64>) {
+        let _ = self.try_apply_impulse(id, impulse);
+    }
+    pub fn try_apply_impulse(
+        &mut self,
+        id: EntityId,
+        impulse: Vector2<SubPixelI64>,
+    ) -> PhysicsResult<Vector2<SubPixelI64>> {
+        let velocity = self.require_velocity(id)?;
+        let mass = self.mass(id).unwrap_or(1);
+        let change = vec2(
+            SubPixelI64::new(impulse.x.raw() / mass),
+            SubPixelI64::new(impulse.y.raw() / mass),
+        );
+        let velocity = match (
+            velocity.x.checked_add(change.x),
+            velocity.y.checked_add(change.y),
+        ) {
+            (Some(x), Some(y)) => vec2(x, y),
+            _ => return Err(PhysicsError::Overflow),
+        };
+        self.set_velocity(id, velocity);
+        Ok(velocity)
+    }
+    fn explode(
+        &mut self,
+        point: Vector2<SubPixelI64>,
+        radius: SubPixelI64,
+        strength: SubPixelI64,
+        falloff: Falloff,
+        line_of_sight: bool,
+    ) -> usize {
+        let area = Aabb::from_centre_and_half_size(point, vec2(radius, radius));
+        let ids = self.id_buffers.sorted(self.velocity.keys());
+        let mut affected = 0;
+        for &id in ids.iter() {
+            let centre = match (self.position.get(&id), self.shape.get(&id)) {
+                (Some(position), Some(shape)) => {
+                    let aabb = shape.aabb(*position);
+                    if !aabb.is_intersecting(&area) {
+                        continue;
+                    }
+                    aabb.centre()
+                }
+                _ => continue,
+            };
+            let offset = centre - point;
+            let impulse = match explosion::impulse(offset, radius, strength, falloff) {
+                Some(impulse) => impulse,
+                None => continue,
+            };
+            if line_of_sight {
+                let filter = |other_id| other_id != id;
+                let layers = ALL_COLLISION_LAYERS;
+                if self.raycast_filtered(point, offset, layers, false, filter).is_some() {
+                    continue;
+                }
+            }
+            self.apply_impulse(id, impulse);
+            self.explosion_events.push(ExplosionEvent {
+                entity_id: id,
+                impulse,
+            });
+            affected += 1;
+        }
+        self.id_buffers.recycle(ids);
+        affected
+    }
+    pub fn explode_at(
+        &mut self,
+        point: Vector2<SubPixelI64>,
+        radius: SubPixelI64,
+        strength: SubPixelI64,
+        falloff: Falloff,
+    ) -> usize {
+        self.explode(point, radius, strength, falloff, false)
+    }
+    pub fn explode_at_with_line_of_sight(
+        &mut self,
+        point: Vector2<SubPixelI64>,
+        radius: SubPixelI64,
+        strength: SubPixelI64,
+        falloff: Falloff,
+    ) -> usize {
+        self.explode(point, radius, strength, falloff, true)
+    }
+    pub fn explosion_events(&self) -> &[ExplosionEvent] {
+        &self.explosion_events
+    }
+    pub fn user_data(&self) -> &UserData {
+        &self.user_data
+    }
+    pub fn user_data_mut(&mut self) -> &mut UserData {
+        &mut self.user_data
+    }
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+    pub fn resources_mut(&mut self) -> &mut Resources {
+        &mut self.resources
+    }
+    pub fn frame_count(&self) -> u64 {
+        self.resources.get::<FrameCount>().map_or(0, |frame_count| frame_count.0)
+    }
+    fn clear(&mut self) {
+        self.player_id = None;
+        self.entity_id_allocator.reset();
+        self.position.clear();
+        self.shape.clear();
+        self.colour.clear();
+        self.z_index.clear();
+        self.sprite.clear();
+        self.animation.clear();
+        self.colour_tween.clear();
+        self.flash.clear();
+        self.hidden.clear();
+        self.pushable.clear();
+        self.blend.clear();
+        self.velocity.clear();
+        self.mass.clear();
+        self.restitution.clear();
+        self.rope.clear();
+        self.attractor.clear();
+        self.force.clear();
+        self.gravity.clear();
+        self.drag.clear();
+        self.material.clear();
+        self.contact_events.clear();
+        self.impact_events.clear();
+        self.touching.clear();
+        self.previously_touching.clear();
+        self.bounce_pad.clear();
+        self.launch_events.clear();
+        self.portals.clear();
+        self.portal_suppressed.clear();
+        self.teleport_events.clear();
+        self.hazard.clear();
+        self.hazard_cooldowns.clear();
+        self.damage_events.clear();
+        self.health.clear();
+        self.death_events.clear();
+        self.sensor.clear();
+        self.pickup.clear();
+        self.collector.clear();
+        self.pickup_events.clear();
+        self.lifetime.clear();
+        self.scheduler.clear();
+        self.timer_events.clear();
+        self.state_machine.clear();
+        self.signals.clear();
+        self.state_change_events.clear();
+        self.path_follower.clear();
+        self.homing.clear();
+        self.target_lost_events.clear();
+        self.command_errors.clear();
+        self.resources.camera.reset();
+        self.verlet.clear();
+        self.particles.clear();
+        self.movement_remainder.clear();
+        self.time_remainder = 0;
+        self.start_inside_edge_policy.clear();
+        self.skin.clear();
+        self.movement_mode.clear();
+        self.collision_layers.clear();
+        self.out_of_bounds_policy.clear();
+        self.out_of_bounds.clear();
+        self.out_of_bounds_events.clear();
+        self.explosion_events.clear();
+        self.pair_cache.clear();
+        self.low_velocity_streak.clear();
+        self.resting.clear();
+        self.asleep.clear();
+        self.user_data.clear();
+        self.resources.clear();
+        self.shape_changed.clear();
+        self.changed_shapes.clear();
+        self.position_changes.clear();
+        self.colour_changes.clear();
+        self.pool.clear();
+        self.commands.clear();
+        self.baked = None;
+    }
+    pub fn iter_position_shape<'a>(
+        &'a self,
+    ) -> impl Iterator<Item = ShapeQueryItem<'a>> + 'a {
+        let shape = &self.shape;
+        self.position.iter().filter_map(move |(id, position)| {
+            shape.get(id).map(|shape| (*id, position, shape))
+        })
+    }
+    pub fn iter_position_velocity_mut<'a>(
+        &'a mut self,
+    ) -> impl Iterator<Item = VectorQueryItem<'a>> + 'a {
+        let velocity = &self.velocity;
+        self.position
+            .iter_mut_where(move |id, _| velocity.contains_key(id))
+            .map(move |(id, position)| (*id, position, &velocity[id]))
+    }
+    pub fn iter_velocity_mut_position<'a>(
+        &'a mut self,
+    ) -> impl Iterator<Item = VectorQueryItem<'a>> + 'a {
+        let position = &self.position;
+        self.velocity
+            .iter_mut_where(move |id, _| position.contains_key(id))
+            .map(move |(id, velocity)| (*id, velocity, &position[id]))
+    }
+    pub fn iter_velocity_mut_mass<'a>(
+        &'a mut self,
+    ) -> impl Iterator<Item = (EntityId, &'a mut Vector2<SubPixelI64>, i64)> + 'a {
+        let mass = &self.mass;
+        self.velocity
+            .iter_mut_where(move |id, _| mass.contains_key(id))
+            .map(move |(id, velocity)| (*id, velocity, mass[id]))
+    }
+    pub fn prefabs(&self) -> &PrefabRegistry {
+        &self.prefabs
+    }
+    pub fn prefabs_mut(&mut self) -> &mut PrefabRegistry {
+        &mut self.prefabs
+    }
+    pub fn instantiate(
+        &mut self,
+        name: &str,
+        position: Vector2<SubPixelI64>,
+    ) -> PhysicsResult<EntityId> {
+        let prefab = self.prefabs.get(name).cloned().ok_or(PhysicsError::MissingPrefab)?;
+        prefab.validate()?;
+        let mut builder = self.spawn()
+            .position(position)
+            .shape(prefab.shape)
+            .colour(prefab.colour)
+            .body_type(prefab.body_type)
+            .layer(prefab.layers);
+        if let Some(velocity) = prefab.velocity {
+            builder = builder.velocity(velocity);
+        }
+        if let Some(mass) = prefab.mass {
+            builder = builder.mass(mass);
+        }
+        if let Some(lifetime) = prefab.lifetime {
+            builder = builder.lifetime(lifetime);
+        }
+        let id = builder.build()?;
+        self.set_movement_mode(id, prefab.movement_mode);
+        self.set_start_inside_edge_policy(id, prefab.start_inside_edge_policy);
+        if let Err(error) = self.set_skin(id, prefab.skin) {
+            self.remove_entity(id);
+            return Err(error);
+        }
+        Ok(id)
+    }
+    pub fn spawn_from_pool(
+        &mut self,
+        name: &str,
+        position: Vector2<SubPixelI64>,
+    ) -> PhysicsResult<EntityId> {
+        let prefab = self.prefabs.get(name).cloned().ok_or(PhysicsError::MissingPrefab)?;
+        prefab.validate()?;
+        let id = match self.pool.take(name) {
+            Some(id) => id,
+            None => {
+                let id = self.instantiate(name, position)?;
+                self.pool.add_member(id, name);
+                return Ok(id);
+            }
+        };
+        let body_type = prefab.body_type;
+        if let Err(error) = self.insert_prefab(id, position, prefab) {
+            self.purge_entity(id);
+            self.pool.give_back(id);
+            return Err(error);
+        }
+        if body_type == BodyType::Static {
+            let aabb = self.shape[&id].aabb(position);
+            if self.grow_world_to_fit(&aabb) {
+                self.rebuild_broadphase();
+            } else {
+                self.broadphase.insert(aabb, SpatialInfo { entity_id: id });
+                self.pair_cache.clear();
+            }
+        }
+        Ok(id)
+    }
+    pub fn return_to_pool(&mut self, id: EntityId) -> bool {
+        if !self.pool.give_back(id) {
+            return false;
+        }
+        self.purge_entity(id);
+        true
+    }
+    pub fn prewarm_pool(&mut self, name: &str, count: usize) -> PhysicsResult<()> {
+        let position = self.world_bounds.top_left();
+        let ids = (0..count)
+            .map(|_| self.spawn_from_pool(name, position))
+            .collect::<Vec<_>>();
+        let mut result = Ok(());
+        for id in ids {
+            match id {
+                Ok(id) => {
+                    self.return_to_pool(id);
+                }
+                Err(error) => result = Err(error),
+            }
+        }
+        result
+    }
+    pub fn is_pooled(&self, id: EntityId) -> bool {
+        self.pool.is_pooled(id)
+    }
+    pub fn num_free_in_pool(&self, name: &str) -> usize {
+        self.pool.num_free(name)
+    }
+    pub fn add_entities<I>(&mut self, entities: I) -> PhysicsResult<Vec<EntityId>>
+    where
+        I: IntoIterator<Item = (Vector2<SubPixelI64>, Prefab)>,
+    {
+        let entities = entities.into_iter().collect::<Vec<_>>();
+        for (_, prefab) in entities.iter() {
+            prefab.validate()?;
+        }
+        let allocator = self.entity_id_allocator.clone();
+        let ids = entities
+            .iter()
+            .map(|_| self.entity_id_allocator.allocate())
+            .collect::<PhysicsResult<Vec<_>>>();
+        let ids = match ids {
+            Ok(ids) => ids,
+            Err(error) => {
+                self.entity_id_allocator = allocator;
+                return Err(error);
+            }
+        };
+        for (&id, (position, prefab)) in ids.iter().zip(entities) {
+            if let Err(error) = self.insert_prefab(id, position, prefab) {
+                for &id in ids.iter() {
+                    self.remove_entity(id);
+                }
+                self.entity_id_allocator = allocator;
+                return Err(error);
+            }
+        }
+        self.rebuild_broadphase();
+        Ok(ids)
+    }
+    fn insert_prefab(
+        &mut self,
+        id: EntityId,
+        position: Vector2<SubPixelI64>,
+        prefab: Prefab,
+    ) -> PhysicsResult<()> {
+        self.position.insert(id, position);
+        self.shape.insert(id, prefab.shape);
+        self.shape_dirty.insert(id);
+        self.shape_changed.insert(id);
+        self.colour.insert(id, prefab.colour);
+        if prefab.body_type == BodyType::Dynamic {
+            let velocity = prefab
+                .velocity
+                .unwrap_or_else(|| vec2(Zero::zero(), Zero::zero()));
+            self.velocity.insert(id, velocity);
+        }
+        if prefab.layers != ALL_COLLISION_LAYERS {
+            self.collision_layers.insert(id, prefab.layers);
+        }
+        self.set_lifetime(id, prefab.lifetime);
+        self.set_movement_mode(id, prefab.movement_mode);
+        self.set_start_inside_edge_policy(id, prefab.start_inside_edge_policy);
+        self.set_mass(id, prefab.mass)?;
+        self.set_skin(id, prefab.skin)
+    }
+    pub fn spawn(&mut self) -> EntityBuilder<'_> {
+        EntityBuilder {
+            world: self,
+            position: vec2(Zero::zero(), Zero::zero()),
+            shape: None,
+            colour: [1., 1., 1., 1.],
+            velocity: None,
+            body_type: None,
+            layers: ALL_COLLISION_LAYERS,
+            mass: None,
+            restitution: None,
+            material: None,
+            bounce_pad: None,
+            lifetime: None,
+            z_index: None,
+        }
+    }
+    pub fn contains_entity(&self, id: EntityId) -> bool {
+        self.position.contains_key(&id)
+    }
+    fn require_entity(&self, id: EntityId) -> PhysicsResult<()> {
+        if self.contains_entity(id) {
+            Ok(())
+        } else {
+            Err(PhysicsError::MissingEntity(id))
+        }
+    }
+    fn require_velocity(&self, id: EntityId) -> PhysicsResult<Vector2<SubPixelI64>> {
+        self.require_entity(id)?;
+        self.velocity
+            .get(&id)
+            .cloned()
+            .ok_or(PhysicsError::MissingComponent(id, ComponentKind::Velocity))
+    }
+    fn require_shape(&self, id: EntityId) -> PhysicsResult<&Shape<SubPixelI64>> {
+        self.require_entity(id)?;
+        self.shape
+            .get(&id)
+            .ok_or(PhysicsError::MissingComponent(id, ComponentKind::Shape))
+    }
+    pub fn try_remove_entity(&mut self, id: EntityId) -> PhysicsResult<()> {
+        self.require_entity(id)?;
+        self.remove_entity(id);
+        Ok(())
+    }
+    pub fn remove_entity(&mut self, id: EntityId) {
+        self.purge_entity(id);
+        self.pool.remove(id);
+    }
+    /// Removes every component of `id`. Entities returned to a pool go through
+    /// here too, so a recycled id starts out as blank as a new one.
+    fn purge_entity(&mut self, id: EntityId) {
+        if self.player_id == Some(id) {
+            self.player_id = None;
+        }
+        self.remove_proxy(id);
+        if self.rendered.remove(&id).is_some() {
+            self.unreported_removals.push(id);
+        }
+        self.position_changes.remove(id);
+        self.colour_changes.remove(id);
+        for island in self.islands.iter_mut() {
+            island.retain(|&member| member != id);
+        }
+        self.scheduler.cancel_for(id);
+        self.lose_homing_target(id);
+        self.position.remove(&id);
+        self.shape.remove(&id);
+        self.colour.remove(&id);
+        self.z_index.remove(&id);
+        self.sprite.remove(&id);
+        self.animation.remove(&id);
+        self.colour_tween.remove(&id);
+        self.flash.remove(&id);
+        self.hidden.remove(&id);
+        self.pushable.remove(&id);
+        self.blend.remove(&id);
+        self.velocity.remove(&id);
+        self.mass.remove(&id);
+        self.restitution.remove(&id);
+        self.rope.remove(&id);
+        self.attractor.remove(&id);
+        self.force.remove(&id);
+        self.gravity.remove(&id);
+        self.drag.remove(&id);
+        self.material.remove(&id);
+        self.bounce_pad.remove(&id);
+        self.portal_suppressed.remove(&id);
+        self.hazard.remove(&id);
+        self.hazard_cooldowns.remove_entity(id);
+        self.touching.retain(|&(a, b)| a != id && b != id);
+        self.previously_touching.retain(|&(a, b)| a != id && b != id);
+        self.health.remove(&id);
+        self.sensor.remove(&id);
+        self.pickup.remove(&id);
+        self.collector.remove(&id);
+        self.lifetime.remove(&id);
+        self.state_machine.remove(&id);
+        self.signals.retain(|&(target, _)| target != id);
+        self.path_follower.remove(&id);
+        self.homing.remove(&id);
+        self.resting.remove(&id);
+        self.movement_remainder.remove(&id);
+        self.start_inside_edge_policy.remove(&id);
+        self.skin.remove(&id);
+        self.movement_mode.remove(&id);
+        self.collision_layers.remove(&id);
+        self.out_of_bounds_policy.remove(&id);
+        self.out_of_bounds.remove(&id);
+        self.pair_cache.clear();
+        self.low_velocity_streak.remove(&id);
+        self.asleep.remove(&id);
+        self.user_data.remove_entity(id);
+    }
+    pub fn set_position(&mut self, id: EntityId, position: Vector2<SubPixelI64>) {
+        let _ = self.try_set_position(id, position);
+    }
+    pub fn try_set_position(
+        &mut self,
+        id: EntityId,
+        position: Vector2<SubPixelI64>,
+    ) -> PhysicsResult<()> {
+        self.require_entity(id)?;
+        let old_aabb = self.shape.get(&id).map(|shape| shape.aabb(self.position[&id]));
+        self.position.insert(id, position);
+        if let (Some(old_aabb), false) = (old_aabb, self.velocity.contains_key(&id)) {
+            self.update_proxy(id, &old_aabb);
+        }
+        self.wake(id);
+        Ok(())
+    }
+    pub fn set_shape(&mut self, id: EntityId, shape: Shape<SubPixelI64>) {
+        let _ = self.try_set_shape(id, shape);
+    }
+    pub fn try_set_shape(
+        &mut self,
+        id: EntityId,
+        shape: Shape<SubPixelI64>,
+    ) -> PhysicsResult<()> {
+        let old_aabb = self.require_shape(id)?.aabb(self.position[&id]);
+        let dynamic = self.velocity.contains_key(&id);
+        if dynamic && !shape.can_move() {
+            return Err(PhysicsError::ImmovableShape);
+        }
+        self.shape.insert(id, shape);
+        self.shape_dirty.insert(id);
+        self.shape_changed.insert(id);
+        if dynamic {
+            self.pair_cache.clear();
+        } else {
+            self.update_proxy(id, &old_aabb);
+        }
+        self.wake(id);
+        Ok(())
+    }
+    pub fn translate_shape(&mut self, id: EntityId, offset: Vector2<SubPixelI64>) {
+        let _ = self.try_translate_shape(id, offset);
+    }
+    pub fn try_translate_shape(
+        &mut self,
+        id: EntityId,
+        offset: Vector2<SubPixelI64>,
+    ) -> PhysicsResult<()> {
+        let (shape, position_offset) = self.require_shape(id)?.translated(offset);
+        if let Some(position) = self.position.get_mut(&id) {
+            *position += position_offset;
+        }
+        self.try_set_shape(id, shape)
+    }
+    pub fn set_start_inside_edge_policy(
+        &mut self,
+        id: EntityId,
+        policy: Option<StartInsideEdgePolicy>,
+    ) {
+        if let Some(policy) = policy {
+            self.start_inside_edge_policy.insert(id, policy);
+        } else {
+            self.start_inside_edge_policy.remove(&id);
+        }
+    }
+    pub fn set_movement_mode(&mut self, id: EntityId, mode: Option<MovementMode>) {
+        if let Some(mode) = mode {
+            self.movement_mode.insert(id, mode);
+        } else {
+            self.movement_mode.remove(&id);
+        }
+    }
+    pub fn movement_mode(&self, id: EntityId) -> MovementMode {
+        self.movement_mode
+            .get(&id)
+            .cloned()
+            .unwrap_or(self.resources.config.movement_mode)
+    }
+    pub fn set_skin(
+        &mut self,
+        id: EntityId,
+        skin: Option<SubPixelI64>,
+    ) -> PhysicsResult<()> {
+        if let Some(skin) = skin {
+            if skin < SubPixelI64::new(0) {
+                return Err(PhysicsError::InvalidSkin);
+            }
+            self.skin.insert(id, skin);
+        } else {
+            self.skin.remove(&id);
+        }
+        Ok(())
+    }
+    pub fn skin(&self, id: EntityId) -> SubPixelI64 {
+        self.skin.get(&id).cloned().unwrap_or(self.resources.config.default_skin)
+    }
+    pub fn set_collision_layers(&mut self, id: EntityId, layers: u32) {
+        if layers == ALL_COLLISION_LAYERS {
+            self.collision_layers.remove(&id);
+        } else {
+            self.collision_layers.insert(id, layers);
+        }
+    }
+    pub fn collision_layers(&self, id: EntityId) -> u32 {
+        self.collision_layers
+            .get(&id)
+            .cloned()
+            .unwrap_or(ALL_COLLISION_LAYERS)
+    }
+    pub fn set_out_of_bounds_policy(
+        &mut self,
+        id: EntityId,
+        policy: Option<OutOfBoundsPolicy>,
+    ) {
+        match policy {
+            Some(policy) => self.out_of_bounds_policy.insert(id, policy),
+            None => self.out_of_bounds_policy.remove(&id),
+        };
+        self.out_of_bounds.remove(&id);
+    }
+    pub fn out_of_bounds_policy(&self, id: EntityId) -> Option<OutOfBoundsPolicy> {
+        self.out_of_bounds_policy.get(&id).cloned()
+    }
+    pub fn set_kill_zone(&mut self, kill_zone: Option<Aabb<SubPixelI64>>) {
+        self.kill_zone = kill_zone;
+    }
+    pub fn kill_zone(&self) -> Option<Aabb<SubPixelI64>> {
+        self.kill_zone
+    }
+    pub fn out_of_bounds_events(&self) -> &[OutOfBoundsEvent] {
+        &self.out_of_bounds_events
+    }
+    fn apply_out_of_bounds_policies(&mut self) {
+        self.out_of_bounds_events.clear();
+        let rect = self.kill_zone.unwrap_or(self.world_bounds);
+        let mut despawn = self.id_buffers.take();
+        let ids = self.id_buffers.sorted(self.out_of_bounds_policy.keys());
+        for &id in ids.iter() {
+            let policy = self.out_of_bounds_policy[&id];
+            if !self.velocity.contains_key(&id) {
+                continue;
+            }
+            let (position, aabb) = match (self.position.get(&id), self.shape.get(&id)) {
+                (Some(&position), Some(shape)) => (position, shape.aabb(position)),
+                _ => continue,
+            };
+            if rect.contains(&aabb) {
+                self.out_of_bounds.remove(&id);
+                continue;
+            }
+            let offset = match policy {
+                OutOfBoundsPolicy::Despawn => {
+                    despawn.push(id);
+                    None
+                }
+                OutOfBoundsPolicy::Clamp => {
+                    Some(out_of_bounds::clamp_offset(&rect, &aabb))
+                }
+                OutOfBoundsPolicy::Wrap => {
+                    let offset = out_of_bounds::wrap_offset(&rect, &aabb);
+                    if sub_pixel_i64::vector_is_zero(offset) {
+                        continue;
+                    }
+                    Some(offset)
+                }
+                OutOfBoundsPolicy::EventOnly => {
+                    if !self.out_of_bounds.insert(id) {
+                        continue;
+                    }
+                    None
+                }
+            };
+            if let Some(offset) = offset {
+                self.position.insert(id, position + offset);
+            }
+            self.out_of_bounds_events.push(OutOfBoundsEvent {
+                entity_id: id,
+                position,
+                policy,
+            });
+        }
+        self.id_buffers.recycle(ids);
+        self.out_of_bounds_events
+            .sort_unstable_by_key(|event| event.entity_id);
+        for &id in despawn.iter() {
+            self.remove_entity(id);
+        }
+        self.id_buffers.recycle(despawn);
+    }
+    /// Replaces the contents of `overlapping` with the entities on a layer in
+    /// `mask` that overlap `shape` at `position`, in id order. Passing the same
+    /// buffer to each query avoids allocating.
+    pub fn overlap_shape(
+        &self,
+        shape: &Shape<SubPixelI64>,
+        position: Vector2<SubPixelI64>,
+        mask: u32,
+        overlapping: &mut Vec<EntityId>,
+    ) {
+        overlapping.clear();
+        {
+            let mut test = |id: EntityId| {
+                if self.collision_layers(id) & mask == 0 {
+                    return;
+                }
+                if let (Some(other_position), Some(other_shape)) =
+                    (self.position.get(&id), self.shape.get(&id))
+                {
+                    let tolerance = Zero::zero();
+                    if shape.overlaps(position, other_shape, *other_position, tolerance) {
+                        overlapping.push(id);
+                    }
+                }
+            };
+            let aabb = shape.aabb(position);
+            self.for_each_static(&aabb, &mut test);
+            for &id in self.velocity.keys() {
+                test(id);
+            }
+        }
+        overlapping.sort_unstable();
+        overlapping.dedup();
+    }
+    /// Like `overlap_shape`, but fills `visible` with the entities in the cone
+    /// that have a clear line of sight from `origin`, nearest first.
+    pub fn entities_in_cone(
+        &self,
+        origin: Vector2<SubPixelI64>,
+        direction: Angle,
+        half_angle: Angle,
+        range: SubPixelI64,
+        mask: u32,
+        visible: &mut Vec<EntityId>,
+    ) {
+        let range2 = {
+            let range = physics_num::widen(range);
+            range * range
+        };
+        visible.clear();
+        {
+            let mut test = |id: EntityId| {
+                if self.collision_layers(id) & mask == 0 {
+                    return;
+                }
+                let offset = match self.centre(id) {
+                    Some(centre) => centre - origin,
+                    None => return,
+                };
+                let distance2 = physics_num::magnitude2_wide(offset);
+                if distance2 > range2 {
+                    return;
+                }
+                if let Some(angle) = Angle::of_vector(offset) {
+                    if angle.distance(direction) > half_angle {
+                        return;
+                    }
+                    let filter = |other_id| other_id != id;
+                    let layers = ALL_COLLISION_LAYERS;
+                    if self.raycast_filtered(origin, offset, layers, false, filter)
+                        .is_some()
+                    {
+                        return;
+                    }
+                }
+                visible.push(id);
+            };
+            let area = Aabb::from_centre_and_half_size(origin, vec2(range, range));
+            self.for_each_static(&area, &mut test);
+            for &id in self.velocity.keys() {
+                test(id);
+            }
+        }
+        visible.sort_unstable();
+        visible.dedup();
+        visible.sort_unstable_by_key(|&id| {
+            let offset = self.centre(id).map(|centre| centre - origin);
+            (offset.map(physics_num::magnitude2_wide), id)
+        });
+    }
+    fn raycast_filtered<F>(
+        &self,
+        start: Vector2<SubPixelI64>,
+        ray: Vector2<SubPixelI64>,
+        mask: u32,
+        include_dynamic: bool,
+        filter: F,
+    ) -> Option<RaycastHit>
+    where
+        F: Fn(EntityId) -> bool,
+    {
+        let mut closest = BestMap::new();
+        {
+            let mut test = |id: EntityId| {
+                if self.collision_layers(id) & mask == 0 || !filter(id) {
+                    return;
+                }
+                if let (Some(position), Some(shape)) =
+                    (self.position.get(&id), self.shape.get(&id))
+                {
+                    if let Some(collision_info) = shape.raycast(*position, start, ray) {
+                        closest.insert_lt(
+                            (collision_info.magnitude2, id),
+                            RaycastHit {
+                                entity_id: id,
+                                point: start + collision_info.allowed_movement,
+                                surface: collision_info.line_segment,
+                                material: self.material.get(&id).cloned(),
+                            },
+                        );
+                    }
+                }
+            };
+            let zero = Zero::zero();
+            let aabb = Shape::LineSegment(LineSegment::new(vec2(zero, zero), ray))
+                .aabb(start);
+            self.for_each_static(&aabb, &mut test);
+            if include_dynamic {
+                for &id in self.velocity.keys() {
+                    test(id);
+                }
+            }
+        }
+        closest.into_value()
+    }
+    pub fn raycast(
+        &self,
+        start: Vector2<SubPixelI64>,
+        ray: Vector2<SubPixelI64>,
+        mask: u32,
+    ) -> Option<RaycastHit> {
+        self.raycast_filtered(start, ray, mask, true, |_| true)
+    }
+    fn centre(&self, id: EntityId) -> Option<Vector2<SubPixelI64>> {
+        match (self.position.get(&id), self.shape.get(&id)) {
+            (Some(position), Some(shape)) => Some(shape.aabb(*position).centre()),
+            _ => None,
+        }
+    }
+    pub fn set_rope(&mut self, id: EntityId, rope: Option<Rope>) {
+        if let Some(rope) = rope {
+            self.rope.insert(id, rope);
+            self.wake(id);
+        } else {
+            self.rope.remove(&id);
+        }
+    }
+    pub fn attach_rope(&mut self, id: EntityId, anchor: Vector2<SubPixelI64>) {
+        if let Some(attachment) = self.centre(id) {
+            let length = physics_num::magnitude(attachment - anchor);
+            self.set_rope(id, Some(Rope::new(anchor, length)));
+        }
+    }
+    pub fn rope(&self, id: EntityId) -> Option<&Rope> {
+        self.rope.get(&id)
+    }
+    pub fn set_rope_length(&mut self, id: EntityId, length: SubPixelI64) -> bool {
+        if let Some(rope) = self.rope.get_mut(&id) {
+            *rope = Rope::new(rope.anchor, length);
+        } else {
+            return false;
+        }
+        self.wake(id);
+        true
+    }
+    pub fn rope_blocked(&self, id: EntityId) -> Option<RopeBlocked> {
+        let rope = self.rope.get(&id)?;
+        let attachment = self.centre(id)?;
+        self.raycast_filtered(
+            rope.anchor,
+            attachment - rope.anchor,
+            self.collision_layers(id),
+            true,
+            |other_id| other_id != id,
+        ).map(|hit| RopeBlocked {
+            entity_id: id,
+            blocker_id: hit.entity_id,
+            point: hit.point,
+        })
+    }
+    fn apply_rope_constraints(&mut self) {
+        let ids = self.id_buffers.sorted(self.rope.keys());
+        for &id in ids.iter() {
+            if self.asleep.contains(&id) {
+                continue;
+            }
+            let rope = self.rope[&id];
+            let (attachment, velocity) =
+                match (self.centre(id), self.velocity.get(&id)) {
+                    (Some(attachment), Some(velocity)) => (attachment, *velocity),
+                    _ => continue,
+                };
+            let correction = match rope.constrain(attachment, velocity) {
+                Some(correction) => correction,
+                None => continue,
+            };
+            self.velocity.insert(id, correction.velocity);
+            let island_index = match self.islands
+                .iter()
+                .position(|island| island.contains(&id))
+            {
+                Some(island_index) => island_index,
+                None => continue,
+            };
+            let destination = match self.push_step(id, correction.offset, island_index) {
+                MovementStep::NoMovement => continue,
+                MovementStep::NoCollision { destination } => destination,
+                MovementStep::Collision { destination, .. } => destination,
+            };
+            self.position.insert(id, destination);
+        }
+        self.id_buffers.recycle(ids);
+    }
+    pub fn set_attractor(&mut self, id: EntityId, attractor: Option<Attractor>) {
+        if let Some(attractor) = attractor {
+            self.attractor.insert(id, attractor);
+        } else {
+            self.attractor.remove(&id);
+        }
+    }
+    pub fn attractor(&self, id: EntityId) -> Option<&Attractor> {
+        self.attractor.get(&id)
+    }
+    pub fn apply_force(&mut self, id: EntityId, force: Vector2<SubPixelI64>) {
+        if !self.velocity.contains_key(&id) || sub_pixel_i64::vector_is_zero(force) {
+            return;
+        }
+        let total = self.force
+            .get_or_insert_with(id, || vec2(Zero::zero(), Zero::zero()));
+        *total += force;
+        self.wake(id);
+    }
+    pub fn force(&self, id: EntityId) -> Vector2<SubPixelI64> {
+        self.force
+            .get(&id)
+            .cloned()
+            .unwrap_or_else(|| vec2(Zero::zero(), Zero::zero()))
+    }
+    pub fn set_gravity(&mut self, id: EntityId, gravity: Option<GravityOverride>) {
+        if let Some(gravity) = gravity {
+            self.gravity.insert(id, gravity);
+        } else {
+            self.gravity.remove(&id);
+        }
+        self.resting.remove(&id);
+        self.wake(id);
+    }
+    pub fn gravity_override(&self, id: EntityId) -> Option<&GravityOverride> {
+        self.gravity.get(&id)
+    }
+    pub fn gravity(&self, id: EntityId) -> Vector2<SubPixelI64> {
+        match self.gravity.get(&id) {
+            Some(gravity) => gravity.apply(self.resources.config.gravity),
+            None => self.resources.config.gravity,
+        }
+    }
+    pub fn set_drag(&mut self, id: EntityId, drag: Option<Drag>) {
+        if let Some(drag) = drag {
+            self.drag.insert(id, drag);
+        } else {
+            self.drag.remove(&id);
+        }
+    }
+    pub fn drag(&self, id: EntityId) -> Drag {
+        self.drag.get(&id).cloned().unwrap_or_default()
+    }
+    pub fn materials(&self) -> &MaterialRegistry {
+        &self.materials
+    }
+    pub fn materials_mut(&mut self) -> &mut MaterialRegistry {
+        &mut self.materials
+    }
+    pub fn set_material(&mut self, id: EntityId, material: Option<MaterialId>) {
+        if let Some(material) = material {
+            self.material.insert(id, material);
+        } else {
+            self.material.remove(&id);
+        }
+    }
+    pub fn material(&self, id: EntityId) -> Option<MaterialId> {
+        self.material.get(&id).cloned()
+    }
+    pub fn contact_events(&self) -> &[ContactEvent] {
+        &self.contact_events
+    }
+    pub fn impact_events(&self) -> &[ContactEvent] {
+        &self.impact_events
+    }
+    pub fn set_bounce_pad(&mut self, id: EntityId, bounce_pad: Option<BouncePad>) {
+        if let Some(bounce_pad) = bounce_pad {
+            self.bounce_pad.insert(id, bounce_pad);
+        } else {
+            self.bounce_pad.remove(&id);
+        }
+    }
+    pub fn bounce_pad(&self, id: EntityId) -> Option<&BouncePad> {
+        self.bounce_pad.get(&id)
+    }
+    pub fn launch_events(&self) -> &[LaunchEvent] {
+        &self.launch_events
+    }
+    fn touch_surface(&mut self, id: EntityId, contact: Contact) -> bool {
+        let other_id = contact.other_id;
+        let material = self.material(other_id);
+        let velocity = match self.velocity.get(&id) {
+            Some(&velocity) => velocity,
+            None => return false,
+        };
+        let other_velocity = self.velocity
+            .get(&other_id)
+            .cloned()
+            .unwrap_or_else(|| vec2(Zero::zero(), Zero::zero()));
+        let approach_speed = match self.contact_normal(id, contact) {
+            Some(normal) => impulse::approach_speed(velocity, other_velocity, normal),
+            None => Zero::zero(),
+        };
+        let event = ContactEvent {
+            entity_id: id,
+            other_id,
+            surface: contact.surface,
+            material,
+            entity_material: self.material(id),
+            approach_speed,
+        };
+        self.contact_events.push(event);
+        let key = (id, other_id);
+        self.touching.insert(key);
+        let threshold = self.resources.config.impact_threshold;
+        if approach_speed > Zero::zero() && approach_speed >= threshold
+            && !self.previously_touching.contains(&key)
+        {
+            self.impact_events.push(event);
+        }
+        let velocity = match material.and_then(|material| self.materials.get(material)) {
+            Some(material) => {
+                let surface = contact.surface.vector();
+                let velocity = material.surface_response(velocity, surface);
+                self.velocity.insert(id, velocity);
+                velocity
+            }
+            None => velocity,
+        };
+        let bounce_pad = match self.bounce_pad.get(&other_id) {
+            Some(&bounce_pad) => bounce_pad,
+            None => return false,
+        };
+        let gravity = self.gravity(id);
+        let launch = match self.contact_normal(id, contact)
+            .and_then(|normal| bounce_pad.launch(velocity, normal, gravity))
+        {
+            Some(launch) => launch,
+            None => return false,
+        };
+        self.set_velocity(id, launch);
+        self.launch_events.push(LaunchEvent {
+            entity_id: id,
+            pad_id: other_id,
+            velocity: launch,
+        });
+        true
+    }
+    fn contact_restitution(&self, id: EntityId) -> Restitution {
+        let restitution = self.restitution(id);
+        match self.material(id).and_then(|material| self.materials.get(material)) {
+            Some(material) => restitution.min(material.restitution),
+            None => restitution,
+        }
+    }
+    pub fn add_portal_pair(&mut self, portal_pair: PortalPair) -> usize {
+        self.portals.push(portal_pair);
+        self.portals.len() - 1
+    }
+    pub fn portal_pairs(&self) -> &[PortalPair] {
+        &self.portals
+    }
+    pub fn portal_pair_mut(&mut self, index: usize) -> Option<&mut PortalPair> {
+        self.portals.get_mut(index)
+    }
+    pub fn teleport_events(&self) -> &[TeleportEvent] {
+        &self.teleport_events
+    }
+    fn apply_portals(&mut self) {
+        if self.portals.is_empty() {
+            return;
+        }
+        let ids = self.id_buffers.sorted(self.velocity.keys());
+        for &id in ids.iter() {
+            let (position, aabb, velocity) = match (
+                self.position.get(&id),
+                self.shape.get(&id),
+                self.velocity.get(&id),
+            ) {
+                (Some(&position), Some(shape), Some(&velocity)) => {
+                    (position, shape.aabb(position), velocity)
+                }
+                _ => continue,
+            };
+            if self.portal_suppressed.contains(&id) {
+                let touching = self.portals.iter().any(|pair| {
+                    pair.a.region.is_intersecting(&aabb)
+                        || pair.b.region.is_intersecting(&aabb)
+                });
+                if !touching {
+                    self.portal_suppressed.remove(&id);
+                }
+                continue;
+            }
+            let entry = self.portals
+                .iter()
+                .enumerate()
+                .filter_map(|(index, pair)| {
+                    if pair.a.is_entering(&aabb, velocity) {
+                        Some((index, PortalSide::A, pair.a, pair.b))
+                    } else if pair.b.is_entering(&aabb, velocity) {
+                        Some((index, PortalSide::B, pair.b, pair.a))
+                    } else {
+                        None
+                    }
+                })
+                .next();
+            let (pair_index, entered, from, to) = match entry {
+                Some(entry) => entry,
+                None => continue,
+            };
+            let transfer = from.transfer(&to, &aabb, velocity);
+            let position = position + (transfer.centre - aabb.centre());
+            self.position.insert(id, position);
+            self.set_velocity(id, transfer.velocity);
+            self.movement_remainder.remove(&id);
+            self.pair_cache.remove(id);
+            self.portal_suppressed.insert(id);
+            self.teleport_events.push(TeleportEvent {
+                entity_id: id,
+                pair_index,
+                entered,
+                position,
+                velocity: transfer.velocity,
+            });
+        }
+        self.id_buffers.recycle(ids);
+    }
+    pub fn set_hazard(&mut self, id: EntityId, hazard: Option<Hazard>) {
+        if let Some(hazard) = hazard {
+            self.hazard.insert(id, hazard);
+        } else {
+            self.hazard.remove(&id);
+            self.hazard_cooldowns.remove_entity(id);
+        }
+    }
+    pub fn hazard(&self, id: EntityId) -> Option<&Hazard> {
+        self.hazard.get(&id)
+    }
+    pub fn damage_events(&self) -> &[DamageEvent] {
+        &self.damage_events
+    }
+    fn apply_hazards(&mut self) {
+        if self.hazard.is_empty() {
+            return;
+        }
+        self.hazard_cooldowns.tick();
+        for i in 0..self.contact_events.len() {
+            let event = self.contact_events[i];
+            let pairs = [
+                (event.other_id, event.entity_id),
+                (event.entity_id, event.other_id),
+            ];
+            for &(hazard_id, target_id) in pairs.iter() {
+                let hazard = match self.hazard.get(&hazard_id) {
+                    Some(&hazard) => hazard,
+                    None => continue,
+                };
+                if self.damage_events.iter().any(|event| {
+                    event.hazard_id == hazard_id && event.target_id == target_id
+                }) {
+                    continue;
+                }
+                if !self.hazard_cooldowns
+                    .try_trigger(hazard_id, target_id, hazard.cooldown)
+                {
+                    continue;
+                }
+                self.apply_impulse(target_id, hazard.knockback);
+                self.damage_events.push(DamageEvent {
+                    hazard_id,
+                    target_id,
+                    damage: hazard.damage,
+                    knockback: hazard.knockback,
+                });
+            }
+        }
+    }
+    pub fn set_health(&mut self, id: EntityId, health: Option<Health>) {
+        if let Some(health) = health {
+            self.health.insert(id, health);
+        } else {
+            self.health.remove(&id);
+        }
+    }
+    pub fn health(&self, id: EntityId) -> Option<&Health> {
+        self.health.get(&id)
+    }
+    pub fn heal(&mut self, id: EntityId, amount: u32) {
+        if let Some(health) = self.health.get_mut(&id) {
+            health.heal(amount);
+        }
+    }
+    pub fn damage(
+        &mut self,
+        id: EntityId,
+        amount: u32,
+        source_id: Option<EntityId>,
+    ) -> DamageOutcome {
+        let outcome = match self.health.get_mut(&id) {
+            Some(health) => health.damage(amount),
+            None => return DamageOutcome::Ignored,
+        };
+        if outcome == DamageOutcome::Killed {
+            self.death_events.push(DeathEvent {
+                entity_id: id,
+                source_id,
+            });
+        }
+        outcome
+    }
+    pub fn death_events(&self) -> &[DeathEvent] {
+        &self.death_events
+    }
+    fn apply_damage_events(&mut self) {
+        if self.health.is_empty() {
+            return;
+        }
+        let invulnerable = |_: &EntityId, health: &Health| health.is_invulnerable();
+        for (_, health) in self.health.iter_mut_where(invulnerable) {
+            health.tick();
+        }
+        for i in 0..self.damage_events.len() {
+            let event = self.damage_events[i];
+            self.damage(event.target_id, event.damage, Some(event.hazard_id));
+        }
+    }
+    pub fn set_sensor(&mut self, id: EntityId, sensor: bool) {
+        if sensor {
+            self.sensor.insert(id);
+        } else {
+            self.sensor.remove(&id);
+        }
+        self.pair_cache.remove(id);
+    }
+    pub fn is_sensor(&self, id: EntityId) -> bool {
+        self.sensor.contains(&id)
+    }
+    pub fn set_pickup(&mut self, id: EntityId, pickup: Option<Pickup>) {
+        if let Some(pickup) = pickup {
+            self.pickup.insert(id, pickup);
+            self.set_sensor(id, true);
+        } else {
+            self.pickup.remove(&id);
+        }
+    }
+    pub fn pickup(&self, id: EntityId) -> Option<&Pickup> {
+        self.pickup.get(&id)
+    }
+    pub fn set_collector(&mut self, id: EntityId, collector: bool) {
+        if collector {
+            self.collector.insert(id);
+        } else {
+            self.collector.remove(&id);
+        }
+    }
+    pub fn is_collector(&self, id: EntityId) -> bool {
+        self.collector.contains(&id)
+    }
+    pub fn pickup_events(&self) -> &[PickupEvent] {
+        &self.pickup_events
+    }
+    pub fn nearest_entity<F>(
+        &self,
+        point: Vector2<SubPixelI64>,
+        max_distance: SubPixelI64,
+        filter: F,
+    ) -> Option<EntityId>
+    where
+        F: Fn(EntityId) -> bool,
+    {
+        let max_distance2 = {
+            let max_distance = physics_num::widen(max_distance);
+            max_distance * max_distance
+        };
+        let mut nearest = BestMap::new();
+        {
+            let mut test = |id: EntityId| {
+                if !filter(id) {
+                    return;
+                }
+                if let Some(centre) = self.centre(id) {
+                    let distance2 = physics_num::magnitude2_wide(centre - point);
+                    if distance2 <= max_distance2 {
+                        nearest.insert_lt((distance2, id), id);
+                    }
+                }
+            };
+            let half_size = vec2(max_distance, max_distance);
+            let area = Aabb::from_centre_and_half_size(point, half_size);
+            self.for_each_static(&area, &mut test);
+            for &id in self.velocity.keys() {
+                test(id);
+            }
+        }
+        nearest.into_value()
+    }
+    pub fn set_path_follower(
+        &mut self,
+        id: EntityId,
+        path_follower: Option<PathFollower>,
+    ) {
+        if let Some(path_follower) = path_follower {
+            self.path_follower.insert(id, path_follower);
+        } else {
+            self.path_follower.remove(&id);
+        }
+    }
+    pub fn path_follower(&self, id: EntityId) -> Option<&PathFollower> {
+        self.path_follower.get(&id)
+    }
+    pub fn path_follower_mut(&mut self, id: EntityId) -> Option<&mut PathFollower> {
+        self.path_follower.get_mut(&id)
+    }
+    fn drive_path_followers(&mut self) {
+        if self.path_follower.is_empty() {
+            return;
+        }
+        let ids = self.id_buffers.sorted(self.path_follower.keys());
+        for &id in ids.iter() {
+            let position = match self.position.get(&id) {
+                Some(&position) => position,
+                None => continue,
+            };
+            let velocity = match self.path_follower.get_mut(&id) {
+                Some(path_follower) => path_follower.velocity(position),
+                None => continue,
+            };
+            self.set_velocity(id, velocity);
+        }
+        self.id_buffers.recycle(ids);
+    }
+    pub fn set_homing(&mut self, id: EntityId, homing: Option<Homing>) {
+        if let Some(homing) = homing {
+            self.homing.insert(id, homing);
+        } else {
+            self.homing.remove(&id);
+        }
+    }
+    pub fn homing(&self, id: EntityId) -> Option<&Homing> {
+        self.homing.get(&id)
+    }
+    /// Entities homing on a removed entity lose it immediately, rather than
+    /// chasing wherever it was last seen (or a new entity given the same id).
+    fn lose_homing_target(&mut self, target_id: EntityId) {
+        if self.homing.is_empty() {
+            return;
+        }
+        let ids = self.id_buffers.sorted(self.homing.keys());
+        for &id in ids.iter() {
+            if self.homing[&id].target() == target_id {
+                self.homing.remove(&id);
+                self.target_lost_events.push(TargetLostEvent {
+                    entity_id: id,
+                    target_id,
+                });
+            }
+        }
+        self.id_buffers.recycle(ids);
+    }
+    pub fn target_lost_events(&self) -> &[TargetLostEvent] {
+        &self.target_lost_events
+    }
+    fn steer_homing(&mut self) {
+        if self.homing.is_empty() {
+            return;
+        }
+        let ids = self.id_buffers.sorted(self.homing.keys());
+        for &id in ids.iter() {
+            let (centre, velocity) = match (self.centre(id), self.velocity.get(&id)) {
+                (Some(centre), Some(&velocity)) => (centre, velocity),
+                _ => continue,
+            };
+            let homing = self.homing[&id];
+            let target_id = homing.target();
+            let sighting = self.centre(target_id).filter(|&target_centre| {
+                if !homing.requires_line_of_sight() {
+                    return true;
+                }
+                let filter = |other_id| other_id != id && other_id != target_id;
+                let layers = ALL_COLLISION_LAYERS;
+                let ray = target_centre - centre;
+                self.raycast_filtered(centre, ray, layers, false, filter).is_none()
+            });
+            let aim = match self.homing.get_mut(&id) {
+                Some(homing) => homing.observe(sighting),
+                None => continue,
+            };
+            match aim {
+                Some(aim) => {
+                    let max_turn = homing.max_turn();
+                    let velocity = homing::steer(velocity, aim - centre, max_turn);
+                    self.set_velocity(id, velocity);
+                }
+                None => {
+                    self.homing.remove(&id);
+                    self.target_lost_events.push(TargetLostEvent {
+                        entity_id: id,
+                        target_id,
+                    });
+                }
+            }
+        }
+        self.id_buffers.recycle(ids);
+    }
+    pub fn camera(&self) -> &Camera {
+        &self.resources.camera
+    }
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.resources.camera
+    }
+    pub fn subscribe_frames(&mut self) -> FrameSubscriber {
+        self.frame_publisher
+            .get_or_insert_with(FramePublisher::new)
+            .subscribe()
+    }
+    fn publish_frame(&mut self) {
+        if let Some(mut publisher) = self.frame_publisher.take() {
+            let view = self.resources.camera.view();
+            publisher.begin(self.frame_count(), view);
+            for update in self.render_updates() {
+                publisher.push(update);
+            }
+            publisher.publish();
+            self.frame_publisher = Some(publisher);
+        }
+    }
+    fn accumulate_pickup_magnets(&mut self) {
+        if self.pickup.is_empty() || self.collector.is_empty() {
+            return;
+        }
+        let ids = self.id_buffers.sorted(self.pickup.keys());
+        for &id in ids.iter() {
+            let pickup = self.pickup[&id];
+            if !self.velocity.contains_key(&id) {
+                continue;
+            }
+            let centre = match self.centre(id) {
+                Some(centre) => centre,
+                None => continue,
+            };
+            let collector = self.nearest_entity(centre, pickup.magnet_radius, |other_id| {
+                self.collector.contains(&other_id)
+            });
+            let pull = match collector
+                .and_then(|collector_id| self.centre(collector_id))
+                .and_then(|target| pickup.magnet_pull(target - centre))
+            {
+                Some(pull) => pull,
+                None => continue,
+            };
+            let mass = self.mass(id).unwrap_or(1);
+            self.apply_force(id, vec2(pull.x * mass, pull.y * mass));
+        }
+        self.id_buffers.recycle(ids);
+    }
+    fn collect_pickups(&mut self) {
+        if self.pickup.is_empty() || self.collector.is_empty() {
+            return;
+        }
+        let collectors = self.id_buffers.sorted(self.collector.iter());
+        let ids = self.id_buffers.sorted(self.pickup.keys());
+        let mut collected = self.id_buffers.take();
+        for &id in ids.iter() {
+            let (position, shape) = match (self.position.get(&id), self.shape.get(&id)) {
+                (Some(position), Some(shape)) => (*position, shape),
+                _ => continue,
+            };
+            let collector_id = collectors.iter().cloned().find(|&collector_id| {
+                collector_id != id
+                    && match (
+                        self.position.get(&collector_id),
+                        self.shape.get(&collector_id),
+                    ) {
+                        (Some(&other_position), Some(other_shape)) => shape.overlaps(
+                            position,
+                            other_shape,
+                            other_position,
+                            Zero::zero(),
+                        ),
+                        _ => false,
+                    }
+            });
+            if let Some(collector_id) = collector_id {
+                self.pickup_events.push(PickupEvent {
+                    pickup_id: id,
+                    collector_id,
+                    payload: self.pickup[&id].payload,
+                });
+                collected.push(id);
+            }
+        }
+        for &id in collected.iter() {
+            self.remove_entity(id);
+        }
+        self.id_buffers.recycle(collected);
+        self.id_buffers.recycle(ids);
+        self.id_buffers.recycle(collectors);
+    }
+    pub fn set_lifetime(&mut self, id: EntityId, lifetime: Option<Lifetime>) {
+        if let Some(lifetime) = lifetime {
+            self.lifetime.insert(id, lifetime);
+        } else {
+            self.lifetime.remove(&id);
+        }
+    }
+    pub fn lifetime(&self, id: EntityId) -> Option<&Lifetime> {
+        self.lifetime.get(&id)
+    }
+    fn tick_lifetimes(&mut self) {
+        if self.lifetime.is_empty() {
+            return;
+        }
+        let ids = self.id_buffers.sorted(self.lifetime.keys());
+        let ticks = self.time_ticks;
+        for &id in ids.iter() {
+            let expired = self.lifetime
+                .get_mut(&id)
+                .is_some_and(|lifetime| (0..ticks).any(|_| lifetime.tick()));
+            if !expired {
+                continue;
+            }
+            if let Some(lifetime) = self.lifetime.remove(&id) {
+                if lifetime.emits_death_event() {
+                    self.death_events.push(DeathEvent {
+                        entity_id: id,
+                        source_id: None,
+                    });
+                }
+            }
+            self.commands.despawn(id);
+        }
+        self.id_buffers.recycle(ids);
+    }
+    pub fn scheduler(&self) -> &Scheduler {
+        &self.scheduler
+    }
+    pub fn scheduler_mut(&mut self) -> &mut Scheduler {
+        &mut self.scheduler
+    }
+    pub fn timer_events(&self) -> &[TimerEvent] {
+        &self.timer_events
+    }
+    pub fn set_state_machine(
+        &mut self,
+        id: EntityId,
+        state_machine: Option<StateMachine>,
+    ) {
+        if let Some(state_machine) = state_machine {
+            self.state_machine.insert(id, state_machine);
+        } else {
+            self.state_machine.remove(&id);
+        }
+    }
+    pub fn state_machine(&self, id: EntityId) -> Option<&StateMachine> {
+        self.state_machine.get(&id)
+    }
+    pub fn signal(&mut self, id: EntityId, name: &str) {
+        self.signals.push((id, name.to_string()));
+    }
+    pub fn state_change_events(&self) -> &[StateChangeEvent] {
+        &self.state_change_events
+    }
+    fn evaluate_state_machines(&mut self) {
+        if self.state_machine.is_empty() {
+            self.signals.clear();
+            return;
+        }
+        let player_centre = self.player_id.and_then(|player_id| self.centre(player_id));
+        let ids = self.id_buffers.sorted(self.state_machine.keys());
+        let mut contacted = self.id_buffers.take();
+        for event in self.contact_events.iter() {
+            contacted.push(event.entity_id);
+            contacted.push(event.other_id);
+        }
+        contacted.sort_unstable();
+        self.signals.sort_unstable_by_key(|&(target, _)| target);
+        self.timer_targets.clear();
+        self.timer_targets.extend(self.timer_events.iter().filter_map(|event| {
+            event.entity.map(|entity| (entity, event.name.clone()))
+        }));
+        self.timer_targets.sort_unstable_by_key(|&(target, _)| target);
+        for &id in ids.iter() {
+            let contact = contacted.binary_search(&id).is_ok();
+            let distance2 = match (player_centre, self.centre(id)) {
+                (Some(player_centre), Some(centre)) if Some(id) != self.player_id => {
+                    Some(physics_num::magnitude2_wide(player_centre - centre))
+                }
+                _ => None,
+            };
+            let signals = targeted(&self.signals, id);
+            let timer_targets = targeted(&self.timer_targets, id);
+            let state_machine = match self.state_machine.get_mut(&id) {
+                Some(state_machine) => state_machine,
+                None => continue,
+            };
+            let from = state_machine.state();
+            let changed = state_machine.step(|trigger| match *trigger {
+                Trigger::After(_) => false,
+                Trigger::Event(ref name) => {
+                    signals.iter().any(|(_, signal)| signal == name)
+                        || timer_targets.iter().any(|(_, timer)| **timer == **name)
+                }
+                Trigger::Contact => contact,
+                Trigger::PlayerWithin(radius) => distance2.is_some_and(|distance2| {
+                    let radius = physics_num::widen(radius);
+                    distance2 <= radius * radius
+                }),
+                Trigger::PlayerBeyond(radius) => distance2.is_some_and(|distance2| {
+                    let radius = physics_num::widen(radius);
+                    distance2 > radius * radius
+                }),
+            });
+            if let Some(to) = changed {
+                self.state_change_events.push(StateChangeEvent {
+                    entity_id: id,
+                    from,
+                    to,
+                });
+            }
+        }
+        self.id_buffers.recycle(ids);
+        self.id_buffers.recycle(contacted);
+        self.signals.clear();
+    }
+    fn accumulate_attractor_forces(&mut self) {
+        if self.attractor.is_empty() {
+            return;
+        }
+        let attractor_ids = self.id_buffers.sorted(self.attractor.keys());
+        let body_ids = self.id_buffers.sorted(self.velocity.keys());
+        for &attractor_id in attractor_ids.iter() {
+            let attractor = self.attractor[&attractor_id];
+            let centre = match self.centre(attractor_id) {
+                Some(centre) => centre,
+                None => continue,
+            };
+            for &id in body_ids.iter() {
+                if id == attractor_id {
+                    continue;
+                }
+                let acceleration = match self.centre(id)
+                    .and_then(|position| attractor.acceleration(centre - position))
+                {
+                    Some(acceleration) => acceleration,
+                    None => continue,
+                };
+                let mass = self.mass(id).unwrap_or(1);
+                self.apply_force(id, vec2(acceleration.x * mass, acceleration.y * mass));
+            }
+        }
+        self.id_buffers.recycle(body_ids);
+        self.id_buffers.recycle(attractor_ids);
+    }
+    pub fn add_verlet_system(&mut self, system: VerletSystem) -> usize {
+        self.verlet.push(system);
+        self.verlet.len() - 1
+    }
+    pub fn verlet_systems(&self) -> &[VerletSystem] {
+        &self.verlet
+    }
+    pub fn verlet_system_mut(&mut self, index: usize) -> Option<&mut VerletSystem> {
+        self.verlet.get_mut(index)
+    }
+    pub fn particles(&self) -> &ParticleSystem {
+        &self.particles
+    }
+    pub fn particles_mut(&mut self) -> &mut ParticleSystem {
+        &mut self.particles
+    }
+    fn step_particles(&mut self) {
+        if self.particles.is_empty() {
+            return;
+        }
+        let mut particles = mem::replace(
+            &mut self.particles,
+            ParticleSystem::new(self.resources.config.gravity, 0),
+        );
+        particles.step(|start, movement| {
+            self.raycast_filtered(start, movement, ALL_COLLISION_LAYERS, false, |_| true)
+                .map(|hit| (hit.point, hit.surface))
+        });
+        self.particles = particles;
+    }
+    fn step_verlet_systems(&mut self) {
+        let mut systems = std::mem::take(&mut self.verlet);
+        for system in systems.iter_mut() {
+            system.step(|start, movement| {
+                self.raycast(start, movement, ALL_COLLISION_LAYERS)
+                    .map(|hit| hit.point)
+            });
+        }
+        self.verlet = systems;
+    }
+    pub fn set_pushable(&mut self, id: EntityId, pushable: bool) {
+        if pushable {
+            self.pushable.insert(id);
+        } else {
+            self.pushable.remove(&id);
+        }
+    }
+    pub fn is_pushable(&self, id: EntityId) -> bool {
+        self.pushable.contains(&id)
+    }
+    fn push_step(
+        &mut self,
+        id: EntityId,
+        movement: Vector2<SubPixelI64>,
+        island_index: usize,
+    ) -> MovementStep {
+        let position = match self.position.get(&id) {
+            Some(position) => *position,
+            None => return MovementStep::NoMovement,
+        };
+        let start_inside_edge_policy = self.start_inside_edge_policy
+            .get(&id)
+            .cloned()
+            .unwrap_or(self.resources.config.start_inside_edge_policy);
+        let world = CollisionWorld {
+            position: &self.position,
+            shape: &self.shape,
+            velocity: &self.velocity,
+            sensor: &self.sensor,
+            skin: &self.skin,
+            island: &self.islands[island_index],
+            broadphase: &self.broadphase,
+            baked: self.baked.as_deref(),
+            config: &self.resources.config,
+            wrap_bounds: self.wrap_bounds(),
+        };
+        movement_step(
+            id,
+            position,
+            &world,
+            &mut self.pair_cache,
+            movement,
+            start_inside_edge_policy,
+            &mut self.stats,
+        )
+    }
+    fn push_blocker(
+        &mut self,
+        id: EntityId,
+        movement: Vector2<SubPixelI64>,
+        island_index: usize,
+        chain_length: usize,
+    ) {
+        if chain_length >= self.resources.config.max_push_chain {
+            return;
+        }
+        if let MovementStep::Collision {
+            allowed_movement,
+            other_id,
+            start_inside_normal: None,
+            ..
+        } = self.push_step(id, movement, island_index)
+        {
+            if self.pushable.contains(&other_id)
+                && self.velocity.contains_key(&other_id)
+            {
+                self.push_entity(
+                    other_id,
+                    movement - allowed_movement,
+                    island_index,
+                    chain_length + 1,
+                );
+            }
+        }
+    }
+    fn push_entity(
+        &mut self,
+        id: EntityId,
+        movement: Vector2<SubPixelI64>,
+        island_index: usize,
+        chain_length: usize,
+    ) {
+        self.push_blocker(id, movement, island_index, chain_length);
+        let destination = match self.push_step(id, movement, island_index) {
+            MovementStep::NoMovement => return,
+            MovementStep::NoCollision { destination } => destination,
+            MovementStep::Collision { destination, .. } => destination,
+        };
+        let destination = match self.wrap_bounds() {
+            Some(bounds) => bounds.wrap_point(destination),
+            None => destination,
+        };
+        self.position.insert(id, destination);
+        self.wake(id);
+    }
+    pub fn is_asleep(&self, id: EntityId) -> bool {
+        self.asleep.contains(&id)
+    }
+    pub fn wake(&mut self, id: EntityId) {
+        self.asleep.remove(&id);
+        self.low_velocity_streak.remove(&id);
+    }
+    pub fn try_set_velocity(
+        &mut self,
+        id: EntityId,
+        velocity: Vector2<SubPixelI64>,
+    ) -> PhysicsResult<()> {
+        self.require_velocity(id)?;
+        self.set_velocity(id, velocity);
+        Ok(())
+    }
+    pub fn set_velocity(&mut self, id: EntityId, velocity: Vector2<SubPixelI64>) {
+        if let Some(current) = self.velocity.get_mut(&id) {
+            *current = velocity;
+        }
+        self.resting.remove(&id);
+        self.wake(id);
+    }
+    fn is_below_sleep_threshold(&self, id: EntityId) -> bool {
+        let threshold =
+            physics_num::widen(self.resources.config.sleep_velocity_threshold);
+        self.velocity
+            .get(&id)
+            .map(|&velocity| {
+                physics_num::magnitude2_wide(velocity) <= threshold * threshold
+            })
+            .unwrap_or(true)
+    }
+    fn update_island_sleep(&mut self, island_index: usize) -> bool {
+        let island = &self.islands[island_index];
+        if !island.iter().all(|&id| self.is_below_sleep_threshold(id)) {
+            for &id in island.iter() {
+                self.asleep.remove(&id);
+                self.low_velocity_streak.remove(&id);
+            }
+            return false;
+        }
+        if island.iter().all(|id| self.asleep.contains(id)) {
+            return true;
+        }
+        let mut all_rested = true;
+        for &id in island.iter() {
+            let streak = self.low_velocity_streak.get_or_insert_with(id, || 0);
+            *streak += 1;
+            all_rested &= *streak >= self.resources.config.sleep_after_updates;
+        }
+        if !all_rested {
+            return false;
+        }
+        for &id in island.iter() {
+            self.asleep.insert(id);
+            self.pair_cache.remove(id);
+            if let Some(velocity) = self.velocity.get_mut(&id) {
+                *velocity = vec2(Zero::zero(), Zero::zero());
+            }
+        }
+        true
+    }
+    pub fn islands(&self) -> &[Vec<EntityId>] {
+        &self.islands
+    }
+    pub fn resolution_order<'a>(&'a self) -> impl Iterator<Item = EntityId> + 'a {
+        self.islands.iter().flat_map(|island| island.iter().cloned())
+    }
+    fn build_islands(&mut self) {
+        self.island_builder.clear();
+        for (id, velocity) in self.velocity.iter() {
+            if let Some(position) = self.position.get(id) {
+                if let Some(shape) = self.shape.get(id) {
+                    let aabb = shape
+                        .aabb(*position)
+                        .union(&shape.aabb(*position + *velocity))
+                        .expand(self.resources.config.broadphase_margin);
+                    self.island_builder.add(*id, aabb);
+                    if let Some(bounds) = self.wrap_bounds() {
+                        for offset in bounds.seam_offsets(&aabb) {
+                            self.island_builder.add(*id, aabb.translate(-offset));
+                        }
+                    }
+                }
+            }
+        }
+        self.island_builder.build(&mut self.islands);
+    }
+    fn accelerate(&mut self, id: EntityId, delta: Vector2<SubPixelI64>) {
+        let time_scale = self.resources.time_scale;
+        let delta = if time_scale.is_identity() {
+            delta
+        } else {
+            let remainder = self.movement_remainder
+                .get_or_insert_with(id, Default::default);
+            time_scale.scale_vector(delta, &mut remainder.velocity)
+        };
+        if let Some(velocity) = self.velocity.get_mut(&id) {
+            *velocity += delta;
+        }
+    }
+    fn integrate_velocities(&mut self) {
+        let ids = self.id_buffers.sorted(self.velocity.keys());
+        for &id in ids.iter() {
+            if self.asleep.contains(&id) || !self.is_body_active(id) {
+                continue;
+            }
+            let gravity = if self.resting.contains(&id) {
+                vec2(Zero::zero(), Zero::zero())
+            } else {
+                self.gravity(id)
+            };
+            let acceleration = match self.force.get(&id).cloned() {
+                Some(force) => {
+                    self.resting.remove(&id);
+                    let mass = self.mass(id).unwrap_or(1);
+                    vec2(
+                        SubPixelI64::new(force.x.raw() / mass),
+                        SubPixelI64::new(force.y.raw() / mass),
+                    )
+                }
+                None => vec2(Zero::zero(), Zero::zero()),
+            };
+            if self.path_follower.contains_key(&id) {
+                continue;
+            }
+            self.accelerate(id, gravity + acceleration);
+            if let Some(drag) = self.drag.get(&id).cloned() {
+                let ticks = self.time_ticks;
+                if let Some(velocity) = self.velocity.get_mut(&id) {
+                    for _ in 0..ticks {
+                        *velocity = drag.apply(*velocity);
+                    }
+                }
+            }
+        }
+        self.id_buffers.recycle(ids);
+        self.force.clear();
+    }
+    fn move_entity(&mut self, id: EntityId, island_index: usize) {
+        let was_resting = self.resting.remove(&id);
+        let support = if was_resting {
+            self.support_contact(id, island_index)
+        } else {
+            None
+        };
+        let launched = match support {
+            Some(contact) => self.touch_surface(id, contact),
+            None => false,
+        };
+        if support.is_some() && !launched {
+            self.resting.insert(id);
+        } else if was_resting && !self.path_follower.contains_key(&id) {
+            let gravity = self.gravity(id);
+            self.accelerate(id, gravity);
+        }
+        let velocity = match self.velocity.get(&id) {
+            Some(&velocity) => velocity,
+            None => return,
+        };
+        let time_scale = self.resources.time_scale;
+        let movement = if time_scale.is_identity() {
+            velocity
+        } else {
+            let remainder = self.movement_remainder
+                .get_or_insert_with(id, Default::default);
+            time_scale.scale_vector(velocity, &mut remainder.movement)
+        };
+        let start_inside_edge_policy = self.start_inside_edge_policy
+            .get(&id)
+            .cloned()
+            .unwrap_or(self.resources.config.start_inside_edge_policy);
+        let skin = self.skin(id);
+        if !self.pushable.is_empty() {
+            self.push_blocker(id, movement, island_index, 0);
+        }
+        let world = CollisionWorld {
+            position: &self.position,
+            shape: &self.shape,
+            velocity: &self.velocity,
+            sensor: &self.sensor,
+            skin: &self.skin,
+            island: &self.islands[island_index],
+            broadphase: &self.broadphase,
+            baked: self.baked.as_deref(),
+            config: &self.resources.config,
+            wrap_bounds: self.wrap_bounds(),
+        };
+        self.touched.clear();
+        let position = match self.movement_mode(id) {
+            MovementMode::Slide => position_after_movement(
+                id,
+                &world,
+                &mut self.pair_cache,
+                movement,
+                start_inside_edge_policy,
+                skin,
+                &mut self.touched,
+                &mut self.stats,
+            ),
+            MovementMode::AxisSeparated => position_after_axis_separated_movement(
+                id,
+                &world,
+                &mut self.pair_cache,
+                movement,
+                start_inside_edge_policy,
+                &mut self.touched,
+                &mut self.stats,
+            ),
+        };
+        if let Some(position) = position {
+            let position = match self.wrap_bounds() {
+                Some(bounds) => bounds.wrap_point(position),
+                None => position,
+            };
+            self.position.insert(id, position);
+        }
+        if self.resources.config.speculative_contacts {
+            self.add_speculative_contact(id, island_index, skin);
+        }
+        for i in 0..self.touched.len() {
+            let contact = self.touched[i];
+            if self.touched[..i]
+                .iter()
+                .any(|earlier| earlier.other_id == contact.other_id)
+            {
+                continue;
+            }
+            if self.asleep.contains(&contact.other_id) {
+                self.wake(contact.other_id);
+            }
+            if self.gizmos_enabled {
+                self.record_contact_gizmo(id, contact);
+            }
+            if support.map(|support| support.other_id) != Some(contact.other_id)
+                && self.touch_surface(id, contact)
+            {
+                continue;
+            }
+            if self.resolve_resting_contact(id, contact) {
+                continue;
+            }
+            self.apply_contact_response(id, contact);
+        }
+    }
+    fn add_speculative_contact(
+        &mut self,
+        id: EntityId,
+        island_index: usize,
+        skin: SubPixelI64,
+    ) {
+        let (position, velocity) =
+            match (self.position.get(&id), self.velocity.get(&id)) {
+                (Some(position), Some(velocity)) => (*position, *velocity),
+                _ => return,
+            };
+        let probe = physics_num::normalize_to_length(velocity, skin);
+        if sub_pixel_i64::vector_is_zero(probe) {
+            return;
+        }
+        let (line_segment, other_id) = {
+            let world = CollisionWorld {
+                position: &self.position,
+                shape: &self.shape,
+                velocity: &self.velocity,
+                sensor: &self.sensor,
+                skin: &self.skin,
+                island: &self.islands[island_index],
+                broadphase: &self.broadphase,
+                baked: self.baked.as_deref(),
+                config: &self.resources.config,
+                wrap_bounds: self.wrap_bounds(),
+            };
+            match movement_step(
+                id,
+                position,
+                &world,
+                &mut self.pair_cache,
+                probe,
+                StartInsideEdgePolicy::AllowSeparating,
+                &mut self.stats,
+            ) {
+                MovementStep::Collision {
+                    line_segment,
+                    other_id,
+                    start_inside_normal: None,
+                    ..
+                } => (line_segment, other_id),
+                _ => return,
+            }
+        };
+        if !self.touched.iter().any(|contact| contact.other_id == other_id) {
+            self.touched.push(Contact {
+                other_id,
+                surface: line_segment,
+            });
+        }
+        if !self.velocity.contains_key(&other_id) {
+            let constrained = sub_pixel_i64::slide_movement(
+                velocity,
+                line_segment.vector(),
+                SubPixelI64::new(0),
+            );
+            self.velocity.insert(id, constrained);
+        }
+    }
+    fn contact_normal(
+        &self,
+        id: EntityId,
+        contact: Contact,
+    ) -> Option<Vector2<SubPixelI64>> {
+        let other_id = contact.other_id;
+        let centre = |position: Option<&Vector2<SubPixelI64>>,
+                      shape: Option<&Shape<SubPixelI64>>| {
+            match (position, shape) {
+                (Some(position), Some(shape)) => Some(shape.aabb(*position).centre()),
+                _ => None,
+            }
+        };
+        let a_to_b = match (
+            centre(self.position.get(&id), self.shape.get(&id)),
+            centre(self.position.get(&other_id), self.shape.get(&other_id)),
+        ) {
+            (Some(a), Some(b)) => b - a,
+            _ => return None,
+        };
+        let surface = contact.surface.vector();
+        let normal = vec2(surface.y, -surface.x);
+        if physics_num::dot(normal, a_to_b) < Zero::zero() {
+            Some(-normal)
+        } else {
+            Some(normal)
+        }
+    }
+    fn resolve_resting_contact(&mut self, id: EntityId, contact: Contact) -> bool {
+        let gravity = self.gravity(id);
+        if !self.resources.config.resting_contacts
+            || sub_pixel_i64::vector_is_zero(gravity)
+        {
+            return false;
+        }
+        let normal = match self.contact_normal(id, contact) {
+            Some(normal) => normal,
+            None => return false,
+        };
+        if !stacking::supports(normal, gravity) {
+            return false;
+        }
+        let velocity = match self.velocity.get(&id) {
+            Some(velocity) => *velocity,
+            None => return false,
+        };
+        let other_id = contact.other_id;
+        let support_velocity = match self.velocity.get(&other_id) {
+            Some(&support_velocity) => {
+                let movable = self.mass.contains_key(&other_id)
+                    && !self.resting.contains(&other_id);
+                if movable
+                    && !stacking::is_resting(velocity - support_velocity, normal, gravity)
+                {
+                    return false;
+                }
+                support_velocity
+            }
+            None => vec2(Zero::zero(), Zero::zero()),
+        };
+        self.velocity
+            .insert(id, stacking::resting_velocity(velocity, support_velocity, normal));
+        self.resting.insert(id);
+        true
+    }
+    fn support_contact(&mut self, id: EntityId, island_index: usize) -> Option<Contact> {
+        let gravity = self.gravity(id);
+        match self.push_step(id, gravity, island_index) {
+            MovementStep::Collision {
+                line_segment,
+                other_id,
+                ..
+            } => {
+                let contact = Contact {
+                    other_id,
+                    surface: line_segment,
+                };
+                let supports = self.contact_normal(id, contact)
+                    .map(|normal| stacking::supports(normal, gravity))
+                    .unwrap_or(false);
+                if supports
+                    && (!self.velocity.contains_key(&other_id)
+                        || self.resting.contains(&other_id))
+                {
+                    Some(contact)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+    fn sort_islands_along_gravity(&mut self) {
+        let gravity = self.resources.config.gravity;
+        let position = &self.position;
+        for island in self.islands.iter_mut() {
+            island.sort_unstable_by_key(|id| {
+                let depth = position
+                    .get(id)
+                    .map(|&position| stacking::depth_along_gravity(position, gravity))
+                    .unwrap_or(0);
+                (-depth, *id)
+            });
+        }
+    }
+    fn apply_contact_response(&mut self, id: EntityId, contact: Contact) {
+        let other_id = contact.other_id;
+        let mass_b = if let Some(mass) = self.mass.get(&other_id) {
+            *mass
+        } else {
+            return;
+        };
+        let (velocity_a, velocity_b) =
+            match (self.velocity.get(&id), self.velocity.get(&other_id)) {
+                (Some(a), Some(b)) => (*a, *b),
+                _ => return,
+            };
+        let normal = match self.contact_normal(id, contact) {
+            Some(normal) => normal,
+            None => return,
+        };
+        let restitution = match self.resources.config.collision_response {
+            CollisionResponse::Inelastic => Restitution::inelastic(),
+            CollisionResponse::Elastic => {
+                self.contact_restitution(id).min(self.contact_restitution(other_id))
+            }
+        };
+        if let Some(change) = impulse::with_restitution(
+            velocity_a,
+            self.mass.get(&id).cloned(),
+            velocity_b,
+            mass_b,
+            normal,
+            restitution,
+        ) {
+            self.velocity.insert(id, velocity_a + change.a);
+            self.velocity.insert(other_id, velocity_b + change.b);
+            self.wake(other_id);
+        }
+    }
+    pub fn set_mass(&mut self, id: EntityId, mass: Option<i64>) -> PhysicsResult<()> {
+        if let Some(mass) = mass {
+            if mass <= 0 {
+                return Err(PhysicsError::InvalidMass);
+            }
+            self.mass.insert(id, mass);
+        } else {
+            self.mass.remove(&id);
+        }
+        Ok(())
+    }
+    pub fn mass(&self, id: EntityId) -> Option<i64> {
+        self.mass.get(&id).cloned()
+    }
+    pub fn set_restitution(&mut self, id: EntityId, restitution: Option<Restitution>) {
+        if let Some(restitution) = restitution {
+            self.restitution.insert(id, restitution);
+        } else {
+            self.restitution.remove(&id);
+        }
+    }
+    pub fn restitution(&self, id: EntityId) -> Restitution {
+        self.restitution.get(&id).cloned().unwrap_or_default()
+    }
+    pub fn add_world_bounds(&mut self, open_top: bool) -> PhysicsResult<Vec<EntityId>> {
+        let bounds = self.world_bounds;
+        let top_left = bounds.top_left();
+        let size = bounds.size();
+        let thickness = SubPixelI64::new_pixels(WORLD_BOUNDS_THICKNESS_PIXELS);
+        let mut walls = vec![
+            (top_left, vec2(thickness, size.y)),
+            (
+                vec2(top_left.x + size.x - thickness, top_left.y),
+                vec2(thickness, size.y),
+            ),
+            (
+                vec2(top_left.x, top_left.y + size.y - thickness),
+                vec2(size.x, thickness),
+            ),
+        ];
+        if !open_top {
+            walls.push((top_left, vec2(size.x, thickness)));
+        }
+        walls
+            .into_iter()
+            .map(|(position, dimensions)| {
+                self.spawn()
+                    .position(position)
+                    .shape(Shape::AxisAlignedRect(AxisAlignedRect::new(dimensions)))
+                    .colour(WORLD_BOUNDS_COLOUR)
+                    .body_type(BodyType::Static)
+                    .build()
+            })
+            .collect()
+    }
+    pub fn init_demo(&mut self) -> PhysicsResult<()> {
+        self.clear();
+        let player_id = self.spawn()
+            .position_pixels(vec2(200., 50.))
+            .shape(Shape::AxisAlignedRect(AxisAlignedRect::new(vec2(
+                SubPixelI64::new_pixels_f32(32.),
+                SubPixelI64::new_pixels_f32(64.),
+            ))))
+            .colour([1., 0., 0., 1.])
+            .body_type(BodyType::Dynamic)
+            .build()?;
+
+        self.player_id = Some(player_id);
+
+        self.spawn()
+            .position_pixels(vec2(50., 200.))
+            .shape(Shape::AxisAlignedRect(AxisAlignedRect::new(vec2(
+                SubPixelI64::new_pixels_f32(400.),
+                SubPixelI64::new_pixels_f32(20.),
+            ))))
+            .colour([1., 1., 0., 1.])
+            .build()?;
+
+        self.spawn()
+            .position_pixels(vec2(20., 20.))
+            .shape(Shape::LineSegment(LineSegment::new(
+                vec2(Zero::zero(), Zero::zero()),
+                vec2(
+                    SubPixelI64::new_pixels_f32(50.),
+                    SubPixelI64::new_pixels_f32(100.),
+                ),
+            )))
+            .colour([0., 1., 0., 1.])
+            .build()?;
+        Ok(())
+    }
+    fn sorted_ids(&self) -> Vec<EntityId> {
+        let mut ids = self.shape.keys().cloned().collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+    fn for_each_static<F>(&self, aabb: &Aabb<SubPixelI64>, mut f: F)
+    where
+        F: FnMut(EntityId),
+    {
+        self.broadphase.for_each_intersection(aabb, |_aabb, spatial_info| {
+            if !self.velocity.contains_key(&spatial_info.entity_id) {
+                f(spatial_info.entity_id);
+            }
+        });
+        if let Some(ref baked) = self.baked {
+            baked.for_each_intersection(aabb, |collider| {
+                if !self.velocity.contains_key(&collider.entity_id) {
+                    f(collider.entity_id);
+                }
+            });
+        }
+    }
+    fn is_baked(&self, id: EntityId) -> bool {
+        self.baked.as_ref().is_some_and(|baked| baked.contains(id))
+    }
+    fn is_bakeable(&self, id: EntityId) -> bool {
+        let rect = match self.shape.get(&id) {
+            Some(&Shape::AxisAlignedRect(_)) => self.position.contains_key(&id),
+            _ => false,
+        };
+        let decorated = self.sprite.contains_key(&id)
+            || self.animation.contains_key(&id)
+            || self.colour_tween.contains_key(&id)
+            || self.flash.contains_key(&id)
+            || self.hidden.contains(&id)
+            || self.blend.contains_key(&id);
+        let behaviour = self.pushable.contains(&id)
+            || self.restitution.contains_key(&id)
+            || self.bounce_pad.contains_key(&id)
+            || self.hazard.contains_key(&id)
+            || self.health.contains_key(&id)
+            || self.sensor.contains(&id)
+            || self.pickup.contains_key(&id)
+            || self.collector.contains(&id)
+            || self.lifetime.contains_key(&id)
+            || self.state_machine.contains_key(&id)
+            || self.path_follower.contains_key(&id)
+            || self.homing.contains_key(&id)
+            || self.rope.contains_key(&id)
+            || self.attractor.contains_key(&id)
+            || self.skin.contains_key(&id)
+            || self.start_inside_edge_policy.contains_key(&id)
+            || self.out_of_bounds_policy.contains_key(&id)
+            || self.user_data.contains_entity(id);
+        rect && !decorated && !behaviour && !self.velocity.contains_key(&id)
+            && self.player_id != Some(id) && !self.pool.is_member(id)
+    }
+    /// Merges adjacent static rects that look and collide the same into baked
+    /// colliders. Each merged collider keeps the lowest id among the entities
+    /// it covers and the rest are removed. The returned map sends the id of
+    /// every baked entity to the id that now stands for it, which is its own id
+    /// for the entities that survive.
+    pub fn bake_statics(&mut self) -> BTreeMap<EntityId, EntityId> {
+        let mut groups = BTreeMap::new();
+        let ids = self.sorted_ids()
+            .into_iter()
+            .filter(|&id| self.is_bakeable(id))
+            .collect::<Vec<_>>();
+        for id in ids {
+            let colour = self.colour.get(&id).map(|colour| {
+                [
+                    colour[0].to_bits(),
+                    colour[1].to_bits(),
+                    colour[2].to_bits(),
+                    colour[3].to_bits(),
+                ]
+            });
+            let key = (
+                colour,
+                self.z_index.get(&id).cloned(),
+                self.collision_layers(id),
+                self.material.get(&id).cloned(),
+            );
+            let aabb = self.shape[&id].aabb(self.position[&id]);
+            groups.entry(key).or_insert_with(Vec::new).push((id, aabb));
+        }
+        let mut renamed = BTreeMap::new();
+        let mut baked = Vec::new();
+        for members in groups.into_values() {
+            let merged = static_bake::merge_rects(
+                members.iter().map(|&(_, aabb)| aabb).collect(),
+            );
+            let mut survivors = vec![None; merged.len()];
+            for &(id, aabb) in members.iter() {
+                let survivor = match merged.iter().position(|rect| rect.contains(&aabb)) {
+                    Some(index) => *survivors[index].get_or_insert(id),
+                    None => {
+                        baked.push((id, aabb));
+                        id
+                    }
+                };
+                renamed.insert(id, survivor);
+            }
+            for (aabb, survivor) in merged.into_iter().zip(survivors) {
+                if let Some(id) = survivor {
+                    let shape = Shape::AxisAlignedRect(AxisAlignedRect::new(aabb.size()));
+                    self.position.insert(id, aabb.top_left());
+                    if self.shape.insert(id, shape).as_ref() != self.shape.get(&id) {
+                        self.shape_dirty.insert(id);
+                        self.shape_changed.insert(id);
+                    }
+                    baked.push((id, aabb));
+                }
+            }
+        }
+        for (&id, &survivor) in renamed.iter() {
+            if id != survivor {
+                self.remove_entity(id);
+            }
+        }
+        let chunk_size = self.resources.config.chunk_size;
+        self.baked = Some(Rc::new(StaticGeometry::new(baked, chunk_size)));
+        self.rebuild_broadphase();
+        renamed
+    }
+    pub fn static_geometry(&self) -> Option<&StaticGeometry<SubPixelI64>> {
+        self.baked.as_deref()
+    }
+    /// Moves a single broadphase proxy from `old_aabb` to the entity's current
+    /// AABB, falling back to a full rebuild when the world has to grow or the
+    /// entity is part of the baked static geometry.
+    fn update_proxy(&mut self, id: EntityId, old_aabb: &Aabb<SubPixelI64>) {
+        let aabb = match (self.position.get(&id), self.shape.get(&id)) {
+            (Some(&position), Some(shape)) => shape.aabb(position),
+            _ => return,
+        };
+        let info = SpatialInfo { entity_id: id };
+        if self.is_baked(id) || self.grow_world_to_fit(&aabb)
+            || !self.broadphase.update(old_aabb, aabb, info)
+        {
+            self.rebuild_broadphase();
+        } else {
+            self.pair_cache.clear();
+        }
+    }
+    /// Drops a static entity's broadphase proxy, or its collider if it was
+    /// baked. Dynamic entities have no proxy.
+    fn remove_proxy(&mut self, id: EntityId) {
+        if self.velocity.contains_key(&id) {
+            return;
+        }
+        let aabb = match (self.position.get(&id), self.shape.get(&id)) {
+            (Some(&position), Some(shape)) => shape.aabb(position),
+            _ => return,
+        };
+        let baked = match self.baked {
+            Some(ref baked) if baked.contains(id) => baked.clone(),
+            _ => {
+                self.broadphase.remove(&aabb, &SpatialInfo { entity_id: id });
+                return;
+            }
+        };
+        let colliders = baked
+            .colliders()
+            .iter()
+            .filter(|collider| collider.entity_id != id)
+            .map(|collider| (collider.entity_id, collider.aabb));
+        let remaining = StaticGeometry::new(colliders, self.resources.config.chunk_size);
+        self.baked = if remaining.is_empty() {
+            None
+        } else {
+            Some(Rc::new(remaining))
+        };
+    }
+    fn rebuild_broadphase(&mut self) {
+        let stale_bake = self.baked.as_ref().is_some_and(|baked| {
+            baked.colliders().iter().any(|collider| {
+                let id = collider.entity_id;
+                match (self.position.get(&id), self.shape.get(&id)) {
+                    (Some(position), Some(shape)) => {
+                        self.velocity.contains_key(&id)
+                            || shape.aabb(*position) != collider.aabb
+                    }
+                    _ => false,
+                }
+            })
+        });
+        if stale_bake {
+            self.baked = None;
+        }
+        let entity_bounds = self.position
+            .iter()
+            .filter_map(|(id, position)| self.shape.get(id).map(|s| s.aabb(*position)))
+            .fold(None, |union: Option<Aabb<SubPixelI64>>, aabb| {
+                Some(union.map_or(aabb, |union| union.union(&aabb)))
+            });
+        if let Some(aabb) = entity_bounds {
+            self.grow_world_to_fit(&aabb);
+        }
+        let items = self.sorted_ids()
+            .into_iter()
+            .filter(|&id| !self.is_baked(id) && !self.velocity.contains_key(&id))
+            .filter_map(|id| {
+                self.position.get(&id).map(|position| {
+                    (self.shape[&id].aabb(*position), SpatialInfo { entity_id: id })
+                })
+            })
+            .collect::<Vec<_>>();
+        self.broadphase.bulk_load(items);
+        self.pair_cache.clear();
+        self.stats.broadphase_rebuilds += 1;
+    }
+    pub fn diff(&self, other: &World) -> StateDelta {
+        let mut delta = StateDelta {
+            player_id: other.player_id,
+            ..Default::default()
+        };
+        for id in self.sorted_ids() {
+            let removed = !other.shape.contains_key(&id);
+            let pooled =
+                self.position.contains_key(&id) && !other.position.contains_key(&id);
+            if removed || pooled {
+                delta.despawns.push(id);
+            }
+        }
+        for id in other.sorted_ids() {
+            let position = if let Some(position) = other.position.get(&id) {
+                *position
+            } else {
+                continue;
+            };
+            let spawned =
+                !self.shape.contains_key(&id) || !self.position.contains_key(&id);
+            if spawned {
+                delta.spawns.push(Spawn {
+                    id,
+                    position,
+                    shape: other.shape[&id].clone(),
+                });
+            } else {
+                let components = &mut delta.components;
+                components.positions.record(id, false, &self.position, &other.position);
+                components.shapes.record(id, false, &self.shape, &other.shape);
+            }
+            self.diff_components(other, id, spawned, &mut delta.components);
+        }
+        if self.resources.rng != other.resources.rng {
+            delta.rng_state = Some(other.resources.rng.state());
+        }
+        if self.scheduler != other.scheduler {
+            delta.scheduler = Some(other.scheduler.clone());
+        }
+        self.diff_simulation(other, &mut delta.simulation);
+        delta
+    }
+    fn diff_simulation(&self, other: &World, simulation: &mut SimulationDelta) {
+        fn changed<T: PartialEq + Clone>(before: &T, after: &T) -> Option<T> {
+            if before == after {
+                None
+            } else {
+                Some(after.clone())
+            }
+        }
+        if self.entity_id_allocator != other.entity_id_allocator {
+            simulation.next_entity_id = Some(other.entity_id_allocator.next);
+        }
+        simulation.world_bounds = changed(&self.world_bounds, &other.world_bounds);
+        simulation.time_remainder = changed(&self.time_remainder, &other.time_remainder);
+        if self.touching != other.touching {
+            simulation.touching = Some(other.sorted_touching());
+        }
+        simulation.hazard_cooldowns =
+            changed(&self.hazard_cooldowns, &other.hazard_cooldowns);
+        simulation.portals = changed(&self.portals, &other.portals);
+        simulation.signals = changed(&self.signals, &other.signals);
+        simulation.verlet = changed(&self.verlet, &other.verlet);
+        simulation.particles = changed(&self.particles, &other.particles);
+    }
+    fn sorted_touching(&self) -> Vec<(EntityId, EntityId)> {
+        let mut touching = self.touching.iter().cloned().collect::<Vec<_>>();
+        touching.sort_unstable();
+        touching
+    }
+    fn diff_components(
+        &self,
+        other: &World,
+        id: EntityId,
+        spawned: bool,
+        components: &mut ComponentDeltas,
+    ) {
+        components.velocities.record(id, spawned, &self.velocity, &other.velocity);
+        components.colours.record(id, spawned, &self.colour, &other.colour);
+        components.z_indices.record(id, spawned, &self.z_index, &other.z_index);
+        components.sprites.record(id, spawned, &self.sprite, &other.sprite);
+        components.animations.record(id, spawned, &self.animation, &other.animation);
+        components.colour_tweens.record(
+            id,
+            spawned,
+            &self.colour_tween,
+            &other.colour_tween,
+        );
+        components.flashes.record(id, spawned, &self.flash, &other.flash);
+        components.blends.record(id, spawned, &self.blend, &other.blend);
+        components.masses.record(id, spawned, &self.mass, &other.mass);
+        components.restitutions.record(
+            id,
+            spawned,
+            &self.restitution,
+            &other.restitution,
+        );
+        components.ropes.record(id, spawned, &self.rope, &other.rope);
+        components.attractors.record(id, spawned, &self.attractor, &other.attractor);
+        components.forces.record(id, spawned, &self.force, &other.force);
+        components.gravities.record(id, spawned, &self.gravity, &other.gravity);
+        components.drags.record(id, spawned, &self.drag, &other.drag);
+        components.materials.record(id, spawned, &self.material, &other.material);
+        components.bounce_pads.record(id, spawned, &self.bounce_pad, &other.bounce_pad);
+        components.hazards.record(id, spawned, &self.hazard, &other.hazard);
+        components.healths.record(id, spawned, &self.health, &other.health);
+        components.pickups.record(id, spawned, &self.pickup, &other.pickup);
+        components.lifetimes.record(id, spawned, &self.lifetime, &other.lifetime);
+        components.state_machines.record(
+            id,
+            spawned,
+            &self.state_machine,
+            &other.state_machine,
+        );
+        components.path_followers.record(
+            id,
+            spawned,
+            &self.path_follower,
+            &other.path_follower,
+        );
+        components.homings.record(id, spawned, &self.homing, &other.homing);
+        components.start_inside_edge_policies.record(
+            id,
+            spawned,
+            &self.start_inside_edge_policy,
+            &other.start_inside_edge_policy,
+        );
+        components.skins.record(id, spawned, &self.skin, &other.skin);
+        components.movement_modes.record(
+            id,
+            spawned,
+            &self.movement_mode,
+            &other.movement_mode,
+        );
+        components.collision_layers.record(
+            id,
+            spawned,
+            &self.collision_layers,
+            &other.collision_layers,
+        );
+        components.out_of_bounds_policies.record(
+            id,
+            spawned,
+            &self.out_of_bounds_policy,
+            &other.out_of_bounds_policy,
+        );
+        components.hidden.record(id, spawned, &self.hidden, &other.hidden);
+        components.pushable.record(id, spawned, &self.pushable, &other.pushable);
+        components.sensors.record(id, spawned, &self.sensor, &other.sensor);
+        components.collectors.record(id, spawned, &self.collector, &other.collector);
+        components.movement_remainders.record(
+            id,
+            spawned,
+            &self.movement_remainder,
+            &other.movement_remainder,
+        );
+        components.low_velocity_streaks.record(
+            id,
+            spawned,
+            &self.low_velocity_streak,
+            &other.low_velocity_streak,
+        );
+        components.resting.record(id, spawned, &self.resting, &other.resting);
+        components.asleep.record(id, spawned, &self.asleep, &other.asleep);
+        components.out_of_bounds.record(
+            id,
+            spawned,
+            &self.out_of_bounds,
+            &other.out_of_bounds,
+        );
+        components.portal_suppressed.record(
+            id,
+            spawned,
+            &self.portal_suppressed,
+            &other.portal_suppressed,
+        );
+    }
+    /// Hashes everything `diff` compares, so states with equal hashes
+    /// produce an empty delta. Resources are not hashed.
+    pub fn state_hash(&self) -> u64 {
+        let mut hasher = FnvHasher::default();
+        hasher.write_u32(self.player_id.unwrap_or(!0));
+        hasher.write_u64(self.resources.rng.state());
+        self.scheduler.hash(&mut hasher);
+        self.entity_id_allocator.hash(&mut hasher);
+        self.world_bounds.hash(&mut hasher);
+        self.time_remainder.hash(&mut hasher);
+        self.sorted_touching().hash(&mut hasher);
+        self.hazard_cooldowns.hash(&mut hasher);
+        self.portals.hash(&mut hasher);
+        self.signals.hash(&mut hasher);
+        self.verlet.hash(&mut hasher);
+        self.particles.hash(&mut hasher);
+        for id in self.sorted_ids() {
+            let state = &mut hasher;
+            state.write_u32(id);
+            self.position.get(&id).hash(state);
+            self.shape.get(&id).hash(state);
+            self.velocity.get(&id).hash(state);
+            if let Some(colour) = self.colour.get(&id) {
+                state.write_u8(1);
+                colour_tween::hash_colour(colour, state);
+            } else {
+                state.write_u8(0);
+            }
+            self.z_index.get(&id).hash(state);
+            self.sprite.get(&id).hash(state);
+            self.animation.get(&id).hash(state);
+            self.colour_tween.get(&id).hash(state);
+            self.flash.get(&id).hash(state);
+            self.blend.get(&id).hash(state);
+            self.mass.get(&id).hash(state);
+            self.restitution.get(&id).hash(state);
+            self.rope.get(&id).hash(state);
+            self.attractor.get(&id).hash(state);
+            self.force.get(&id).hash(state);
+            self.gravity.get(&id).hash(state);
+            self.drag.get(&id).hash(state);
+            self.material.get(&id).hash(state);
+            self.bounce_pad.get(&id).hash(state);
+            self.hazard.get(&id).hash(state);
+            self.health.get(&id).hash(state);
+            self.pickup.get(&id).hash(state);
+            self.lifetime.get(&id).hash(state);
+            self.state_machine.get(&id).hash(state);
+            self.path_follower.get(&id).hash(state);
+            self.homing.get(&id).hash(state);
+            self.start_inside_edge_policy.get(&id).hash(state);
+            self.skin.get(&id).hash(state);
+            self.movement_mode.get(&id).hash(state);
+            self.collision_layers.get(&id).hash(state);
+            self.out_of_bounds_policy.get(&id).hash(state);
+            self.hidden.contains(&id).hash(state);
+            self.pushable.contains(&id).hash(state);
+            self.sensor.contains(&id).hash(state);
+            self.collector.contains(&id).hash(state);
+            self.movement_remainder.get(&id).hash(state);
+            self.low_velocity_streak.get(&id).hash(state);
+            self.resting.contains(&id).hash(state);
+            self.asleep.contains(&id).hash(state);
+            self.out_of_bounds.contains(&id).hash(state);
+            self.portal_suppressed.contains(&id).hash(state);
+        }
+        hasher.finish()
+    }
+    /// Everything `apply_delta` needs to turn a new world into a copy of this
+    /// one, apart from user data and resources that aren't persistent.
+    pub fn snapshot(&self) -> StateDelta {
+        let mut delta = World::new(vec2(0., 0.), self.resources.config).diff(self);
+        delta.rng_state = Some(self.resources.rng.state());
+        delta.scheduler = Some(self.scheduler.clone());
+        delta.simulation = SimulationDelta {
+            next_entity_id: Some(self.entity_id_allocator.next),
+            world_bounds: Some(self.world_bounds),
+            time_remainder: Some(self.time_remainder),
+            touching: Some(self.sorted_touching()),
+            hazard_cooldowns: Some(self.hazard_cooldowns.clone()),
+            portals: Some(self.portals.clone()),
+            signals: Some(self.signals.clone()),
+            verlet: Some(self.verlet.clone()),
+            particles: Some(self.particles.clone()),
+        };
+        delta.resources = Some(self.resources.persistent());
+        delta
+    }
+    pub fn apply_delta(&mut self, delta: &StateDelta) {
+        let components = &delta.components;
+        let mut rebuild = !delta.despawns.is_empty() || !delta.spawns.is_empty()
+            || !components.shapes.is_empty()
+            || !components.velocities.removed.is_empty();
+        for &id in delta.despawns.iter() {
+            self.remove_entity(id);
+        }
+        for spawn in delta.spawns.iter() {
+            self.entity_id_allocator.reserve(spawn.id);
+            self.pool.remove(spawn.id);
+            self.position.insert(spawn.id, spawn.position);
+            self.shape.insert(spawn.id, spawn.shape.clone());
+            self.shape_dirty.insert(spawn.id);
+            self.shape_changed.insert(spawn.id);
+        }
+        for &(id, _) in components.positions.set.iter() {
+            if !self.velocity.contains_key(&id) {
+                rebuild = true;
+            }
+        }
+        components.positions.apply(&mut self.position);
+        for &(id, _) in components.shapes.set.iter() {
+            self.shape_dirty.insert(id);
+            self.shape_changed.insert(id);
+        }
+        components.shapes.apply(&mut self.shape);
+        for &(id, _) in components.velocities.set.iter() {
+            rebuild |= !self.velocity.contains_key(&id);
+            self.wake(id);
+        }
+        for &id in components.velocities.removed.iter() {
+            self.movement_remainder.remove(&id);
+            self.wake(id);
+        }
+        components.velocities.apply(&mut self.velocity);
+        components.colours.apply(&mut self.colour);
+        components.z_indices.apply(&mut self.z_index);
+        components.sprites.apply(&mut self.sprite);
+        components.animations.apply(&mut self.animation);
+        components.colour_tweens.apply(&mut self.colour_tween);
+        components.flashes.apply(&mut self.flash);
+        components.blends.apply(&mut self.blend);
+        components.masses.apply(&mut self.mass);
+        components.restitutions.apply(&mut self.restitution);
+        components.ropes.apply(&mut self.rope);
+        components.attractors.apply(&mut self.attractor);
+        components.forces.apply(&mut self.force);
+        components.gravities.apply(&mut self.gravity);
+        components.drags.apply(&mut self.drag);
+        components.materials.apply(&mut self.material);
+        components.bounce_pads.apply(&mut self.bounce_pad);
+        components.hazards.apply(&mut self.hazard);
+        components.healths.apply(&mut self.health);
+        components.pickups.apply(&mut self.pickup);
+        components.lifetimes.apply(&mut self.lifetime);
+        components.state_machines.apply(&mut self.state_machine);
+        components.path_followers.apply(&mut self.path_follower);
+        components.homings.apply(&mut self.homing);
+        components.start_inside_edge_policies.apply(&mut self.start_inside_edge_policy);
+        components.skins.apply(&mut self.skin);
+        components.movement_modes.apply(&mut self.movement_mode);
+        components.collision_layers.apply(&mut self.collision_layers);
+        components.out_of_bounds_policies.apply(&mut self.out_of_bounds_policy);
+        components.hidden.apply(&mut self.hidden);
+        components.pushable.apply(&mut self.pushable);
+        components.sensors.apply(&mut self.sensor);
+        components.collectors.apply(&mut self.collector);
+        components.movement_remainders.apply(&mut self.movement_remainder);
+        components.low_velocity_streaks.apply(&mut self.low_velocity_streak);
+        components.resting.apply(&mut self.resting);
+        components.asleep.apply(&mut self.asleep);
+        components.out_of_bounds.apply(&mut self.out_of_bounds);
+        components.portal_suppressed.apply(&mut self.portal_suppressed);
+        self.player_id = delta.player_id;
+        if let Some(rng_state) = delta.rng_state {
+            self.resources.rng.set_state(rng_state);
+        }
+        if let Some(ref scheduler) = delta.scheduler {
+            self.scheduler = scheduler.clone();
+        }
+        rebuild |= self.apply_simulation(&delta.simulation);
+        if let Some(ref resources) = delta.resources {
+            let previous = self.resources.config;
+            self.resources.restore(resources);
+            self.reconfigure(previous);
+        }
+        if rebuild {
+            self.rebuild_broadphase();
+        }
+    }
+    /// Returns whether the broadphase needs rebuilding.
+    fn apply_simulation(&mut self, simulation: &SimulationDelta) -> bool {
+        if let Some(next) = simulation.next_entity_id {
+            self.entity_id_allocator.next = next;
+        }
+        if let Some(time_remainder) = simulation.time_remainder {
+            self.time_remainder = time_remainder;
+        }
+        if let Some(ref touching) = simulation.touching {
+            self.touching = touching.iter().cloned().collect();
+        }
+        if let Some(ref hazard_cooldowns) = simulation.hazard_cooldowns {
+            self.hazard_cooldowns = hazard_cooldowns.clone();
+        }
+        if let Some(ref portals) = simulation.portals {
+            self.portals = portals.clone();
+        }
+        if let Some(ref signals) = simulation.signals {
+            self.signals = signals.clone();
+        }
+        if let Some(ref verlet) = simulation.verlet {
+            self.verlet = verlet.clone();
+        }
+        if let Some(ref particles) = simulation.particles {
+            self.particles = particles.clone();
+        }
+        let world_bounds = match simulation.world_bounds {
+            Some(world_bounds) => world_bounds,
+            None => return false,
+        };
+        self.world_bounds = world_bounds;
+        self.broadphase = Broadphase::new(
+            self.resources.config.broadphase,
+            &world_bounds,
+            self.resources.config.chunk_size,
+            self.resources.config.quad_tree,
+        );
+        true
+    }
+    pub fn walkability_grid(
+        &self,
+        top_left: Vector2<SubPixelI64>,
+        cell_size: SubPixelI64,
+        width: usize,
+        height: usize,
+        agent: &Shape<SubPixelI64>,
+    ) -> WalkabilityGrid {
+        let agent_offset = agent.aabb(vec2(Zero::zero(), Zero::zero())).centre();
+        WalkabilityGrid::new(top_left, cell_size, width, height, |centre| {
+            let agent_position = centre - agent_offset;
+            let agent_aabb = agent.aabb(agent_position);
+            let mut blocked = false;
+            self.for_each_static(&agent_aabb, |id| {
+                if blocked {
+                    return;
+                }
+                if let (Some(position), Some(shape)) =
+                    (self.position.get(&id), self.shape.get(&id))
+                {
+                    blocked =
+                        agent.overlaps(agent_position, shape, *position, Zero::zero());
+                }
+            });
+            blocked
+        })
+    }
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        let tolerance = self.resources
+            .config
+            .validation_tolerance
+            .unwrap_or(Zero::zero());
+        let ids = self.sorted_ids();
+        for (i, &id_a) in ids.iter().enumerate() {
+            for &id_b in ids[i + 1..].iter() {
+                if !self.velocity.contains_key(&id_a)
+                    && !self.velocity.contains_key(&id_b)
+                {
+                    continue;
+                }
+                if self.sensor.contains(&id_a) || self.sensor.contains(&id_b) {
+                    continue;
+                }
+                if let (Some(position_a), Some(position_b)) =
+                    (self.position.get(&id_a), self.position.get(&id_b))
+                {
+                    if self.shape[&id_a].overlaps(
+                        *position_a,
+                        &self.shape[&id_b],
+                        *position_b,
+                        tolerance,
+                    ) {
+                        return Err(ValidationError::Overlap(id_a, id_b));
+                    }
+                }
+            }
+        }
+        let mut proxies = FnvHashMap::default();
+        self.broadphase.for_each_item(|aabb, spatial_info| {
+            proxies.insert(spatial_info.entity_id, *aabb);
+        });
+        if let Some(ref baked) = self.baked {
+            for collider in baked.colliders() {
+                proxies.insert(collider.entity_id, collider.aabb);
+            }
+        }
+        let orphan = proxies
+            .keys()
+            .filter(|id| {
+                !self.position.contains_key(id) || self.velocity.contains_key(id)
+            })
+            .min();
+        if let Some(&id) = orphan {
+            return Err(ValidationError::OrphanProxy(id));
+        }
+        for (id, shape) in self.shape.iter() {
+            if self.velocity.contains_key(id) {
+                continue;
+            }
+            if let Some(position) = self.position.get(id) {
+                match proxies.get(id) {
+                    None => return Err(ValidationError::MissingProxy(*id)),
+                    Some(aabb) if *aabb != shape.aabb(*position) => {
+                        return Err(ValidationError::StaleProxy(*id))
+                    }
+                    Some(_) => (),
+                }
+            }
+        }
+        Ok(())
+    }
+    pub fn set_z_index(&mut self, id: EntityId, z_index: Option<i32>) {
+        match z_index {
+            Some(z_index) => self.z_index.insert(id, z_index),
+            None => self.z_index.remove(&id),
+        };
+    }
+    pub fn z_index(&self, id: EntityId) -> i32 {
+        self.z_index.get(&id).cloned().unwrap_or(0)
+    }
+    pub fn set_sprite(&mut self, id: EntityId, sprite: Option<Sprite>) {
+        match sprite {
+            Some(sprite) => self.sprite.insert(id, sprite),
+            None => self.sprite.remove(&id),
+        };
+    }
+    pub fn sprite(&self, id: EntityId) -> Option<&Sprite> {
+        self.sprite.get(&id)
+    }
+    pub fn set_animation(&mut self, id: EntityId, animation: Option<Animation>) {
+        match animation {
+            Some(animation) => {
+                let state = AnimationState::new(animation);
+                self.apply_animation_frame(id, &state);
+                self.animation.insert(id, state);
+            }
+            None => {
+                self.animation.remove(&id);
+            }
+        }
+    }
+    pub fn animation(&self, id: EntityId) -> Option<&AnimationState> {
+        self.animation.get(&id)
+    }
+    fn apply_animation_frame(&mut self, id: EntityId, state: &AnimationState) {
+        let frame = state.current_frame();
+        self.sprite.insert(id, frame.sprite);
+        if let Some(ref hitbox) = frame.hitbox {
+            self.set_shape(id, hitbox.clone());
+        }
+    }
+    fn advance_animations(&mut self) {
+        let ids = self.id_buffers.sorted(self.animation.keys());
+        let ticks = self.time_ticks;
+        for &id in ids.iter() {
+            let changed = self.animation
+                .get_mut(&id)
+                .map(|state| (0..ticks).fold(false, |changed, _| state.tick() || changed))
+                .unwrap_or(false);
+            if changed {
+                if let Some(state) = self.animation.remove(&id) {
+                    self.apply_animation_frame(id, &state);
+                    self.animation.insert(id, state);
+                }
+            }
+        }
+        self.id_buffers.recycle(ids);
+    }
+    pub fn tween_colour(&mut self, id: EntityId, target: [f32; 4], updates: u32) {
+        if let Some(&colour) = self.colour.get(&id) {
+            self.colour_tween
+                .insert(id, ColourTween::new(colour, target, updates));
+        }
+    }
+    pub fn flash(&mut self, id: EntityId, colour: [f32; 4], updates: u32) {
+        if self.colour.contains_key(&id) && updates > 0 {
+            self.flash.insert(
+                id,
+                Flash {
+                    colour,
+                    remaining: updates,
+                },
+            );
+        }
+    }
+    pub fn flash_white(&mut self, id: EntityId, updates: u32) {
+        self.flash(id, colour_tween::WHITE, updates);
+    }
+    pub fn set_visible(&mut self, id: EntityId, visible: bool) {
+        if visible {
+            self.hidden.remove(&id);
+        } else {
+            self.hidden.insert(id);
+        }
+    }
+    pub fn is_visible(&self, id: EntityId) -> bool {
+        !self.hidden.contains(&id)
+    }
+    pub fn set_blend(&mut self, id: EntityId, blend: Option<BlendHint>) {
+        match blend {
+            Some(blend) => self.blend.insert(id, blend),
+            None => self.blend.remove(&id),
+        };
+    }
+    pub fn blend(&self, id: EntityId) -> BlendHint {
+        self.blend.get(&id).cloned().unwrap_or_default()
+    }
+    fn displayed_colour(&self, id: EntityId, colour: [f32; 4]) -> [f32; 4] {
+        self.flash.get(&id).map_or(colour, |flash| flash.colour)
+    }
+    fn advance_colour_effects(&mut self) {
+        for (id, tween) in self.colour_tween.iter_mut() {
+            if let Some(colour) = self.colour.get_mut(id) {
+                *colour = tween.tick();
+            }
+        }
+        self.colour_tween.retain(|_, tween| !tween.is_finished());
+        for flash in self.flash.values_mut() {
+            flash.remaining -= 1;
+        }
+        self.flash.retain(|_, flash| flash.remaining > 0);
+    }
+    fn track_changes(&mut self) {
+        self.position_changes.track(&self.position);
+        self.colour_changes.track(&self.colour);
+        self.changed_shapes.clear();
+        for &id in self.shape_changed.iter() {
+            if self.shape.contains_key(&id) {
+                self.changed_shapes.push(id);
+            }
+        }
+        self.changed_shapes.sort_unstable();
+        self.shape_changed.clear();
+    }
+    pub fn changed_positions<'a>(&'a self) -> impl Iterator<Item = EntityId> + 'a {
+        self.position_changes.changed().iter().cloned()
+    }
+    pub fn changed_shapes<'a>(&'a self) -> impl Iterator<Item = EntityId> + 'a {
+        self.changed_shapes.iter().cloned()
+    }
+    pub fn changed_colours<'a>(&'a self) -> impl Iterator<Item = EntityId> + 'a {
+        self.colour_changes.changed().iter().cloned()
+    }
+    pub fn is_position_changed(&self, id: EntityId) -> bool {
+        self.position_changes.is_changed(id)
+    }
+    pub fn is_shape_changed(&self, id: EntityId) -> bool {
+        self.changed_shapes.binary_search(&id).is_ok()
+    }
+    pub fn is_colour_changed(&self, id: EntityId) -> bool {
+        self.colour_changes.is_changed(id)
+    }
+    fn render_update(
+        &self,
+        entity_id: EntityId,
+        position: Vector2<SubPixelI64>,
+    ) -> Option<RenderUpdate<'_>> {
+        if self.hidden.contains(&entity_id) {
+            return None;
+        }
+        let shape = self.shape.get(&entity_id);
+        let (shape, colour) = match (shape, self.colour.get(&entity_id)) {
+            (Some(shape), Some(&colour)) => (shape, colour),
+            _ => return None,
+        };
+        Some(RenderUpdate {
+            entity_id,
+            z_index: self.z_index(entity_id),
+            position,
+            shape,
+            colour: self.displayed_colour(entity_id, colour),
+            sprite: self.sprite.get(&entity_id),
+            animation_frame: self.animation
+                .get(&entity_id)
+                .map(AnimationState::frame_index),
+            blend: self.blend(entity_id),
+        })
+    }
+    fn for_each_render_update<'a, F>(&'a self, mut f: F)
+    where
+        F: FnMut(RenderUpdate<'a>),
+    {
+        for (&entity_id, &position) in self.position.iter() {
+            let update = match self.render_update(entity_id, position) {
+                Some(update) => update,
+                None => continue,
+            };
+            f(update);
+            if let Some(bounds) = self.wrap_bounds() {
+                for offset in bounds.seam_offsets(&update.shape.aabb(position)) {
+                    f(RenderUpdate {
+                        position: position - offset,
+                        ..update
+                    });
+                }
+            }
+        }
+    }
+    /// Visible entities ordered by z index then id, with wrap-around seam
+    /// copies after the entity they belong to. The ordering reuses a scratch
+    /// buffer, so this only allocates while the number of entities grows.
+    pub fn render_updates(&self) -> impl Iterator<Item = RenderUpdate<'_>> {
+        let mut order = self.render_order.take();
+        order.clear();
+        self.for_each_render_update(|update| {
+            let sequence = order.len();
+            order.push((update.z_index, sequence, update.entity_id, update.position));
+        });
+        order.sort_unstable_by_key(|&(z_index, sequence, _, _)| (z_index, sequence));
+        RenderUpdates {
+            world: self,
+            order,
+            next: 0,
+        }
+    }
+    pub fn render_updates_changed(&mut self) -> impl Iterator<Item = RenderUpdate<'_>> {
+        self.render_changed.clear();
+        self.render_removals.clear();
+        self.render_removals.append(&mut self.unreported_removals);
+        for (&id, &position) in self.position.iter() {
+            let colour = match self.colour.get(&id) {
+                Some(&colour) if self.shape.contains_key(&id) && self.is_visible(id) => {
+                    self.displayed_colour(id, colour)
+                }
+                _ => continue,
+            };
+            let unchanged = !self.shape_dirty.contains(&id)
+                && self.rendered.get(&id) == Some(&(position, colour));
+            if !unchanged {
+                self.rendered.insert(id, (position, colour));
+                self.render_changed.insert(id);
+            }
+        }
+        self.shape_dirty.clear();
+        let position = &self.position;
+        let hidden = &self.hidden;
+        let render_removals = &mut self.render_removals;
+        self.rendered.retain(|id, _| {
+            let keep = position.contains_key(id) && !hidden.contains(id);
+            if !keep {
+                render_removals.push(*id);
+            }
+            keep
+        });
+        self.render_removals.sort();
+        self.render_removals.dedup();
+        let this: &Self = self;
+        this.render_updates()
+            .filter(move |update| this.render_changed.contains(&update.entity_id))
+    }
+    pub fn set_gizmos_enabled(&mut self, enabled: bool) {
+        self.gizmos_enabled = enabled;
+        self.contact_gizmos.clear();
+    }
+    pub fn gizmos_enabled(&self) -> bool {
+        self.gizmos_enabled
+    }
+    fn record_contact_gizmo(&mut self, id: EntityId, contact: Contact) {
+        let centre = match (self.position.get(&id), self.shape.get(&id)) {
+            (Some(&position), Some(shape)) => shape.aabb(position).centre(),
+            _ => return,
+        };
+        let (point, normal) = gizmo::contact_point_and_normal(
+            centre,
+            contact.surface,
+            SubPixelI64::new_pixels(1),
+        );
+        self.contact_gizmos.push(Gizmo::Contact {
+            entity_id: id,
+            other_id: contact.other_id,
+            point,
+            normal,
+        });
+    }
+    /// Velocity gizmos for dynamic bodies in id order, then the contacts recorded
+    /// during the last update. Empty unless gizmos are enabled.
+    pub fn gizmos<'a>(&'a self) -> impl Iterator<Item = Gizmo> + 'a {
+        let velocities = if self.gizmos_enabled {
+            Some(self.velocity.iter())
+        } else {
+            None
+        };
+        velocities
+            .into_iter()
+            .flatten()
+            .filter_map(move |(&id, &velocity)| {
+                let position = self.position.get(&id)?;
+                let shape = self.shape.get(&id)?;
+                Some(Gizmo::Velocity {
+                    entity_id: id,
+                    origin: shape.aabb(*position).centre(),
+                    velocity,
+                })
+            })
+            .chain(self.contact_gizmos.iter().cloned())
+    }
+    pub fn render_removals(&self) -> &[EntityId] {
+        &self.render_removals
+    }
+    pub fn begin_update(&mut self, input_model: &InputModel) -> bool {
+        profile!("begin_update");
+        self.stats = Default::default();
+        self.command_errors.clear();
+        #[cfg(feature = "validation")]
+        {
+            self.validation_error = None;
+        }
+        self.explosion_events.clear();
+        self.contact_events.clear();
+        self.impact_events.clear();
+        self.launch_events.clear();
+        self.teleport_events.clear();
+        self.damage_events.clear();
+        self.death_events.clear();
+        self.pickup_events.clear();
+        self.timer_events.clear();
+        self.state_change_events.clear();
+        self.target_lost_events.clear();
+        if self.paused {
+            return false;
+        }
+        self.resources.get_or_insert_with(FrameCount::default).0 += 1;
+        mem::swap(&mut self.touching, &mut self.previously_touching);
+        self.touching.clear();
+        if let Some(player_id) = self.player_id {
+            if let Some(velocity) = self.velocity.get_mut(&player_id) {
+                let config = &self.resources.config;
+                *velocity = update_player_velocity(*velocity, input_model, config);
+            }
+        }
+        self.contact_gizmos.clear();
+        self.time_ticks = self.resources.time_scale.ticks(&mut self.time_remainder);
+        for _ in 0..self.time_ticks {
+            self.scheduler.tick(&mut self.timer_events);
+        }
+        true
+    }
+    pub fn run_system(&mut self, system: System) {
+        match system {
+            System::Integrate => self.integrate(),
+            System::Broadphase => self.update_islands(),
+            System::Narrowphase => self.move_islands(),
+            System::Resolve => self.resolve(),
+            System::Events => self.dispatch_events(),
+        }
+    }
+    fn integrate(&mut self) {
+        profile!("integrate");
+        self.advance_animations();
+        self.advance_colour_effects();
+        self.accumulate_attractor_forces();
+        self.accumulate_pickup_magnets();
+        self.drive_path_followers();
+        self.steer_homing();
+        self.integrate_velocities();
+    }
+    fn update_islands(&mut self) {
+        profile!("broadphase");
+        self.build_islands();
+        if self.resources.config.resting_contacts
+            && !sub_pixel_i64::vector_is_zero(self.resources.config.gravity)
+        {
+            self.sort_islands_along_gravity();
+        }
+    }
+    fn move_islands(&mut self) {
+        profile!("narrowphase");
+        for island_index in 0..self.islands.len() {
+            if !self.is_island_active(island_index) {
+                continue;
+            }
+            if self.update_island_sleep(island_index) {
+                continue;
+            }
+            profile!("island", island_index);
+            for i in 0..self.islands[island_index].len() {
+                let id = self.islands[island_index][i];
+                self.move_entity(id, island_index);
+            }
+        }
+    }
+    fn resolve(&mut self) {
+        profile!("resolve");
+        self.apply_hazards();
+        self.apply_damage_events();
+        self.collect_pickups();
+        self.apply_portals();
+        self.apply_rope_constraints();
+        self.step_verlet_systems();
+        self.step_particles();
+        self.apply_out_of_bounds_policies();
+        self.tick_lifetimes();
+        self.evaluate_state_machines();
+    }
+    fn dispatch_events(&mut self) {
+        profile!("dispatch_events");
+        self.apply_commands();
+        self.track_changes();
+        let camera_target = self.player_id.and_then(|id| self.centre(id));
+        self.resources.camera.update(camera_target);
+        self.publish_frame();
+        #[cfg(feature = "validation")]
+        {
+            if self.resources.config.validation_tolerance.is_some() {
+                self.validation_error =
+                    self.validate().err().map(PhysicsError::Validation);
+            }
+        }
+    }
+}
+
+/// A `World` together with the schedule of systems that updates it. Derefs to
+/// the world, so queries and edits read the same on either.
+#[derive(Clone)]
+pub struct GameState {
+    world: World,
+    schedule: Schedule,
+    #[cfg(feature = "alloc-audit")]
+    allocation_audit: bool,
+}
+
+impl Deref for GameState {
+    type Target = World;
+    fn deref(&self) -> &World {
+        &self.world
+    }
+}
+
+impl DerefMut for GameState {
+    fn deref_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+}
+
+impl GameState {
+    pub fn new(size_hint: Vector2<f32>, config: PhysicsConfig) -> Self {
+        Self {
+            world: World::new(size_hint, config),
+            schedule: Default::default(),
+            #[cfg(feature = "alloc-audit")]
+            allocation_audit: false,
+        }
+    }
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+    pub fn register_hook<F>(&mut self, stage: UpdateStage, f: F)
+    where
+        F: 'static + Clone + FnMut(&mut WorldView),
+    {
+        self.schedule.add_hook(stage, f);
+    }
+    pub fn clear_hooks(&mut self) {
+        self.schedule.clear_hooks();
+    }
+    pub fn add_plugin<P: Plugin>(&mut self, plugin: P) {
+        plugin.build(&mut self.world, &mut self.schedule);
+    }
+    pub fn schedule_mut(&mut self) -> &mut Schedule {
+        &mut self.schedule
+    }
+    /// Dynamic bodies are resolved in `resolution_order`: islands by lowest member id,
+    /// and bodies within an island by id, or by depth along gravity with resting
+    /// contacts enabled. Hash map iteration order never affects the result.
+    pub fn update(&mut self, input_model: &InputModel) {
+        profile!("update");
+        #[cfg(feature = "alloc-audit")]
+        let allocations = alloc_audit::allocations();
+        if self.world.begin_update(input_model) {
+            self.schedule.run(&mut self.world);
+        }
+        #[cfg(feature = "alloc-audit")]
+        {
+            let allocated = alloc_audit::allocations() - allocations;
+            if self.allocation_audit && allocated > 0 {
+                panic!("update allocated {} times after warm-up", allocated);
+            }
+        }
+    }
+    #[cfg(feature = "alloc-audit")]
+    pub fn set_allocation_audit(&mut self, enabled: bool) {
+        self.allocation_audit = enabled;
+    }
+    pub fn step_n(&mut self, frames: usize, inputs: &[InputModel]) -> Vec<u64> {
+        let no_input = InputModel::default();
+        (0..frames)
+            .map(|frame| {
+                let input = inputs.get(frame).or(inputs.last()).unwrap_or(&no_input);
+                self.update(input);
+                self.state_hash()
+            })
+            .collect()
+    }
+    pub fn branch(&self) -> GameState {
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chain::Chain;
+    use convex_polygon::ConvexPolygon;
+    use heightfield::Heightfield;
+    use sprite::SourceRect;
+    use systems::Removal;
+    use state_delta::TableDelta;
+    use std::cell::RefCell;
+
+    fn block() -> Shape<SubPixelI64> {
+        let size = SubPixelI64::new_pixels(8);
+        Shape::AxisAlignedRect(AxisAlignedRect::new(vec2(size, size)))
+    }
+
+    fn single_rect_world() -> (GameState, EntityId) {
+        spawn_moving_rect(GameState::new(vec2(640., 480.), PhysicsConfig::default()))
+    }
+
+    fn falling_rect_world(gravity: i64) -> (GameState, EntityId) {
+        let config = PhysicsConfig {
+            gravity: vec2(Zero::zero(), SubPixelI64::new(gravity)),
+            ..PhysicsConfig::default()
+        };
+        spawn_moving_rect(GameState::new(vec2(640., 480.), config))
+    }
+
+    fn spawn_moving_rect(mut game_state: GameState) -> (GameState, EntityId) {
+        let id = game_state
+            .spawn()
+            .position_pixels(vec2(100., 100.))
+            .shape(block())
+            .velocity(vec2(SubPixelI64::new(40), Zero::zero()))
+            .build()
+            .unwrap();
+        (game_state, id)
+    }
+
+    #[test]
+    fn user_data_is_dropped_with_its_entity() {
+        let (mut game_state, id) = single_rect_world();
+        game_state.user_data_mut().insert(id, "player");
+        assert_eq!(game_state.user_data().get::<&str>(id), Some(&"player"));
+        assert!(game_state.contains_entity(id));
+        game_state.remove_entity(id);
+        assert!(!game_state.contains_entity(id));
+        assert_eq!(game_state.user_data().get::<&str>(id), None);
+    }
+
+    fn block_beside_wall(max_slide_iterations: usize) -> (GameState, EntityId) {
+        let (mut game_state, id) = single_rect_world();
+        let config = *game_state.config();
+        game_state.set_config(PhysicsConfig {
+            max_slide_iterations,
+            ..config
+        });
+        game_state.set_velocity(id, vec2(SubPixelI64::new(40), SubPixelI64::new(40)));
+        let wall = Shape::AxisAlignedRect(AxisAlignedRect::new(vec2(
+            SubPixelI64::new_pixels(8),
+            SubPixelI64::new_pixels(64),
+        )));
+        game_state
+            .spawn()
+            .position(vec2(SubPixelI64::new(108 * 256 + 10), SubPixelI64::new_pixels(80)))
+            .shape(wall)
+            .body_type(BodyType::Static)
+            .build()
+            .unwrap();
+        (game_state, id)
+    }
+
+    fn block_approaching_wall(
+        gap: i64,
+        velocity: Vector2<SubPixelI64>,
+    ) -> (GameState, EntityId) {
+        let (mut game_state, id) = single_rect_world();
+        game_state.set_velocity(id, velocity);
+        let wall = Shape::AxisAlignedRect(AxisAlignedRect::new(vec2(
+            SubPixelI64::new_pixels(8),
+            SubPixelI64::new_pixels(64),
+        )));
+        game_state
+            .spawn()
+            .position(vec2(
+                SubPixelI64::new(108 * 256 + gap),
+                SubPixelI64::new_pixels(80),
+            ))
+            .shape(wall)
+            .body_type(BodyType::Static)
+            .build()
+            .unwrap();
+        (game_state, id)
+    }
+
+    #[test]
+    fn convex_polygons_stop_at_walls() {
+        let (mut game_state, block_id) = block_approaching_wall(20, Zero::zero());
+        game_state.remove_entity(block_id);
+        let pixels = SubPixelI64::new_pixels;
+        let wedge = ConvexPolygon::new(vec![
+            vec2(pixels(0), pixels(0)),
+            vec2(pixels(8), pixels(4)),
+            vec2(pixels(0), pixels(8)),
+        ]);
+        let id = game_state
+            .spawn()
+            .position_pixels(vec2(80., 100.))
+            .shape(Shape::ConvexPolygon(wedge))
+            .velocity(vec2(pixels(4), Zero::zero()))
+            .build()
+            .unwrap();
+        let input = InputModel::default();
+        for _ in 0..10 {
+            game_state.update(&input);
+        }
+        let tip = game_state.position(id).unwrap().x + pixels(8);
+        let wall = SubPixelI64::new(108 * 256 + 20);
+        assert!(tip <= wall && tip > wall - pixels(1));
+    }
+
+    #[test]
+    fn skin_keeps_bodies_off_surfaces() {
+        let input = InputModel::default();
+        for &skin in [0, 16].iter() {
+            let velocity = vec2(SubPixelI64::new(40), Zero::zero());
+            let (mut game_state, id) = block_approaching_wall(20, velocity);
+            game_state.set_skin(id, Some(SubPixelI64::new(skin))).unwrap();
+            assert_eq!(game_state.skin(id), SubPixelI64::new(skin));
+            game_state.update(&input);
+            let moved = game_state.position(id).unwrap().x - SubPixelI64::new_pixels(100);
+            let gap = SubPixelI64::new(20) - moved;
+            assert!(gap >= SubPixelI64::new(skin) && gap <= SubPixelI64::new(skin + 1));
+            game_state.set_skin(id, None).unwrap();
+            assert_eq!(game_state.skin(id), game_state.config().default_skin);
+        }
+    }
+
+    #[test]
+    fn narrowphase_rounding_trades_safety_for_accuracy() {
+        use physics_config::NarrowphaseRounding::*;
+        let skin = SubPixelI64::new(16);
+        let settle = |narrowphase_rounding, velocity| {
+            let (mut game_state, id) = block_approaching_wall(1000, velocity);
+            let config = *game_state.config();
+            game_state.set_config(PhysicsConfig {
+                narrowphase_rounding,
+                ..config
+            });
+            game_state.set_skin(id, Some(skin)).unwrap();
+            game_state.update(&InputModel::default());
+            let position = game_state.position(id).unwrap();
+            let moved = position.x - SubPixelI64::new_pixels(100);
+            (SubPixelI64::new(1000) - moved, position.y)
+        };
+        assert_eq!(PhysicsConfig::default().narrowphase_rounding, RoundTowardStart);
+        for &(x, y) in [(1500, 0), (1537, 700)].iter() {
+            let velocity = vec2(SubPixelI64::new(x), SubPixelI64::new(y));
+            let (toward_start, slid) = settle(RoundTowardStart, velocity);
+            let (floor, floor_slid) = settle(ExactFloor, velocity);
+            let (with_skin, skin_slid) = settle(ExactWithSkin, velocity);
+            assert_eq!(floor, skin);
+            assert_eq!(toward_start, floor + SubPixelI64::new(1));
+            assert!(with_skin > floor && with_skin <= floor + skin);
+            if y == 0 {
+                assert_eq!(with_skin, floor + skin);
+            }
+            let expected = SubPixelI64::new_pixels(100) + SubPixelI64::new(y);
+            assert_eq!((slid, floor_slid, skin_slid), (expected, expected, expected));
+        }
+        // Already inside the skin: the back-off stops at the start, leaving only
+        // the slide's own skin to push the body away.
+        let (mut game_state, id) = block_approaching_wall(8, vec2(skin, Zero::zero()));
+        let config = *game_state.config();
+        game_state.set_config(PhysicsConfig {
+            narrowphase_rounding: ExactWithSkin,
+            ..config
+        });
+        game_state.set_skin(id, Some(skin)).unwrap();
+        game_state.update(&InputModel::default());
+        let moved = game_state.position(id).unwrap().x - SubPixelI64::new_pixels(100);
+        assert_eq!(moved, -skin);
+    }
+
+    #[test]
+    fn speculative_contacts_stop_bodies_within_the_skin() {
+        let velocity = vec2(SubPixelI64::new(40), Zero::zero());
+        for &speculative_contacts in [true, false].iter() {
+            let (mut game_state, id) = block_approaching_wall(50, velocity);
+            let config = *game_state.config();
+            game_state.set_config(PhysicsConfig {
+                speculative_contacts,
+                ..config
+            });
+            game_state.update(&InputModel::default());
+            let moved = game_state.position(id).unwrap().x - SubPixelI64::new_pixels(100);
+            assert_eq!(moved, SubPixelI64::new(40));
+            let expected = if speculative_contacts {
+                vec2(Zero::zero(), Zero::zero())
+            } else {
+                velocity
+            };
+            assert_eq!(game_state.velocity(id), Some(expected));
+        }
+    }
+
+    #[test]
+    fn axis_separated_movement_resolves_each_axis_alone() {
+        let velocity = vec2(SubPixelI64::new(40), SubPixelI64::new(30));
+        let (mut game_state, id) = block_approaching_wall(10, velocity);
+        let config = *game_state.config();
+        game_state.set_config(PhysicsConfig {
+            speculative_contacts: false,
+            ..config
+        });
+        assert_eq!(game_state.movement_mode(id), MovementMode::Slide);
+        game_state.set_movement_mode(id, Some(MovementMode::AxisSeparated));
+        game_state.update(&InputModel::default());
+        let start = vec2(SubPixelI64::new_pixels(100), SubPixelI64::new_pixels(100));
+        let moved = game_state.position(id).unwrap() - start;
+        assert_eq!(moved, vec2(SubPixelI64::new(9), SubPixelI64::new(30)));
+        assert_eq!(game_state.stats().slide_iterations, 2);
+        game_state.set_movement_mode(id, None);
+        assert_eq!(game_state.movement_mode(id), MovementMode::Slide);
+    }
+
+    #[test]
+    fn slide_iterations_come_from_config() {
+        let start = vec2(SubPixelI64::new_pixels(100), SubPixelI64::new_pixels(100));
+        let (mut sliding, id) = block_beside_wall(16);
+        sliding.update(&InputModel::default());
+        let slid = sliding.position(id).unwrap() - start;
+        assert!(slid.x <= SubPixelI64::new(10));
+        assert!(slid.y > SubPixelI64::new(30));
+        assert_eq!(sliding.stats().max_slide_iterations_hit, 0);
+        let (mut stopped, id) = block_beside_wall(1);
+        stopped.update(&InputModel::default());
+        let moved = stopped.position(id).unwrap() - start;
+        assert!(moved.y <= SubPixelI64::new(10));
+        assert_eq!(stopped.stats().max_slide_iterations_hit, 1);
+    }
+
+    #[test]
+    fn player_speed_comes_from_config() {
+        let (mut game_state, id) = single_rect_world();
+        game_state.set_player_id(Some(id));
+        let config = *game_state.config();
+        game_state.set_config(PhysicsConfig {
+            player_velocity_multiplier: 3,
+            ..config
+        });
+        let mut input = InputModel::default();
+        input.set_right(1.);
+        game_state.update(&input);
+        let velocity = vec2(SubPixelI64::new_pixels(3), Zero::zero());
+        assert_eq!(game_state.velocity(id), Some(velocity));
+    }
+
+    #[test]
+    fn resting_bodies_fall_asleep_and_wake_on_demand() {
+        let (mut game_state, id) = single_rect_world();
+        let config = *game_state.config();
+        game_state.set_config(PhysicsConfig {
+            sleep_after_updates: 3,
+            ..config
+        });
+        let input = InputModel::default();
+        game_state.update(&input);
+        assert!(!game_state.is_asleep(id));
+        game_state.set_velocity(id, vec2(SubPixelI64::new(1), Zero::zero()));
+        for _ in 0..2 {
+            game_state.update(&input);
+            assert!(!game_state.is_asleep(id));
+        }
+        game_state.update(&input);
+        assert!(game_state.is_asleep(id));
+        assert_eq!(game_state.velocity(id), Some(vec2(Zero::zero(), Zero::zero())));
+        let position = game_state.position(id);
+        game_state.update(&input);
+        assert_eq!(game_state.position(id), position);
+        let velocity = vec2(SubPixelI64::new(40), Zero::zero());
+        game_state.set_velocity(id, velocity);
+        assert!(!game_state.is_asleep(id));
+        game_state.update(&input);
+        assert_eq!(game_state.position(id), position.map(|p| p + velocity));
+    }
+
+    #[test]
+    fn stats_count_each_update() {
+        let (mut game_state, id) = single_rect_world();
+        let input = InputModel::default();
+        game_state.update(&input);
+        let alone = game_state.stats();
+        assert_eq!(alone.broadphase_candidates, 0);
+        assert_eq!(alone.narrowphase_tests, 0);
+        assert_eq!(alone.slide_iterations, 1);
+        let neighbour = game_state.position(id).unwrap()
+            + vec2(SubPixelI64::new_pixels(9), Zero::zero());
+        game_state
+            .spawn()
+            .position(neighbour)
+            .shape(block())
+            .body_type(BodyType::Static)
+            .build()
+            .unwrap();
+        game_state.update(&input);
+        let crowded = game_state.stats();
+        assert!(crowded.broadphase_candidates >= 1);
+        assert!(crowded.narrowphase_tests >= 1);
+        assert!(crowded.slide_iterations >= 1);
+        assert_eq!(crowded.max_slide_iterations_hit, 0);
+        game_state.set_velocity(id, vec2(Zero::zero(), Zero::zero()));
+        game_state.set_paused(true);
+        game_state.update(&input);
+        assert_eq!(game_state.stats(), UpdateStats::default());
+    }
+
+    #[test]
+    fn hitstop_freezes_velocity_and_position() {
+        let (mut game_state, id) = falling_rect_world(16);
+        let input = InputModel::default();
+        game_state.update(&input);
+        let before = (game_state.position(id), game_state.velocity(id));
+        game_state.set_paused(true);
+        for _ in 0..30 {
+            game_state.update(&input);
+        }
+        assert_eq!((game_state.position(id), game_state.velocity(id)), before);
+        game_state.set_paused(false);
+        game_state.update(&input);
+        let velocity = game_state.velocity(id).unwrap();
+        assert_eq!(velocity.y, before.1.unwrap().y + SubPixelI64::new(16));
+    }
+
+    #[test]
+    fn slow_motion_scales_integration_and_timers() {
+        let (mut game_state, id) = falling_rect_world(16);
+        game_state.set_lifetime(id, Some(Lifetime::new(4)));
+        game_state.set_time_scale(1, 2).unwrap();
+        let input = InputModel::default();
+        for _ in 0..4 {
+            game_state.update(&input);
+        }
+        assert_eq!(game_state.velocity(id).unwrap().y, SubPixelI64::new(32));
+        assert!(game_state.contains_entity(id));
+        for _ in 0..4 {
+            game_state.update(&input);
+        }
+        assert!(!game_state.contains_entity(id));
+    }
+
+    #[test]
+    fn invalid_time_scale_is_rejected() {
+        let mut game_state = GameState::new(vec2(640., 480.), PhysicsConfig::default());
+        assert_eq!(
+            game_state.set_time_scale(1, 0),
+            Err(PhysicsError::InvalidTimeScale)
+        );
+        for &(numerator, denominator) in [(-1, 2), (0, 1), (1, -2)].iter() {
+            assert_eq!(
+                game_state.set_time_scale(numerator, denominator),
+                Err(PhysicsError::InvalidTimeScale)
+            );
+        }
+        assert_eq!(game_state.time_scale(), TimeScale::default());
+    }
+
+    #[test]
+    fn extreme_time_scales_saturate() {
+        let fast = TimeScale::new(i64::MAX, 1).unwrap();
+        let mut remainder = 0;
+        assert_eq!(fast.ticks(&mut remainder), u32::MAX);
+        let velocity = vec2(SubPixelI64::new(2), SubPixelI64::new(-2));
+        let mut movement_remainder = vec2(Zero::zero(), Zero::zero());
+        assert_eq!(
+            fast.scale_vector(velocity, &mut movement_remainder),
+            vec2(SubPixelI64::new(i64::MAX), SubPixelI64::new(i64::MIN))
+        );
+        let slow = TimeScale::new(i64::MAX - 1, i64::MAX).unwrap();
+        let mut remainder = i64::MAX - 1;
+        assert_eq!(slow.ticks(&mut remainder), 1);
+        assert_eq!(remainder, i64::MAX - 2);
+    }
+
+    #[test]
+    fn instantiate_copies_every_prefab_field() {
+        let mut game_state = GameState::new(vec2(640., 480.), PhysicsConfig::default());
+        let mut prefab = Prefab::new(block());
+        prefab.colour = [1., 0., 0., 1.];
+        prefab.body_type = BodyType::Dynamic;
+        prefab.velocity = Some(vec2(SubPixelI64::new(3), Zero::zero()));
+        prefab.mass = Some(2);
+        prefab.layers = 0b10;
+        prefab.movement_mode = Some(MovementMode::AxisSeparated);
+        prefab.skin = Some(SubPixelI64::new(5));
+        prefab.lifetime = Some(Lifetime::new(3));
+        game_state.prefabs_mut().register("crate", prefab.clone());
+        assert_eq!(game_state.prefabs().names().collect::<Vec<_>>(), ["crate"]);
+        let position = vec2(SubPixelI64::new_pixels(10), SubPixelI64::new_pixels(10));
+        let instantiated = game_state.instantiate("crate", position).unwrap();
+        let added = game_state.add_entities(vec![(position, prefab)]).unwrap()[0];
+        for &id in [instantiated, added].iter() {
+            assert_eq!(game_state.position(id), Some(position));
+            assert_eq!(game_state.colour.get(&id), Some(&[1., 0., 0., 1.]));
+            let velocity = vec2(SubPixelI64::new(3), Zero::zero());
+            assert_eq!(game_state.velocity(id), Some(velocity));
+            assert_eq!(game_state.mass(id), Some(2));
+            assert_eq!(game_state.collision_layers(id), 0b10);
+            assert_eq!(game_state.movement_mode(id), MovementMode::AxisSeparated);
+            assert_eq!(game_state.skin(id), SubPixelI64::new(5));
+            assert!(game_state.lifetime(id).is_some());
+        }
+        assert!(game_state.prefabs_mut().remove("crate").is_some());
+        assert!(game_state.prefabs().get("crate").is_none());
+    }
+
+    #[test]
+    fn component_iterators_join_their_tables() {
+        let (mut game_state, id) = single_rect_world();
+        game_state.set_mass(id, Some(3)).unwrap();
+        let wall = game_state
+            .spawn()
+            .position_pixels(vec2(10., 10.))
+            .shape(block())
+            .body_type(BodyType::Static)
+            .build()
+            .unwrap();
+        let mut shaped = game_state
+            .iter_position_shape()
+            .map(|(id, _, _)| id)
+            .collect::<Vec<_>>();
+        shaped.sort();
+        assert!(shaped.contains(&id) && shaped.contains(&wall));
+        for (_, position, velocity) in game_state.iter_position_velocity_mut() {
+            *position += *velocity;
+        }
+        let expected = vec2(
+            SubPixelI64::new_pixels(100) + SubPixelI64::new(40),
+            SubPixelI64::new_pixels(100),
+        );
+        assert_eq!(game_state.position(id), Some(expected));
+        for (_, velocity, _) in game_state.iter_velocity_mut_position() {
+            velocity.y = SubPixelI64::new(1);
+        }
+        let massed = game_state
+            .iter_velocity_mut_mass()
+            .map(|(id, velocity, mass)| (id, *velocity, mass))
+            .collect::<Vec<_>>();
+        assert_eq!(massed, [(id, vec2(SubPixelI64::new(40), SubPixelI64::new(1)), 3)]);
+    }
+
+    #[test]
+    fn world_grows_to_fit_entities() {
+        for &auto_grow_world in [true, false].iter() {
+            let mut game_state =
+                GameState::new(vec2(640., 480.), PhysicsConfig::default());
+            let config = *game_state.config();
+            game_state.set_config(PhysicsConfig {
+                auto_grow_world,
+                ..config
+            });
+            let bounds = game_state.world_bounds();
+            let far = vec2(SubPixelI64::new_pixels(2000), SubPixelI64::new_pixels(-900));
+            let id = game_state
+                .spawn()
+                .position(far)
+                .shape(block())
+                .body_type(BodyType::Static)
+                .build()
+                .unwrap();
+            if auto_grow_world {
+                assert!(game_state.world_bounds().contains(&block().aabb(far)));
+                assert!(game_state.world_bounds().contains(&bounds));
+                let mut found = Vec::new();
+                game_state.overlap_shape(&block(), far, ALL_COLLISION_LAYERS, &mut found);
+                assert_eq!(found, [id]);
+            } else {
+                assert_eq!(game_state.world_bounds(), bounds);
+            }
+        }
+    }
+
+    #[test]
+    fn world_bounds_walls_keep_bodies_inside() {
+        let mut closed = GameState::new(vec2(640., 480.), PhysicsConfig::default());
+        assert_eq!(closed.add_world_bounds(false).unwrap().len(), 4);
+        let (mut game_state, id) = falling_rect_world(64);
+        let bounds = game_state.world_bounds();
+        let walls = game_state.add_world_bounds(true).unwrap();
+        assert_eq!(walls.len(), 3);
+        for wall in walls {
+            let position = game_state.position(wall).unwrap();
+            assert!(bounds.contains(&game_state.shape[&wall].aabb(position)));
+        }
+        for _ in 0..600 {
+            game_state.update(&InputModel::default());
+        }
+        let position = game_state.position(id).unwrap();
+        assert!(bounds.contains(&block().aabb(position)));
+        let floor = bounds.bottom_right_coord().y
+            - SubPixelI64::new_pixels(WORLD_BOUNDS_THICKNESS_PIXELS + 8);
+        assert!(position.y <= floor && position.y > floor - SubPixelI64::new_pixels(1));
+    }
+
+    #[test]
+    fn set_position_moves_a_single_proxy() {
+        let mut game_state = GameState::new(vec2(640., 480.), PhysicsConfig::default());
+        let wall = game_state
+            .spawn()
+            .position_pixels(vec2(300., 100.))
+            .shape(block())
+            .body_type(BodyType::Static)
+            .build()
+            .unwrap();
+        let beside = vec2(SubPixelI64::new_pixels(150), SubPixelI64::new_pixels(300));
+        game_state.commands().set_position(wall, beside);
+        game_state.update(&InputModel::default());
+        assert_eq!(game_state.stats().broadphase_rebuilds, 0);
+        assert!(game_state.command_errors().is_empty());
+        let mut found = Vec::new();
+        game_state.overlap_shape(&block(), beside, ALL_COLLISION_LAYERS, &mut found);
+        assert_eq!(found, [wall]);
+        let old = vec2(SubPixelI64::new_pixels(300), SubPixelI64::new_pixels(100));
+        game_state.overlap_shape(&block(), old, ALL_COLLISION_LAYERS, &mut found);
+        assert!(found.is_empty());
+        let far = vec2(SubPixelI64::new_pixels(5000), SubPixelI64::new_pixels(100));
+        game_state.commands().set_position(wall, far);
+        game_state.update(&InputModel::default());
+        assert_eq!(game_state.stats().broadphase_rebuilds, 1);
+        game_state.overlap_shape(&block(), far, ALL_COLLISION_LAYERS, &mut found);
+        assert_eq!(found, [wall]);
+    }
+
+    #[test]
+    fn step_n_hashes_every_frame() {
+        let (mut a, id) = falling_rect_world(16);
+        let mut b = a.branch();
+        let mut right = InputModel::default();
+        right.set_right(1.);
+        a.set_player_id(Some(id));
+        b.set_player_id(Some(id));
+        let inputs = [InputModel::default(), right];
+        let hashes = a.step_n(4, &inputs);
+        assert_eq!(hashes.len(), 4);
+        assert_eq!(*hashes.last().unwrap(), a.state_hash());
+        assert!(hashes.windows(2).all(|pair| pair[0] != pair[1]));
+        let with_repeated_last = (0..4)
+            .map(|frame| {
+                b.update(&inputs[frame.min(1)]);
+                b.state_hash()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(hashes, with_repeated_last);
+    }
+
+    #[test]
+    fn render_updates_are_ordered_by_z_then_id() {
+        let (mut game_state, id) = single_rect_world();
+        let background = game_state
+            .spawn()
+            .position_pixels(vec2(10., 10.))
+            .shape(block())
+            .body_type(BodyType::Static)
+            .build()
+            .unwrap();
+        let middle = game_state
+            .spawn()
+            .position_pixels(vec2(30., 10.))
+            .shape(block())
+            .body_type(BodyType::Static)
+            .build()
+            .unwrap();
+        game_state.set_z_index(background, Some(-1));
+        game_state.set_z_index(id, Some(2));
+        let order = game_state
+            .render_updates()
+            .map(|update| (update.entity_id, update.z_index))
+            .collect::<Vec<_>>();
+        assert_eq!(order, [(background, -1), (middle, 0), (id, 2)]);
+    }
+
+    #[test]
+    fn timer_events_only_drive_their_own_state_machine() {
+        let (mut game_state, id) = single_rect_world();
+        let other = game_state
+            .spawn()
+            .position_pixels(vec2(30., 10.))
+            .shape(block())
+            .body_type(BodyType::Static)
+            .build()
+            .unwrap();
+        let machine = StateMachine::new(0).with_transition(
+            0,
+            Trigger::Event("alarm".to_string()),
+            1,
+        );
+        game_state.set_state_machine(id, Some(machine.clone()));
+        game_state.set_state_machine(other, Some(machine));
+        game_state.scheduler_mut().after("alarm", 1);
+        game_state.scheduler_mut().after_for(other, "alarm", 2);
+        let input = InputModel::default();
+        game_state.update(&input);
+        assert_eq!(game_state.timer_events()[0].entity, None);
+        assert!(game_state.state_change_events().is_empty());
+        game_state.update(&input);
+        assert_eq!(game_state.timer_events()[0].entity, Some(other));
+        assert_eq!(
+            game_state.state_change_events(),
+            [StateChangeEvent {
+                entity_id: other,
+                from: 0,
+                to: 1,
+            }]
+        );
+        game_state.signal(id, "alarm");
+        game_state.update(&input);
+        assert_eq!(game_state.state_machine(id).map(|m| m.state()), Some(1));
+    }
+
+    #[test]
+    fn pushers_move_chains_of_pushable_bodies() {
+        let start = |i| SubPixelI64::new_pixels(109 + 9 * i);
+        for &max_push_chain in [4, 1].iter() {
+            let (mut game_state, id) = single_rect_world();
+            let config = *game_state.config();
+            game_state.set_config(PhysicsConfig {
+                max_push_chain,
+                ..config
+            });
+            let chain = (0..3)
+                .map(|i| {
+                    let pushed = game_state
+                        .spawn()
+                        .position(vec2(start(i), SubPixelI64::new_pixels(100)))
+                        .shape(block())
+                        .velocity(vec2(Zero::zero(), Zero::zero()))
+                        .build()
+                        .unwrap();
+                    game_state.set_pushable(pushed, i < 2);
+                    pushed
+                })
+                .collect::<Vec<_>>();
+            assert!(game_state.is_pushable(chain[0]) && !game_state.is_pushable(chain[2]));
+            for _ in 0..20 {
+                game_state.update(&InputModel::default());
+            }
+            assert!(game_state.position(id).unwrap().x > SubPixelI64::new_pixels(100));
+            let moved = chain
+                .iter()
+                .enumerate()
+                .map(|(i, &pushed)| game_state.position(pushed).unwrap().x > start(i as i64))
+                .collect::<Vec<_>>();
+            assert_eq!(moved, [true, max_push_chain > 1, false]);
+        }
+    }
+
+    #[test]
+    fn render_updates_changed_reports_only_changes() {
+        let (mut game_state, id) = single_rect_world();
+        let wall = game_state
+            .spawn()
+            .position_pixels(vec2(30., 10.))
+            .shape(block())
+            .body_type(BodyType::Static)
+            .build()
+            .unwrap();
+        fn changed(game_state: &mut GameState) -> Vec<EntityId> {
+            game_state
+                .render_updates_changed()
+                .map(|update| update.entity_id)
+                .collect()
+        }
+        assert_eq!(changed(&mut game_state), [id, wall]);
+        assert!(changed(&mut game_state).is_empty());
+        assert!(game_state.render_removals().is_empty());
+        game_state.set_position(wall, vec2(SubPixelI64::new_pixels(40), Zero::zero()));
+        assert_eq!(changed(&mut game_state), [wall]);
+        game_state.set_shape(id, block());
+        assert_eq!(changed(&mut game_state), [id]);
+        game_state.set_visible(id, false);
+        assert!(changed(&mut game_state).is_empty());
+        assert_eq!(game_state.render_removals(), [id]);
+        game_state.set_visible(id, true);
+        assert_eq!(changed(&mut game_state), [id]);
+        game_state.remove_entity(wall);
+        assert!(changed(&mut game_state).is_empty());
+        assert_eq!(game_state.render_removals(), [wall]);
+    }
+
+    #[cfg(feature = "alloc-audit")]
+    #[test]
+    fn steady_state_updates_do_not_allocate() {
+        let mut game_state = GameState::new(vec2(640., 480.), PhysicsConfig::default());
+        game_state.init_demo().unwrap();
+        game_state.set_gizmos_enabled(true);
+        let _frames = game_state.subscribe_frames();
+        let input_model = InputModel::default();
+        for _ in 0..120 {
+            game_state.update(&input_model);
+        }
+        game_state.set_allocation_audit(true);
+        let mut found = Vec::with_capacity(16);
+        for _ in 0..120 {
+            game_state.update(&input_model);
+            let allocations = alloc_audit::allocations();
+            let rendered = game_state.render_updates().count();
+            let gizmos = game_state.gizmos().count();
+            let player = game_state.player_id().unwrap();
+            let position = game_state.position(player).unwrap();
+            let layers = ALL_COLLISION_LAYERS;
+            game_state.overlap_shape(&block(), position, layers, &mut found);
+            assert_eq!(alloc_audit::allocations(), allocations);
+            assert!(rendered > 0 && gizmos > 0 && !found.is_empty());
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn update_phases_are_traced() {
+        use std::sync::{Arc, Mutex};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::subscriber::{self, Subscriber};
+        use tracing::{Event, Metadata};
+
+        struct SpanNames(Arc<Mutex<Vec<&'static str>>>);
+        impl Subscriber for SpanNames {
+            fn enabled(&self, _metadata: &Metadata) -> bool {
+                true
+            }
+            fn new_span(&self, span: &Attributes) -> Id {
+                let mut names = self.0.lock().unwrap();
+                names.push(span.metadata().name());
+                Id::from_u64(names.len() as u64)
+            }
+            fn record(&self, _span: &Id, _values: &Record) {}
+            fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+            fn event(&self, _event: &Event) {}
+            fn enter(&self, _span: &Id) {}
+            fn exit(&self, _span: &Id) {}
+        }
+
+        let (mut game_state, _) = single_rect_world();
+        let names = Arc::new(Mutex::new(Vec::new()));
+        subscriber::with_default(SpanNames(names.clone()), || {
+            game_state.update(&InputModel::default());
+        });
+        let names = names.lock().unwrap();
+        for phase in [
+            "update",
+            "begin_update",
+            "integrate",
+            "broadphase",
+            "narrowphase",
+            "island",
+            "resolve",
+            "slide",
+            "dispatch_events",
+        ].iter()
+        {
+            assert!(names.contains(phase), "no {} span in {:?}", phase, *names);
+        }
+        assert_eq!(names[0], "update");
+    }
+
+    #[test]
+    fn contested_space_goes_to_the_lower_id() {
+        fn contest(lower_on_left: bool, churn: bool) -> (i64, i64, Vec<EntityId>) {
+            let (mut game_state, id) = single_rect_world();
+            game_state.remove_entity(id);
+            let speed = SubPixelI64::new_pixels(6);
+            let mut spawn = |x: f32, velocity: SubPixelI64| {
+                game_state
+                    .spawn()
+                    .position_pixels(vec2(x, 200.))
+                    .shape(block())
+                    .velocity(vec2(velocity, Zero::zero()))
+                    .build()
+                    .unwrap()
+            };
+            let (lower, higher) = if lower_on_left {
+                (spawn(100., speed), spawn(116., -speed))
+            } else {
+                (spawn(116., -speed), spawn(100., speed))
+            };
+            if churn {
+                let fillers = (0..64)
+                    .map(|i| spawn(300. + 10. * i as f32, Zero::zero()))
+                    .collect::<Vec<_>>();
+                for filler in fillers {
+                    game_state.remove_entity(filler);
+                }
+            }
+            let start = |game_state: &GameState, id| game_state.position(id).unwrap().x;
+            let (lower_start, higher_start) =
+                (start(&game_state, lower), start(&game_state, higher));
+            game_state.update(&InputModel::default());
+            let order = game_state.resolution_order().collect();
+            let moved = |id, start: SubPixelI64| {
+                (game_state.position(id).unwrap().x - start).raw().abs()
+            };
+            (moved(lower, lower_start), moved(higher, higher_start), order)
+        }
+        let speed = SubPixelI64::new_pixels(6).raw();
+        for &lower_on_left in [true, false].iter() {
+            let (lower, higher, order) = contest(lower_on_left, false);
+            assert_eq!(lower, speed);
+            assert!(higher < speed / 2);
+            assert_eq!(order, [1, 2]);
+            assert_eq!(contest(lower_on_left, true), (lower, higher, order));
+        }
+    }
+
+    #[cfg(feature = "validation")]
+    #[test]
+    fn updates_report_overlaps_when_validating() {
+        let config = PhysicsConfig {
+            validation_tolerance: Some(Zero::zero()),
+            ..PhysicsConfig::default()
+        };
+        let mut game_state = GameState::new(vec2(640., 480.), config);
+        let mut spawn = |x| {
+            game_state
+                .spawn()
+                .position_pixels(vec2(x, 100.))
+                .shape(block())
+                .velocity(vec2(Zero::zero(), Zero::zero()))
+                .build()
+                .unwrap()
+        };
+        let (a, b) = (spawn(100.), spawn(120.));
+        game_state.update(&InputModel::default());
+        assert_eq!(game_state.validation_error(), None);
+        let overlapping = SubPixelI64::new_pixels(104);
+        game_state.set_position(b, vec2(overlapping, SubPixelI64::new_pixels(100)));
+        game_state.update(&InputModel::default());
+        let overlap = PhysicsError::Validation(ValidationError::Overlap(a, b));
+        assert_eq!(game_state.validation_error(), Some(overlap));
+    }
+
+    #[test]
+    fn removed_statics_leave_the_broadphase() {
+        let mut game_state = GameState::new(vec2(640., 480.), PhysicsConfig::default());
+        let walls = [30., 60., 90.]
+            .iter()
+            .map(|&x| {
+                game_state
+                    .spawn()
+                    .position_pixels(vec2(x, 10.))
+                    .shape(block())
+                    .body_type(BodyType::Static)
+                    .build()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        fn proxies(game_state: &GameState) -> Vec<EntityId> {
+            let bounds = game_state.world_bounds;
+            let mut ids = Vec::new();
+            game_state
+                .broadphase
+                .for_each_intersection(&bounds, |_aabb, info| ids.push(info.entity_id));
+            if let Some(baked) = game_state.static_geometry() {
+                baked.for_each_intersection(&bounds, |collider| {
+                    ids.push(collider.entity_id)
+                });
+            }
+            ids.sort();
+            ids
+        }
+        assert_eq!(proxies(&game_state), walls);
+        game_state.remove_entity(walls[1]);
+        assert_eq!(proxies(&game_state), [walls[0], walls[2]]);
+        assert_eq!(game_state.validate(), Ok(()));
+        assert_eq!(game_state.bake_statics().len(), 2);
+        let baked = proxies(&game_state);
+        game_state.remove_entity(baked[0]);
+        assert_eq!(proxies(&game_state), [baked[1]]);
+        assert_eq!(game_state.validate(), Ok(()));
+        let aabb = block().aabb(vec2(Zero::zero(), Zero::zero()));
+        game_state.broadphase.insert(aabb, SpatialInfo { entity_id: baked[0] });
+        assert_eq!(game_state.validate(), Err(ValidationError::OrphanProxy(baked[0])));
+    }
+
+    #[test]
+    fn baking_keeps_the_lowest_id_of_each_merged_run() {
+        let mut game_state = GameState::new(vec2(640., 480.), PhysicsConfig::default());
+        let mut wall = |x: f32, colour: [f32; 4]| {
+            game_state
+                .spawn()
+                .position_pixels(vec2(x, 300.))
+                .shape(block())
+                .colour(colour)
+                .body_type(BodyType::Static)
+                .build()
+                .unwrap()
+        };
+        let white = [1., 1., 1., 1.];
+        let row = [wall(200., white), wall(208., white), wall(216., white)];
+        let red = wall(224., [1., 0., 0., 1.]);
+        let renamed = game_state.bake_statics();
+        let expected = [(row[0], row[0]), (row[1], row[0]), (row[2], row[0]), (red, red)];
+        assert_eq!(renamed.into_iter().collect::<Vec<_>>(), expected);
+        assert!(row[1..].iter().all(|&id| !game_state.contains_entity(id)));
+        let merged = Shape::AxisAlignedRect(AxisAlignedRect::new(vec2(
+            SubPixelI64::new_pixels(24),
+            SubPixelI64::new_pixels(8),
+        )));
+        assert_eq!(game_state.shape[&row[0]], merged);
+        assert_eq!(game_state.colour.get(&row[0]), Some(&white));
+        let position = vec2(SubPixelI64::new_pixels(200), SubPixelI64::new_pixels(300));
+        assert_eq!(game_state.position(row[0]), Some(position));
+        assert_eq!(game_state.static_geometry().map(StaticGeometry::len), Some(2));
+        assert_eq!(game_state.validate(), Ok(()));
+    }
+
+    #[test]
+    fn removal_purges_per_entity_state() {
+        let (mut game_state, id) = single_rect_world();
+        let hunter = game_state
+            .spawn()
+            .position_pixels(vec2(30., 10.))
+            .shape(block())
+            .velocity(vec2(SubPixelI64::new(40), Zero::zero()))
+            .build()
+            .unwrap();
+        game_state.set_homing(hunter, Some(Homing::new(id, Angle::QUARTER_TURN)));
+        game_state.scheduler_mut().every_for(id, "blink", 2);
+        game_state.update(&InputModel::default());
+        game_state.render_updates_changed().count();
+        assert!(game_state.is_position_changed(id));
+        assert!(game_state.islands().iter().any(|island| island.contains(&id)));
+        game_state.remove_entity(id);
+        assert!(game_state.homing(hunter).is_none());
+        let lost = TargetLostEvent {
+            entity_id: hunter,
+            target_id: id,
+        };
+        assert_eq!(game_state.target_lost_events(), [lost]);
+        assert!(game_state.scheduler().is_empty());
+        assert!(!game_state.is_position_changed(id));
+        assert!(game_state.islands().iter().all(|island| !island.contains(&id)));
+        assert!(game_state.render_updates_changed().all(|update| update.entity_id != id));
+        assert_eq!(game_state.render_removals(), [id]);
+    }
+
+    #[test]
+    fn visibility_blend_and_alpha_reach_render_updates() {
+        let (mut game_state, id) = single_rect_world();
+        let translucent = game_state
+            .spawn()
+            .position_pixels(vec2(30., 10.))
+            .shape(block())
+            .colour([0., 1., 0., 0.5])
+            .build()
+            .unwrap();
+        game_state.set_blend(translucent, Some(BlendHint::Additive));
+        game_state.set_visible(id, false);
+        assert!(!game_state.is_visible(id));
+        let updates = game_state
+            .render_updates()
+            .map(|update| (update.entity_id, update.colour, update.blend))
+            .collect::<Vec<_>>();
+        assert_eq!(updates, [(translucent, [0., 1., 0., 0.5], BlendHint::Additive)]);
+        game_state.set_visible(id, true);
+        assert_eq!(game_state.render_updates().count(), 2);
+    }
+
+    #[test]
+    fn sprites_are_surfaced_in_render_updates() {
+        let (mut game_state, id) = single_rect_world();
+        let sprite = Sprite::new(3, SourceRect {
+            x: 8,
+            y: 0,
+            width: 8,
+            height: 8,
+        });
+        game_state.set_sprite(id, Some(sprite));
+        let sprites = game_state
+            .render_updates()
+            .map(|update| update.sprite.cloned())
+            .collect::<Vec<_>>();
+        assert_eq!(sprites, [Some(sprite)]);
+        game_state.set_sprite(id, None);
+        assert!(game_state.render_updates().all(|update| update.sprite.is_none()));
+    }
+
+    #[test]
+    fn failed_commands_are_reported() {
+        let (mut game_state, id) = single_rect_world();
+        let mut prefab = Prefab::new(block());
+        prefab.mass = Some(0);
+        game_state.prefabs_mut().register("weightless", prefab);
+        let position = vec2(SubPixelI64::new_pixels(10), SubPixelI64::new_pixels(10));
+        assert_eq!(
+            game_state.instantiate("missing", position),
+            Err(PhysicsError::MissingPrefab)
+        );
+        assert_eq!(
+            game_state.instantiate("weightless", position),
+            Err(PhysicsError::InvalidMass)
+        );
+        game_state.commands().spawn("missing", position);
+        game_state.commands().spawn("weightless", position);
+        game_state.commands().despawn(id);
+        game_state.commands().despawn(id);
+        game_state.update(&InputModel::default());
+        let errors = game_state
+            .command_errors()
+            .iter()
+            .map(|command_error| command_error.error)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                PhysicsError::MissingPrefab,
+                PhysicsError::InvalidMass,
+                PhysicsError::MissingEntity(id),
+            ]
+        );
+        assert_eq!(game_state.command_errors()[2].command, Command::Despawn(id));
+        game_state.update(&InputModel::default());
+        assert!(game_state.command_errors().is_empty());
+    }
+
+    #[test]
+    fn add_entities_rolls_back_when_the_world_is_full() {
+        let (mut game_state, id) = single_rect_world();
+        game_state.entity_id_allocator.reserve(u32::MAX - 2);
+        let position = vec2(SubPixelI64::new_pixels(10), SubPixelI64::new_pixels(10));
+        let entities = (0..3).map(|_| (position, Prefab::new(block())));
+        assert_eq!(game_state.add_entities(entities), Err(PhysicsError::WorldFull));
+        assert_eq!(game_state.entity_id_allocator.next, u32::MAX - 1);
+        assert_eq!(game_state.position.keys().cloned().collect::<Vec<_>>(), [id]);
+        assert_eq!(game_state.shape.len(), 1);
+    }
+
+    #[test]
+    fn invalid_entities_are_rejected() {
+        let (mut game_state, id) = single_rect_world();
+        let chain = Shape::Chain(Chain::new(vec![
+            vec2(Zero::zero(), Zero::zero()),
+            vec2(SubPixelI64::new_pixels(8), Zero::zero()),
+        ]));
+        let moving_chain = game_state
+            .spawn()
+            .shape(chain.clone())
+            .velocity(vec2(SubPixelI64::new(1), Zero::zero()))
+            .build();
+        assert_eq!(moving_chain, Err(PhysicsError::ImmovableShape));
+        let weightless = game_state.spawn().shape(block()).mass(0).build();
+        assert_eq!(weightless, Err(PhysicsError::InvalidMass));
+        assert_eq!(game_state.set_mass(id, Some(-1)), Err(PhysicsError::InvalidMass));
+        let skin = Some(SubPixelI64::new(-1));
+        assert_eq!(game_state.set_skin(id, skin), Err(PhysicsError::InvalidSkin));
+        let mut prefab = Prefab::new(chain);
+        prefab.body_type = BodyType::Dynamic;
+        let entities = vec![(vec2(Zero::zero(), Zero::zero()), Prefab::new(block()))]
+            .into_iter()
+            .chain(Some((vec2(Zero::zero(), Zero::zero()), prefab)));
+        let num_entities = game_state.shape.len();
+        assert_eq!(game_state.add_entities(entities), Err(PhysicsError::ImmovableShape));
+        assert_eq!(game_state.shape.len(), num_entities);
+    }
+
+    #[test]
+    fn chains_never_move() {
+        let (mut game_state, id) = single_rect_world();
+        let chain = Shape::Chain(Chain::new(vec![
+            vec2(Zero::zero(), Zero::zero()),
+            vec2(SubPixelI64::new_pixels(8), SubPixelI64::new_pixels(8)),
+        ]));
+        let result = game_state.try_set_shape(id, chain.clone());
+        assert_eq!(result, Err(PhysicsError::ImmovableShape));
+        let position = vec2(SubPixelI64::new_pixels(95), SubPixelI64::new_pixels(100));
+        let mut delta = StateDelta::default();
+        delta.spawns.push(Spawn {
+            id: 100,
+            position,
+            shape: chain,
+        });
+        let velocity = vec2(SubPixelI64::new(40), Zero::zero());
+        delta.components.velocities.set.push((100, velocity));
+        game_state.apply_delta(&delta);
+        game_state.update(&InputModel::default());
+        assert_eq!(game_state.position(100), Some(position));
+    }
+
+    fn decorated_world() -> (GameState, EntityId, EntityId) {
+        let (mut game_state, id) = falling_rect_world(16);
+        let floor = game_state
+            .spawn()
+            .position_pixels(vec2(0., 200.))
+            .shape(block())
+            .colour([0.5, 0.25, 1., 0.75])
+            .body_type(BodyType::Static)
+            .z_index(-2)
+            .build()
+            .unwrap();
+        game_state.set_health(id, Some(Health::new(10, 5)));
+        game_state.set_mass(id, Some(3)).unwrap();
+        game_state.set_lifetime(id, Some(Lifetime::new(90)));
+        game_state.set_drag(id, Some(Drag::new(1, 2)));
+        game_state.set_movement_mode(id, Some(MovementMode::AxisSeparated));
+        game_state.set_collision_layers(id, 0b101);
+        game_state.set_collector(id, true);
+        game_state.set_visible(floor, false);
+        game_state.set_sensor(floor, true);
+        game_state.set_blend(floor, Some(BlendHint::Additive));
+        let hazard = Hazard {
+            damage: 2,
+            knockback: vec2(Zero::zero(), SubPixelI64::new(-64)),
+            cooldown: 10,
+        };
+        game_state.set_hazard(floor, Some(hazard));
+        (game_state, id, floor)
+    }
+
+    #[test]
+    fn branches_step_like_the_original() {
+        let (mut game_state, id, floor) = decorated_world();
+        let under_block =
+            vec2(SubPixelI64::new_pixels(104), SubPixelI64::new_pixels(130));
+        game_state.set_position(floor, under_block);
+        game_state.set_sensor(floor, false);
+        game_state.user_data_mut().insert(id, 5u32);
+        game_state.register_hook(UpdateStage::BeforeIntegration, move |world_view| {
+            if let Some(count) = world_view.user_data_mut().get_mut::<u32>(id) {
+                *count += 1;
+            }
+        });
+        game_state
+            .schedule_mut()
+            .add_after(System::Resolve, move |game_state| game_state.signal(id, "tick"));
+        let input = InputModel::default();
+        for _ in 0..10 {
+            game_state.update(&input);
+        }
+        let mut branch = game_state.branch();
+        assert_eq!(branch.state_hash(), game_state.state_hash());
+        let mut damage_events = 0;
+        for _ in 0..60 {
+            game_state.update(&input);
+            branch.update(&input);
+            assert_eq!(branch.state_hash(), game_state.state_hash());
+            assert_eq!(branch.damage_events(), game_state.damage_events());
+            assert_eq!(branch.touching, game_state.touching);
+            assert_eq!(branch.islands, game_state.islands);
+            assert_eq!(branch.stats(), game_state.stats());
+            damage_events += game_state.damage_events().len();
+        }
+        assert!(damage_events > 0);
+        assert!(branch.diff(&game_state).is_empty());
+        assert_eq!(game_state.user_data().get::<u32>(id), Some(&75));
+        assert_eq!(branch.user_data().get::<u32>(id), Some(&75));
+    }
+
+    #[test]
+    fn restored_snapshots_step_like_the_original() {
+        let (mut game_state, id, floor) = decorated_world();
+        let under_block =
+            vec2(SubPixelI64::new_pixels(104), SubPixelI64::new_pixels(130));
+        game_state.set_position(floor, under_block);
+        game_state.set_sensor(floor, false);
+        game_state.set_time_scale(2, 3).unwrap();
+        let origin = vec2(SubPixelI64::new_pixels(50), SubPixelI64::new_pixels(50));
+        let drift = vec2(SubPixelI64::new(16), Zero::zero());
+        game_state.particles_mut().spawn(origin, drift, 100, [1.; 4]);
+        let input = InputModel::default();
+        for _ in 0..20 {
+            game_state.update(&input);
+        }
+        game_state.signal(id, "land");
+        let mut restored = GameState::new(vec2(640., 480.), PhysicsConfig::default());
+        restored.apply_delta(&game_state.snapshot());
+        assert_eq!(restored.state_hash(), game_state.state_hash());
+        assert_eq!(restored.step_n(60, &[]), game_state.step_n(60, &[]));
+        assert!(restored.diff(&game_state).is_empty());
+    }
+
+    #[test]
+    fn snapshot_keeps_every_component() {
+        let (game_state, id, floor) = decorated_world();
+        let mut remote = GameState::new(vec2(640., 480.), *game_state.config());
+        remote.apply_delta(&game_state.snapshot());
+        assert!(remote.diff(&game_state).components.is_empty());
+        assert_eq!(remote.health(id), game_state.health(id));
+        assert_eq!(remote.mass(id), Some(3));
+        assert_eq!(remote.lifetime(id), game_state.lifetime(id));
+        assert_eq!(remote.drag(id), Drag::new(1, 2));
+        assert_eq!(remote.movement_mode(id), MovementMode::AxisSeparated);
+        assert_eq!(remote.collision_layers(id), 0b101);
+        assert!(remote.is_collector(id));
+        assert!(!remote.is_visible(floor));
+        assert!(remote.is_sensor(floor));
+        assert_eq!(remote.blend(floor), BlendHint::Additive);
+        assert_eq!(remote.hazard(floor), game_state.hazard(floor));
+        assert_eq!(remote.z_index(floor), -2);
+        assert_eq!(remote.state_hash(), game_state.state_hash());
+    }
+
+    #[test]
+    fn built_in_resources_are_snapshotted() {
+        let mut game_state = GameState::new(vec2(640., 480.), PhysicsConfig::default());
+        let config = *game_state.config();
+        game_state.set_config(PhysicsConfig {
+            gravity: vec2(Zero::zero(), SubPixelI64::new(16)),
+            broadphase: BroadphaseKind::SweepAndPrune,
+            ..config
+        });
+        game_state.set_time_scale(1, 2).unwrap();
+        game_state.seed_rng(7);
+        game_state.camera_mut().shake(SubPixelI64::new_pixels(3));
+        let gravity = Rc::new(RefCell::new(None));
+        let seen = gravity.clone();
+        game_state.register_hook(UpdateStage::BeforeIntegration, move |world_view| {
+            *seen.borrow_mut() = Some(world_view.resources().config().gravity);
+        });
+        game_state.update(&InputModel::default());
+        assert_eq!(*gravity.borrow(), Some(game_state.config().gravity));
+        let mut remote = GameState::new(vec2(640., 480.), PhysicsConfig::default());
+        remote.apply_delta(&game_state.snapshot());
+        assert_eq!(remote.config(), game_state.config());
+        assert_eq!(remote.broadphase.kind(), BroadphaseKind::SweepAndPrune);
+        assert_eq!(remote.time_scale(), game_state.time_scale());
+        assert_eq!(remote.camera(), game_state.camera());
+        assert_eq!(remote.rng().next_u64(), game_state.rng().next_u64());
+    }
+
+    #[test]
+    fn diff_carries_non_positional_changes() {
+        let (mut game_state, id, floor) = decorated_world();
+        let mut remote = game_state.branch();
+        game_state.damage(id, 4, None);
+        game_state.set_lifetime(id, None);
+        game_state.set_collector(id, false);
+        game_state.set_visible(floor, true);
+        let wide = Shape::AxisAlignedRect(AxisAlignedRect::new(vec2(
+            SubPixelI64::new_pixels(64),
+            SubPixelI64::new_pixels(8),
+        )));
+        game_state.set_shape(floor, wide.clone());
+        let delta = remote.diff(&game_state);
+        assert!(delta.spawns.is_empty() && delta.despawns.is_empty());
+        assert_eq!(delta.components.positions, TableDelta::default());
+        assert_eq!(delta.components.lifetimes.removed, vec![id]);
+        assert_eq!(delta.components.collectors.removed, vec![id]);
+        assert_eq!(delta.components.hidden.removed, vec![floor]);
+        assert_eq!(delta.components.healths.first_entity(), Some(id));
+        remote.apply_delta(&delta);
+        assert!(remote.diff(&game_state).is_empty());
+        assert_eq!(remote.health(id).unwrap().current(), 6);
+        assert_eq!(remote.lifetime(id), None);
+        assert!(remote.is_visible(floor));
+        assert_eq!(remote.shape.get(&floor), Some(&wide));
+        assert_eq!(remote.validate(), Ok(()));
+    }
+
+    #[test]
+    fn diff_carries_sleep_state() {
+        let (mut game_state, id) = single_rect_world();
+        let config = *game_state.config();
+        game_state.set_config(PhysicsConfig {
+            sleep_after_updates: 2,
+            ..config
+        });
+        game_state.set_velocity(id, vec2(Zero::zero(), Zero::zero()));
+        let input = InputModel::default();
+        game_state.update(&input);
+        let mut remote = game_state.branch();
+        game_state.update(&input);
+        game_state.update(&input);
+        assert!(game_state.is_asleep(id));
+        remote.update(&input);
+        remote.wake(id);
+        assert_ne!(remote.state_hash(), game_state.state_hash());
+        let delta = remote.diff(&game_state);
+        assert_eq!(delta.components.asleep.inserted, vec![id]);
+        assert_eq!(delta.components.low_velocity_streaks.set, vec![(id, 2)]);
+        remote.apply_delta(&delta);
+        assert_eq!(remote.state_hash(), game_state.state_hash());
+        assert!(remote.is_asleep(id));
+    }
+
+    #[test]
+    fn overlap_shape_finds_static_and_dynamic_entities() {
+        let (mut game_state, id) = single_rect_world();
+        game_state.set_collision_layers(id, 0b01);
+        let wall = game_state
+            .spawn()
+            .position_pixels(vec2(120., 100.))
+            .shape(block())
+            .body_type(BodyType::Static)
+            .layer(0b10)
+            .build()
+            .unwrap();
+        let probe = Shape::AxisAlignedRect(AxisAlignedRect::new(vec2(
+            SubPixelI64::new_pixels(24),
+            SubPixelI64::new_pixels(4),
+        )));
+        let position = vec2(SubPixelI64::new_pixels(104), SubPixelI64::new_pixels(102));
+        let mut found = Vec::new();
+        game_state.overlap_shape(&probe, position, ALL_COLLISION_LAYERS, &mut found);
+        assert_eq!(found, vec![id, wall]);
+        game_state.overlap_shape(&probe, position, 0b10, &mut found);
+        assert_eq!(found, vec![wall]);
+        let touching = vec2(SubPixelI64::new_pixels(128), SubPixelI64::new_pixels(102));
+        game_state.overlap_shape(&probe, touching, ALL_COLLISION_LAYERS, &mut found);
+        assert!(found.is_empty());
+        let segment = Shape::LineSegment(LineSegment::new(
+            vec2(Zero::zero(), Zero::zero()),
+            vec2(SubPixelI64::new_pixels(40), Zero::zero()),
+        ));
+        let across = vec2(SubPixelI64::new_pixels(90), SubPixelI64::new_pixels(104));
+        game_state.overlap_shape(&segment, across, ALL_COLLISION_LAYERS, &mut found);
+        assert_eq!(found, vec![id, wall]);
+    }
+
+    #[test]
+    fn moving_heightfield_is_rejected() {
+        let (mut game_state, id) = single_rect_world();
+        let step = SubPixelI64::new_pixels(4);
+        let heights = vec![Zero::zero(), step, Zero::zero()];
+        let heightfield = Shape::Heightfield(Heightfield::new(step, heights));
+        let moving = game_state
+            .spawn()
+            .shape(heightfield.clone())
+            .body_type(BodyType::Dynamic)
+            .build();
+        assert_eq!(moving, Err(PhysicsError::ImmovableShape));
+        let result = game_state.try_set_shape(id, heightfield.clone());
+        assert_eq!(result, Err(PhysicsError::ImmovableShape));
+        assert!(game_state.spawn().shape(heightfield).build().is_ok());
+    }
+
+    #[test]
+    fn pooled_static_joins_the_broadphase() {
+        let (mut game_state, id) = falling_rect_world(16);
+        let width = SubPixelI64::new_pixels(40);
+        let platform = AxisAlignedRect::new(vec2(width, SubPixelI64::new_pixels(4)));
+        let prefab = Prefab::new(Shape::AxisAlignedRect(platform));
+        game_state.prefabs_mut().register("platform", prefab);
+        game_state.prewarm_pool("platform", 1).unwrap();
+        let position = vec2(SubPixelI64::new_pixels(90), SubPixelI64::new_pixels(120));
+        let platform_id = game_state.spawn_from_pool("platform", position).unwrap();
+        for _ in 0..60 {
+            game_state.update(&InputModel::default());
+        }
+        assert_eq!(game_state.position(platform_id), Some(position));
+        assert!(game_state.position(id).unwrap().y <= SubPixelI64::new_pixels(112));
+    }
+
+    #[test]
+    fn pooling_is_sent_as_despawn_and_spawn() {
+        let mut game_state = GameState::new(vec2(640., 480.), PhysicsConfig::default());
+        let mut prefab = Prefab::new(block());
+        prefab.body_type = BodyType::Dynamic;
+        game_state.prefabs_mut().register("bullet", prefab);
+        let position = vec2(SubPixelI64::new_pixels(10), SubPixelI64::new_pixels(10));
+        let id = game_state.spawn_from_pool("bullet", position).unwrap();
+        let mut remote = game_state.branch();
+        assert!(game_state.return_to_pool(id));
+        let delta = remote.diff(&game_state);
+        assert_eq!(delta.despawns, vec![id]);
+        remote.apply_delta(&delta);
+        assert_eq!(remote.position(id), None);
+        let position = vec2(SubPixelI64::new_pixels(50), SubPixelI64::new_pixels(10));
+        assert_eq!(game_state.spawn_from_pool("bullet", position), Ok(id));
+        let delta = remote.diff(&game_state);
+        assert_eq!(delta.spawns.iter().map(|spawn| spawn.id).collect::<Vec<_>>(), [id]);
+        remote.apply_delta(&delta);
+        assert_eq!(remote.position(id), Some(position));
+    }
+
+    #[test]
+    fn recycled_entities_start_blank() {
+        let world = || {
+            let config = PhysicsConfig::default();
+            let mut game_state = GameState::new(vec2(640., 480.), config);
+            let mut prefab = Prefab::new(block());
+            prefab.body_type = BodyType::Dynamic;
+            game_state.prefabs_mut().register("bullet", prefab);
+            game_state
+        };
+        let position = vec2(SubPixelI64::new_pixels(10), SubPixelI64::new_pixels(10));
+        let mut fresh = world();
+        let id = fresh.spawn_from_pool("bullet", position).unwrap();
+        let mut recycled = world();
+        assert_eq!(recycled.spawn_from_pool("bullet", position), Ok(id));
+        recycled.set_player_id(Some(id));
+        recycled.set_health(id, Some(Health::new(10, 5)));
+        recycled.set_drag(id, Some(Drag::new(1, 2)));
+        recycled.set_restitution(id, Some(Restitution::new(1, 2)));
+        recycled.set_z_index(id, Some(3));
+        recycled.set_visible(id, false);
+        recycled.set_pushable(id, true);
+        recycled.set_collector(id, true);
+        recycled.set_collision_layers(id, 0b10);
+        recycled.apply_force(id, vec2(SubPixelI64::new(8), Zero::zero()));
+        recycled.signal(id, "hit");
+        recycled.user_data_mut().insert(id, 7u32);
+        recycled.update(&InputModel::default());
+        assert!(recycled.return_to_pool(id));
+        assert!(!recycled.contains_entity(id));
+        assert!(!recycled.user_data().contains_entity(id));
+        assert_eq!(recycled.spawn_from_pool("bullet", position), Ok(id));
+        fresh.update(&InputModel::default());
+        recycled.update(&InputModel::default());
+        assert!(fresh.diff(&recycled).is_empty());
+        assert_eq!(fresh.state_hash(), recycled.state_hash());
+    }
+
+    #[test]
+    fn systems_run_one_at_a_time() {
+        let (mut game_state, id) = falling_rect_world(16);
+        let start = game_state.position(id).unwrap();
+        assert!(game_state.begin_update(&InputModel::default()));
+        game_state.run_system(System::Integrate);
+        let velocity = vec2(SubPixelI64::new(40), SubPixelI64::new(16));
+        assert_eq!(game_state.velocity(id), Some(velocity));
+        assert_eq!(game_state.position(id), Some(start));
+        game_state.run_system(System::Broadphase);
+        assert_eq!(game_state.position(id), Some(start));
+        game_state.run_system(System::Narrowphase);
+        assert_eq!(game_state.position(id), Some(start + velocity));
+        game_state.run_system(System::Resolve);
+        game_state.run_system(System::Events);
+        assert_eq!(game_state.velocity(id), Some(velocity));
+    }
+
+    #[test]
+    fn hooks_see_and_edit_the_world() {
+        let (mut game_state, id) = falling_rect_world(16);
+        game_state.user_data_mut().insert(id, vec2(SubPixelI64::new(40), Zero::zero()));
+        game_state.register_hook(UpdateStage::BeforeIntegration, move |world_view| {
+            let boost = *world_view.user_data().get::<Vector2<SubPixelI64>>(id).unwrap();
+            let velocity = world_view.velocity(id).unwrap();
+            world_view.set_velocity(id, velocity + boost);
+        });
+        game_state.register_hook(UpdateStage::AfterCollisionResolution, move |world_view| {
+            world_view.user_data_mut().remove::<Vector2<SubPixelI64>>(id);
+            assert_eq!(world_view.entities_with_velocity().collect::<Vec<_>>(), vec![id]);
+        });
+        let start = game_state.position(id).unwrap();
+        game_state.update(&InputModel::default());
+        let velocity = vec2(SubPixelI64::new(80), SubPixelI64::new(16));
+        assert_eq!(game_state.velocity(id), Some(velocity));
+        assert_eq!(game_state.position(id), Some(start + velocity));
+        assert!(!game_state.user_data().contains::<Vector2<SubPixelI64>>(id));
+    }
+
+    #[test]
+    fn hooks_are_schedule_steps() {
+        let (mut game_state, id) = falling_rect_world(16);
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let before = seen.clone();
+        game_state.register_hook(UpdateStage::BeforeIntegration, move |world_view| {
+            before.borrow_mut().push(world_view.velocity(id).unwrap().y);
+        });
+        let after = seen.clone();
+        game_state.schedule_mut().add_after(System::Integrate, move |world| {
+            after.borrow_mut().push(world.velocity(id).unwrap().y);
+        });
+        assert_eq!(game_state.schedule_mut().len(), 7);
+        game_state.update(&InputModel::default());
+        let expected = vec![SubPixelI64::new(0), SubPixelI64::new(16)];
+        assert_eq!(*seen.borrow(), expected);
+        game_state.clear_hooks();
+        assert_eq!(game_state.schedule_mut().len(), 6);
+    }
+
+    struct Probe;
+
+    impl Plugin for Probe {
+        fn build(&self, world: &mut World, schedule: &mut Schedule) {
+            let id = world.player_id();
+            schedule.add_system(move |world| world.set_player_id(id));
+            schedule.add_after(System::Integrate, |_| ());
+        }
+    }
+
+    #[test]
+    fn plugins_add_systems_to_the_schedule() {
+        let mut game_state = GameState::new(vec2(640., 480.), PhysicsConfig::default());
+        game_state.add_plugin(Probe);
+        let schedule = game_state.schedule_mut();
+        assert_eq!(schedule.len(), 7);
+        assert_eq!(schedule.remove(System::Resolve), Removal::Removed);
+        assert_eq!(schedule.remove(System::Resolve), Removal::NotScheduled);
+    }
+
+    #[test]
+    fn branches_run_their_own_copies_of_custom_systems() {
+        let mut game_state = GameState::new(vec2(640., 480.), PhysicsConfig::default());
+        let mut updates = 0;
+        game_state.schedule_mut().add_system(move |world| {
+            updates += 1;
+            world.set_player_id(Some(updates));
+        });
+        game_state.update(&InputModel::default());
+        let mut branch = game_state.branch();
+        branch.update(&InputModel::default());
+        branch.update(&InputModel::default());
+        game_state.update(&InputModel::default());
+        assert_eq!(branch.player_id(), Some(3));
+        assert_eq!(game_state.player_id(), Some(2));
+    }
+
+    #[test]
+    fn late_hooks_run_between_resolution_and_events() {
+        let mut game_state = GameState::new(vec2(640., 480.), PhysicsConfig::default());
+        let order = Rc::new(RefCell::new(Vec::new()));
+        let log = |name| {
+            let order = order.clone();
+            move |_: &mut World| order.borrow_mut().push(name)
+        };
+        game_state.schedule_mut().add_after(System::Resolve, log("resolve"));
+        game_state.schedule_mut().add_after(System::Events, log("events"));
+        let hook = order.clone();
+        game_state.register_hook(UpdateStage::AfterCollisionResolution, move |_| {
+            hook.borrow_mut().push("hook")
+        });
+        game_state.update(&InputModel::default());
+        assert_eq!(*order.borrow(), ["resolve", "hook", "events"]);
     }
 }
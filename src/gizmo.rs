@@ -0,0 +1,62 @@
+use cgmath::{Vector2, vec2};
+use game::EntityId;
+use line_segment::LineSegment;
+use physics_num::{self, PhysicsNum};
+use pixel_num::SubPixelI64;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gizmo {
+    Velocity {
+        entity_id: EntityId,
+        origin: Vector2<SubPixelI64>,
+        velocity: Vector2<SubPixelI64>,
+    },
+    Contact {
+        entity_id: EntityId,
+        other_id: EntityId,
+        point: Vector2<SubPixelI64>,
+        normal: Vector2<SubPixelI64>,
+    },
+}
+
+pub fn contact_point_and_normal<N: PhysicsNum>(
+    centre: Vector2<N>,
+    surface: LineSegment<N>,
+    normal_length: N,
+) -> (Vector2<N>, Vector2<N>) {
+    let vector = surface.vector();
+    let to_centre = centre - surface.start;
+    let numerator = physics_num::widen(vector.x) * physics_num::widen(to_centre.x)
+        + physics_num::widen(vector.y) * physics_num::widen(to_centre.y);
+    let denominator = physics_num::magnitude2_wide(vector);
+    let point = if denominator == 0 {
+        surface.start
+    } else {
+        let numerator = numerator.max(0).min(denominator);
+        let along = |component: N| {
+            physics_num::saturating_narrow(physics_num::widen(component) * numerator / denominator)
+        };
+        surface.start + vec2(along(vector.x), along(vector.y))
+    };
+    let mut normal = vec2(vector.y, -vector.x);
+    if physics_num::dot(normal, to_centre) < N::zero() {
+        normal = -normal;
+    }
+    (point, physics_num::normalize_to_length(normal, normal_length))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn normal_faces_centre() {
+        let surface = LineSegment::new(vec2(0, 0), vec2(100, 0));
+        let (point, normal) = contact_point_and_normal(vec2(30, -20), surface, 10);
+        assert_eq!(point, vec2(30, 0));
+        assert_eq!(normal, vec2(0, -10));
+        let (point, normal) = contact_point_and_normal(vec2(150, 20), surface, 10);
+        assert_eq!(point, vec2(100, 0));
+        assert_eq!(normal, vec2(0, 10));
+    }
+}
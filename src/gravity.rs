@@ -0,0 +1,56 @@
+use cgmath::{Vector2, vec2};
+use physics_num;
+use pixel_num::SubPixelI64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GravityOverride {
+    Scale { numerator: i64, denominator: i64 },
+    Vector(Vector2<SubPixelI64>),
+}
+
+impl GravityOverride {
+    pub fn scale(numerator: i64, denominator: i64) -> Self {
+        assert!(denominator > 0, "gravity scale denominator must be positive");
+        GravityOverride::Scale {
+            numerator,
+            denominator,
+        }
+    }
+    pub fn reversed() -> Self {
+        Self::scale(-1, 1)
+    }
+    pub fn apply(&self, world_gravity: Vector2<SubPixelI64>) -> Vector2<SubPixelI64> {
+        match *self {
+            GravityOverride::Scale {
+                numerator,
+                denominator,
+            } => {
+                let (numerator, denominator) = (numerator as i128, denominator as i128);
+                let scale = |value| {
+                    let scaled = physics_num::widen(value) * numerator / denominator;
+                    physics_num::saturating_narrow(scaled)
+                };
+                vec2(scale(world_gravity.x), scale(world_gravity.y))
+            }
+            GravityOverride::Vector(gravity) => gravity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(x: i64, y: i64) -> Vector2<SubPixelI64> {
+        vec2(SubPixelI64::new(x), SubPixelI64::new(y))
+    }
+
+    #[test]
+    fn overrides_replace_or_scale_world_gravity() {
+        let world = v(0, 24);
+        assert_eq!(GravityOverride::scale(1, 3).apply(world), v(0, 8));
+        assert_eq!(GravityOverride::scale(0, 1).apply(world), v(0, 0));
+        assert_eq!(GravityOverride::reversed().apply(world), v(0, -24));
+        assert_eq!(GravityOverride::Vector(v(24, 0)).apply(world), v(24, 0));
+    }
+}
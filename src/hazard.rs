@@ -0,0 +1,89 @@
+use cgmath::Vector2;
+use fnv::FnvHashMap;
+use game::EntityId;
+use pixel_num::SubPixelI64;
+use std::hash::{Hash, Hasher};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Hazard {
+    pub damage: u32,
+    pub knockback: Vector2<SubPixelI64>,
+    pub cooldown: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DamageEvent {
+    pub hazard_id: EntityId,
+    pub target_id: EntityId,
+    pub damage: u32,
+    pub knockback: Vector2<SubPixelI64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HazardCooldowns {
+    remaining: FnvHashMap<(EntityId, EntityId), u32>,
+}
+
+/// Hashes the cooldowns in (hazard, target) order.
+impl Hash for HazardCooldowns {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut remaining = self.remaining.iter().collect::<Vec<_>>();
+        remaining.sort_unstable();
+        remaining.hash(state);
+    }
+}
+
+impl HazardCooldowns {
+    pub fn tick(&mut self) {
+        for remaining in self.remaining.values_mut() {
+            *remaining -= 1;
+        }
+        self.remaining.retain(|_, remaining| *remaining > 0);
+    }
+    pub fn try_trigger(
+        &mut self,
+        hazard_id: EntityId,
+        target_id: EntityId,
+        cooldown: u32,
+    ) -> bool {
+        let key = (hazard_id, target_id);
+        if self.remaining.contains_key(&key) {
+            return false;
+        }
+        if cooldown > 0 {
+            self.remaining.insert(key, cooldown);
+        }
+        true
+    }
+    pub fn remove_entity(&mut self, id: EntityId) {
+        self.remaining
+            .retain(|&(hazard_id, target_id), _| hazard_id != id && target_id != id);
+    }
+    pub fn clear(&mut self) {
+        self.remaining.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cooldown_limits_triggers_per_pair() {
+        let mut cooldowns = HazardCooldowns::default();
+        let triggers = (0..10)
+            .map(|_| {
+                cooldowns.tick();
+                let a = cooldowns.try_trigger(1, 2, 4);
+                let b = cooldowns.try_trigger(1, 3, 0);
+                (a, b)
+            })
+            .collect::<Vec<_>>();
+        let a = triggers.iter().filter(|&&(a, _)| a).count();
+        let b = triggers.iter().filter(|&&(_, b)| b).count();
+        assert_eq!((a, b), (3, 10));
+        assert!(triggers[0].0 && triggers[4].0 && triggers[8].0);
+        cooldowns.remove_entity(2);
+        assert!(cooldowns.try_trigger(1, 2, 4));
+    }
+}
@@ -0,0 +1,93 @@
+use game::EntityId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Health {
+    current: u32,
+    max: u32,
+    invulnerability: u32,
+    invulnerable_remaining: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageOutcome {
+    Ignored,
+    Damaged { remaining: u32 },
+    Killed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeathEvent {
+    pub entity_id: EntityId,
+    pub source_id: Option<EntityId>,
+}
+
+impl Health {
+    pub fn new(max: u32, invulnerability: u32) -> Self {
+        assert!(max > 0, "max health must be positive");
+        Self {
+            current: max,
+            max,
+            invulnerability,
+            invulnerable_remaining: 0,
+        }
+    }
+    pub fn current(&self) -> u32 {
+        self.current
+    }
+    pub fn max(&self) -> u32 {
+        self.max
+    }
+    pub fn is_dead(&self) -> bool {
+        self.current == 0
+    }
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerable_remaining > 0
+    }
+    pub fn tick(&mut self) {
+        self.invulnerable_remaining = self.invulnerable_remaining.saturating_sub(1);
+    }
+    pub fn damage(&mut self, amount: u32) -> DamageOutcome {
+        if amount == 0 || self.is_dead() || self.is_invulnerable() {
+            return DamageOutcome::Ignored;
+        }
+        self.current = self.current.saturating_sub(amount);
+        if self.is_dead() {
+            return DamageOutcome::Killed;
+        }
+        self.invulnerable_remaining = self.invulnerability;
+        DamageOutcome::Damaged {
+            remaining: self.current,
+        }
+    }
+    pub fn heal(&mut self, amount: u32) {
+        if !self.is_dead() {
+            self.current = self.current.saturating_add(amount).min(self.max);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn invulnerability_window_ignores_damage() {
+        let mut health = Health::new(10, 2);
+        assert_eq!(health.damage(3), DamageOutcome::Damaged { remaining: 7 });
+        assert_eq!(health.damage(3), DamageOutcome::Ignored);
+        health.tick();
+        assert_eq!(health.damage(3), DamageOutcome::Ignored);
+        health.tick();
+        assert_eq!(health.damage(3), DamageOutcome::Damaged { remaining: 4 });
+        health.heal(100);
+        assert_eq!(health.current(), 10);
+        health.tick();
+        health.tick();
+        assert_eq!(health.damage(20), DamageOutcome::Killed);
+        assert!(health.is_dead());
+        health.tick();
+        health.heal(5);
+        assert_eq!(health.damage(1), DamageOutcome::Ignored);
+        assert_eq!(health.current(), 0);
+    }
+}
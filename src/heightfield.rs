@@ -0,0 +1,146 @@
+use aabb::Aabb;
+use alloc::vec::Vec;
+use cgmath::{Vector2, vec2};
+use chain::Chain;
+use line_segment::LineSegment;
+use physics_num::{self, PhysicsNum};
+use shape::{Collide, CollisionInfo, StartInsideEdgePolicy};
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct Heightfield<N: PhysicsNum> {
+    column_width: N,
+    heights: Vec<N>,
+    chain: Chain<N>,
+}
+
+impl<N: PhysicsNum> Heightfield<N> {
+    pub fn new(column_width: N, heights: Vec<N>) -> Self {
+        assert!(column_width > N::zero(), "column width must be positive");
+        assert!(heights.len() >= 2, "heightfield needs at least two heights");
+        let mut x = N::zero();
+        let mut points = Vec::with_capacity(heights.len());
+        for &height in heights.iter() {
+            points.push(vec2(x, -height));
+            x += column_width;
+        }
+        Self {
+            column_width,
+            heights,
+            chain: Chain::new(points),
+        }
+    }
+    pub fn column_width(&self) -> N {
+        self.column_width
+    }
+    pub fn heights(&self) -> &[N] {
+        &self.heights
+    }
+    pub fn chain(&self) -> &Chain<N> {
+        &self.chain
+    }
+    pub fn num_edges(&self) -> usize {
+        self.chain.num_edges()
+    }
+    pub fn edge(&self, index: usize) -> LineSegment<N> {
+        self.chain.edge(index)
+    }
+    fn edge_range(&self, min_x: N, max_x: N) -> Option<(usize, usize)> {
+        let width = physics_num::widen(self.column_width);
+        let num_edges = self.num_edges() as i128;
+        let first = physics_num::widen(min_x).div_euclid(width) - 1;
+        let end = physics_num::widen(max_x).div_euclid(width) + 2;
+        if end <= 0 || first >= num_edges {
+            return None;
+        }
+        Some((first.max(0) as usize, end.min(num_edges) as usize))
+    }
+    pub fn swept_test_against<MovingShape>(
+        &self,
+        position: Vector2<N>,
+        moving: &MovingShape,
+        moving_position: Vector2<N>,
+        movement: Vector2<N>,
+        start_inside_edge_policy: StartInsideEdgePolicy,
+    ) -> Option<CollisionInfo<N>>
+    where
+        MovingShape: Collide<N>,
+    {
+        let start = moving.aabb(moving_position);
+        let swept = start.union(&moving.aabb(moving_position + movement));
+        let (first, end) = self.edge_range(
+            swept.top_left().x - position.x,
+            swept.bottom_right_coord().x - position.x,
+        )?;
+        moving.movement_collision_test(
+            moving_position,
+            &self.chain.slice(first, end),
+            position,
+            movement,
+            start_inside_edge_policy,
+        )
+    }
+}
+
+impl<N: PhysicsNum> Collide<N> for Heightfield<N> {
+    fn aabb(&self, top_left: Vector2<N>) -> Aabb<N> {
+        self.chain.aabb(top_left)
+    }
+    fn for_each_edge_facing<F>(&self, direction: Vector2<N>, f: F)
+    where
+        F: FnMut(LineSegment<N>),
+    {
+        self.chain.for_each_edge_facing(direction, f)
+    }
+    fn for_each_vertex_facing<F>(&self, direction: Vector2<N>, f: F)
+    where
+        F: FnMut(Vector2<N>),
+    {
+        self.chain.for_each_vertex_facing(direction, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axis_aligned_rect::AxisAlignedRect;
+
+    fn hills() -> Heightfield<i64> {
+        Heightfield::new(10, vec![0, 5, 10, 5, 0, 5, 10, 5, 0, 5, 10, 5, 0])
+    }
+
+    #[test]
+    fn edge_range_covers_swept_columns() {
+        let heightfield = hills();
+        assert_eq!(heightfield.edge_range(25, 34), Some((1, 5)));
+        assert_eq!(heightfield.edge_range(-50, -20), None);
+        assert_eq!(heightfield.edge_range(-5, 500), Some((0, 12)));
+    }
+
+    #[test]
+    fn matches_full_chain() {
+        let heightfield = hills();
+        let rect = AxisAlignedRect::new(vec2(4, 4));
+        for x in 0..30 {
+            let position = vec2(x * 4 - 10, -40);
+            let movement = vec2(7 - x % 5, 60);
+            let full = rect.movement_collision_test(
+                position,
+                &heightfield,
+                vec2(0, 0),
+                movement,
+                StartInsideEdgePolicy::BlockAll,
+            );
+            let windowed = heightfield.swept_test_against(
+                vec2(0, 0),
+                &rect,
+                position,
+                movement,
+                StartInsideEdgePolicy::BlockAll,
+            );
+            assert_eq!(
+                full.map(|c| c.allowed_movement),
+                windowed.map(|c| c.allowed_movement)
+            );
+        }
+    }
+}
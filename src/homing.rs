@@ -0,0 +1,119 @@
+use cgmath::Vector2;
+use game::EntityId;
+use physics_num;
+use pixel_num::SubPixelI64;
+use trig::Angle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Homing {
+    target: EntityId,
+    max_turn: Angle,
+    line_of_sight: bool,
+    memory: u32,
+    last_seen: Option<Vector2<SubPixelI64>>,
+    unseen: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TargetLostEvent {
+    pub entity_id: EntityId,
+    pub target_id: EntityId,
+}
+
+pub fn steer(
+    velocity: Vector2<SubPixelI64>,
+    offset: Vector2<SubPixelI64>,
+    max_turn: Angle,
+) -> Vector2<SubPixelI64> {
+    let heading = Angle::of_vector(velocity);
+    let (heading, desired) = match (heading, Angle::of_vector(offset)) {
+        (Some(heading), Some(desired)) => (heading, desired),
+        _ => return velocity,
+    };
+    let speed = physics_num::magnitude(velocity);
+    let turn = (desired - heading).raw() as i16 as i32;
+    let max_turn = (max_turn.raw() as i32).min(i16::MAX as i32);
+    if turn.abs() <= max_turn {
+        return physics_num::normalize_to_length(offset, speed);
+    }
+    let turn = Angle::from_raw((max_turn * turn.signum()) as i16 as u16);
+    physics_num::normalize_to_length(turn.rotate(velocity), speed)
+}
+
+impl Homing {
+    pub fn new(target: EntityId, max_turn: Angle) -> Self {
+        Self {
+            target,
+            max_turn,
+            line_of_sight: false,
+            memory: 0,
+            last_seen: None,
+            unseen: 0,
+        }
+    }
+    pub fn with_line_of_sight(mut self, memory: u32) -> Self {
+        self.line_of_sight = true;
+        self.memory = memory;
+        self
+    }
+    pub fn target(&self) -> EntityId {
+        self.target
+    }
+    pub fn max_turn(&self) -> Angle {
+        self.max_turn
+    }
+    pub fn requires_line_of_sight(&self) -> bool {
+        self.line_of_sight
+    }
+    pub fn last_seen(&self) -> Option<Vector2<SubPixelI64>> {
+        self.last_seen
+    }
+    pub fn observe(
+        &mut self,
+        sighting: Option<Vector2<SubPixelI64>>,
+    ) -> Option<Vector2<SubPixelI64>> {
+        if let Some(position) = sighting {
+            self.last_seen = Some(position);
+            self.unseen = 0;
+            return sighting;
+        }
+        self.unseen = self.unseen.saturating_add(1);
+        if self.unseen > self.memory {
+            None
+        } else {
+            self.last_seen
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cgmath::vec2;
+
+    fn v(x: i64, y: i64) -> Vector2<SubPixelI64> {
+        vec2(SubPixelI64::new(x), SubPixelI64::new(y))
+    }
+
+    #[test]
+    fn turn_rate_limits_steering() {
+        let quarter = Angle::QUARTER_TURN;
+        assert_eq!(steer(v(100, 0), v(0, 50), quarter), v(0, 100));
+        assert_eq!(steer(v(100, 0), v(0, -50), quarter), v(0, -100));
+        let turned = steer(v(100, 0), v(0, 50), Angle::from_degrees(45));
+        assert_eq!(turned, v(71, 71));
+        let turned = steer(v(100, 0), v(-50, 0), Angle::from_degrees(30));
+        assert_eq!(turned, v(87, -50));
+        assert_eq!(steer(v(0, 0), v(10, 0), quarter), v(0, 0));
+    }
+
+    #[test]
+    fn target_lost_after_memory_expires() {
+        let mut homing = Homing::new(1, Angle::QUARTER_TURN).with_line_of_sight(2);
+        assert_eq!(homing.observe(None), None);
+        assert_eq!(homing.observe(Some(v(5, 5))), Some(v(5, 5)));
+        assert_eq!(homing.observe(None), Some(v(5, 5)));
+        assert_eq!(homing.observe(None), Some(v(5, 5)));
+        assert_eq!(homing.observe(None), None);
+    }
+}
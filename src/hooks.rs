@@ -0,0 +1,22 @@
+use game::WorldView;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateStage {
+    BeforeIntegration,
+    AfterCollisionResolution,
+}
+
+/// A hook registered on a schedule. Like custom systems, hooks are cloned along
+/// with the schedule rather than shared with it.
+pub trait Hook: FnMut(&mut WorldView) {
+    fn clone_hook(&self) -> Box<dyn Hook>;
+}
+
+impl<F> Hook for F
+where
+    F: 'static + Clone + FnMut(&mut WorldView),
+{
+    fn clone_hook(&self) -> Box<dyn Hook> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,240 @@
+use cgmath::{Vector2, vec2};
+use physics_num;
+use pixel_num::SubPixelI64;
+use num::Zero;
+
+fn scale(
+    normal: (i128, i128),
+    numerator: i128,
+    denominator: i128,
+) -> Vector2<SubPixelI64> {
+    vec2(
+        physics_num::saturating_narrow(normal.0 * numerator / denominator),
+        physics_num::saturating_narrow(normal.1 * numerator / denominator),
+    )
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Restitution {
+    numerator: i64,
+    denominator: i64,
+}
+
+impl Restitution {
+    pub fn new(numerator: i64, denominator: i64) -> Self {
+        assert!(denominator > 0, "restitution denominator must be positive");
+        assert!(
+            numerator >= 0 && numerator <= denominator,
+            "restitution must be between zero and one"
+        );
+        Self {
+            numerator,
+            denominator,
+        }
+    }
+    pub fn inelastic() -> Self {
+        Self::new(0, 1)
+    }
+    pub fn elastic() -> Self {
+        Self::new(1, 1)
+    }
+    pub fn numerator(&self) -> i64 {
+        self.numerator
+    }
+    pub fn denominator(&self) -> i64 {
+        self.denominator
+    }
+    pub fn min(self, other: Self) -> Self {
+        if self.numerator as i128 * other.denominator as i128
+            <= other.numerator as i128 * self.denominator as i128
+        {
+            self
+        } else {
+            other
+        }
+    }
+}
+
+impl Default for Restitution {
+    fn default() -> Self {
+        Self::elastic()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VelocityChange {
+    pub a: Vector2<SubPixelI64>,
+    pub b: Vector2<SubPixelI64>,
+}
+
+pub fn inelastic(
+    velocity_a: Vector2<SubPixelI64>,
+    mass_a: Option<i64>,
+    velocity_b: Vector2<SubPixelI64>,
+    mass_b: i64,
+    a_to_b_normal: Vector2<SubPixelI64>,
+) -> Option<VelocityChange> {
+    with_restitution(
+        velocity_a,
+        mass_a,
+        velocity_b,
+        mass_b,
+        a_to_b_normal,
+        Restitution::inelastic(),
+    )
+}
+
+pub fn with_restitution(
+    velocity_a: Vector2<SubPixelI64>,
+    mass_a: Option<i64>,
+    velocity_b: Vector2<SubPixelI64>,
+    mass_b: i64,
+    a_to_b_normal: Vector2<SubPixelI64>,
+    restitution: Restitution,
+) -> Option<VelocityChange> {
+    let normal = (
+        physics_num::widen(a_to_b_normal.x),
+        physics_num::widen(a_to_b_normal.y),
+    );
+    let normal_len2 = normal.0 * normal.0 + normal.1 * normal.1;
+    if normal_len2 == 0 {
+        return None;
+    }
+    let relative = velocity_a - velocity_b;
+    let approach = physics_num::widen(relative.x) * normal.0
+        + physics_num::widen(relative.y) * normal.1;
+    if approach <= 0 {
+        return None;
+    }
+    let mass_b = mass_b as i128;
+    let restitution_denominator = restitution.denominator as i128;
+    let approach = approach * (restitution_denominator + restitution.numerator as i128);
+    let normal_len2 = normal_len2 * restitution_denominator;
+    let change = match mass_a {
+        None => VelocityChange {
+            a: vec2(Zero::zero(), Zero::zero()),
+            b: scale(normal, approach, normal_len2),
+        },
+        Some(mass_a) => {
+            let mass_a = mass_a as i128;
+            let denominator = normal_len2 * (mass_a + mass_b);
+            VelocityChange {
+                a: -scale(normal, approach * mass_b, denominator),
+                b: scale(normal, approach * mass_a, denominator),
+            }
+        }
+    };
+    Some(change)
+}
+
+pub fn approach_speed(
+    velocity_a: Vector2<SubPixelI64>,
+    velocity_b: Vector2<SubPixelI64>,
+    a_to_b_normal: Vector2<SubPixelI64>,
+) -> SubPixelI64 {
+    let normal_len = physics_num::isqrt_wide(physics_num::magnitude2_wide(a_to_b_normal));
+    if normal_len == 0 {
+        return Zero::zero();
+    }
+    let relative = velocity_a - velocity_b;
+    let approach = physics_num::widen(relative.x) * physics_num::widen(a_to_b_normal.x)
+        + physics_num::widen(relative.y) * physics_num::widen(a_to_b_normal.y);
+    if approach <= 0 {
+        return Zero::zero();
+    }
+    physics_num::saturating_narrow(approach / normal_len)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(x: i64, y: i64) -> Vector2<SubPixelI64> {
+        vec2(SubPixelI64::new(x), SubPixelI64::new(y))
+    }
+
+    #[test]
+    fn equal_masses_share_normal_velocity() {
+        let change = inelastic(v(1024, 0), Some(10), v(0, 0), 10, v(0, 0));
+        assert_eq!(change, None);
+        let change = inelastic(v(1024, 300), Some(10), v(0, 0), 10, v(64, 0)).unwrap();
+        assert_eq!(change.a, v(-512, 0));
+        assert_eq!(change.b, v(512, 0));
+    }
+
+    #[test]
+    fn conserves_momentum() {
+        let (mass_a, mass_b) = (3, 5);
+        let velocity_a = v(800, 0);
+        let velocity_b = v(-160, 0);
+        let change =
+            inelastic(velocity_a, Some(mass_a), velocity_b, mass_b, v(1, 0)).unwrap();
+        let before = velocity_a * SubPixelI64::new(mass_a)
+            + velocity_b * SubPixelI64::new(mass_b);
+        let after = (velocity_a + change.a) * SubPixelI64::new(mass_a)
+            + (velocity_b + change.b) * SubPixelI64::new(mass_b);
+        assert_eq!(before, after);
+        assert_eq!(velocity_a + change.a, velocity_b + change.b);
+    }
+
+    #[test]
+    fn elastic_collision_exchanges_momentum() {
+        let (mass_a, mass_b) = (2, 6);
+        let velocity_a = v(1200, 0);
+        let velocity_b = v(-400, 0);
+        let change = with_restitution(
+            velocity_a,
+            Some(mass_a),
+            velocity_b,
+            mass_b,
+            v(3, 0),
+            Restitution::elastic(),
+        ).unwrap();
+        let after_a = velocity_a + change.a;
+        let after_b = velocity_b + change.b;
+        assert_eq!(after_a, v(-1200, 0));
+        assert_eq!(after_b, v(400, 0));
+        let momentum = |a: Vector2<SubPixelI64>, b: Vector2<SubPixelI64>| {
+            a * SubPixelI64::new(mass_a) + b * SubPixelI64::new(mass_b)
+        };
+        assert_eq!(momentum(velocity_a, velocity_b), momentum(after_a, after_b));
+        let equal = with_restitution(
+            v(512, 0),
+            Some(4),
+            v(0, 0),
+            4,
+            v(1, 0),
+            Restitution::elastic(),
+        ).unwrap();
+        assert_eq!(equal.a, v(-512, 0));
+        assert_eq!(equal.b, v(512, 0));
+        let immovable = with_restitution(
+            v(512, 0),
+            None,
+            v(0, 0),
+            4,
+            v(1, 0),
+            Restitution::new(1, 2),
+        ).unwrap();
+        assert_eq!(immovable.b, v(768, 0));
+        let (half, two_thirds) = (Restitution::new(1, 2), Restitution::new(2, 3));
+        assert_eq!(half.min(two_thirds), half);
+        assert_eq!(two_thirds.min(half), half);
+    }
+
+    #[test]
+    fn separating_bodies_are_unaffected() {
+        assert_eq!(inelastic(v(-10, 0), Some(1), v(0, 0), 1, v(1, 0)), None);
+        assert_eq!(inelastic(v(10, 0), None, v(0, 0), 1, v(1, 0)).unwrap().b, v(10, 0));
+    }
+
+    #[test]
+    fn approach_speed_along_normal() {
+        let speed = |a, b, normal| approach_speed(a, b, normal).raw();
+        assert_eq!(speed(v(300, 400), v(0, 0), v(0, 10)), 400);
+        assert_eq!(speed(v(300, 400), v(0, 100), v(0, 1)), 300);
+        assert_eq!(speed(v(300, 400), v(0, 0), v(0, -10)), 0);
+        assert_eq!(speed(v(300, 400), v(0, 0), v(0, 0)), 0);
+        assert_eq!(speed(v(100, 100), v(0, 0), v(3, 4)), 140);
+    }
+}
@@ -0,0 +1,120 @@
+use aabb::Aabb;
+use game::EntityId;
+use physics_num::PhysicsNum;
+
+fn find(parent: &mut [usize], mut index: usize) -> usize {
+    while parent[index] != index {
+        parent[index] = parent[parent[index]];
+        index = parent[index];
+    }
+    index
+}
+
+fn union(parent: &mut [usize], a: usize, b: usize) {
+    let a = find(parent, a);
+    let b = find(parent, b);
+    if a < b {
+        parent[b] = a;
+    } else if b < a {
+        parent[a] = b;
+    }
+}
+
+#[derive(Default)]
+pub struct IslandBuilder<N> {
+    bodies: Vec<(EntityId, Aabb<N>)>,
+    parent: Vec<usize>,
+    sweep_order: Vec<usize>,
+    active: Vec<usize>,
+    island_of_root: Vec<Option<usize>>,
+}
+
+impl<N: PhysicsNum> IslandBuilder<N> {
+    pub fn clear(&mut self) {
+        self.bodies.clear();
+    }
+    pub fn add(&mut self, id: EntityId, aabb: Aabb<N>) {
+        self.bodies.push((id, aabb));
+    }
+    pub fn build(&mut self, islands: &mut Vec<Vec<EntityId>>) {
+        for island in islands.iter_mut() {
+            island.clear();
+        }
+        let mut num_islands = 0;
+        self.bodies.sort_unstable_by_key(|&(id, _)| id);
+        self.parent.clear();
+        self.parent.extend(0..self.bodies.len());
+        for index in 1..self.bodies.len() {
+            if self.bodies[index].0 == self.bodies[index - 1].0 {
+                union(&mut self.parent, index, index - 1);
+            }
+        }
+        self.sweep_order.clear();
+        self.sweep_order.extend(0..self.bodies.len());
+        {
+            let bodies = &self.bodies;
+            self.sweep_order.sort_unstable_by_key(|&index| {
+                (bodies[index].1.top_left().x, bodies[index].0, index)
+            });
+        }
+        self.active.clear();
+        for &index in self.sweep_order.iter() {
+            let aabb = &self.bodies[index].1;
+            let bodies = &self.bodies;
+            let left = aabb.top_left().x;
+            self.active
+                .retain(|&other| bodies[other].1.bottom_right_coord().x >= left);
+            for &other in self.active.iter() {
+                if self.bodies[other].1.is_intersecting(aabb) {
+                    union(&mut self.parent, index, other);
+                }
+            }
+            self.active.push(index);
+        }
+        self.island_of_root.clear();
+        self.island_of_root.resize(self.bodies.len(), None);
+        for index in 0..self.bodies.len() {
+            if index > 0 && self.bodies[index].0 == self.bodies[index - 1].0 {
+                continue;
+            }
+            let root = find(&mut self.parent, index);
+            let island_index = match self.island_of_root[root] {
+                Some(island_index) => island_index,
+                None => {
+                    let island_index = num_islands;
+                    num_islands += 1;
+                    self.island_of_root[root] = Some(island_index);
+                    if islands.len() <= island_index {
+                        islands.push(Vec::new());
+                    }
+                    island_index
+                }
+            };
+            islands[island_index].push(self.bodies[index].0);
+        }
+        islands.truncate(num_islands);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cgmath::vec2;
+
+    fn aabb(x: i64, y: i64, size: i64) -> Aabb<i64> {
+        Aabb::new(vec2(x, y), vec2(size, size))
+    }
+
+    #[test]
+    fn groups_touching_bodies() {
+        let mut builder = IslandBuilder::default();
+        builder.add(3, aabb(0, 0, 4));
+        builder.add(0, aabb(100, 0, 4));
+        builder.add(1, aabb(4, 0, 4));
+        builder.add(2, aabb(8, 2, 4));
+        builder.add(4, aabb(100, 50, 4));
+        let mut islands = Vec::new();
+        builder.build(&mut islands);
+        assert_eq!(islands, vec![vec![0], vec![1, 2, 3], vec![4]]);
+    }
+}
@@ -0,0 +1,159 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+#![allow(dead_code)]
+#[macro_use]
+extern crate alloc;
+extern crate best;
+extern crate cgmath;
+#[cfg(feature = "std")]
+extern crate core;
+// newtype_derive expands to `::std::ops` paths.
+#[cfg(not(feature = "std"))]
+extern crate core as std;
+#[macro_use]
+extern crate custom_derive;
+extern crate fnv;
+#[macro_use]
+extern crate newtype_derive;
+extern crate num;
+#[cfg(feature = "tracing")]
+extern crate tracing;
+#[cfg(target_arch = "wasm32")]
+extern crate wasm_bindgen;
+
+pub mod aabb;
+#[cfg(feature = "alloc-audit")]
+pub mod alloc_audit;
+#[cfg(feature = "std")]
+pub mod animation;
+#[cfg(feature = "std")]
+pub mod attractor;
+pub mod axis_aligned_rect;
+#[cfg(feature = "std")]
+pub mod bounce_pad;
+#[cfg(feature = "std")]
+pub mod broadphase;
+#[cfg(feature = "std")]
+pub mod camera;
+pub mod chain;
+#[cfg(feature = "std")]
+pub mod change_tracker;
+#[cfg(feature = "std")]
+pub mod chunked_index;
+pub mod collision;
+#[cfg(feature = "std")]
+pub mod colour_tween;
+#[cfg(feature = "std")]
+pub mod commands;
+pub mod convex_polygon;
+#[cfg(feature = "std")]
+pub mod cow_table;
+#[cfg(feature = "std")]
+pub mod determinism;
+#[cfg(feature = "std")]
+pub mod drag;
+pub mod entity_id;
+pub mod error;
+#[cfg(feature = "std")]
+pub mod explosion;
+#[cfg(all(feature = "std", any(feature = "ffi", target_arch = "wasm32")))]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod flat;
+#[cfg(feature = "std")]
+pub mod frame_buffer;
+#[cfg(feature = "std")]
+pub mod game;
+#[cfg(feature = "std")]
+pub mod gizmo;
+#[cfg(feature = "std")]
+pub mod gravity;
+#[cfg(feature = "std")]
+pub mod hazard;
+#[cfg(feature = "std")]
+pub mod health;
+pub mod heightfield;
+#[cfg(feature = "std")]
+pub mod homing;
+#[cfg(feature = "std")]
+pub mod hooks;
+#[cfg(feature = "std")]
+pub mod impulse;
+#[cfg(feature = "std")]
+pub mod island;
+#[cfg(feature = "std")]
+pub mod lifetime;
+pub mod line_segment;
+#[cfg(feature = "std")]
+pub mod material;
+#[cfg(feature = "std")]
+pub mod loose_quad_tree;
+#[cfg(feature = "std")]
+pub mod minkowski;
+pub mod oriented_rect;
+#[cfg(feature = "std")]
+pub mod out_of_bounds;
+#[cfg(feature = "std")]
+pub mod pair_cache;
+#[cfg(feature = "std")]
+pub mod particles;
+#[cfg(feature = "std")]
+pub mod path_follower;
+#[cfg(feature = "std")]
+pub mod physics_config;
+pub mod physics_num;
+#[cfg(feature = "std")]
+pub mod pickup;
+pub mod pixel_num;
+#[cfg(feature = "std")]
+pub mod pool;
+#[cfg(feature = "std")]
+pub mod portal;
+#[cfg(feature = "std")]
+pub mod prefab;
+#[cfg(feature = "std")]
+pub mod render;
+#[cfg(feature = "std")]
+pub mod resources;
+#[cfg(feature = "std")]
+pub mod rng;
+#[cfg(feature = "std")]
+pub mod rope;
+#[cfg(feature = "std")]
+pub mod scheduler;
+#[cfg(feature = "std")]
+pub mod scratch;
+pub mod shape;
+#[cfg(feature = "std")]
+pub mod sprite;
+#[cfg(feature = "std")]
+pub mod stacking;
+#[cfg(feature = "std")]
+pub mod state_delta;
+#[cfg(feature = "std")]
+pub mod state_machine;
+#[cfg(feature = "std")]
+pub mod static_bake;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod steering;
+#[cfg(feature = "std")]
+pub mod sweep_and_prune;
+#[cfg(feature = "std")]
+pub mod systems;
+#[cfg(feature = "std")]
+pub mod tessellation;
+pub mod trig;
+#[cfg(feature = "std")]
+pub mod user_data;
+pub mod validation;
+#[cfg(feature = "std")]
+pub mod verlet;
+#[cfg(feature = "std")]
+pub mod walkability;
+#[cfg(all(feature = "std", target_arch = "wasm32"))]
+pub mod wasm;
+
+#[cfg(feature = "alloc-audit")]
+#[global_allocator]
+static ALLOCATOR: alloc_audit::CountingAllocator = alloc_audit::CountingAllocator;
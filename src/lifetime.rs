@@ -0,0 +1,44 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Lifetime {
+    remaining: u32,
+    death_event: bool,
+}
+
+impl Lifetime {
+    pub fn new(updates: u32) -> Self {
+        assert!(updates > 0, "lifetime must be at least one update");
+        Self {
+            remaining: updates,
+            death_event: false,
+        }
+    }
+    pub fn with_death_event(mut self) -> Self {
+        self.death_event = true;
+        self
+    }
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+    pub fn emits_death_event(&self) -> bool {
+        self.death_event
+    }
+    pub fn tick(&mut self) -> bool {
+        self.remaining = self.remaining.saturating_sub(1);
+        self.remaining == 0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn expires_after_requested_updates() {
+        let mut lifetime = Lifetime::new(3);
+        assert!(!lifetime.tick());
+        assert!(!lifetime.tick());
+        assert!(lifetime.tick());
+        assert_eq!(lifetime.remaining(), 0);
+        assert!(lifetime.tick());
+    }
+}
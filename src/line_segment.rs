@@ -3,7 +3,7 @@ use physics_num::PhysicsNum;
 use cgmath::{Vector2, vec2};
 use shape::Collide;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
 pub struct LineSegment<N> {
     pub start: Vector2<N>,
     pub end: Vector2<N>,
@@ -1,15 +1,55 @@
 use aabb::*;
 use cgmath::{Vector2, vec2};
+use std::iter::Peekable;
 use std::num::NonZeroUsize;
-use physics_num::PhysicsNum;
+use std::vec;
+use physics_num::{self, PhysicsNum};
 use num::Zero;
 
+type BulkItems<T, N> = Peekable<vec::IntoIter<(u64, u32, Aabb<N>, T)>>;
+
 #[derive(Debug, Clone)]
 pub struct LooseQuadTree<T, N: PhysicsNum> {
     seq: u64,
     nodes: Vec<Node<T, N>>,
+    top_left: Vector2<N>,
     size: Vector2<N>,
     next_free: usize,
+    params: QuadTreeParams,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuadTreeParams {
+    pub looseness_numerator: i64,
+    pub looseness_denominator: i64,
+    pub max_depth: usize,
+    pub split_threshold: usize,
+}
+
+impl Default for QuadTreeParams {
+    fn default() -> Self {
+        Self {
+            looseness_numerator: 2,
+            looseness_denominator: 1,
+            max_depth: 30,
+            split_threshold: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuadTreeStats {
+    pub node_count: usize,
+    pub max_depth: usize,
+    pub entries_histogram: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeInfo<N> {
+    pub bounds: Aabb<N>,
+    pub loose_bounds: Aabb<N>,
+    pub depth: usize,
+    pub num_entries: usize,
 }
 
 #[derive(Debug, Clone)]
@@ -42,38 +82,91 @@ impl<T, N: PhysicsNum> LooseQuadTree<T, N> {
     const BOTTOM_LEFT: usize = 2;
     const BOTTOM_RIGHT: usize = 3;
     const NUM_CHILDREN: usize = 4;
+    const MAX_BULK_DEPTH: u32 = 30;
 
     pub fn new(size: Vector2<N>) -> Self {
+        Self::with_bounds(&Aabb::new(vec2(Zero::zero(), Zero::zero()), size))
+    }
+
+    pub fn with_bounds(bounds: &Aabb<N>) -> Self {
+        Self::with_params(bounds, Default::default())
+    }
+
+    pub fn with_params(bounds: &Aabb<N>, params: QuadTreeParams) -> Self {
+        assert!(
+            params.looseness_denominator > 0
+                && params.looseness_numerator > params.looseness_denominator,
+            "looseness must be greater than one"
+        );
         Self {
             seq: 1,
             nodes: vec![Default::default()],
-            size,
+            top_left: bounds.top_left(),
+            size: bounds.size(),
             next_free: 1,
+            params,
         }
     }
 
+    pub fn params(&self) -> QuadTreeParams {
+        self.params
+    }
+
+    fn fits_child(
+        params: &QuadTreeParams,
+        size: Vector2<N>,
+        child_size: Vector2<N>,
+    ) -> bool {
+        let slack = (params.looseness_numerator - params.looseness_denominator) as i128;
+        let denominator = params.looseness_denominator as i128;
+        let fits_axis = |size: N, child_size: N| {
+            physics_num::widen(size) * denominator
+                <= physics_num::widen(child_size) * slack
+        };
+        fits_axis(size.x, child_size.x) && fits_axis(size.y, child_size.y)
+    }
+
+    fn loose(bounds: &Aabb<N>, params: &QuadTreeParams) -> Aabb<N> {
+        let half_size = bounds.size() / PhysicsNum::two();
+        let scale = |value: N| {
+            physics_num::saturating_narrow(
+                physics_num::widen(value) * params.looseness_numerator as i128
+                    / params.looseness_denominator as i128,
+            )
+        };
+        Aabb::from_centre_and_half_size(
+            bounds.centre(),
+            vec2(scale(half_size.x), scale(half_size.y)),
+        )
+    }
+
     pub fn clear(&mut self) {
         self.seq += 1;
         self.nodes[0].reuse(self.seq);
     }
 
     pub fn insert(&mut self, aabb: Aabb<N>, t: T) {
-        let mut centre = aabb.centre();
+        let mut centre = aabb.centre() - self.top_left;
         let mut index = 0;
+        let mut depth = 0;
         let mut max_size = self.size / PhysicsNum::two();
         let nodes = &mut self.nodes;
         let next_free = &mut self.next_free;
+        let params = &self.params;
         loop {
             let child_offset = {
                 while nodes.len() <= index {
                     nodes.push(Default::default());
                 }
-                let mut node = &mut nodes[index];
+                let node = &mut nodes[index];
                 if node.seq != self.seq {
                     node.reuse(self.seq);
                 }
-                let size = aabb.size();
-                if size.x > max_size.x || size.y > max_size.y {
+                let fits = depth < params.max_depth
+                    && Self::fits_child(params, aabb.size(), max_size);
+                let below_threshold = node.child_offset.is_none()
+                    && node.items.len() < params.split_threshold;
+                if !fits || below_threshold {
                     node.items.push((aabb, t));
                     break;
                 }
@@ -83,92 +176,224 @@ impl<T, N: PhysicsNum> LooseQuadTree<T, N> {
                         *next_free += Self::NUM_CHILDREN;
                         NonZeroUsize::new(free).expect("unexpected state")
                     })
-                    .get() as usize
+                    .get()
             };
+            while nodes.len() < child_offset + Self::NUM_CHILDREN {
+                nodes.push(Default::default());
+            }
+            for i in 0..Self::NUM_CHILDREN {
+                if nodes[child_offset + i].seq != self.seq {
+                    nodes[child_offset + i].reuse(self.seq);
+                }
+            }
             if centre.x < max_size.x {
                 if centre.y < max_size.y {
                     index = child_offset + Self::TOP_LEFT;
                 } else {
                     index = child_offset + Self::BOTTOM_LEFT;
-                    centre.y = centre.y - max_size.y;
+                    centre.y -= max_size.y;
                 }
             } else {
                 if centre.y < max_size.y {
                     index = child_offset + Self::TOP_RIGHT;
-                    centre.x = centre.x - max_size.x;
+                    centre.x -= max_size.x;
                 } else {
                     index = child_offset + Self::BOTTOM_RIGHT;
-                    centre = centre - max_size;
+                    centre -= max_size;
                 }
             }
-            max_size = max_size / PhysicsNum::two();
+            max_size /= PhysicsNum::two();
+            depth += 1;
+        }
+    }
+
+    /// Walks the same path `insert` would take for `aabb`, so finding the
+    /// item costs one node per level rather than a full traversal.
+    pub fn remove(&mut self, aabb: &Aabb<N>, t: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        let mut centre = aabb.centre() - self.top_left;
+        let mut max_size = self.size / PhysicsNum::two();
+        let mut index = 0;
+        loop {
+            let node = match self.nodes.get_mut(index) {
+                Some(node) if node.seq == self.seq => node,
+                _ => return false,
+            };
+            let position = node.items
+                .iter()
+                .position(|(item_aabb, item)| item_aabb == aabb && item == t);
+            if let Some(position) = position {
+                node.items.swap_remove(position);
+                return true;
+            }
+            let child_offset = match node.child_offset {
+                Some(child_offset) => child_offset.get(),
+                None => return false,
+            };
+            let mut quadrant = Self::TOP_LEFT;
+            if centre.x >= max_size.x {
+                quadrant += Self::TOP_RIGHT;
+                centre.x -= max_size.x;
+            }
+            if centre.y >= max_size.y {
+                quadrant += Self::BOTTOM_LEFT;
+                centre.y -= max_size.y;
+            }
+            index = child_offset + quadrant;
+            max_size /= PhysicsNum::two();
         }
     }
 
+    fn node_path(&self, aabb: &Aabb<N>) -> (u64, u32) {
+        let mut centre = aabb.centre() - self.top_left;
+        let mut max_size = self.size / PhysicsNum::two();
+        let size = aabb.size();
+        let mut code = 0;
+        let mut depth = 0;
+        let max_depth = Self::MAX_BULK_DEPTH.min(self.params.max_depth as u32);
+        while depth < max_depth && Self::fits_child(&self.params, size, max_size) {
+            let mut quadrant = Self::TOP_LEFT;
+            if centre.x >= max_size.x {
+                quadrant += Self::TOP_RIGHT;
+                centre.x -= max_size.x;
+            }
+            if centre.y >= max_size.y {
+                quadrant += Self::BOTTOM_LEFT;
+                centre.y -= max_size.y;
+            }
+            code = (code << 2) | quadrant as u64;
+            depth += 1;
+            max_size /= PhysicsNum::two();
+        }
+        (code << (2 * (Self::MAX_BULK_DEPTH - depth)), depth)
+    }
+
+    fn bulk_load_rec(
+        &mut self,
+        index: usize,
+        depth: u32,
+        prefix: u64,
+        items: &mut BulkItems<T, N>,
+    ) {
+        let in_subtree = |code: u64| {
+            depth == 0 || code >> (2 * (Self::MAX_BULK_DEPTH - depth)) == prefix
+        };
+        loop {
+            let at_this_node = match items.peek() {
+                Some(&(code, item_depth, _, _)) => {
+                    item_depth == depth && in_subtree(code)
+                }
+                None => false,
+            };
+            if !at_this_node {
+                break;
+            }
+            if let Some((_, _, aabb, t)) = items.next() {
+                self.nodes[index].items.push((aabb, t));
+            }
+        }
+        match items.peek() {
+            Some(&(code, _, _, _)) if in_subtree(code) => (),
+            _ => return,
+        }
+        let child_offset = self.next_free;
+        self.next_free += Self::NUM_CHILDREN;
+        while self.nodes.len() < self.next_free {
+            self.nodes.push(Default::default());
+        }
+        for i in 0..Self::NUM_CHILDREN {
+            self.nodes[child_offset + i].reuse(self.seq);
+        }
+        self.nodes[index].child_offset = NonZeroUsize::new(child_offset);
+        for i in 0..Self::NUM_CHILDREN {
+            let child_prefix = (prefix << 2) | i as u64;
+            self.bulk_load_rec(child_offset + i, depth + 1, child_prefix, items);
+        }
+    }
+
+    pub fn bulk_load<I: IntoIterator<Item = (Aabb<N>, T)>>(&mut self, items: I) {
+        let mut keyed = items
+            .into_iter()
+            .map(|(aabb, t)| {
+                let (code, depth) = self.node_path(&aabb);
+                (code, depth, aabb, t)
+            })
+            .collect::<Vec<_>>();
+        keyed.sort_by_key(|&(code, depth, _, _)| (code, depth));
+        self.seq += 1;
+        self.next_free = 1;
+        self.nodes[0].reuse(self.seq);
+        self.bulk_load_rec(0, 0, 0, &mut keyed.into_iter().peekable());
+    }
+
     fn for_each_intersection_rec<F: FnMut(&Aabb<N>, &T)>(
         nodes: &[Node<T, N>],
+        params: &QuadTreeParams,
         current_index: usize,
         current_node_aabb: &Aabb<N>,
         aabb_to_test: &Aabb<N>,
         f: &mut F,
+        visits: &mut usize,
     ) {
         if let Some(node) = nodes.get(current_index) {
-            for &(ref aabb, ref t) in node.items.iter() {
+            *visits += 1;
+            for (aabb, t) in node.items.iter() {
                 if aabb.is_intersecting(aabb_to_test) {
                     f(aabb, t);
                 }
             }
             if let Some(child_offset) = node.child_offset {
-                let child_offset = child_offset.get() as usize;
+                let child_offset = child_offset.get();
                 let AabbSplitFour {
                     top_left,
                     top_right,
                     bottom_left,
                     bottom_right,
                 } = current_node_aabb.split_four();
-                if top_left.double_about_centre().is_intersecting(aabb_to_test) {
+                if Self::loose(&top_left, params).is_intersecting(aabb_to_test) {
                     Self::for_each_intersection_rec(
                         nodes,
+                        params,
                         child_offset + Self::TOP_LEFT,
                         &top_left,
                         aabb_to_test,
                         f,
+                        visits,
                     );
                 }
-                if top_right
-                    .double_about_centre()
-                    .is_intersecting(aabb_to_test)
-                {
+                if Self::loose(&top_right, params).is_intersecting(aabb_to_test) {
                     Self::for_each_intersection_rec(
                         nodes,
+                        params,
                         child_offset + Self::TOP_RIGHT,
                         &top_right,
                         aabb_to_test,
                         f,
+                        visits,
                     );
                 }
-                if bottom_left
-                    .double_about_centre()
-                    .is_intersecting(aabb_to_test)
-                {
+                if Self::loose(&bottom_left, params).is_intersecting(aabb_to_test) {
                     Self::for_each_intersection_rec(
                         nodes,
+                        params,
                         child_offset + Self::BOTTOM_LEFT,
                         &bottom_left,
                         aabb_to_test,
                         f,
+                        visits,
                     );
                 }
-                if bottom_right
-                    .double_about_centre()
-                    .is_intersecting(aabb_to_test)
-                {
+                if Self::loose(&bottom_right, params).is_intersecting(aabb_to_test) {
                     Self::for_each_intersection_rec(
                         nodes,
+                        params,
                         child_offset + Self::BOTTOM_RIGHT,
                         &bottom_right,
                         aabb_to_test,
                         f,
+                        visits,
                     );
                 }
             }
@@ -179,8 +404,253 @@ impl<T, N: PhysicsNum> LooseQuadTree<T, N> {
         &self,
         aabb: &Aabb<N>,
         mut f: F,
+    ) -> usize {
+        let root_aabb = Aabb::new(self.top_left, self.size);
+        let mut visits = 0;
+        Self::for_each_intersection_rec(
+            &self.nodes,
+            &self.params,
+            0,
+            &root_aabb,
+            aabb,
+            &mut f,
+            &mut visits,
+        );
+        visits
+    }
+
+    fn for_each_item_rec<F: FnMut(&Aabb<N>, &T)>(
+        nodes: &[Node<T, N>],
+        current_index: usize,
+        seq: u64,
+        f: &mut F,
     ) {
-        let root_aabb = Aabb::new(vec2(Zero::zero(), Zero::zero()), self.size);
-        Self::for_each_intersection_rec(&self.nodes, 0, &root_aabb, aabb, &mut f);
+        if let Some(node) = nodes.get(current_index) {
+            if node.seq != seq {
+                return;
+            }
+            for (aabb, t) in node.items.iter() {
+                f(aabb, t);
+            }
+            if let Some(child_offset) = node.child_offset {
+                for i in 0..Self::NUM_CHILDREN {
+                    Self::for_each_item_rec(nodes, child_offset.get() + i, seq, f);
+                }
+            }
+        }
+    }
+
+    pub fn for_each_item<F: FnMut(&Aabb<N>, &T)>(&self, mut f: F) {
+        Self::for_each_item_rec(&self.nodes, 0, self.seq, &mut f);
+    }
+
+    fn for_each_node_rec<F: FnMut(NodeInfo<N>)>(
+        &self,
+        index: usize,
+        bounds: Aabb<N>,
+        depth: usize,
+        f: &mut F,
+    ) {
+        let node = match self.nodes.get(index) {
+            Some(node) if node.seq == self.seq => node,
+            _ => return,
+        };
+        f(NodeInfo {
+            bounds,
+            loose_bounds: Self::loose(&bounds, &self.params),
+            depth,
+            num_entries: node.items.len(),
+        });
+        if let Some(child_offset) = node.child_offset {
+            let AabbSplitFour {
+                top_left,
+                top_right,
+                bottom_left,
+                bottom_right,
+            } = bounds.split_four();
+            let children = [top_left, top_right, bottom_left, bottom_right];
+            for (i, &child_bounds) in children.iter().enumerate() {
+                let child_index = child_offset.get() + i;
+                self.for_each_node_rec(child_index, child_bounds, depth + 1, f);
+            }
+        }
+    }
+
+    pub fn for_each_node<F: FnMut(NodeInfo<N>)>(&self, mut f: F) {
+        let root_aabb = Aabb::new(self.top_left, self.size);
+        self.for_each_node_rec(0, root_aabb, 0, &mut f);
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = NodeInfo<N>> {
+        let mut nodes = Vec::new();
+        self.for_each_node(|info| nodes.push(info));
+        nodes.into_iter()
+    }
+
+    pub fn stats(&self) -> QuadTreeStats {
+        let mut stats = QuadTreeStats {
+            node_count: 0,
+            max_depth: 0,
+            entries_histogram: Vec::new(),
+        };
+        self.for_each_node(|info| {
+            stats.node_count += 1;
+            stats.max_depth = stats.max_depth.max(info.depth);
+            if stats.entries_histogram.len() <= info.num_entries {
+                stats.entries_histogram.resize(info.num_entries + 1, 0);
+            }
+            stats.entries_histogram[info.num_entries] += 1;
+        });
+        stats
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn bulk_load_matches_incremental_insertion() {
+        let items = (0..200)
+            .map(|i| {
+                let size = vec2(1 + (i * 7) % 13, 1 + (i * 5) % 40);
+                (Aabb::new(vec2((i * 37) % 500, (i * 91) % 500), size), i)
+            })
+            .collect::<Vec<_>>();
+        let mut incremental = LooseQuadTree::new(vec2(512, 512));
+        for &(aabb, i) in items.iter() {
+            incremental.insert(aabb, i);
+        }
+        let mut bulk = LooseQuadTree::new(vec2(512, 512));
+        bulk.insert(Aabb::new(vec2(0, 0), vec2(1, 1)), 1000);
+        bulk.bulk_load(items.iter().cloned());
+        for query in 0..50 {
+            let top_left = vec2((query * 53) % 480, (query * 29) % 480);
+            let aabb = Aabb::new(top_left, vec2(30, 20));
+            let mut expected = Vec::new();
+            incremental.for_each_intersection(&aabb, |_, &i| expected.push(i));
+            let mut actual = Vec::new();
+            bulk.for_each_intersection(&aabb, |_, &i| actual.push(i));
+            expected.sort();
+            actual.sort();
+            assert_eq!(expected, actual);
+        }
+        let mut count = 0;
+        bulk.for_each_item(|_, _| count += 1);
+        assert_eq!(count, items.len());
+        let stats = bulk.stats();
+        assert_eq!(stats.node_count, bulk.nodes().count());
+        assert_eq!(stats.entries_histogram.iter().sum::<usize>(), stats.node_count);
+        let entries = stats
+            .entries_histogram
+            .iter()
+            .enumerate()
+            .map(|(entries, nodes)| entries * nodes)
+            .sum::<usize>();
+        assert_eq!(entries, items.len());
+    }
+    #[test]
+    fn params_do_not_change_query_results() {
+        let items = (0..150)
+            .map(|i| {
+                let size = vec2(1 + (i * 11) % 60, 1 + (i * 3) % 25);
+                (Aabb::new(vec2((i * 43) % 450, (i * 67) % 450), size), i)
+            })
+            .collect::<Vec<_>>();
+        let params = [
+            QuadTreeParams::default(),
+            QuadTreeParams {
+                looseness_numerator: 3,
+                looseness_denominator: 2,
+                ..Default::default()
+            },
+            QuadTreeParams {
+                max_depth: 2,
+                split_threshold: 4,
+                ..Default::default()
+            },
+        ];
+        let bounds = Aabb::new(vec2(0, 0), vec2(512, 512));
+        for &params in params.iter() {
+            let mut tree = LooseQuadTree::with_params(&bounds, params);
+            for &(aabb, i) in items.iter() {
+                tree.insert(aabb, i);
+            }
+            assert!(tree.stats().max_depth <= params.max_depth);
+            for query in 0..40 {
+                let top_left = vec2((query * 59) % 480, (query * 31) % 480);
+                let aabb = Aabb::new(top_left, vec2(25, 35));
+                let mut expected = items
+                    .iter()
+                    .filter(|&&(item, _)| item.is_intersecting(&aabb))
+                    .map(|&(_, i)| i)
+                    .collect::<Vec<_>>();
+                let mut actual = Vec::new();
+                tree.for_each_intersection(&aabb, |_, &i| actual.push(i));
+                expected.sort();
+                actual.sort();
+                assert_eq!(expected, actual);
+            }
+        }
+    }
+
+    #[test]
+    fn remove_finds_items_at_any_depth() {
+        let items = (0..150)
+            .map(|i| {
+                let size = vec2(1 + (i * 11) % 90, 1 + (i * 3) % 25);
+                (Aabb::new(vec2((i * 43) % 450, (i * 67) % 450), size), i)
+            })
+            .collect::<Vec<_>>();
+        let bounds = Aabb::new(vec2(0, 0), vec2(512, 512));
+        let params = QuadTreeParams {
+            split_threshold: 3,
+            ..Default::default()
+        };
+        let mut incremental = LooseQuadTree::with_params(&bounds, params);
+        for &(aabb, i) in items.iter() {
+            incremental.insert(aabb, i);
+        }
+        let mut bulk = LooseQuadTree::with_bounds(&bounds);
+        bulk.bulk_load(items.iter().cloned());
+        for tree in [&mut incremental, &mut bulk].iter_mut() {
+            for &(aabb, i) in items.iter().filter(|&&(_, i)| i % 2 == 0) {
+                assert!(tree.remove(&aabb, &i));
+            }
+            assert!(!tree.remove(&items[0].0, &0));
+            let mut remaining = Vec::new();
+            tree.for_each_item(|_, &i| remaining.push(i));
+            remaining.sort();
+            let expected = (0..150).filter(|i| i % 2 == 1).collect::<Vec<_>>();
+            assert_eq!(remaining, expected);
+        }
+    }
+
+    #[test]
+    fn nodes_describe_the_tree_shape() {
+        let bounds = Aabb::new(vec2(0, 0), vec2(512, 512));
+        let mut tree = LooseQuadTree::with_bounds(&bounds);
+        assert_eq!(
+            tree.stats(),
+            QuadTreeStats {
+                node_count: 0,
+                max_depth: 0,
+                entries_histogram: Vec::new(),
+            }
+        );
+        tree.insert(Aabb::new(vec2(10, 10), vec2(4, 4)), 0);
+        tree.insert(Aabb::new(vec2(200, 200), vec2(300, 300)), 1);
+        let stats = tree.stats();
+        assert!(stats.max_depth > 0);
+        assert_eq!(stats.node_count, tree.nodes().count());
+        let root = tree.nodes().next().unwrap();
+        assert_eq!((root.bounds, root.depth), (bounds, 0));
+        for node in tree.nodes() {
+            assert!(node.loose_bounds.contains(&node.bounds));
+            assert!(bounds.contains(&node.bounds));
+            assert_eq!(node.bounds.size().x, 512 >> node.depth);
+        }
+        let entries = tree.nodes().map(|node| node.num_entries).sum::<usize>();
+        assert_eq!(entries, 2);
     }
 }
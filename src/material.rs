@@ -0,0 +1,136 @@
+use cgmath::{Vector2, vec2};
+use game::EntityId;
+use impulse::Restitution;
+use line_segment::LineSegment;
+use physics_num;
+use pixel_num::SubPixelI64;
+
+pub type MaterialId = u32;
+
+pub const FRICTION_SCALE: i64 = 1 << 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Material {
+    pub friction: i64,
+    pub restitution: Restitution,
+    pub surface_velocity: Vector2<SubPixelI64>,
+    pub footstep: String,
+}
+
+impl Material {
+    pub fn new(footstep: &str) -> Self {
+        Self {
+            friction: 0,
+            restitution: Restitution::elastic(),
+            surface_velocity: vec2(SubPixelI64::new(0), SubPixelI64::new(0)),
+            footstep: footstep.to_string(),
+        }
+    }
+    pub fn with_friction(mut self, friction: i64) -> Self {
+        assert!(
+            (0..=FRICTION_SCALE).contains(&friction),
+            "friction must be between zero and FRICTION_SCALE"
+        );
+        self.friction = friction;
+        self
+    }
+    pub fn with_restitution(mut self, restitution: Restitution) -> Self {
+        self.restitution = restitution;
+        self
+    }
+    pub fn with_surface_velocity(
+        mut self,
+        surface_velocity: Vector2<SubPixelI64>,
+    ) -> Self {
+        self.surface_velocity = surface_velocity;
+        self
+    }
+    pub fn surface_response(
+        &self,
+        velocity: Vector2<SubPixelI64>,
+        surface: Vector2<SubPixelI64>,
+    ) -> Vector2<SubPixelI64> {
+        let surface_len2 = physics_num::magnitude2_wide(surface);
+        if surface_len2 == 0 || self.friction == 0 {
+            return velocity;
+        }
+        let along = |v: Vector2<SubPixelI64>| {
+            physics_num::widen(v.x) * physics_num::widen(surface.x)
+                + physics_num::widen(v.y) * physics_num::widen(surface.y)
+        };
+        let relative = along(velocity) - along(self.surface_velocity);
+        let removed = relative * self.friction as i128 / FRICTION_SCALE as i128;
+        let scale = |value| {
+            physics_num::saturating_narrow(physics_num::widen(value) * removed / surface_len2)
+        };
+        velocity - vec2(scale(surface.x), scale(surface.y))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ContactEvent {
+    pub entity_id: EntityId,
+    pub other_id: EntityId,
+    pub surface: LineSegment<SubPixelI64>,
+    pub material: Option<MaterialId>,
+    pub entity_material: Option<MaterialId>,
+    pub approach_speed: SubPixelI64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MaterialRegistry {
+    materials: Vec<Material>,
+}
+
+impl MaterialRegistry {
+    pub fn register(&mut self, material: Material) -> MaterialId {
+        self.materials.push(material);
+        (self.materials.len() - 1) as MaterialId
+    }
+    pub fn get(&self, id: MaterialId) -> Option<&Material> {
+        self.materials.get(id as usize)
+    }
+    pub fn get_mut(&mut self, id: MaterialId) -> Option<&mut Material> {
+        self.materials.get_mut(id as usize)
+    }
+    pub fn len(&self) -> usize {
+        self.materials.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.materials.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(x: i64, y: i64) -> Vector2<SubPixelI64> {
+        vec2(SubPixelI64::new(x), SubPixelI64::new(y))
+    }
+
+    #[test]
+    fn friction_acts_along_surface_towards_surface_velocity() {
+        let floor = v(512, 0);
+        let ice = Material::new("ice");
+        assert_eq!(ice.surface_response(v(100, 30), floor), v(100, 30));
+        let mud = Material::new("mud").with_friction(FRICTION_SCALE / 2);
+        assert_eq!(mud.surface_response(v(100, 30), floor), v(50, 30));
+        assert_eq!(mud.surface_response(v(-100, 30), -floor), v(-50, 30));
+        let conveyor = Material::new("metal")
+            .with_friction(FRICTION_SCALE)
+            .with_surface_velocity(v(40, 0));
+        assert_eq!(conveyor.surface_response(v(100, 30), floor), v(40, 30));
+        assert_eq!(conveyor.surface_response(v(0, 0), floor), v(40, 0));
+    }
+
+    #[test]
+    fn registry_hands_out_sequential_ids() {
+        let mut registry = MaterialRegistry::default();
+        let stone = registry.register(Material::new("stone"));
+        let grass = registry.register(Material::new("grass"));
+        assert_eq!((stone, grass), (0, 1));
+        assert_eq!(registry.get(grass).unwrap().footstep, "grass");
+        assert!(registry.get(2).is_none());
+    }
+}
@@ -0,0 +1,139 @@
+use best::BestMap;
+use cgmath::{Vector2, vec2};
+use collision::{self, Collision, TimeOfImpact};
+use convex_polygon::ConvexPolygon;
+use num::Zero;
+use physics_num::{self, PhysicsNum};
+use shape::{Collide, CollisionInfo, Shape};
+
+fn turn<N: PhysicsNum>(o: Vector2<N>, a: Vector2<N>, b: Vector2<N>) -> i128 {
+    let (ax, ay) = (physics_num::widen(a.x - o.x), physics_num::widen(a.y - o.y));
+    let (bx, by) = (physics_num::widen(b.x - o.x), physics_num::widen(b.y - o.y));
+    ax * by - ay * bx
+}
+
+fn convex_hull<N: PhysicsNum>(mut points: Vec<Vector2<N>>) -> Vec<Vector2<N>> {
+    points.sort_by(|a, b| (a.x, a.y).partial_cmp(&(b.x, b.y)).expect("unordered"));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    let mut hull: Vec<Vector2<N>> = Vec::with_capacity(points.len() * 2);
+    for pass in 0..2 {
+        let start = hull.len();
+        for i in 0..points.len() {
+            let point = if pass == 0 {
+                points[i]
+            } else {
+                points[points.len() - 1 - i]
+            };
+            while hull.len() >= start + 2
+                && turn(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0
+            {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        hull.pop();
+    }
+    hull
+}
+
+pub fn minkowski_difference<N: PhysicsNum>(
+    moving: &Shape<N>,
+    stationary: &Shape<N>,
+    stationary_position: Vector2<N>,
+) -> Option<ConvexPolygon<N>> {
+    let moving_vertices = moving.vertices();
+    let mut points = Vec::new();
+    for stationary_vertex in stationary.vertices() {
+        for &moving_vertex in moving_vertices.iter() {
+            points.push(stationary_vertex + stationary_position - moving_vertex);
+        }
+    }
+    let hull = convex_hull(points);
+    if hull.len() < 3 {
+        None
+    } else {
+        Some(ConvexPolygon::new(hull))
+    }
+}
+
+pub fn point_movement_collision_test<N: PhysicsNum>(
+    position: Vector2<N>,
+    movement: Vector2<N>,
+    difference: &ConvexPolygon<N>,
+) -> Option<CollisionInfo<N>> {
+    let mut best_collision = BestMap::new();
+    difference.for_each_edge_facing(-movement, |edge| {
+        match collision::vertex_moving_towards_edge(position, movement, edge, N::one()) {
+            Ok(Collision::StartInsideEdge) => {
+                let vector = edge.vector();
+                let normal = vec2(vector.y, -vector.x);
+                let zero = vec2(Zero::zero(), Zero::zero());
+                best_collision.insert_lt(
+                    Zero::zero(),
+                    (edge, zero, TimeOfImpact::zero(), Some(normal)),
+                );
+            }
+            Ok(Collision::CollidesWithEdgeAfter(allowed_movement, time)) => {
+                best_collision.insert_lt(
+                    physics_num::magnitude2(allowed_movement),
+                    (edge, allowed_movement, time, None),
+                );
+            }
+            Err(_) => (),
+        }
+    });
+    if let Some((magnitude2, value)) = best_collision.into_key_and_value() {
+        let (line_segment, allowed_movement, time_of_impact, start_inside_normal) = value;
+        Some(CollisionInfo {
+            magnitude2,
+            allowed_movement,
+            time_of_impact,
+            line_segment,
+            start_inside_normal,
+        })
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axis_aligned_rect::AxisAlignedRect;
+    use line_segment::LineSegment;
+
+    fn rect(width: i64, height: i64) -> Shape<i64> {
+        Shape::AxisAlignedRect(AxisAlignedRect::new(vec2(width, height)))
+    }
+
+    #[test]
+    fn rect_difference_is_expanded_rect() {
+        let difference = minkowski_difference(&rect(2, 3), &rect(4, 5), vec2(10, 10))
+            .unwrap();
+        let aabb = difference.aabb(vec2(0, 0));
+        assert_eq!(aabb.top_left(), vec2(8, 7));
+        assert_eq!(aabb.size(), vec2(6, 8));
+        assert_eq!(difference.vertices().len(), 4);
+    }
+
+    #[test]
+    fn rect_segment_difference_is_hexagon() {
+        let segment = Shape::LineSegment(LineSegment::new(vec2(0, 0), vec2(4, 4)));
+        let difference = minkowski_difference(&rect(2, 2), &segment, vec2(0, 0)).unwrap();
+        assert_eq!(difference.vertices().len(), 6);
+    }
+
+    #[test]
+    fn point_formulation_matches_shape_test() {
+        let moving = rect(2, 2);
+        let stationary = rect(4, 4);
+        let difference = minkowski_difference(&moving, &stationary, vec2(10, 0)).unwrap();
+        let collision =
+            point_movement_collision_test(vec2(0, 1), vec2(20, 0), &difference).unwrap();
+        assert_eq!(collision.allowed_movement, vec2(7, 0));
+        assert_eq!(collision.line_segment.vector().x, 0);
+    }
+}
@@ -1,9 +1,9 @@
-use gfx;
-use gfx_device_gl;
-use gfx_window_glutin;
-use glutin;
+use native::gfx;
+use native::gfx_device_gl;
+use native::gfx_window_glutin;
+use native::glutin;
 
-use graphics::formats;
+use native::graphics::formats;
 
 pub struct GlutinWindow {
     pub window: glutin::GlWindow,
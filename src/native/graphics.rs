@@ -1,5 +1,5 @@
 pub mod formats {
-    use gfx;
+    use native::gfx;
     pub type Colour = gfx::format::Srgba8;
     pub type Depth = gfx::format::DepthStencil;
 }
@@ -12,8 +12,8 @@ mod consts {
 
 mod dimensions {
     use super::formats;
-    use cgmath::{Vector2, vec2};
-    use gfx;
+    use native::cgmath::{Vector2, vec2};
+    use native::gfx;
     pub fn rtv_dimensions<R: gfx::Resources>(
         rtv: &gfx::handle::RenderTargetView<R, formats::Colour>,
     ) -> Vector2<u16> {
@@ -23,8 +23,11 @@ mod dimensions {
 }
 
 mod buffer_types {
-    use cgmath::Vector2;
-    use gfx;
+    use native::cgmath::Vector2;
+    use native::gfx::{
+        self, gfx_constant_struct, gfx_constant_struct_meta, gfx_impl_struct_meta,
+        gfx_vertex_struct, gfx_vertex_struct_meta,
+    };
     gfx_vertex_struct!(QuadCorners {
         corner_zero_to_one: [f32; 2] = "a_CornerZeroToOne",
     });
@@ -49,14 +52,13 @@ mod buffer_types {
 mod buffer_alloc {
     use super::buffer_types;
     use super::consts;
-    use gfx;
+    use native::gfx;
+    type InstanceAndUploadBuffers<R, T> =
+        (gfx::handle::Buffer<R, T>, gfx::handle::Buffer<R, T>);
     pub fn create_instance_and_upload_buffers<R, F, T>(
         size: usize,
         factory: &mut F,
-    ) -> Result<
-        (gfx::handle::Buffer<R, T>, gfx::handle::Buffer<R, T>),
-        gfx::buffer::CreationError,
-    >
+    ) -> Result<InstanceAndUploadBuffers<R, T>, gfx::buffer::CreationError>
     where
         R: gfx::Resources,
         F: gfx::Factory<R> + gfx::traits::FactoryExt<R>,
@@ -108,7 +110,7 @@ mod instance_renderer {
     use super::buffer_types;
     use super::formats;
     use super::InstanceWriter;
-    use gfx;
+    use native::gfx;
     const MAX_NUM_INSTANCES: usize = 1024;
     pub struct ShaderBytes {
         pub vertex: &'static [u8],
@@ -178,7 +180,7 @@ mod instance_renderer {
         pub fn instance_writer<F>(
             &mut self,
             factory: &mut F,
-        ) -> InstanceWriter<R, D::Instance>
+        ) -> InstanceWriter<'_, R, D::Instance>
         where
             F: gfx::Factory<R> + gfx::traits::FactoryExt<R>,
         {
@@ -215,12 +217,15 @@ pub mod quad {
     use super::buffer_types;
     use super::formats;
     use super::instance_renderer::{self, PipelineData, ShaderBytes};
-    use gfx;
+    use native::gfx::{
+        self, gfx_impl_struct_meta, gfx_pipeline, gfx_pipeline_inner, gfx_vertex_struct,
+        gfx_vertex_struct_meta,
+    };
 
     gfx_vertex_struct!(Instance {
         position_of_top_left_in_pixels: [f32; 2] = "i_PositionOfTopLeftInPixels",
         dimensions_in_pixels: [f32; 2] = "i_DimensionsInPixels",
-        colour: [f32; 3] = "i_Colour",
+        colour: [f32; 4] = "i_Colour",
     });
 
     gfx_pipeline!(pipe {
@@ -267,12 +272,15 @@ pub mod line_segment {
     use super::buffer_types;
     use super::formats;
     use super::instance_renderer::{self, PipelineData, ShaderBytes};
-    use gfx;
+    use native::gfx::{
+        self, gfx_impl_struct_meta, gfx_pipeline, gfx_pipeline_inner, gfx_vertex_struct,
+        gfx_vertex_struct_meta,
+    };
 
     gfx_vertex_struct!(Instance {
         start: [f32; 2] = "i_Start",
         end: [f32; 2] = "i_End",
-        colour: [f32; 3] = "i_Colour",
+        colour: [f32; 4] = "i_Colour",
     });
 
     gfx_pipeline!(pipe {
@@ -316,8 +324,9 @@ pub mod line_segment {
     pub type Renderer<R> = instance_renderer::Renderer<R, pipe::Data<R>>;
 }
 
-use cgmath::Vector2;
-use gfx;
+use native::cgmath::Vector2;
+use native::render::{self, Primitive};
+use native::gfx;
 
 pub struct InstanceWriter<'a, R: gfx::Resources, T: 'a + Copy> {
     num_instances: &'a mut usize,
@@ -345,9 +354,9 @@ impl<'a, R: gfx::Resources, T: Copy> Drop for InstanceWriter<'a, R, T> {
 }
 
 impl<'a, R: gfx::Resources, T: Copy> InstanceWriter<'a, R, T> {
-    pub fn iter_mut(&mut self) -> InstanceWriterIterMut<T> {
+    pub fn iter_mut(&mut self) -> InstanceWriterIterMut<'_, T> {
         InstanceWriterIterMut {
-            num_instances: &mut self.num_instances,
+            num_instances: self.num_instances,
             iter_mut: self.writer.iter_mut(),
         }
     }
@@ -359,7 +368,7 @@ pub struct Frame<'a, R: gfx::Resources> {
 }
 
 impl<'a, R: gfx::Resources> Frame<'a, R> {
-    pub fn updater(&mut self) -> FrameUpdater {
+    pub fn updater(&mut self) -> FrameUpdater<'_> {
         FrameUpdater {
             quad: self.quad.iter_mut(),
             line_segment: self.line_segment.iter_mut(),
@@ -377,7 +386,7 @@ impl<'a> FrameUpdater<'a> {
         &mut self,
         top_left: Vector2<f32>,
         size: Vector2<f32>,
-        colour: [f32; 3],
+        colour: [f32; 4],
     ) {
         if let Some(quad) = self.quad.next() {
             quad.position_of_top_left_in_pixels = top_left.into();
@@ -389,7 +398,7 @@ impl<'a> FrameUpdater<'a> {
         &mut self,
         start: Vector2<f32>,
         end: Vector2<f32>,
-        colour: [f32; 3],
+        colour: [f32; 4],
     ) {
         if let Some(line_segment) = self.line_segment.next() {
             line_segment.start = start.into();
@@ -399,6 +408,29 @@ impl<'a> FrameUpdater<'a> {
     }
 }
 
+/// Every instance of a pipeline is drawn in a single batch with alpha blending,
+/// so `BlendHint`s are ignored and additive primitives draw as alpha.
+impl<'a> render::Renderer for FrameUpdater<'a> {
+    fn draw(&mut self, primitive: Primitive) {
+        match primitive {
+            Primitive::AxisAlignedRect {
+                top_left,
+                size,
+                colour,
+            }
+            | Primitive::Sprite {
+                top_left,
+                size,
+                colour,
+                ..
+            } => self.axis_aligned_rect(top_left, size, colour),
+            Primitive::LineSegment { start, end, colour } => {
+                self.line_segment(start, end, colour)
+            }
+        }
+    }
+}
+
 pub struct Renderer<R: gfx::Resources> {
     pub quad: quad::Renderer<R>,
     pub line_segment: line_segment::Renderer<R>,
@@ -429,7 +461,7 @@ impl<R: gfx::Resources> Renderer<R> {
             ),
         }
     }
-    pub fn prepare_frame<F>(&mut self, factory: &mut F) -> Frame<R>
+    pub fn prepare_frame<F>(&mut self, factory: &mut F) -> Frame<'_, R>
     where
         F: gfx::Factory<R> + gfx::traits::FactoryExt<R>,
     {
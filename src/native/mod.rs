@@ -0,0 +1,128 @@
+//! The desktop demo: the demo world in a glutin window, drawn with gfx.
+
+extern crate cgmath;
+extern crate gfx;
+extern crate gfx_device_gl;
+extern crate gfx_window_glutin;
+extern crate glutin;
+extern crate integer_physics_experiment;
+
+mod glutin_window;
+mod graphics;
+
+use self::integer_physics_experiment::{game, render};
+use self::cgmath::vec2;
+use self::game::{GameState, InputModel};
+use self::glutin_window::GlutinWindow;
+use self::gfx::Device;
+use self::glutin::GlContext;
+use self::graphics::Renderer;
+
+const GIZMO_VELOCITY_SCALE: i64 = 8;
+
+enum ExternalEvent {
+    Quit,
+    Reset,
+    ToggleGizmos,
+}
+
+fn process_input(
+    events_loop: &mut glutin::EventsLoop,
+    input_model: &mut InputModel,
+) -> Option<ExternalEvent> {
+    let mut external_event = None;
+
+    events_loop.poll_events(|event| if let glutin::Event::WindowEvent { event, .. } = event { match event {
+        glutin::WindowEvent::CloseRequested => {
+            external_event = Some(ExternalEvent::Quit);
+        }
+        glutin::WindowEvent::KeyboardInput { input, .. } => {
+            if let Some(virtual_keycode) = input.virtual_keycode {
+                match input.state {
+                    glutin::ElementState::Pressed => match virtual_keycode {
+                        glutin::VirtualKeyCode::Return => {
+                            external_event = Some(ExternalEvent::Reset)
+                        }
+                        glutin::VirtualKeyCode::G => {
+                            external_event = Some(ExternalEvent::ToggleGizmos)
+                        }
+                        glutin::VirtualKeyCode::Left => input_model.set_left(1.),
+                        glutin::VirtualKeyCode::Right => input_model.set_right(1.),
+                        glutin::VirtualKeyCode::Up => input_model.set_up(1.),
+                        glutin::VirtualKeyCode::Down => input_model.set_down(1.),
+                        _ => (),
+                    },
+                    glutin::ElementState::Released => match virtual_keycode {
+                        glutin::VirtualKeyCode::Left => input_model.set_left(0.),
+                        glutin::VirtualKeyCode::Right => input_model.set_right(0.),
+                        glutin::VirtualKeyCode::Up => input_model.set_up(0.),
+                        glutin::VirtualKeyCode::Down => input_model.set_down(0.),
+                        _ => (),
+                    },
+                }
+            }
+        }
+        _ => (),
+    } });
+
+    external_event
+}
+
+pub fn main() {
+    let width = 960;
+    let height = 640;
+    let GlutinWindow {
+        window,
+        mut device,
+        mut factory,
+        render_target_view,
+        mut events_loop,
+        mut encoder,
+        ..
+    } = GlutinWindow::new(width, height);
+
+    let mut renderer =
+        Renderer::new(render_target_view.clone(), &mut factory, &mut encoder);
+
+    let mut game_state =
+        GameState::new(vec2(width as f32, height as f32), Default::default());
+    game_state.init_demo().expect("Failed to spawn the demo");
+
+    let mut input_model = InputModel::default();
+
+    loop {
+        encoder.clear(&render_target_view, [0.0, 0.0, 0.0, 1.0]);
+        match process_input(&mut events_loop, &mut input_model) {
+            Some(ExternalEvent::Quit) => break,
+            Some(ExternalEvent::Reset) => (),
+            Some(ExternalEvent::ToggleGizmos) => {
+                let enabled = game_state.gizmos_enabled();
+                game_state.set_gizmos_enabled(!enabled);
+            }
+            None => (),
+        }
+        game_state.update(&input_model);
+        let view = game_state.camera().view();
+        {
+            let mut frame = renderer.prepare_frame(&mut factory);
+            let mut updater = frame.updater();
+
+            render::render_frame(
+                &mut updater,
+                &view,
+                game_state.render_updates(),
+            );
+            render::render_gizmos(
+                &mut updater,
+                &view,
+                game_state.gizmos(),
+                GIZMO_VELOCITY_SCALE,
+            );
+        }
+        renderer.encode(&mut encoder);
+        encoder.flush(&mut device);
+        window.swap_buffers().expect("Failed to swap buffers");
+        device.cleanup();
+    }
+}
+
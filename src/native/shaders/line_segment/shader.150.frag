@@ -1,8 +1,8 @@
 #version 150 core
 
-in vec3 v_Colour;
+in vec4 v_Colour;
 out vec4 Target;
 
 void main() {
-    Target = vec4(v_Colour, 1);
+    Target = v_Colour;
 }
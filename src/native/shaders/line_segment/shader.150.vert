@@ -3,13 +3,13 @@
 in vec2 a_CornerZeroToOne;
 in vec2 i_Start;
 in vec2 i_End;
-in vec3 i_Colour;
+in vec4 i_Colour;
 
 uniform WindowProperties {
     vec2 u_WindowSizeInPixels;
 };
 
-out vec3 v_Colour;
+out vec4 v_Colour;
 
 const float WIDTH = 2;
 
@@ -0,0 +1,95 @@
+use aabb::Aabb;
+use cgmath::{Vector2, vec2};
+use convex_polygon::ConvexPolygon;
+use line_segment::LineSegment;
+use physics_num::PhysicsNum;
+use shape::Collide;
+use trig::Angle;
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct OrientedRect<N: PhysicsNum> {
+    half_extents: Vector2<N>,
+    angle: Angle,
+    polygon: ConvexPolygon<N>,
+}
+
+impl<N: PhysicsNum> OrientedRect<N> {
+    pub fn new(half_extents: Vector2<N>, angle: Angle) -> Self {
+        let corner = |x: N, y: N| angle.rotate(vec2(x, y));
+        let (x, y) = (half_extents.x, half_extents.y);
+        let polygon = ConvexPolygon::new(vec![
+            corner(-x, -y),
+            corner(x, -y),
+            corner(x, y),
+            corner(-x, y),
+        ]);
+        Self {
+            half_extents,
+            angle,
+            polygon,
+        }
+    }
+    pub fn half_extents(&self) -> Vector2<N> {
+        self.half_extents
+    }
+    pub fn angle(&self) -> Angle {
+        self.angle
+    }
+    pub fn polygon(&self) -> &ConvexPolygon<N> {
+        &self.polygon
+    }
+    pub fn vertices(&self) -> &[Vector2<N>] {
+        self.polygon.vertices()
+    }
+}
+
+impl<N: PhysicsNum> Collide<N> for OrientedRect<N> {
+    fn aabb(&self, top_left: Vector2<N>) -> Aabb<N> {
+        self.polygon.aabb(top_left)
+    }
+    fn for_each_edge_facing<F>(&self, direction: Vector2<N>, f: F)
+    where
+        F: FnMut(LineSegment<N>),
+    {
+        self.polygon.for_each_edge_facing(direction, f)
+    }
+    fn for_each_vertex_facing<F>(&self, direction: Vector2<N>, f: F)
+    where
+        F: FnMut(Vector2<N>),
+    {
+        self.polygon.for_each_vertex_facing(direction, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axis_aligned_rect::AxisAlignedRect;
+    use shape::StartInsideEdgePolicy;
+
+    #[test]
+    fn unrotated_matches_rect() {
+        let oriented = OrientedRect::new(vec2(4, 2), Angle::ZERO);
+        let aabb = oriented.aabb(vec2(10, 10));
+        assert_eq!(aabb.top_left(), vec2(6, 8));
+        assert_eq!(aabb.size(), vec2(8, 4));
+    }
+
+    #[test]
+    fn rect_lands_on_tilted_platform() {
+        let platform = OrientedRect::new(vec2(1000, 10), Angle::from_degrees(45));
+        let rect = AxisAlignedRect::new(vec2(10, 10));
+        let collision = rect
+            .movement_collision_test(
+                vec2(0, -200),
+                &platform,
+                vec2(0, 0),
+                vec2(0, 400),
+                StartInsideEdgePolicy::BlockAll,
+            )
+            .unwrap();
+        let edge = collision.line_segment.vector();
+        assert!(edge.x != 0 && edge.y != 0);
+        assert!(collision.allowed_movement.y > 0 && collision.allowed_movement.y < 200);
+    }
+}
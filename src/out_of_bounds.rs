@@ -0,0 +1,61 @@
+use aabb::Aabb;
+use cgmath::{Vector2, vec2};
+use game::EntityId;
+use physics_num::PhysicsNum;
+use pixel_num::sub_pixel_i64::SubPixelI64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutOfBoundsPolicy {
+    Despawn,
+    Clamp,
+    Wrap,
+    EventOnly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutOfBoundsEvent {
+    pub entity_id: EntityId,
+    pub position: Vector2<SubPixelI64>,
+    pub policy: OutOfBoundsPolicy,
+}
+
+pub fn clamp_offset<N: PhysicsNum>(rect: &Aabb<N>, aabb: &Aabb<N>) -> Vector2<N> {
+    let clamp_axis = |min: N, max: N, value: N, size: N| {
+        if value + size > max {
+            (max - size).max(min) - value
+        } else if value < min {
+            min - value
+        } else {
+            N::zero()
+        }
+    };
+    let min = rect.top_left();
+    let max = rect.bottom_right_coord();
+    let top_left = aabb.top_left();
+    let size = aabb.size();
+    vec2(
+        clamp_axis(min.x, max.x, top_left.x, size.x),
+        clamp_axis(min.y, max.y, top_left.y, size.y),
+    )
+}
+
+pub fn wrap_offset<N: PhysicsNum>(rect: &Aabb<N>, aabb: &Aabb<N>) -> Vector2<N> {
+    let centre = aabb.centre();
+    rect.wrap_point(centre) - centre
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn offsets() {
+        let rect = Aabb::new(vec2(0, 0), vec2(100, 100));
+        let aabb = |x, y, width| Aabb::new(vec2(x, y), vec2(width, 10));
+        assert_eq!(clamp_offset(&rect, &aabb(95, -3, 10)), vec2(-5, 3));
+        assert_eq!(clamp_offset(&rect, &aabb(10, 10, 10)), vec2(0, 0));
+        assert_eq!(clamp_offset(&rect, &aabb(-5, 0, 120)), vec2(5, 0));
+        assert_eq!(wrap_offset(&rect, &aabb(96, 10, 10)), vec2(-100, 0));
+        assert_eq!(wrap_offset(&rect, &aabb(90, 10, 10)), vec2(0, 0));
+    }
+}
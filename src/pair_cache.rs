@@ -0,0 +1,125 @@
+use fnv::FnvHashMap;
+use aabb::Aabb;
+use game::EntityId;
+use broadphase::Broadphase;
+use pixel_num::SubPixelI64;
+use stats::UpdateStats;
+
+#[derive(Clone)]
+struct Entry {
+    query_aabb: Aabb<SubPixelI64>,
+    candidates: Vec<EntityId>,
+}
+
+#[derive(Clone, Default)]
+pub struct PairCache {
+    entries: FnvHashMap<EntityId, Entry>,
+    spare: Vec<Vec<EntityId>>,
+}
+
+impl PairCache {
+    pub fn clear(&mut self) {
+        self.spare
+            .extend(self.entries.drain().map(|(_, entry)| entry.candidates));
+    }
+    pub fn remove(&mut self, id: EntityId) {
+        if let Some(entry) = self.entries.remove(&id) {
+            self.spare.push(entry.candidates);
+        }
+    }
+    pub fn candidates<T, F>(
+        &mut self,
+        id: EntityId,
+        aabb: &Aabb<SubPixelI64>,
+        margin: SubPixelI64,
+        broadphase: &Broadphase<T, SubPixelI64>,
+        entity_id: F,
+        stats: &mut UpdateStats,
+    ) -> &[EntityId]
+    where
+        F: Fn(&T) -> EntityId,
+    {
+        let stale = self.entries
+            .get(&id)
+            .map(|entry| !entry.query_aabb.contains(aabb))
+            .unwrap_or(true);
+        if stale {
+            let query_aabb = aabb.expand(margin);
+            let spare = &mut self.spare;
+            let mut candidates = self.entries
+                .remove(&id)
+                .map(|entry| entry.candidates)
+                .or_else(|| spare.pop())
+                .unwrap_or_default();
+            candidates.clear();
+            stats.broadphase_visits +=
+                broadphase.for_each_intersection(&query_aabb, |_aabb, t| {
+                    let other_id = entity_id(t);
+                    if other_id != id {
+                        candidates.push(other_id);
+                    }
+                });
+            self.entries.insert(
+                id,
+                Entry {
+                    query_aabb,
+                    candidates,
+                },
+            );
+        }
+        &self.entries[&id].candidates
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use broadphase::BroadphaseKind;
+    use cgmath::vec2;
+
+    fn square(x: i64, y: i64) -> Aabb<SubPixelI64> {
+        let size = SubPixelI64::new_pixels(8);
+        Aabb::new(
+            vec2(SubPixelI64::new_pixels(x), SubPixelI64::new_pixels(y)),
+            vec2(size, size),
+        )
+    }
+
+    #[test]
+    fn candidates_are_reused_until_the_query_leaves_its_margin() {
+        let bounds = square(0, 0).expand(SubPixelI64::new_pixels(256));
+        let mut broadphase = Broadphase::new(
+            BroadphaseKind::SweepAndPrune,
+            &bounds,
+            SubPixelI64::new_pixels(64),
+            Default::default(),
+        );
+        broadphase.insert(square(0, 0), 1);
+        broadphase.insert(square(12, 0), 2);
+        broadphase.insert(square(64, 0), 3);
+        let margin = SubPixelI64::new_pixels(8);
+        let mut pair_cache = PairCache::default();
+        let mut stats = UpdateStats::default();
+        let candidates = pair_cache
+            .candidates(1, &square(0, 0), margin, &broadphase, |&id| id, &mut stats)
+            .to_vec();
+        assert_eq!(candidates, vec![2]);
+        let visits = stats.broadphase_visits;
+        assert!(visits > 0);
+        let nudged = square(2, 2);
+        let candidates = pair_cache
+            .candidates(1, &nudged, margin, &broadphase, |&id| id, &mut stats)
+            .to_vec();
+        assert_eq!(candidates, vec![2]);
+        assert_eq!(stats.broadphase_visits, visits);
+        let far = square(52, 0);
+        let candidates = pair_cache
+            .candidates(1, &far, margin, &broadphase, |&id| id, &mut stats)
+            .to_vec();
+        assert_eq!(candidates, vec![3]);
+        let visits = stats.broadphase_visits;
+        pair_cache.remove(1);
+        pair_cache.candidates(1, &far, margin, &broadphase, |&id| id, &mut stats);
+        assert!(stats.broadphase_visits > visits);
+    }
+}
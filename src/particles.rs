@@ -0,0 +1,159 @@
+use cgmath::Vector2;
+use colour_tween;
+use line_segment::LineSegment;
+use pixel_num::sub_pixel_i64::{self, SubPixelI64};
+use std::hash::{Hash, Hasher};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Particle {
+    pub position: Vector2<SubPixelI64>,
+    pub velocity: Vector2<SubPixelI64>,
+    pub remaining: u32,
+    pub colour: [f32; 4],
+}
+
+impl Hash for Particle {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.position.hash(state);
+        self.velocity.hash(state);
+        self.remaining.hash(state);
+        colour_tween::hash_colour(&self.colour, state);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    gravity: Vector2<SubPixelI64>,
+    collides: bool,
+    max_particles: usize,
+}
+
+impl ParticleSystem {
+    pub fn new(gravity: Vector2<SubPixelI64>, max_particles: usize) -> Self {
+        Self {
+            particles: Vec::new(),
+            gravity,
+            collides: true,
+            max_particles,
+        }
+    }
+    pub fn set_gravity(&mut self, gravity: Vector2<SubPixelI64>) {
+        self.gravity = gravity;
+    }
+    pub fn set_collides(&mut self, collides: bool) {
+        self.collides = collides;
+    }
+    pub fn collides(&self) -> bool {
+        self.collides
+    }
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+    pub fn spawn(
+        &mut self,
+        position: Vector2<SubPixelI64>,
+        velocity: Vector2<SubPixelI64>,
+        lifetime: u32,
+        colour: [f32; 4],
+    ) -> bool {
+        if lifetime == 0 || self.particles.len() >= self.max_particles {
+            return false;
+        }
+        self.particles.push(Particle {
+            position,
+            velocity,
+            remaining: lifetime,
+            colour,
+        });
+        true
+    }
+    pub fn step<F>(&mut self, mut sweep: F)
+    where
+        F: FnMut(Vector2<SubPixelI64>, Vector2<SubPixelI64>)
+            -> Option<(Vector2<SubPixelI64>, LineSegment<SubPixelI64>)>,
+    {
+        let gravity = self.gravity;
+        let collides = self.collides;
+        for particle in self.particles.iter_mut() {
+            particle.remaining -= 1;
+            particle.velocity += gravity;
+            let movement = particle.velocity;
+            if sub_pixel_i64::vector_is_zero(movement) {
+                continue;
+            }
+            let hit = if collides {
+                sweep(particle.position, movement)
+            } else {
+                None
+            };
+            match hit {
+                Some((stop, surface)) => {
+                    particle.position = stop;
+                    particle.velocity = sub_pixel_i64::slide_movement(
+                        particle.velocity,
+                        surface.vector(),
+                        SubPixelI64::new(0),
+                    );
+                }
+                None => particle.position += movement,
+            }
+        }
+        self.particles.retain(|particle| particle.remaining > 0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cgmath::vec2;
+
+    fn v(x: i64, y: i64) -> Vector2<SubPixelI64> {
+        vec2(SubPixelI64::new(x), SubPixelI64::new(y))
+    }
+
+    #[test]
+    fn particles_fall_and_expire() {
+        let mut system = ParticleSystem::new(v(0, 2), 2);
+        assert!(system.spawn(v(0, 0), v(10, 0), 3, [1.; 4]));
+        assert!(system.spawn(v(0, 0), v(0, 0), 1, [1.; 4]));
+        assert!(!system.spawn(v(0, 0), v(0, 0), 5, [1.; 4]));
+        system.step(|_, _| None);
+        assert_eq!(system.len(), 1);
+        assert_eq!(system.particles()[0].position, v(10, 2));
+        system.step(|_, _| None);
+        assert_eq!(system.particles()[0].position, v(20, 6));
+        system.step(|_, _| None);
+        assert!(system.is_empty());
+    }
+
+    #[test]
+    fn colliding_particles_slide_along_surface() {
+        let mut system = ParticleSystem::new(v(0, 4), 16);
+        system.spawn(v(0, 0), v(8, 20), 10, [1.; 4]);
+        let floor = LineSegment::new(v(-100, 10), v(100, 10));
+        let sweep = |start: Vector2<SubPixelI64>, movement: Vector2<SubPixelI64>| {
+            let end = start + movement;
+            if end.y >= SubPixelI64::new(10) {
+                Some((vec2(end.x, SubPixelI64::new(9)), floor))
+            } else {
+                None
+            }
+        };
+        system.step(sweep);
+        assert_eq!(system.particles()[0].position, v(8, 9));
+        assert_eq!(system.particles()[0].velocity.y, SubPixelI64::new(0));
+        system.set_collides(false);
+        system.step(sweep);
+        assert_eq!(system.particles()[0].position, v(16, 13));
+    }
+}
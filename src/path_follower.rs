@@ -0,0 +1,135 @@
+use cgmath::{Vector2, vec2};
+use num::Zero;
+use physics_num;
+use pixel_num::SubPixelI64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PathMode {
+    Once,
+    Loop,
+    PingPong,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PathFollower {
+    waypoints: Vec<Vector2<SubPixelI64>>,
+    speed: SubPixelI64,
+    mode: PathMode,
+    target: usize,
+    forward: bool,
+}
+
+impl PathFollower {
+    pub fn new(
+        waypoints: Vec<Vector2<SubPixelI64>>,
+        speed: SubPixelI64,
+        mode: PathMode,
+    ) -> Self {
+        assert!(!waypoints.is_empty(), "path must have at least one waypoint");
+        assert!(speed > Zero::zero(), "path speed must be positive");
+        Self {
+            waypoints,
+            speed,
+            mode,
+            target: 0,
+            forward: true,
+        }
+    }
+    pub fn waypoints(&self) -> &[Vector2<SubPixelI64>] {
+        &self.waypoints
+    }
+    pub fn speed(&self) -> SubPixelI64 {
+        self.speed
+    }
+    pub fn set_speed(&mut self, speed: SubPixelI64) {
+        assert!(speed > Zero::zero(), "path speed must be positive");
+        self.speed = speed;
+    }
+    pub fn mode(&self) -> PathMode {
+        self.mode
+    }
+    pub fn target(&self) -> usize {
+        self.target
+    }
+    fn advance(&mut self) -> bool {
+        let last = self.waypoints.len() - 1;
+        if last == 0 {
+            return false;
+        }
+        match self.mode {
+            PathMode::Once => {
+                if self.target == last {
+                    return false;
+                }
+                self.target += 1;
+            }
+            PathMode::Loop => self.target = (self.target + 1) % self.waypoints.len(),
+            PathMode::PingPong => {
+                if self.target == last {
+                    self.forward = false;
+                } else if self.target == 0 {
+                    self.forward = true;
+                }
+                if self.forward {
+                    self.target += 1;
+                } else {
+                    self.target -= 1;
+                }
+            }
+        }
+        true
+    }
+    pub fn velocity(&mut self, position: Vector2<SubPixelI64>) -> Vector2<SubPixelI64> {
+        let mut offset = self.waypoints[self.target] - position;
+        while offset.x.is_zero() && offset.y.is_zero() {
+            if !self.advance() {
+                return vec2(Zero::zero(), Zero::zero());
+            }
+            offset = self.waypoints[self.target] - position;
+        }
+        let speed = physics_num::widen(self.speed);
+        if physics_num::magnitude2_wide(offset) <= speed * speed {
+            offset
+        } else {
+            physics_num::normalize_to_length(offset, self.speed)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(x: i64, y: i64) -> Vector2<SubPixelI64> {
+        vec2(SubPixelI64::new(x), SubPixelI64::new(y))
+    }
+
+    fn run(follower: &mut PathFollower, updates: usize) -> Vec<Vector2<SubPixelI64>> {
+        let mut position = v(0, 0);
+        (0..updates)
+            .map(|_| {
+                position = position + follower.velocity(position);
+                position
+            })
+            .collect()
+    }
+
+    #[test]
+    fn modes_visit_waypoints_exactly() {
+        let waypoints = vec![v(0, 0), v(10, 0), v(10, 10)];
+        let speed = SubPixelI64::new(6);
+        let mut once = PathFollower::new(waypoints.clone(), speed, PathMode::Once);
+        assert_eq!(
+            run(&mut once, 5),
+            vec![v(6, 0), v(10, 0), v(10, 6), v(10, 10), v(10, 10)]
+        );
+        let speed = SubPixelI64::new(10);
+        let mut looped = PathFollower::new(waypoints.clone(), speed, PathMode::Loop);
+        assert_eq!(run(&mut looped, 4), vec![v(10, 0), v(10, 10), v(3, 3), v(0, 0)]);
+        let mut ping_pong = PathFollower::new(waypoints, speed, PathMode::PingPong);
+        assert_eq!(
+            run(&mut ping_pong, 5),
+            vec![v(10, 0), v(10, 10), v(10, 0), v(0, 0), v(10, 0)]
+        );
+    }
+}
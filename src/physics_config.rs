@@ -0,0 +1,84 @@
+use cgmath::{Vector2, vec2};
+use pixel_num::sub_pixel_i64::{self, SubPixelI64};
+use shape::StartInsideEdgePolicy;
+use broadphase::BroadphaseKind;
+use loose_quad_tree::QuadTreeParams;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum MovementMode {
+    #[default]
+    Slide,
+    AxisSeparated,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CollisionResponse {
+    #[default]
+    Inelastic,
+    Elastic,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NarrowphaseRounding {
+    #[default]
+    RoundTowardStart,
+    ExactFloor,
+    ExactWithSkin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhysicsConfig {
+    pub max_slide_iterations: usize,
+    pub default_skin: SubPixelI64,
+    pub speculative_contacts: bool,
+    pub player_velocity_multiplier: i64,
+    pub start_inside_edge_policy: StartInsideEdgePolicy,
+    pub movement_mode: MovementMode,
+    pub collision_response: CollisionResponse,
+    pub max_push_chain: usize,
+    pub gravity: Vector2<SubPixelI64>,
+    pub resting_contacts: bool,
+    pub max_particles: usize,
+    pub broadphase: BroadphaseKind,
+    pub broadphase_margin: SubPixelI64,
+    pub auto_grow_world: bool,
+    pub chunk_size: SubPixelI64,
+    pub quad_tree: QuadTreeParams,
+    pub wrap_world: bool,
+    pub sleep_velocity_threshold: SubPixelI64,
+    pub sleep_after_updates: u32,
+    /// Overlap allowed by `World::validate`. With the `validation` feature,
+    /// setting it also validates the world after every update.
+    pub validation_tolerance: Option<SubPixelI64>,
+    pub impact_threshold: SubPixelI64,
+    pub narrowphase_rounding: NarrowphaseRounding,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        Self {
+            max_slide_iterations: 16,
+            default_skin: SubPixelI64::new(sub_pixel_i64::SUB_PIXELS_PER_PIXEL / 10),
+            speculative_contacts: true,
+            player_velocity_multiplier: 4,
+            start_inside_edge_policy: Default::default(),
+            movement_mode: Default::default(),
+            collision_response: Default::default(),
+            max_push_chain: 4,
+            gravity: vec2(SubPixelI64::new(0), SubPixelI64::new(0)),
+            resting_contacts: true,
+            max_particles: 4096,
+            broadphase: Default::default(),
+            broadphase_margin: SubPixelI64::new(8 * sub_pixel_i64::SUB_PIXELS_PER_PIXEL),
+            auto_grow_world: true,
+            chunk_size: SubPixelI64::new(256 * sub_pixel_i64::SUB_PIXELS_PER_PIXEL),
+            quad_tree: Default::default(),
+            wrap_world: false,
+            sleep_velocity_threshold: SubPixelI64::new(2),
+            sleep_after_updates: 60,
+            validation_tolerance: None,
+            impact_threshold: SubPixelI64::new(sub_pixel_i64::SUB_PIXELS_PER_PIXEL / 2),
+            narrowphase_rounding: Default::default(),
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use cgmath::{BaseNum, Vector2};
-use num::{One, Signed, Zero};
+use cgmath::vec2;
+use num::{NumCast, One, Signed, Zero};
+use error::{PhysicsError, PhysicsResult};
 
-pub trait PhysicsNum: BaseNum + ::std::ops::Neg<Output = Self> + Signed + Ord {
+pub trait PhysicsNum: BaseNum + ::core::ops::Neg<Output = Self> + Signed + Ord {
     fn two() -> Self {
         two()
     }
@@ -23,6 +25,111 @@ pub fn magnitude2<N: PhysicsNum>(v: Vector2<N>) -> N {
     v.x * v.x + v.y * v.y
 }
 
+/// Widens to i128, saturating values that don't fit in i64.
+pub fn widen<N: PhysicsNum>(v: N) -> i128 {
+    let saturated = if v < Zero::zero() { i64::MIN } else { i64::MAX };
+    v.to_i64().unwrap_or(saturated) as i128
+}
+
+pub fn checked_narrow<N: PhysicsNum>(v: i128) -> Option<N> {
+    if v < i64::MIN as i128 || v > i64::MAX as i128 {
+        return None;
+    }
+    <N as NumCast>::from(v as i64)
+}
+
+/// Narrows to `N`, clamping to the i64 range instead of panicking.
+pub fn saturating_narrow<N: PhysicsNum>(v: i128) -> N {
+    let clamped = v.max(i64::MIN as i128).min(i64::MAX as i128) as i64;
+    <N as NumCast>::from(clamped).expect("i64 does not fit in physics number")
+}
+
+pub fn checked_magnitude2_wide<N: PhysicsNum>(v: Vector2<N>) -> Option<i128> {
+    let x = widen(v.x);
+    let y = widen(v.y);
+    (x * x).checked_add(y * y)
+}
+
+/// Saturates at `i128::MAX`, which only happens when both components are
+/// `i64::MIN`.
+pub fn magnitude2_wide<N: PhysicsNum>(v: Vector2<N>) -> i128 {
+    checked_magnitude2_wide(v).unwrap_or(i128::MAX)
+}
+
+pub fn isqrt_wide(v: i128) -> i128 {
+    assert!(v >= 0, "square root of negative number");
+    if v < 2 {
+        return v;
+    }
+    let mut x = 1i128 << (128 - v.leading_zeros()).div_ceil(2);
+    loop {
+        let y = (x + v / x) / 2;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+pub fn isqrt<N: PhysicsNum>(v: N) -> N {
+    saturating_narrow(isqrt_wide(widen(v)))
+}
+
+fn magnitude_wide<N: PhysicsNum>(v: Vector2<N>) -> i128 {
+    match checked_magnitude2_wide(v) {
+        Some(magnitude2) => isqrt_wide(magnitude2),
+        None => isqrt_wide((widen(v.x) / 2).pow(2) + (widen(v.y) / 2).pow(2)) * 2,
+    }
+}
+
+pub fn checked_magnitude<N: PhysicsNum>(v: Vector2<N>) -> PhysicsResult<N> {
+    checked_narrow(magnitude_wide(v)).ok_or(PhysicsError::Overflow)
+}
+
+/// Saturates at the largest representable length.
+pub fn magnitude<N: PhysicsNum>(v: Vector2<N>) -> N {
+    saturating_narrow(magnitude_wide(v))
+}
+
+pub fn checked_normalize_to_length<N: PhysicsNum>(
+    v: Vector2<N>,
+    length: N,
+) -> PhysicsResult<Vector2<N>> {
+    let magnitude = magnitude_wide(v);
+    if magnitude == 0 {
+        return Ok(vec2(Zero::zero(), Zero::zero()));
+    }
+    let length = widen(length);
+    let x = (widen(v.x) * length).checked_div(magnitude);
+    let y = (widen(v.y) * length).checked_div(magnitude);
+    match (x.and_then(checked_narrow), y.and_then(checked_narrow)) {
+        (Some(x), Some(y)) => Ok(vec2(x, y)),
+        _ => Err(PhysicsError::Overflow),
+    }
+}
+
+/// Saturates each component if the scaled vector does not fit.
+pub fn normalize_to_length<N: PhysicsNum>(v: Vector2<N>, length: N) -> Vector2<N> {
+    let magnitude = magnitude_wide(v);
+    if magnitude == 0 {
+        return vec2(Zero::zero(), Zero::zero());
+    }
+    let length = widen(length);
+    vec2(
+        saturating_narrow(widen(v.x) * length / magnitude),
+        saturating_narrow(widen(v.y) * length / magnitude),
+    )
+}
+
+pub fn clamp_length<N: PhysicsNum>(v: Vector2<N>, max_length: N) -> Vector2<N> {
+    let max_length_wide = widen(max_length);
+    if magnitude2_wide(v) > max_length_wide * max_length_wide {
+        normalize_to_length(v, max_length)
+    } else {
+        v
+    }
+}
+
 pub fn dot<N: PhysicsNum>(v: Vector2<N>, w: Vector2<N>) -> N {
     v.x * w.x + v.y * w.y
 }
@@ -34,3 +141,59 @@ fn clamp<N: PhysicsNum>(v: N, min: N, max: N) -> N {
 fn clamp_zero_one<N: PhysicsNum>(v: N) -> N {
     clamp(v, Zero::zero(), One::one())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checked_narrow_rejects_out_of_range() {
+        let max = i64::MAX as i128;
+        assert_eq!(checked_narrow::<i64>(max), Some(i64::MAX));
+        assert_eq!(checked_narrow::<i64>(max + 1), None);
+        assert_eq!(checked_narrow::<i64>(-max - 2), None);
+    }
+
+    #[test]
+    fn isqrt_floors() {
+        assert_eq!(isqrt_wide(0), 0);
+        assert_eq!(isqrt_wide(1), 1);
+        assert_eq!(isqrt_wide(15), 3);
+        assert_eq!(isqrt_wide(16), 4);
+        assert_eq!(isqrt_wide(17), 4);
+        let max = i64::MAX as i128;
+        let root = isqrt_wide(max * max);
+        assert_eq!(root, max);
+        assert_eq!(isqrt_wide(max * max - 1), max - 1);
+    }
+
+    #[test]
+    fn extreme_components_do_not_overflow() {
+        let corner = vec2(i64::MIN, i64::MIN);
+        assert_eq!(checked_magnitude2_wide(corner), None);
+        assert_eq!(magnitude2_wide(corner), i128::MAX);
+        assert_eq!(checked_magnitude(corner), Err(PhysicsError::Overflow));
+        assert_eq!(magnitude(corner), i64::MAX);
+        assert_eq!(checked_magnitude(vec2(i64::MIN, 0)), Err(PhysicsError::Overflow));
+        assert_eq!(checked_magnitude(vec2(i64::MAX, 0)), Ok(i64::MAX));
+        assert_eq!(
+            checked_normalize_to_length(vec2(-1i64, 0), i64::MIN),
+            Err(PhysicsError::Overflow)
+        );
+        assert_eq!(normalize_to_length(vec2(-1i64, 0), i64::MIN), vec2(i64::MAX, 0));
+        let unit = normalize_to_length(corner, 1 << 32);
+        assert_eq!(unit, vec2(-3037000499, -3037000499));
+        assert_eq!(checked_normalize_to_length(corner, 1 << 32), Ok(unit));
+        assert_eq!(saturating_narrow::<i64>(i128::MAX), i64::MAX);
+        assert_eq!(saturating_narrow::<i64>(i128::MIN), i64::MIN);
+    }
+
+    #[test]
+    fn normalize() {
+        assert_eq!(magnitude(vec2(3i64, 4)), 5);
+        assert_eq!(normalize_to_length(vec2(30i64, -40), 10), vec2(6, -8));
+        assert_eq!(normalize_to_length(vec2(0i64, 0), 10), vec2(0, 0));
+        assert_eq!(clamp_length(vec2(3i64, 4), 10), vec2(3, 4));
+        assert_eq!(clamp_length(vec2(300i64, 400), 10), vec2(6, 8));
+    }
+}
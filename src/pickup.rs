@@ -0,0 +1,70 @@
+use cgmath::{Vector2, vec2};
+use game::EntityId;
+use num::Zero;
+use physics_num;
+use pixel_num::SubPixelI64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Pickup {
+    pub payload: u32,
+    pub magnet_radius: SubPixelI64,
+    pub magnet_acceleration: SubPixelI64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PickupEvent {
+    pub pickup_id: EntityId,
+    pub collector_id: EntityId,
+    pub payload: u32,
+}
+
+impl Pickup {
+    pub fn new(payload: u32) -> Self {
+        Self {
+            payload,
+            magnet_radius: Zero::zero(),
+            magnet_acceleration: Zero::zero(),
+        }
+    }
+    pub fn with_magnet(mut self, radius: SubPixelI64, acceleration: SubPixelI64) -> Self {
+        self.magnet_radius = radius;
+        self.magnet_acceleration = acceleration;
+        self
+    }
+    pub fn magnet_pull(
+        &self,
+        offset: Vector2<SubPixelI64>,
+    ) -> Option<Vector2<SubPixelI64>> {
+        let zero = Zero::zero();
+        if self.magnet_radius <= zero || self.magnet_acceleration <= zero {
+            return None;
+        }
+        let radius = physics_num::widen(self.magnet_radius);
+        if physics_num::magnitude2_wide(offset) > radius * radius {
+            return None;
+        }
+        if offset.x.is_zero() && offset.y.is_zero() {
+            return Some(vec2(Zero::zero(), Zero::zero()));
+        }
+        Some(physics_num::normalize_to_length(offset, self.magnet_acceleration))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(x: i64, y: i64) -> Vector2<SubPixelI64> {
+        vec2(SubPixelI64::new(x), SubPixelI64::new(y))
+    }
+
+    #[test]
+    fn magnet_pulls_within_radius() {
+        let coin = Pickup::new(7);
+        assert_eq!(coin.magnet_pull(v(30, 40)), None);
+        let coin = coin.with_magnet(SubPixelI64::new(100), SubPixelI64::new(10));
+        assert_eq!(coin.magnet_pull(v(30, 40)), Some(v(6, 8)));
+        assert_eq!(coin.magnet_pull(v(-60, -80)), Some(v(-6, -8)));
+        assert_eq!(coin.magnet_pull(v(70, 80)), None);
+    }
+}
@@ -1,36 +1,25 @@
-macro_rules! make_i64_wrapper {
-    ($name:ident) => {
-
-        use num;
-
-        custom_derive! {
-            #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default,
-                     NewtypeFrom, NewtypeAdd, NewtypeMul(i64), NewtypeMul,
-                     NewtypeSub,  NewtypeRem, NewtypeDiv, NewtypeNeg,
-                     NewtypeAddAssign, NewtypeSubAssign, NewtypeMulAssign,
-                     NewtypeDivAssign, NewtypeRemAssign)]
-            pub struct $name(i64);
-        }
+macro_rules! impl_i64_wrapper {
+    ($ctor:expr, $ty:ty, $($generics:tt)*) => {
 
-        impl num::Zero for $name {
+        impl<$($generics)*> num::Zero for $ty {
             fn zero() -> Self {
-                $name(0)
+                $ctor(0)
             }
             fn is_zero(&self) -> bool {
                 self.0.is_zero()
             }
         }
 
-        impl num::One for $name {
+        impl<$($generics)*> num::One for $ty {
             fn one() -> Self {
-                $name(1)
+                $ctor(1)
             }
             fn is_one(&self) -> bool {
                 self.0.is_one()
             }
         }
 
-        impl num::ToPrimitive for $name {
+        impl<$($generics)*> num::ToPrimitive for $ty {
             fn to_i64(&self) -> Option<i64> {
                 self.0.to_i64()
             }
@@ -39,31 +28,31 @@ macro_rules! make_i64_wrapper {
             }
         }
 
-        impl num::NumCast for $name {
+        impl<$($generics)*> num::NumCast for $ty {
             fn from<T>(n: T) -> Option<Self>
             where
                 T: num::ToPrimitive,
             {
-                n.to_i64().map($name)
+                n.to_i64().map($ctor)
             }
         }
 
-        impl num::Num for $name {
+        impl<$($generics)*> num::Num for $ty {
             type FromStrRadixErr = <i64 as num::Num>::FromStrRadixErr;
             fn from_str_radix(str: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
-                <i64 as num::Num>::from_str_radix(str, radix).map($name)
+                <i64 as num::Num>::from_str_radix(str, radix).map($ctor)
             }
         }
 
-        impl num::Signed for $name {
+        impl<$($generics)*> num::Signed for $ty {
             fn abs(&self) -> Self {
-                $name(self.0.abs())
+                $ctor(self.0.abs())
             }
             fn abs_sub(&self, other: &Self) -> Self {
-                $name(self.0.abs_sub(&other.0))
+                $ctor(self.0.abs_sub(&other.0))
             }
             fn signum(&self) -> Self {
-                $name(self.0.signum())
+                $ctor(self.0.signum())
             }
             fn is_positive(&self) -> bool {
                 self.0.is_positive()
@@ -73,74 +62,328 @@ macro_rules! make_i64_wrapper {
             }
         }
 
-        impl PhysicsNum for $name {}
+        impl<$($generics)*> PhysicsNum for $ty {}
 
-        impl $name {
+        impl<$($generics)*> $ty {
             pub fn new(value: i64) -> Self {
-                $name(value)
+                $ctor(value)
+            }
+            pub fn raw(self) -> i64 {
+                self.0
+            }
+            pub fn checked_add(self, other: Self) -> Option<Self> {
+                self.0.checked_add(other.0).map($ctor)
+            }
+            pub fn checked_sub(self, other: Self) -> Option<Self> {
+                self.0.checked_sub(other.0).map($ctor)
+            }
+            pub fn checked_mul(self, other: i64) -> Option<Self> {
+                self.0.checked_mul(other).map($ctor)
+            }
+            pub fn checked_div(self, other: i64) -> Option<Self> {
+                self.0.checked_div(other).map($ctor)
             }
+            pub fn saturating_add(self, other: Self) -> Self {
+                $ctor(self.0.saturating_add(other.0))
+            }
+            pub fn saturating_sub(self, other: Self) -> Self {
+                $ctor(self.0.saturating_sub(other.0))
+            }
+            pub fn saturating_mul(self, other: i64) -> Self {
+                $ctor(self.0.saturating_mul(other))
+            }
+        }
+    };
+}
+
+macro_rules! make_i64_wrapper {
+    ($name:ident) => {
+
+        use num;
+
+        custom_derive! {
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default,
+                     NewtypeFrom, NewtypeAdd, NewtypeMul(i64), NewtypeMul,
+                     NewtypeSub,  NewtypeRem, NewtypeDiv, NewtypeNeg,
+                     NewtypeAddAssign, NewtypeSubAssign, NewtypeMulAssign,
+                     NewtypeDivAssign, NewtypeRemAssign)]
+            pub struct $name(i64);
         }
+
+        impl_i64_wrapper!($name, $name,);
     };
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelRounding {
+    Floor,
+    Nearest,
+    Ceil,
+}
+
+fn floor_div(numerator: i64, denominator: i64) -> i64 {
+    let quotient = numerator / denominator;
+    if numerator % denominator != 0 && (numerator < 0) != (denominator < 0) {
+        quotient - 1
+    } else {
+        quotient
+    }
+}
+
 pub mod sub_pixel_i64 {
 
-    use num::Zero;
-    use physics_num::PhysicsNum;
+    use num::{self, Zero};
+    use physics_num::{self, PhysicsNum};
     use super::*;
     use self::pixel_i64::PixelI64;
     use cgmath::{Vector2, vec2};
-    make_i64_wrapper!(SubPixelI64);
-    pub const SUB_PIXELS_PER_PIXEL: i64 = 256;
-    const SUB_PIXELS_PER_PIXEL_SQUARED: i64 = 65536;
-    impl SubPixelI64 {
+    use core::fmt;
+    use core::marker::PhantomData;
+    use core::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Rem, RemAssign,
+                   Sub, SubAssign};
+
+    pub trait SubPixelPrecision:
+        fmt::Debug + Clone + Copy + PartialEq + Eq + PartialOrd + Ord + Default + 'static
+    {
+        const SUB_PIXELS_PER_PIXEL: i64;
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+    pub struct DefaultPrecision;
+
+    impl SubPixelPrecision for DefaultPrecision {
+        const SUB_PIXELS_PER_PIXEL: i64 = 256;
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+    pub struct SubPixel<P>(i64, PhantomData<P>);
+
+    pub type SubPixelI64 = SubPixel<DefaultPrecision>;
+
+    pub const SUB_PIXELS_PER_PIXEL: i64 = DefaultPrecision::SUB_PIXELS_PER_PIXEL;
+
+    impl_i64_wrapper!(
+        |value| SubPixel(value, PhantomData),
+        SubPixel<P>,
+        P: SubPixelPrecision
+    );
+
+    macro_rules! sub_pixel_op {
+        ($op:ident, $method:ident, $assign:ident, $assign_method:ident, $symbol:tt) => {
+            impl<P: SubPixelPrecision> $op for SubPixel<P> {
+                type Output = Self;
+                fn $method(self, other: Self) -> Self {
+                    SubPixel::new(self.0 $symbol other.0)
+                }
+            }
+
+            impl<P: SubPixelPrecision> $assign for SubPixel<P> {
+                fn $assign_method(&mut self, other: Self) {
+                    self.0 = self.0 $symbol other.0;
+                }
+            }
+        };
+    }
+
+    sub_pixel_op!(Add, add, AddAssign, add_assign, +);
+    sub_pixel_op!(Sub, sub, SubAssign, sub_assign, -);
+    sub_pixel_op!(Mul, mul, MulAssign, mul_assign, *);
+    sub_pixel_op!(Div, div, DivAssign, div_assign, /);
+    sub_pixel_op!(Rem, rem, RemAssign, rem_assign, %);
+
+    impl<P: SubPixelPrecision> Mul<i64> for SubPixel<P> {
+        type Output = Self;
+        fn mul(self, other: i64) -> Self {
+            SubPixel::new(self.0 * other)
+        }
+    }
+
+    impl<P: SubPixelPrecision> Neg for SubPixel<P> {
+        type Output = Self;
+        fn neg(self) -> Self {
+            SubPixel::new(-self.0)
+        }
+    }
+
+    impl<P: SubPixelPrecision> From<i64> for SubPixel<P> {
+        fn from(value: i64) -> Self {
+            SubPixel::new(value)
+        }
+    }
+
+    impl<P: SubPixelPrecision> From<SubPixel<P>> for i64 {
+        fn from(value: SubPixel<P>) -> i64 {
+            value.0
+        }
+    }
+
+    impl<P: SubPixelPrecision> SubPixel<P> {
+        pub fn new_pixels(pixels: i64) -> Self {
+            SubPixel::new(pixels * P::SUB_PIXELS_PER_PIXEL)
+        }
         pub fn new_pixels_f32(pixels: f32) -> Self {
-            SubPixelI64(pixels as i64 * SUB_PIXELS_PER_PIXEL)
+            SubPixel::new(pixels as i64 * P::SUB_PIXELS_PER_PIXEL)
+        }
+        #[cfg(feature = "std")]
+        pub fn new_pixels_f64(pixels: f64) -> Self {
+            SubPixel::new((pixels * P::SUB_PIXELS_PER_PIXEL as f64).round() as i64)
         }
         pub fn approx_pixel(self) -> PixelI64 {
-            PixelI64::new(self.0 / SUB_PIXELS_PER_PIXEL)
+            PixelI64::new(self.0 / P::SUB_PIXELS_PER_PIXEL)
+        }
+        pub fn to_pixels(self, rounding: PixelRounding) -> PixelI64 {
+            let per_pixel = P::SUB_PIXELS_PER_PIXEL;
+            let pixels = match rounding {
+                PixelRounding::Floor => floor_div(self.0, per_pixel),
+                PixelRounding::Nearest => floor_div(self.0 + per_pixel / 2, per_pixel),
+                PixelRounding::Ceil => -floor_div(-self.0, per_pixel),
+            };
+            PixelI64::new(pixels)
+        }
+        pub fn to_pixels_f64(self) -> f64 {
+            self.0 as f64 / P::SUB_PIXELS_PER_PIXEL as f64
         }
         pub fn clamp_zero_one_pixel(self) -> Self {
-            SubPixelI64(self.0.clamp(0, SUB_PIXELS_PER_PIXEL))
+            PhysicsNum::clamp(self, Zero::zero(), SubPixel::new_pixels(1))
         }
     }
-    pub fn normalize_vector_if_longer_than_one(
-        v: Vector2<SubPixelI64>,
-    ) -> Vector2<SubPixelI64> {
-        let x2 = v.x.0 * v.x.0;
-        let y2 = v.y.0 * v.y.0;
-        let mag2 = x2 + y2;
-        if mag2 > SUB_PIXELS_PER_PIXEL_SQUARED {
-            let mag = (mag2 as f32).sqrt();
-            let x = (v.x.0 * SUB_PIXELS_PER_PIXEL) as f32 / mag;
-            let y = (v.y.0 * SUB_PIXELS_PER_PIXEL) as f32 / mag;
-            vec2(SubPixelI64(x as i64), SubPixelI64(y as i64))
-        } else {
-            v
+    pub fn normalize_vector_if_longer_than_one<P: SubPixelPrecision>(
+        v: Vector2<SubPixel<P>>,
+    ) -> Vector2<SubPixel<P>> {
+        physics_num::clamp_length(v, SubPixel::new_pixels(1))
+    }
+    /// Projects the remaining movement onto the surface in i128, truncating each
+    /// component toward zero, then nudges the result `padding` sub-pixels away from
+    /// the surface along the direction from the remaining movement to the projection.
+    /// The padding vector is normalised with a floored integer square root and also
+    /// truncated toward zero, so the result never depends on float rounding.
+    /// Projections that don't fit in i64 saturate.
+    pub fn slide_movement<P: SubPixelPrecision>(
+        remaining_movement: Vector2<SubPixel<P>>,
+        surface: Vector2<SubPixel<P>>,
+        padding: SubPixel<P>,
+    ) -> Vector2<SubPixel<P>> {
+        let surface_x = surface.x.0 as i128;
+        let surface_y = surface.y.0 as i128;
+        let surface_len2 = surface_x * surface_x + surface_y * surface_y;
+        if surface_len2 == 0 {
+            return vec2(Zero::zero(), Zero::zero());
         }
+        let remaining_x = remaining_movement.x.0 as i128;
+        let remaining_y = remaining_movement.y.0 as i128;
+        let dot = remaining_x * surface_x + remaining_y * surface_y;
+        let project = |component: i128| match component.checked_mul(dot) {
+            Some(product) => physics_num::saturating_narrow(product / surface_len2),
+            None => physics_num::saturating_narrow(
+                (dot / surface_len2).saturating_mul(component),
+            ),
+        };
+        let slide: Vector2<SubPixel<P>> = vec2(project(surface_x), project(surface_y));
+        let padding =
+            physics_num::normalize_to_length(slide - remaining_movement, padding);
+        slide + padding
     }
-    pub fn vector_to_f32_pixel(v: Vector2<SubPixelI64>) -> Vector2<f32> {
+    pub fn vector_to_f32_pixel<P: SubPixelPrecision>(
+        v: Vector2<SubPixel<P>>,
+    ) -> Vector2<f32> {
         vec2(
-            v.x.0 as f32 / SUB_PIXELS_PER_PIXEL as f32,
-            v.y.0 as f32 / SUB_PIXELS_PER_PIXEL as f32,
+            v.x.0 as f32 / P::SUB_PIXELS_PER_PIXEL as f32,
+            v.y.0 as f32 / P::SUB_PIXELS_PER_PIXEL as f32,
         )
     }
-    pub fn vector_to_f32_sub_pixel(v: Vector2<SubPixelI64>) -> Vector2<f32> {
+    pub fn vector_to_f32_sub_pixel<P: SubPixelPrecision>(
+        v: Vector2<SubPixel<P>>,
+    ) -> Vector2<f32> {
         vec2(v.x.0 as f32, v.y.0 as f32)
     }
-    pub fn vector_from_f32_sub_pixel(v: Vector2<f32>) -> Vector2<SubPixelI64> {
-        vec2(SubPixelI64::new(v.x as i64), SubPixelI64::new(v.y as i64))
+    pub fn vector_from_f32_sub_pixel<P: SubPixelPrecision>(
+        v: Vector2<f32>,
+    ) -> Vector2<SubPixel<P>> {
+        vec2(SubPixel::new(v.x as i64), SubPixel::new(v.y as i64))
     }
-    pub fn vector_is_zero(v: Vector2<SubPixelI64>) -> bool {
+    pub fn vector_is_zero<P: SubPixelPrecision>(v: Vector2<SubPixel<P>>) -> bool {
         v.x.is_zero() && v.y.is_zero()
     }
+    impl<P: SubPixelPrecision> fmt::Display for SubPixel<P> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}px", self.to_pixels_f64())
+        }
+    }
 }
 
 pub mod pixel_i64 {
 
     use physics_num::PhysicsNum;
+    use core::fmt;
     make_i64_wrapper!(PixelI64);
+    impl fmt::Display for PixelI64 {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "{}px", self.0)
+        }
+    }
 }
 
 pub use self::sub_pixel_i64::SubPixelI64;
 pub use self::pixel_i64::PixelI64;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cgmath::vec2;
+
+    #[test]
+    fn pixel_rounding() {
+        let round = |raw, rounding| SubPixelI64::new(raw).to_pixels(rounding).raw();
+        assert_eq!(round(384, PixelRounding::Floor), 1);
+        assert_eq!(round(384, PixelRounding::Nearest), 2);
+        assert_eq!(round(384, PixelRounding::Ceil), 2);
+        assert_eq!(round(-384, PixelRounding::Floor), -2);
+        assert_eq!(round(-383, PixelRounding::Nearest), -1);
+        assert_eq!(round(-384, PixelRounding::Ceil), -1);
+        assert_eq!(round(-512, PixelRounding::Ceil), -2);
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(SubPixelI64::new_pixels_f64(1.5), SubPixelI64::new(384));
+        assert_eq!(SubPixelI64::new_pixels(-2), SubPixelI64::new(-512));
+        assert_eq!(format!("{}", SubPixelI64::new(-384)), "-1.5px");
+        let max = SubPixelI64::new(i64::MAX);
+        assert_eq!(max.checked_add(SubPixelI64::new(1)), None);
+        assert_eq!(
+            SubPixelI64::new(i64::MIN).saturating_sub(SubPixelI64::new(1)),
+            SubPixelI64::new(i64::MIN)
+        );
+    }
+
+    #[test]
+    fn slide_projection() {
+        let v = |x, y| vec2(SubPixelI64::new(x), SubPixelI64::new(y));
+        let slide = sub_pixel_i64::slide_movement;
+        assert_eq!(slide(v(10, 10), v(100, 0), SubPixelI64::new(0)), v(10, 0));
+        assert_eq!(slide(v(10, 10), v(100, 0), SubPixelI64::new(3)), v(10, -3));
+        assert_eq!(slide(v(-7, 3), v(-4, 0), SubPixelI64::new(0)), v(-7, 0));
+        assert_eq!(slide(v(0, 10), v(100, 100), SubPixelI64::new(0)), v(5, 5));
+        assert_eq!(slide(v(0, 10), v(100, 100), SubPixelI64::new(10)), v(12, -2));
+        assert_eq!(slide(v(3, 0), v(1, 3), SubPixelI64::new(0)), v(0, 0));
+        assert_eq!(slide(v(10, 10), v(0, 0), SubPixelI64::new(5)), v(0, 0));
+        let max = i64::MAX;
+        let projected = slide(v(max, max), v(2, 1), SubPixelI64::new(0));
+        assert_eq!(projected, v(max, 5_534_023_222_112_865_484));
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+    struct Coarse;
+
+    impl sub_pixel_i64::SubPixelPrecision for Coarse {
+        const SUB_PIXELS_PER_PIXEL: i64 = 16;
+    }
+
+    #[test]
+    fn custom_precision() {
+        let v = sub_pixel_i64::SubPixel::<Coarse>::new_pixels_f64(2.5);
+        assert_eq!(v.raw(), 40);
+        assert_eq!(v.to_pixels(PixelRounding::Floor).raw(), 2);
+        assert_eq!(v.clamp_zero_one_pixel().raw(), 16);
+    }
+}
@@ -0,0 +1,59 @@
+use fnv::{FnvHashMap, FnvHashSet};
+use game::EntityId;
+
+#[derive(Clone, Default)]
+pub struct EntityPool {
+    free: FnvHashMap<String, Vec<EntityId>>,
+    member_of: FnvHashMap<EntityId, String>,
+    pooled: FnvHashSet<EntityId>,
+}
+
+impl EntityPool {
+    pub fn clear(&mut self) {
+        self.free.clear();
+        self.member_of.clear();
+        self.pooled.clear();
+    }
+    pub fn add_member(&mut self, id: EntityId, name: &str) {
+        self.member_of.insert(id, name.to_string());
+    }
+    pub fn take(&mut self, name: &str) -> Option<EntityId> {
+        let id = self.free.get_mut(name).and_then(|free| free.pop())?;
+        self.pooled.remove(&id);
+        Some(id)
+    }
+    pub fn give_back(&mut self, id: EntityId) -> bool {
+        if self.pooled.contains(&id) {
+            return false;
+        }
+        let name = match self.member_of.get(&id) {
+            Some(name) => name,
+            None => return false,
+        };
+        if !self.free.contains_key(name) {
+            self.free.insert(name.clone(), Vec::new());
+        }
+        if let Some(free) = self.free.get_mut(name) {
+            free.push(id);
+        }
+        self.pooled.insert(id);
+        true
+    }
+    pub fn remove(&mut self, id: EntityId) {
+        if let Some(name) = self.member_of.remove(&id) {
+            if let Some(free) = self.free.get_mut(&name) {
+                free.retain(|&free_id| free_id != id);
+            }
+        }
+        self.pooled.remove(&id);
+    }
+    pub fn is_member(&self, id: EntityId) -> bool {
+        self.member_of.contains_key(&id)
+    }
+    pub fn is_pooled(&self, id: EntityId) -> bool {
+        self.pooled.contains(&id)
+    }
+    pub fn num_free(&self, name: &str) -> usize {
+        self.free.get(name).map(Vec::len).unwrap_or(0)
+    }
+}
@@ -0,0 +1,120 @@
+use aabb::Aabb;
+use cgmath::{Vector2, vec2};
+use game::EntityId;
+use physics_num;
+use pixel_num::SubPixelI64;
+use trig::{Angle, TRIG_ONE};
+
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+pub struct Portal {
+    pub region: Aabb<SubPixelI64>,
+    pub facing: Angle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Hash)]
+pub struct PortalPair {
+    pub a: Portal,
+    pub b: Portal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortalSide {
+    A,
+    B,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TeleportEvent {
+    pub entity_id: EntityId,
+    pub pair_index: usize,
+    pub entered: PortalSide,
+    pub position: Vector2<SubPixelI64>,
+    pub velocity: Vector2<SubPixelI64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transfer {
+    pub centre: Vector2<SubPixelI64>,
+    pub velocity: Vector2<SubPixelI64>,
+}
+
+impl Portal {
+    pub fn new(region: Aabb<SubPixelI64>, facing: Angle) -> Self {
+        Self { region, facing }
+    }
+    pub fn is_entering(
+        &self,
+        aabb: &Aabb<SubPixelI64>,
+        velocity: Vector2<SubPixelI64>,
+    ) -> bool {
+        let into = physics_num::widen(velocity.x) * self.facing.cos() as i128
+            + physics_num::widen(velocity.y) * self.facing.sin() as i128;
+        into < 0 && self.region.is_intersecting(aabb)
+    }
+    pub fn transfer(
+        &self,
+        to: &Portal,
+        aabb: &Aabb<SubPixelI64>,
+        velocity: Vector2<SubPixelI64>,
+    ) -> Transfer {
+        let rotation = to.facing - (self.facing + Angle::HALF_TURN);
+        let offset = rotation.rotate(aabb.centre() - self.region.centre());
+        let (cos, sin) = (to.facing.cos() as i128, to.facing.sin() as i128);
+        let one = TRIG_ONE as i128;
+        let along = (physics_num::widen(offset.x) * cos
+            + physics_num::widen(offset.y) * sin) / one;
+        let half_extent = |size: Vector2<SubPixelI64>| {
+            let x = physics_num::widen(size.x) * cos.abs();
+            let y = physics_num::widen(size.y) * sin.abs();
+            (x + y) / (2 * one)
+        };
+        let clearance =
+            half_extent(to.region.size()) + half_extent(rotated_size(aabb, rotation)) + 1;
+        let shift = clearance - along;
+        let centre = to.region.centre() + offset
+            + vec2(
+                physics_num::saturating_narrow(cos * shift / one),
+                physics_num::saturating_narrow(sin * shift / one),
+            );
+        Transfer {
+            centre,
+            velocity: rotation.rotate(velocity),
+        }
+    }
+}
+
+fn rotated_size(aabb: &Aabb<SubPixelI64>, rotation: Angle) -> Vector2<SubPixelI64> {
+    let size = rotation.rotate(aabb.size());
+    vec2(
+        SubPixelI64::new(size.x.raw().abs()),
+        SubPixelI64::new(size.y.raw().abs()),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(x: i64, y: i64) -> Vector2<SubPixelI64> {
+        vec2(SubPixelI64::new(x), SubPixelI64::new(y))
+    }
+
+    #[test]
+    fn transfer_rotates_velocity_and_exits_clear_of_destination() {
+        let right = Portal::new(Aabb::new(v(100, 0), v(10, 100)), Angle::ZERO);
+        let up = Portal::new(Aabb::new(v(400, 400), v(100, 10)), -Angle::QUARTER_TURN);
+        let body = Aabb::new(v(105, 60), v(20, 20));
+        assert!(right.is_entering(&body, v(-50, 3)));
+        assert!(!right.is_entering(&body, v(50, 3)));
+        assert!(!up.is_entering(&body, v(0, 50)));
+        let transfer = right.transfer(&up, &body, v(-50, 3));
+        assert_eq!(transfer.velocity, v(-3, -50));
+        assert_eq!(transfer.centre, v(430, 389));
+        let exit = Aabb::from_centre_and_half_size(transfer.centre, v(10, 10));
+        assert!(!up.region.is_intersecting(&exit));
+        assert!(!up.is_entering(&exit, transfer.velocity));
+        let back = up.transfer(&right, &exit, -transfer.velocity);
+        assert_eq!(back.velocity, v(50, -3));
+        assert_eq!(back.centre, v(121, 70));
+    }
+}
@@ -0,0 +1,71 @@
+use cgmath::Vector2;
+use error::{PhysicsError, PhysicsResult};
+use fnv::FnvHashMap;
+use game::{ALL_COLLISION_LAYERS, BodyType};
+use lifetime::Lifetime;
+use physics_config::MovementMode;
+use pixel_num::SubPixelI64;
+use shape::{Shape, StartInsideEdgePolicy};
+
+#[derive(Debug, Clone)]
+pub struct Prefab {
+    pub shape: Shape<SubPixelI64>,
+    pub colour: [f32; 4],
+    pub body_type: BodyType,
+    pub velocity: Option<Vector2<SubPixelI64>>,
+    pub mass: Option<i64>,
+    pub layers: u32,
+    pub movement_mode: Option<MovementMode>,
+    pub skin: Option<SubPixelI64>,
+    pub start_inside_edge_policy: Option<StartInsideEdgePolicy>,
+    pub lifetime: Option<Lifetime>,
+}
+
+impl Prefab {
+    pub fn new(shape: Shape<SubPixelI64>) -> Self {
+        Self {
+            shape,
+            colour: [1., 1., 1., 1.],
+            body_type: BodyType::Static,
+            velocity: None,
+            mass: None,
+            layers: ALL_COLLISION_LAYERS,
+            movement_mode: None,
+            skin: None,
+            start_inside_edge_policy: None,
+            lifetime: None,
+        }
+    }
+    pub fn validate(&self) -> PhysicsResult<()> {
+        if self.mass.is_some_and(|mass| mass <= 0) {
+            return Err(PhysicsError::InvalidMass);
+        }
+        if self.skin.is_some_and(|skin| skin < SubPixelI64::new(0)) {
+            return Err(PhysicsError::InvalidSkin);
+        }
+        if self.body_type == BodyType::Dynamic && !self.shape.can_move() {
+            return Err(PhysicsError::ImmovableShape);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct PrefabRegistry {
+    prefabs: FnvHashMap<String, Prefab>,
+}
+
+impl PrefabRegistry {
+    pub fn register(&mut self, name: &str, prefab: Prefab) {
+        self.prefabs.insert(name.to_string(), prefab);
+    }
+    pub fn get(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.get(name)
+    }
+    pub fn remove(&mut self, name: &str) -> Option<Prefab> {
+        self.prefabs.remove(name)
+    }
+    pub fn names<'a>(&'a self) -> impl Iterator<Item = &'a str> + 'a {
+        self.prefabs.keys().map(|name| name.as_str())
+    }
+}
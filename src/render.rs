@@ -0,0 +1,324 @@
+use cgmath::{Vector2, vec2};
+use game::RenderUpdate;
+use gizmo::Gizmo;
+use pixel_num::sub_pixel_i64::{self, SubPixelI64};
+use shape::Shape;
+use sprite::Sprite;
+use tessellation;
+use verlet::VerletSystem;
+use particles::ParticleSystem;
+
+const VELOCITY_GIZMO_COLOUR: [f32; 4] = [0., 1., 1., 1.];
+const CONTACT_GIZMO_COLOUR: [f32; 4] = [1., 0., 1., 1.];
+const CONTACT_NORMAL_PIXELS: i64 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct View {
+    pub top_left: Vector2<f32>,
+    pub scale: f32,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            top_left: vec2(0., 0.),
+            scale: 1.,
+        }
+    }
+}
+
+impl View {
+    pub fn pixels_to_screen(&self, pixels: Vector2<f32>) -> Vector2<f32> {
+        (pixels - self.top_left) * self.scale
+    }
+    pub fn to_screen(&self, position: Vector2<SubPixelI64>) -> Vector2<f32> {
+        self.pixels_to_screen(sub_pixel_i64::vector_to_f32_pixel(position))
+    }
+    pub fn to_screen_size(&self, size: Vector2<SubPixelI64>) -> Vector2<f32> {
+        sub_pixel_i64::vector_to_f32_pixel(size) * self.scale
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BlendHint {
+    #[default]
+    Alpha,
+    Additive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Primitive {
+    AxisAlignedRect {
+        top_left: Vector2<f32>,
+        size: Vector2<f32>,
+        colour: [f32; 4],
+    },
+    LineSegment {
+        start: Vector2<f32>,
+        end: Vector2<f32>,
+        colour: [f32; 4],
+    },
+    Sprite {
+        top_left: Vector2<f32>,
+        size: Vector2<f32>,
+        colour: [f32; 4],
+        sprite: Sprite,
+    },
+}
+
+pub trait Renderer {
+    fn begin_frame(&mut self, _view: &View) {}
+    fn set_blend(&mut self, _blend: BlendHint) {}
+    fn draw(&mut self, primitive: Primitive);
+    fn end_frame(&mut self) {}
+}
+
+pub fn for_each_primitive<F>(update: &RenderUpdate, view: &View, mut f: F)
+where
+    F: FnMut(Primitive),
+{
+    let colour = update.colour;
+    if let Some(&sprite) = update.sprite {
+        let aabb = update.shape.aabb(update.position);
+        f(Primitive::Sprite {
+            top_left: view.to_screen(aabb.top_left()),
+            size: view.to_screen_size(aabb.size()),
+            colour,
+            sprite,
+        });
+        return;
+    }
+    if let Shape::AxisAlignedRect(rect) = update.shape {
+        f(Primitive::AxisAlignedRect {
+            top_left: view.to_screen(update.position),
+            size: view.to_screen_size(rect.dimensions()),
+            colour,
+        });
+        return;
+    }
+    for line in tessellation::lines(update.shape, update.position) {
+        f(Primitive::LineSegment {
+            start: view.pixels_to_screen(line[0]),
+            end: view.pixels_to_screen(line[1]),
+            colour,
+        });
+    }
+}
+
+pub fn render_frame<'a, R, I>(renderer: &mut R, view: &View, updates: I)
+where
+    R: Renderer,
+    I: IntoIterator<Item = RenderUpdate<'a>>,
+{
+    renderer.begin_frame(view);
+    let mut blend = BlendHint::default();
+    renderer.set_blend(blend);
+    for update in updates {
+        if update.blend != blend {
+            blend = update.blend;
+            renderer.set_blend(blend);
+        }
+        for_each_primitive(&update, view, |primitive| renderer.draw(primitive));
+    }
+    renderer.end_frame();
+}
+
+fn scale(v: Vector2<SubPixelI64>, factor: i64) -> Vector2<SubPixelI64> {
+    vec2(v.x * factor, v.y * factor)
+}
+
+pub fn render_gizmos<R, I>(renderer: &mut R, view: &View, gizmos: I, velocity_scale: i64)
+where
+    R: Renderer,
+    I: IntoIterator<Item = Gizmo>,
+{
+    for gizmo in gizmos {
+        let (start, end, colour) = match gizmo {
+            Gizmo::Velocity {
+                origin, velocity, ..
+            } => (
+                origin,
+                origin + scale(velocity, velocity_scale),
+                VELOCITY_GIZMO_COLOUR,
+            ),
+            Gizmo::Contact { point, normal, .. } => (
+                point,
+                point + scale(normal, CONTACT_NORMAL_PIXELS),
+                CONTACT_GIZMO_COLOUR,
+            ),
+        };
+        renderer.draw(Primitive::LineSegment {
+            start: view.to_screen(start),
+            end: view.to_screen(end),
+            colour,
+        });
+    }
+}
+
+pub fn render_verlet<R: Renderer>(
+    renderer: &mut R,
+    view: &View,
+    system: &VerletSystem,
+    colour: [f32; 4],
+) {
+    for segment in system.segments() {
+        renderer.draw(Primitive::LineSegment {
+            start: view.to_screen(segment.start),
+            end: view.to_screen(segment.end),
+            colour,
+        });
+    }
+}
+
+pub fn render_particles<R: Renderer>(
+    renderer: &mut R,
+    view: &View,
+    particles: &ParticleSystem,
+    size_pixels: f32,
+) {
+    let size = vec2(size_pixels, size_pixels) * view.scale;
+    for particle in particles.particles() {
+        renderer.draw(Primitive::AxisAlignedRect {
+            top_left: view.to_screen(particle.position) - size / 2.,
+            size,
+            colour: particle.colour,
+        });
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RecordingRenderer {
+    primitives: Vec<Primitive>,
+}
+
+impl RecordingRenderer {
+    pub fn primitives(&self) -> &[Primitive] {
+        &self.primitives
+    }
+}
+
+impl Renderer for RecordingRenderer {
+    fn begin_frame(&mut self, _view: &View) {
+        self.primitives.clear();
+    }
+    fn draw(&mut self, primitive: Primitive) {
+        self.primitives.push(primitive);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axis_aligned_rect::AxisAlignedRect;
+    use line_segment::LineSegment;
+    use sprite::SourceRect;
+
+    fn pixels(x: i64, y: i64) -> Vector2<SubPixelI64> {
+        vec2(SubPixelI64::new_pixels(x), SubPixelI64::new_pixels(y))
+    }
+
+    fn update<'a>(
+        shape: &'a Shape<SubPixelI64>,
+        sprite: Option<&'a Sprite>,
+        blend: BlendHint,
+    ) -> RenderUpdate<'a> {
+        RenderUpdate {
+            entity_id: 0,
+            z_index: 0,
+            position: pixels(10, 20),
+            shape,
+            colour: [1., 0., 0., 1.],
+            sprite,
+            animation_frame: None,
+            blend,
+        }
+    }
+
+    #[derive(Default)]
+    struct BlendRecorder {
+        blends: Vec<BlendHint>,
+        draws: usize,
+    }
+
+    impl Renderer for BlendRecorder {
+        fn set_blend(&mut self, blend: BlendHint) {
+            self.blends.push(blend);
+        }
+        fn draw(&mut self, _primitive: Primitive) {
+            self.draws += 1;
+        }
+    }
+
+    #[test]
+    fn frames_are_drawn_through_the_view() {
+        let rect = Shape::AxisAlignedRect(AxisAlignedRect::new(pixels(4, 2)));
+        let line = Shape::LineSegment(LineSegment::new(pixels(0, 0), pixels(8, 0)));
+        let view = View {
+            top_left: vec2(10., 0.),
+            scale: 2.,
+        };
+        let mut recording = RecordingRenderer::default();
+        let updates = vec![
+            update(&rect, None, BlendHint::Alpha),
+            update(&line, None, BlendHint::Alpha),
+        ];
+        render_frame(&mut recording, &view, updates);
+        assert_eq!(
+            recording.primitives()[0],
+            Primitive::AxisAlignedRect {
+                top_left: vec2(0., 40.),
+                size: vec2(8., 4.),
+                colour: [1., 0., 0., 1.],
+            }
+        );
+        match recording.primitives()[1] {
+            Primitive::LineSegment { start, end, .. } => {
+                assert_eq!((start, end), (vec2(0., 40.), vec2(16., 40.)))
+            }
+            primitive => panic!("{:?}", primitive),
+        }
+    }
+
+    #[test]
+    fn blend_changes_are_forwarded_once() {
+        let rect = Shape::AxisAlignedRect(AxisAlignedRect::new(pixels(4, 2)));
+        let mut recorder = BlendRecorder::default();
+        let updates = vec![
+            update(&rect, None, BlendHint::Alpha),
+            update(&rect, None, BlendHint::Additive),
+            update(&rect, None, BlendHint::Additive),
+            update(&rect, None, BlendHint::Alpha),
+        ];
+        render_frame(&mut recorder, &View::default(), updates);
+        assert_eq!(
+            recorder.blends,
+            [BlendHint::Alpha, BlendHint::Additive, BlendHint::Alpha]
+        );
+        assert_eq!(recorder.draws, 4);
+    }
+
+    #[test]
+    fn sprites_replace_shape_primitives() {
+        let rect = Shape::AxisAlignedRect(AxisAlignedRect::new(pixels(4, 2)));
+        let sprite = Sprite::new(7, SourceRect {
+            x: 0,
+            y: 16,
+            width: 16,
+            height: 8,
+        });
+        let mut primitives = Vec::new();
+        let sprite_update = update(&rect, Some(&sprite), BlendHint::Alpha);
+        for_each_primitive(&sprite_update, &View::default(), |primitive| {
+            primitives.push(primitive)
+        });
+        assert_eq!(
+            primitives,
+            [Primitive::Sprite {
+                top_left: vec2(10., 20.),
+                size: vec2(4., 2.),
+                colour: [1., 0., 0., 1.],
+                sprite,
+            }]
+        );
+    }
+}
@@ -0,0 +1,184 @@
+use std::any::{Any, TypeId};
+use std::fmt;
+use fnv::FnvHashMap;
+use camera::Camera;
+use game::TimeScale;
+use physics_config::PhysicsConfig;
+use rng::Rng;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FrameCount(pub u64);
+
+type CloneFn = fn(&dyn Any) -> Box<dyn Any>;
+
+struct Entry {
+    value: Box<dyn Any>,
+    clone: Option<CloneFn>,
+}
+
+fn clone_value<T: Any + Clone>(value: &dyn Any) -> Box<dyn Any> {
+    Box::new(
+        value
+            .downcast_ref::<T>()
+            .expect("resource has unexpected type")
+            .clone(),
+    )
+}
+
+/// World-wide state. The physics config, RNG, time scale and camera are
+/// always present; user singletons are stored by type alongside them.
+pub struct Resources {
+    pub(crate) config: PhysicsConfig,
+    pub(crate) rng: Rng,
+    pub(crate) time_scale: TimeScale,
+    pub(crate) camera: Camera,
+    entries: FnvHashMap<TypeId, Entry>,
+}
+
+impl Resources {
+    pub fn new(config: PhysicsConfig, camera: Camera) -> Self {
+        Self {
+            config,
+            rng: Default::default(),
+            time_scale: Default::default(),
+            camera,
+            entries: Default::default(),
+        }
+    }
+    pub fn config(&self) -> &PhysicsConfig {
+        &self.config
+    }
+    pub fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+    pub fn time_scale(&self) -> TimeScale {
+        self.time_scale
+    }
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+    fn insert_entry<T: Any>(
+        &mut self,
+        value: T,
+        clone: Option<CloneFn>,
+    ) -> Option<T> {
+        let entry = Entry {
+            value: Box::new(value),
+            clone,
+        };
+        self.entries
+            .insert(TypeId::of::<T>(), entry)
+            .and_then(|entry| entry.value.downcast().ok())
+            .map(|value| *value)
+    }
+    pub fn insert<T: Any>(&mut self, value: T) -> Option<T> {
+        self.insert_entry(value, None)
+    }
+    pub fn insert_persistent<T: Any + Clone>(&mut self, value: T) -> Option<T> {
+        self.insert_entry(value, Some(clone_value::<T>))
+    }
+    pub fn get<T: Any>(&self) -> Option<&T> {
+        self.entries
+            .get(&TypeId::of::<T>())
+            .and_then(|entry| entry.value.downcast_ref())
+    }
+    pub fn get_mut<T: Any>(&mut self) -> Option<&mut T> {
+        self.entries
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|entry| entry.value.downcast_mut())
+    }
+    pub fn get_or_insert_with<T, F>(&mut self, f: F) -> &mut T
+    where
+        T: Any + Clone,
+        F: FnOnce() -> T,
+    {
+        self.entries
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Entry {
+                value: Box::new(f()),
+                clone: Some(clone_value::<T>),
+            })
+            .value
+            .downcast_mut()
+            .expect("resource has unexpected type")
+    }
+    pub fn contains<T: Any>(&self) -> bool {
+        self.entries.contains_key(&TypeId::of::<T>())
+    }
+    pub fn remove<T: Any>(&mut self) -> Option<T> {
+        self.entries
+            .remove(&TypeId::of::<T>())
+            .and_then(|entry| entry.value.downcast().ok())
+            .map(|value| *value)
+    }
+    /// The number of user singletons.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// Removes every user singleton; the built-in resources are kept.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+    /// The built-in resources and the user singletons that were inserted as
+    /// persistent.
+    pub fn persistent(&self) -> Resources {
+        let entries = self.entries
+            .iter()
+            .filter_map(|(&type_id, entry)| {
+                entry.clone.map(|clone| {
+                    let entry = Entry {
+                        value: clone(&*entry.value),
+                        clone: entry.clone,
+                    };
+                    (type_id, entry)
+                })
+            })
+            .collect();
+        Resources {
+            config: self.config,
+            rng: self.rng,
+            time_scale: self.time_scale,
+            camera: self.camera,
+            entries,
+        }
+    }
+    pub fn restore(&mut self, snapshot: &Resources) {
+        self.config = snapshot.config;
+        self.rng = snapshot.rng;
+        self.time_scale = snapshot.time_scale;
+        self.camera = snapshot.camera;
+        for (&type_id, entry) in snapshot.entries.iter() {
+            if let Some(clone) = entry.clone {
+                let entry = Entry {
+                    value: clone(&*entry.value),
+                    clone: entry.clone,
+                };
+                self.entries.insert(type_id, entry);
+            }
+        }
+    }
+}
+
+impl Clone for Resources {
+    fn clone(&self) -> Self {
+        self.persistent()
+    }
+}
+
+impl fmt::Debug for Resources {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Resources")
+            .field("config", &self.config)
+            .field("rng", &self.rng)
+            .field("time_scale", &self.time_scale)
+            .field("camera", &self.camera)
+            .field("len", &self.entries.len())
+            .finish()
+    }
+}
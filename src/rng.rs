@@ -0,0 +1,63 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+    pub fn set_state(&mut self, state: u64) {
+        self.state = state;
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "bound must be positive");
+        ((self.next_u64() as u128 * bound as u128) >> 64) as u64
+    }
+    pub fn range(&mut self, min: i64, max: i64) -> i64 {
+        assert!(min < max, "empty range");
+        let width = (max as i128 - min as i128) as u64;
+        (min as i128 + self.below(width) as i128) as i64
+    }
+    pub fn chance(&mut self, numerator: u64, denominator: u64) -> bool {
+        self.below(denominator) < numerator
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn deterministic_and_in_range() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..1000 {
+            let value = a.range(-5, 5);
+            assert_eq!(value, b.range(-5, 5));
+            assert!((-5..5).contains(&value));
+        }
+        let mut c = Rng::new(42);
+        let state = a.state();
+        c.set_state(state);
+        assert_eq!(a.next_u64(), c.next_u64());
+        let full_range = Rng::new(0).range(i64::MIN, i64::MAX);
+        assert_eq!(full_range, 7070836379803831726);
+    }
+}
@@ -0,0 +1,91 @@
+use cgmath::{Vector2, vec2};
+use game::EntityId;
+use num::Zero;
+use physics_num;
+use pixel_num::SubPixelI64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rope {
+    pub anchor: Vector2<SubPixelI64>,
+    pub length: SubPixelI64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RopeCorrection {
+    pub offset: Vector2<SubPixelI64>,
+    pub velocity: Vector2<SubPixelI64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RopeBlocked {
+    pub entity_id: EntityId,
+    pub blocker_id: EntityId,
+    pub point: Vector2<SubPixelI64>,
+}
+
+impl Rope {
+    pub fn new(anchor: Vector2<SubPixelI64>, length: SubPixelI64) -> Self {
+        assert!(length >= Zero::zero(), "rope length must not be negative");
+        Self { anchor, length }
+    }
+    pub fn constrain(
+        &self,
+        attachment: Vector2<SubPixelI64>,
+        velocity: Vector2<SubPixelI64>,
+    ) -> Option<RopeCorrection> {
+        let from_anchor = attachment - self.anchor;
+        let distance2 = physics_num::magnitude2_wide(from_anchor);
+        let length = physics_num::widen(self.length);
+        if distance2 <= length * length {
+            return None;
+        }
+        let on_rope = physics_num::normalize_to_length(from_anchor, self.length);
+        let outward = physics_num::widen(velocity.x) * physics_num::widen(from_anchor.x)
+            + physics_num::widen(velocity.y) * physics_num::widen(from_anchor.y);
+        let velocity = if outward > 0 {
+            velocity
+                - vec2(
+                    physics_num::saturating_narrow(
+                        physics_num::widen(from_anchor.x) * outward / distance2,
+                    ),
+                    physics_num::saturating_narrow(
+                        physics_num::widen(from_anchor.y) * outward / distance2,
+                    ),
+                )
+        } else {
+            velocity
+        };
+        Some(RopeCorrection {
+            offset: on_rope - from_anchor,
+            velocity,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn v(x: i64, y: i64) -> Vector2<SubPixelI64> {
+        vec2(SubPixelI64::new(x), SubPixelI64::new(y))
+    }
+
+    #[test]
+    fn slack_rope_does_nothing() {
+        let rope = Rope::new(v(0, 0), SubPixelI64::new(100));
+        assert_eq!(rope.constrain(v(60, 80), v(10, 10)), None);
+        assert_eq!(rope.constrain(v(0, 0), v(10, 10)), None);
+    }
+
+    #[test]
+    fn taut_rope_pulls_back_and_removes_outward_velocity() {
+        let rope = Rope::new(v(100, 100), SubPixelI64::new(100));
+        let correction = rope.constrain(v(100, 250), v(30, 40)).unwrap();
+        assert_eq!(correction.offset, v(0, -50));
+        assert_eq!(correction.velocity, v(30, 0));
+        let correction = rope.constrain(v(100, 250), v(30, -40)).unwrap();
+        assert_eq!(correction.velocity, v(30, -40));
+        let correction = rope.constrain(v(220, 260), v(0, 0)).unwrap();
+        assert_eq!(v(220, 260) + correction.offset, v(160, 180));
+    }
+}
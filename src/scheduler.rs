@@ -0,0 +1,163 @@
+use game::EntityId;
+use std::rc::Rc;
+
+pub type TimerId = u32;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Timer {
+    id: TimerId,
+    entity: Option<EntityId>,
+    name: Rc<str>,
+    remaining: u32,
+    interval: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimerEvent {
+    pub timer_id: TimerId,
+    /// The entity whose state machine receives the event, if any.
+    pub entity: Option<EntityId>,
+    pub name: Rc<str>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Scheduler {
+    timers: Vec<Timer>,
+    next_id: TimerId,
+}
+
+impl Scheduler {
+    fn add(
+        &mut self,
+        entity: Option<EntityId>,
+        name: &str,
+        remaining: u32,
+        interval: Option<u32>,
+    ) -> TimerId {
+        let remaining = remaining.max(1);
+        let interval = interval.map(|interval| interval.max(1));
+        let id = self.next_id;
+        self.next_id += 1;
+        self.timers.push(Timer {
+            id,
+            entity,
+            name: Rc::from(name),
+            remaining,
+            interval,
+        });
+        id
+    }
+    /// Timers fire at least one update from now: zero delays and intervals are
+    /// treated as one.
+    pub fn after(&mut self, name: &str, updates: u32) -> TimerId {
+        self.add(None, name, updates, None)
+    }
+    pub fn every(&mut self, name: &str, interval: u32) -> TimerId {
+        self.add(None, name, interval, Some(interval))
+    }
+    /// Like `after`, but the event also drives `entity`'s state machine.
+    pub fn after_for(&mut self, entity: EntityId, name: &str, updates: u32) -> TimerId {
+        self.add(Some(entity), name, updates, None)
+    }
+    /// Like `every`, but the event also drives `entity`'s state machine.
+    pub fn every_for(&mut self, entity: EntityId, name: &str, interval: u32) -> TimerId {
+        self.add(Some(entity), name, interval, Some(interval))
+    }
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        let len = self.timers.len();
+        self.timers.retain(|timer| timer.id != id);
+        self.timers.len() != len
+    }
+    /// Cancels every timer created with `after_for` or `every_for` for
+    /// `entity`, returning how many there were.
+    pub fn cancel_for(&mut self, entity: EntityId) -> usize {
+        let len = self.timers.len();
+        self.timers.retain(|timer| timer.entity != Some(entity));
+        len - self.timers.len()
+    }
+    pub fn remaining(&self, id: TimerId) -> Option<u32> {
+        self.timers
+            .iter()
+            .find(|timer| timer.id == id)
+            .map(|timer| timer.remaining)
+    }
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.timers.clear();
+        self.next_id = 0;
+    }
+    pub fn tick(&mut self, events: &mut Vec<TimerEvent>) {
+        for timer in self.timers.iter_mut() {
+            timer.remaining -= 1;
+            if timer.remaining == 0 {
+                events.push(TimerEvent {
+                    timer_id: timer.id,
+                    entity: timer.entity,
+                    name: timer.name.clone(),
+                });
+                if let Some(interval) = timer.interval {
+                    timer.remaining = interval;
+                }
+            }
+        }
+        self.timers.retain(|timer| timer.remaining > 0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn one_shot_and_repeating_timers_fire_on_schedule() {
+        let mut scheduler = Scheduler::default();
+        let wave = scheduler.every("wave", 3);
+        let boss = scheduler.after("boss", 5);
+        let cancelled = scheduler.after("never", 2);
+        assert!(scheduler.cancel(cancelled));
+        let mut fired = Vec::new();
+        for update in 1..10 {
+            let mut events = Vec::new();
+            scheduler.tick(&mut events);
+            fired.extend(events.into_iter().map(|event| (update, event.timer_id)));
+        }
+        assert_eq!(fired, vec![(3, wave), (5, boss), (6, wave), (9, wave)]);
+        assert_eq!(scheduler.len(), 1);
+        assert_eq!(scheduler.remaining(wave), Some(3));
+        assert_eq!(scheduler.remaining(boss), None);
+    }
+
+    #[test]
+    fn zero_delays_fire_on_the_next_tick() {
+        let mut scheduler = Scheduler::default();
+        let once = scheduler.after("once", 0);
+        let often = scheduler.every("often", 0);
+        for _ in 0..2 {
+            let mut events = Vec::new();
+            scheduler.tick(&mut events);
+            let fired = events.iter().map(|event| event.timer_id).collect::<Vec<_>>();
+            assert!(fired.contains(&often));
+            assert_eq!(scheduler.remaining(often), Some(1));
+        }
+        assert_eq!(scheduler.remaining(once), None);
+    }
+
+    #[test]
+    fn entity_timers_are_cancelled_together() {
+        let mut scheduler = Scheduler::default();
+        scheduler.after_for(3, "open", 2);
+        scheduler.every_for(3, "blink", 1);
+        let other = scheduler.every_for(4, "blink", 1);
+        let global = scheduler.after("wave", 2);
+        assert_eq!(scheduler.cancel_for(3), 2);
+        assert_eq!(scheduler.cancel_for(3), 0);
+        assert_eq!(scheduler.len(), 2);
+        assert!(scheduler.remaining(other).is_some());
+        assert!(scheduler.remaining(global).is_some());
+    }
+}
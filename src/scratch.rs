@@ -0,0 +1,45 @@
+use game::EntityId;
+
+#[derive(Debug, Default)]
+pub struct IdBuffers {
+    free: Vec<Vec<EntityId>>,
+}
+
+impl IdBuffers {
+    pub fn take(&mut self) -> Vec<EntityId> {
+        let mut ids = self.free.pop().unwrap_or_default();
+        ids.clear();
+        ids
+    }
+    pub fn sorted<'a, I>(&mut self, ids: I) -> Vec<EntityId>
+    where
+        I: IntoIterator<Item = &'a EntityId>,
+    {
+        let mut buffer = self.take();
+        buffer.extend(ids);
+        buffer.sort_unstable();
+        buffer
+    }
+    pub fn recycle(&mut self, ids: Vec<EntityId>) {
+        self.free.push(ids);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn recycled_buffers_keep_their_capacity() {
+        let mut buffers = IdBuffers::default();
+        let outer = buffers.sorted(&[5, 1, 3]);
+        let inner = buffers.sorted(&[2]);
+        assert_eq!((outer.as_slice(), inner.as_slice()), (&[1, 3, 5][..], &[2][..]));
+        let pointer = outer.as_ptr();
+        buffers.recycle(inner);
+        buffers.recycle(outer);
+        let reused = buffers.take();
+        assert!(reused.is_empty() && reused.capacity() >= 3);
+        assert_eq!(reused.as_ptr(), pointer);
+    }
+}
@@ -1,12 +1,20 @@
 use aabb::Aabb;
+use alloc::vec::Vec;
 use physics_num::{self, PhysicsNum};
 use axis_aligned_rect::AxisAlignedRect;
 use best::BestMap;
 use cgmath::{Vector2, vec2};
-use collision::{self, Collision};
+use chain::Chain;
+use collision::{self, Collision, TimeOfImpact};
+use convex_polygon::{self, ConvexPolygon};
+use error::{PhysicsError, PhysicsResult};
+use heightfield::Heightfield;
 use line_segment::LineSegment;
 use num::{One, Zero};
+use oriented_rect::OrientedRect;
+use core::cmp::Ordering;
 
+#[allow(clippy::too_many_arguments)]
 fn for_each_single_direction_intersection<A, B, F, N>(
     shape: &A,
     position: Vector2<N>,
@@ -26,19 +34,226 @@ fn for_each_single_direction_intersection<A, B, F, N>(
         let abs_vertex = rel_vertex + position;
         other_shape.for_each_edge_facing(reverse_movement, |rel_edge| {
             let abs_edge = rel_edge.add_vector(other_position);
-            match collision::vertex_moving_towards_edge(
+            if let Ok(collision) = collision::vertex_moving_towards_edge(
                 abs_vertex,
                 movement,
                 abs_edge,
                 sign,
             ) {
-                Ok(collision) => f(collision, abs_edge),
-                Err(_) => (),
+                f(collision, abs_edge)
             }
         });
     });
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum StartInsideEdgePolicy {
+    BlockAll,
+    #[default]
+    AllowSeparating,
+    PushOut,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MirrorAxis {
+    Horizontal,
+    Vertical,
+}
+
+fn separating_normal<N: PhysicsNum>(
+    edge: LineSegment<N>,
+    moving_centre: Vector2<N>,
+    stationary_centre: Vector2<N>,
+) -> Vector2<N> {
+    let edge_vector = edge.vector();
+    let normal = vec2(edge_vector.y, -edge_vector.x);
+    let side = physics_num::dot(normal, moving_centre - stationary_centre);
+    if side > Zero::zero() {
+        normal
+    } else if side < Zero::zero() {
+        -normal
+    } else {
+        vec2(Zero::zero(), Zero::zero())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CollisionPriority<N: PhysicsNum> {
+    magnitude2: N,
+    time_of_impact: TimeOfImpact<N>,
+    movement: Vector2<N>,
+    edge: Vector2<N>,
+}
+
+impl<N: PhysicsNum> CollisionPriority<N> {
+    fn facing(&self) -> i128 {
+        physics_num::widen(collision::vector2_cross_product(self.movement, self.edge))
+            .abs()
+    }
+    fn edge_magnitude(&self) -> i128 {
+        physics_num::isqrt_wide(physics_num::magnitude2_wide(self.edge))
+    }
+    fn horizontal(&self) -> i128 {
+        physics_num::widen(self.edge.x).abs()
+    }
+    fn compare_tie(&self, other: &Self) -> Ordering {
+        let facing = (other.facing() * self.edge_magnitude())
+            .cmp(&(self.facing() * other.edge_magnitude()));
+        if facing != Ordering::Equal {
+            return facing;
+        }
+        (other.horizontal() * self.edge_magnitude())
+            .cmp(&(self.horizontal() * other.edge_magnitude()))
+    }
+}
+
+impl<N: PhysicsNum> PartialOrd for CollisionPriority<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(
+            self.magnitude2
+                .cmp(&other.magnitude2)
+                .then_with(|| self.time_of_impact.cmp(&other.time_of_impact))
+                .then_with(|| self.compare_tie(other)),
+        )
+    }
+}
+
+fn scale_toward_zero<N: PhysicsNum>(value: N, numerator: N, denominator: N) -> N {
+    let product = physics_num::widen(value) * physics_num::widen(numerator);
+    physics_num::saturating_narrow((product - product.signum()) / physics_num::widen(denominator))
+}
+
+fn rect_movement_collision_test<N: PhysicsNum>(
+    moving: &AxisAlignedRect<N>,
+    position: Vector2<N>,
+    stationary: &AxisAlignedRect<N>,
+    stationary_position: Vector2<N>,
+    movement: Vector2<N>,
+    start_inside_edge_policy: StartInsideEdgePolicy,
+) -> Option<CollisionInfo<N>> {
+    let moving_aabb = moving.aabb(position);
+    let stationary_aabb = stationary.aabb(stationary_position);
+    let (moving_min, moving_max) =
+        (moving_aabb.top_left(), moving_aabb.bottom_right_coord());
+    let (stationary_min, stationary_max) =
+        (stationary_aabb.top_left(), stationary_aabb.bottom_right_coord());
+    let offset_range_x =
+        (stationary_min.x - moving_max.x, stationary_max.x - moving_min.x);
+    let offset_range_y =
+        (stationary_min.y - moving_max.y, stationary_max.y - moving_min.y);
+    let faces = [
+        (stationary.left(), offset_range_x.0, movement.x, offset_range_y, movement.y),
+        (stationary.right(), -offset_range_x.1, -movement.x, offset_range_y, movement.y),
+        (stationary.top(), offset_range_y.0, movement.y, offset_range_x, movement.x),
+        (stationary.bottom(), -offset_range_y.1, -movement.y, offset_range_x, movement.x),
+    ];
+    let mut best_collision = BestMap::new();
+    for &(edge, gap, speed, (low, high), tangential) in faces.iter() {
+        let zero = Zero::zero();
+        if gap < zero || speed < zero || gap > speed {
+            continue;
+        }
+        let overlapping = if speed == zero {
+            low <= zero && high >= zero
+        } else {
+            let offset = physics_num::widen(tangential) * physics_num::widen(gap);
+            let speed = physics_num::widen(speed);
+            physics_num::widen(low) * speed <= offset
+                && offset <= physics_num::widen(high) * speed
+        };
+        if !overlapping {
+            continue;
+        }
+        let abs_edge = edge.add_vector(stationary_position);
+        let (allowed_movement, time_of_impact, start_inside_normal) = if gap == zero {
+            let normal = separating_normal(
+                abs_edge,
+                moving_aabb.centre(),
+                stationary_aabb.centre(),
+            );
+            if start_inside_edge_policy == StartInsideEdgePolicy::AllowSeparating
+                && physics_num::dot(normal, movement) >= zero
+            {
+                continue;
+            }
+            (vec2(zero, zero), TimeOfImpact::zero(), Some(normal))
+        } else {
+            let allowed_movement = vec2(
+                scale_toward_zero(movement.x, gap, speed),
+                scale_toward_zero(movement.y, gap, speed),
+            );
+            let time_of_impact = match TimeOfImpact::from_wide(
+                physics_num::widen(gap),
+                physics_num::widen(speed),
+            ) {
+                Some(time_of_impact) => time_of_impact,
+                None => continue,
+            };
+            (allowed_movement, time_of_impact, None)
+        };
+        let priority = CollisionPriority {
+            magnitude2: physics_num::magnitude2(allowed_movement),
+            time_of_impact,
+            movement,
+            edge: abs_edge.vector(),
+        };
+        best_collision.insert_lt(
+            priority,
+            (abs_edge, allowed_movement, time_of_impact, start_inside_normal),
+        );
+    }
+    if let Some((priority, value)) = best_collision.into_key_and_value() {
+        let (line_segment, allowed_movement, time_of_impact, start_inside_normal) = value;
+        Some(CollisionInfo {
+            magnitude2: priority.magnitude2,
+            allowed_movement,
+            time_of_impact,
+            line_segment,
+            start_inside_normal,
+        })
+    } else {
+        None
+    }
+}
+
+fn polygon_movement_collision_test<N: PhysicsNum>(
+    moving: &ConvexPolygon<N>,
+    position: Vector2<N>,
+    stationary: &ConvexPolygon<N>,
+    stationary_position: Vector2<N>,
+    movement: Vector2<N>,
+    start_inside_edge_policy: StartInsideEdgePolicy,
+) -> Option<CollisionInfo<N>> {
+    let collision = moving.swept_collision_test(
+        position,
+        stationary,
+        stationary_position,
+        movement,
+    )?;
+    let start_inside_normal = if collision.start_inside {
+        let normal = separating_normal(
+            collision.edge,
+            moving.aabb(position).centre(),
+            stationary.aabb(stationary_position).centre(),
+        );
+        if start_inside_edge_policy == StartInsideEdgePolicy::AllowSeparating
+            && physics_num::dot(normal, movement) >= Zero::zero()
+        {
+            return None;
+        }
+        Some(normal)
+    } else {
+        None
+    };
+    Some(CollisionInfo {
+        magnitude2: physics_num::magnitude2(collision.allowed_movement),
+        allowed_movement: collision.allowed_movement,
+        time_of_impact: collision.time_of_impact,
+        line_segment: collision.edge,
+        start_inside_normal,
+    })
+}
+
 pub trait Collide<N: PhysicsNum> {
     fn aabb(&self, top_left: Vector2<N>) -> Aabb<N>;
     fn for_each_edge_facing<F: FnMut(LineSegment<N>)>(&self, direction: Vector2<N>, f: F);
@@ -83,36 +298,63 @@ pub trait Collide<N: PhysicsNum> {
         stationary_shape: &StationaryShape,
         stationary_position: Vector2<N>,
         movement: Vector2<N>,
+        start_inside_edge_policy: StartInsideEdgePolicy,
     ) -> Option<CollisionInfo<N>>
     where
         Self: Sized,
         StationaryShape: Collide<N>,
     {
         let mut best_collision = BestMap::new();
+        let moving_centre = self.aabb(position).centre();
+        let stationary_centre = stationary_shape.aabb(stationary_position).centre();
         self.for_each_movement_intersection(
             position,
             stationary_shape,
             stationary_position,
             movement,
             |collision, abs_edge| {
-                let (magnitude2, allowed_movement) = match collision {
-                    Collision::StartInsideEdge => {
-                        (Zero::zero(), vec2(Zero::zero(), Zero::zero()))
-                    }
-                    Collision::CollidesWithEdgeAfter(movement) => {
-                        (physics_num::magnitude2(movement), movement)
-                    }
+                let (magnitude2, allowed_movement, time_of_impact, start_inside_normal) =
+                    match collision {
+                        Collision::StartInsideEdge => {
+                            let normal = separating_normal(
+                                abs_edge,
+                                moving_centre,
+                                stationary_centre,
+                            );
+                            if start_inside_edge_policy
+                                == StartInsideEdgePolicy::AllowSeparating
+                                && physics_num::dot(normal, movement) >= Zero::zero()
+                            {
+                                return;
+                            }
+                            let zero = vec2(Zero::zero(), Zero::zero());
+                            (Zero::zero(), zero, TimeOfImpact::zero(), Some(normal))
+                        }
+                        Collision::CollidesWithEdgeAfter(movement, time) => {
+                            (physics_num::magnitude2(movement), movement, time, None)
+                        }
+                    };
+                let priority = CollisionPriority {
+                    magnitude2,
+                    time_of_impact,
+                    movement,
+                    edge: abs_edge.vector(),
                 };
-                best_collision.insert_le(magnitude2, (abs_edge, allowed_movement));
+                best_collision.insert_lt(
+                    priority,
+                    (abs_edge, allowed_movement, time_of_impact, start_inside_normal),
+                );
             },
         );
-        if let Some((magnitude2, (line_segment, allowed_movement))) =
-            best_collision.into_key_and_value()
-        {
+        if let Some((priority, value)) = best_collision.into_key_and_value() {
+            let (line_segment, allowed_movement, time_of_impact, start_inside_normal) =
+                value;
             Some(CollisionInfo {
-                magnitude2,
+                magnitude2: priority.magnitude2,
                 allowed_movement,
+                time_of_impact,
                 line_segment,
+                start_inside_normal,
             })
         } else {
             None
@@ -124,46 +366,775 @@ pub trait Collide<N: PhysicsNum> {
 pub struct CollisionInfo<N> {
     pub magnitude2: N,
     pub allowed_movement: Vector2<N>,
+    pub time_of_impact: TimeOfImpact<N>,
     pub line_segment: LineSegment<N>,
+    pub start_inside_normal: Option<Vector2<N>>,
+}
+
+fn aabbs_overlap<N: PhysicsNum>(a: &Aabb<N>, b: &Aabb<N>) -> bool {
+    let a_bottom_right = a.bottom_right_coord();
+    let b_bottom_right = b.bottom_right_coord();
+    a_bottom_right.x > b.top_left().x && b_bottom_right.x > a.top_left().x
+        && a_bottom_right.y > b.top_left().y && b_bottom_right.y > a.top_left().y
+}
+
+fn segment_overlaps_aabb<N: PhysicsNum>(segment: LineSegment<N>, aabb: &Aabb<N>) -> bool {
+    if !aabbs_overlap(&segment.aabb(vec2(Zero::zero(), Zero::zero())), aabb) {
+        return false;
+    }
+    let edge = segment.vector();
+    let normal = (physics_num::widen(edge.y), -physics_num::widen(edge.x));
+    let project = |v: Vector2<N>| {
+        normal.0 * physics_num::widen(v.x) + normal.1 * physics_num::widen(v.y)
+    };
+    let segment_projection = project(segment.start);
+    let top_left = aabb.top_left();
+    let bottom_right = aabb.bottom_right_coord();
+    let corners = [
+        top_left,
+        vec2(bottom_right.x, top_left.y),
+        bottom_right,
+        vec2(top_left.x, bottom_right.y),
+    ];
+    let below = corners.iter().any(|&c| project(c) < segment_projection);
+    let above = corners.iter().any(|&c| project(c) > segment_projection);
+    below && above
 }
 
-#[derive(Debug, Clone)]
+fn shapes_overlap<N: PhysicsNum>(
+    shape_a: &Shape<N>,
+    position_a: Vector2<N>,
+    shape_b: &Shape<N>,
+    position_b: Vector2<N>,
+    tolerance: N,
+) -> bool {
+    match (shape_a, shape_b) {
+        (Shape::AxisAlignedRect(a), Shape::AxisAlignedRect(b)) => {
+            aabbs_overlap(&a.aabb(position_a).expand(-tolerance), &b.aabb(position_b))
+        }
+        (Shape::AxisAlignedRect(rect), &Shape::LineSegment(segment)) => {
+            segment_overlaps_aabb(
+                segment.add_vector(position_b),
+                &rect.aabb(position_a).expand(-tolerance),
+            )
+        }
+        (&Shape::LineSegment(segment), Shape::AxisAlignedRect(rect)) => {
+            segment_overlaps_aabb(
+                segment.add_vector(position_a),
+                &rect.aabb(position_b).expand(-tolerance),
+            )
+        }
+        (&Shape::LineSegment(_), &Shape::LineSegment(_)) => false,
+        (Shape::Chain(chain), _) => (0..chain.num_edges()).any(|i| {
+            let edge = Shape::LineSegment(chain.edge(i));
+            shapes_overlap(&edge, position_a, shape_b, position_b, tolerance)
+        }),
+        (_, &Shape::Chain(_)) => {
+            shapes_overlap(shape_b, position_b, shape_a, position_a, tolerance)
+        }
+        (Shape::Heightfield(heightfield), _) => {
+            (0..heightfield.num_edges()).any(|i| {
+                let edge = Shape::LineSegment(heightfield.edge(i));
+                shapes_overlap(&edge, position_a, shape_b, position_b, tolerance)
+            })
+        }
+        (_, &Shape::Heightfield(_)) => {
+            shapes_overlap(shape_b, position_b, shape_a, position_a, tolerance)
+        }
+        (&Shape::OrientedRect(_), _)
+        | (_, &Shape::OrientedRect(_))
+        | (&Shape::ConvexPolygon(_), _)
+        | (_, &Shape::ConvexPolygon(_)) => {
+            convex_polygon::vertices_overlap(
+                &shape_a.vertices(),
+                position_a,
+                &shape_b.vertices(),
+                position_b,
+                tolerance,
+            )
+        }
+    }
+}
+
+fn movement_collision_test_against<N, MovingShape>(
+    moving: &MovingShape,
+    position: Vector2<N>,
+    stationary: &Shape<N>,
+    stationary_position: Vector2<N>,
+    movement_vector: Vector2<N>,
+    start_inside_edge_policy: StartInsideEdgePolicy,
+) -> Option<CollisionInfo<N>>
+where
+    N: PhysicsNum,
+    MovingShape: Collide<N>,
+{
+    match stationary {
+        Shape::AxisAlignedRect(stationary) => moving.movement_collision_test(
+            position,
+            stationary,
+            stationary_position,
+            movement_vector,
+            start_inside_edge_policy,
+        ),
+        Shape::LineSegment(stationary) => moving.movement_collision_test(
+            position,
+            stationary,
+            stationary_position,
+            movement_vector,
+            start_inside_edge_policy,
+        ),
+        Shape::OrientedRect(stationary) => moving.movement_collision_test(
+            position,
+            stationary,
+            stationary_position,
+            movement_vector,
+            start_inside_edge_policy,
+        ),
+        Shape::ConvexPolygon(stationary) => moving.movement_collision_test(
+            position,
+            stationary,
+            stationary_position,
+            movement_vector,
+            start_inside_edge_policy,
+        ),
+        Shape::Chain(stationary) => moving.movement_collision_test(
+            position,
+            stationary,
+            stationary_position,
+            movement_vector,
+            start_inside_edge_policy,
+        ),
+        Shape::Heightfield(stationary) => stationary.swept_test_against(
+            stationary_position,
+            moving,
+            position,
+            movement_vector,
+            start_inside_edge_policy,
+        ),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
 pub enum Shape<N: PhysicsNum> {
     AxisAlignedRect(AxisAlignedRect<N>),
     LineSegment(LineSegment<N>),
+    OrientedRect(OrientedRect<N>),
+    ConvexPolygon(ConvexPolygon<N>),
+    Chain(Chain<N>),
+    Heightfield(Heightfield<N>),
 }
 
 impl<N: PhysicsNum> Shape<N> {
+    pub fn can_move(&self) -> bool {
+        match self {
+            &Shape::AxisAlignedRect(_)
+            | &Shape::OrientedRect(_)
+            | &Shape::ConvexPolygon(_) => true,
+            &Shape::LineSegment(_) | &Shape::Chain(_) | &Shape::Heightfield(_) => false,
+        }
+    }
+    fn polygon(&self) -> Option<&ConvexPolygon<N>> {
+        match self {
+            Shape::OrientedRect(oriented_rect) => Some(oriented_rect.polygon()),
+            Shape::ConvexPolygon(polygon) => Some(polygon),
+            _ => None,
+        }
+    }
     pub fn aabb(&self, top_left: Vector2<N>) -> Aabb<N> {
         match self {
-            &Shape::AxisAlignedRect(ref rect) => rect.aabb(top_left),
-            &Shape::LineSegment(ref line_segment) => line_segment.aabb(top_left),
+            Shape::AxisAlignedRect(rect) => rect.aabb(top_left),
+            Shape::LineSegment(line_segment) => line_segment.aabb(top_left),
+            Shape::OrientedRect(oriented_rect) => oriented_rect.aabb(top_left),
+            Shape::ConvexPolygon(polygon) => polygon.aabb(top_left),
+            Shape::Chain(chain) => chain.aabb(top_left),
+            Shape::Heightfield(heightfield) => heightfield.aabb(top_left),
         }
     }
+    pub fn vertices(&self) -> Vec<Vector2<N>> {
+        match self {
+            Shape::AxisAlignedRect(rect) => vec![
+                rect.top().start,
+                rect.top().end,
+                rect.bottom().start,
+                rect.bottom().end,
+            ],
+            Shape::LineSegment(line_segment) => {
+                vec![line_segment.start, line_segment.end]
+            }
+            Shape::OrientedRect(oriented_rect) => oriented_rect.vertices().to_vec(),
+            Shape::ConvexPolygon(polygon) => polygon.vertices().to_vec(),
+            Shape::Chain(chain) => chain.points().to_vec(),
+            Shape::Heightfield(heightfield) => heightfield.chain().points().to_vec(),
+        }
+    }
+    /// Returns `None` when `self` is a shape that can't move, such as a line,
+    /// chain or heightfield.
     pub fn movement_collision_test(
         &self,
         position: Vector2<N>,
         stationary: &Self,
         stationary_position: Vector2<N>,
         movement_vector: Vector2<N>,
+        start_inside_edge_policy: StartInsideEdgePolicy,
     ) -> Option<CollisionInfo<N>> {
+        if let (Some(moving), Some(stationary)) = (self.polygon(), stationary.polygon()) {
+            return polygon_movement_collision_test(
+                moving,
+                position,
+                stationary,
+                stationary_position,
+                movement_vector,
+                start_inside_edge_policy,
+            );
+        }
         match self {
-            &Shape::AxisAlignedRect(ref moving) => match stationary {
-                &Shape::AxisAlignedRect(ref stationary) => moving
-                    .movement_collision_test(
-                        position,
-                        stationary,
-                        stationary_position,
-                        movement_vector,
-                    ),
-                &Shape::LineSegment(ref stationary) => moving.movement_collision_test(
+            Shape::AxisAlignedRect(moving) => match stationary {
+                Shape::AxisAlignedRect(stationary) => rect_movement_collision_test(
+                    moving,
+                    position,
+                    stationary,
+                    stationary_position,
+                    movement_vector,
+                    start_inside_edge_policy,
+                ),
+                _ => movement_collision_test_against(
+                    moving,
                     position,
                     stationary,
                     stationary_position,
                     movement_vector,
+                    start_inside_edge_policy,
                 ),
             },
-            &Shape::LineSegment(_) => panic!(),
+            &Shape::LineSegment(_) | &Shape::Chain(_) | &Shape::Heightfield(_) => None,
+            Shape::OrientedRect(moving) => movement_collision_test_against(
+                moving,
+                position,
+                stationary,
+                stationary_position,
+                movement_vector,
+                start_inside_edge_policy,
+            ),
+            Shape::ConvexPolygon(moving) => movement_collision_test_against(
+                moving,
+                position,
+                stationary,
+                stationary_position,
+                movement_vector,
+                start_inside_edge_policy,
+            ),
+        }
+    }
+    pub fn raycast(
+        &self,
+        position: Vector2<N>,
+        start: Vector2<N>,
+        ray: Vector2<N>,
+    ) -> Option<CollisionInfo<N>> {
+        let zero = vec2(Zero::zero(), Zero::zero());
+        movement_collision_test_against(
+            &LineSegment::new(zero, zero),
+            start,
+            self,
+            position,
+            ray,
+            StartInsideEdgePolicy::AllowSeparating,
+        )
+    }
+    pub fn overlaps(
+        &self,
+        position: Vector2<N>,
+        other: &Self,
+        other_position: Vector2<N>,
+        tolerance: N,
+    ) -> bool {
+        shapes_overlap(self, position, other, other_position, tolerance)
+    }
+    pub fn translated(&self, offset: Vector2<N>) -> (Self, Vector2<N>) {
+        match self {
+            Shape::AxisAlignedRect(rect) => {
+                (Shape::AxisAlignedRect(rect.clone()), offset)
+            }
+            Shape::LineSegment(line_segment) => (
+                Shape::LineSegment(line_segment.add_vector(offset)),
+                vec2(Zero::zero(), Zero::zero()),
+            ),
+            Shape::OrientedRect(oriented_rect) => {
+                (Shape::OrientedRect(oriented_rect.clone()), offset)
+            }
+            Shape::ConvexPolygon(polygon) => {
+                (Shape::ConvexPolygon(polygon.clone()), offset)
+            }
+            Shape::Chain(chain) => {
+                let points = chain.points().iter().map(|&p| p + offset).collect();
+                (Shape::Chain(Chain::new(points)), vec2(Zero::zero(), Zero::zero()))
+            }
+            Shape::Heightfield(heightfield) => {
+                (Shape::Heightfield(heightfield.clone()), offset)
+            }
+        }
+    }
+    /// Scales the shape by `numerator / denominator`. Fails with
+    /// `InvalidScale` if the factor is not positive or would collapse a
+    /// heightfield column, and with `Overflow` if a coordinate leaves `N`.
+    pub fn scaled(&self, numerator: i64, denominator: i64) -> PhysicsResult<Self> {
+        if denominator <= 0 || numerator <= 0 {
+            return Err(PhysicsError::InvalidScale);
+        }
+        let scale_one = |x: N| {
+            physics_num::checked_narrow(
+                physics_num::widen(x) * numerator as i128 / denominator as i128,
+            ).ok_or(PhysicsError::Overflow)
+        };
+        let scale = |v: Vector2<N>| Ok(vec2(scale_one(v.x)?, scale_one(v.y)?));
+        let shape = match self {
+            Shape::AxisAlignedRect(rect) => {
+                Shape::AxisAlignedRect(AxisAlignedRect::new(scale(rect.dimensions())?))
+            }
+            Shape::LineSegment(line_segment) => Shape::LineSegment(
+                LineSegment::new(scale(line_segment.start)?, scale(line_segment.end)?),
+            ),
+            Shape::OrientedRect(oriented_rect) => {
+                let half_extents = scale(oriented_rect.half_extents())?;
+                let angle = oriented_rect.angle();
+                Shape::OrientedRect(OrientedRect::new(half_extents, angle))
+            }
+            Shape::ConvexPolygon(polygon) => {
+                let vertices = polygon
+                    .vertices()
+                    .iter()
+                    .map(|&v| scale(v))
+                    .collect::<PhysicsResult<_>>()?;
+                Shape::ConvexPolygon(ConvexPolygon::new(vertices))
+            }
+            Shape::Chain(chain) => {
+                let points = chain
+                    .points()
+                    .iter()
+                    .map(|&p| scale(p))
+                    .collect::<PhysicsResult<_>>()?;
+                Shape::Chain(Chain::new(points))
+            }
+            Shape::Heightfield(heightfield) => {
+                let column_width = scale_one(heightfield.column_width())?;
+                if column_width <= Zero::zero() {
+                    return Err(PhysicsError::InvalidScale);
+                }
+                let heights = heightfield
+                    .heights()
+                    .iter()
+                    .map(|&h| scale_one(h))
+                    .collect::<PhysicsResult<_>>()?;
+                Shape::Heightfield(Heightfield::new(column_width, heights))
+            }
+        };
+        Ok(shape)
+    }
+    /// Mirrors the shape about the centre of its own bounding box, so the
+    /// result occupies exactly the same AABB as the original.
+    pub fn mirrored(&self, axis: MirrorAxis) -> Self {
+        let aabb = match self {
+            Shape::AxisAlignedRect(_) => return self.clone(),
+            Shape::OrientedRect(oriented_rect) => {
+                return Shape::OrientedRect(OrientedRect::new(
+                    oriented_rect.half_extents(),
+                    -oriented_rect.angle(),
+                ))
+            }
+            _ => self.aabb(vec2(Zero::zero(), Zero::zero())),
+        };
+        let sum = aabb.top_left() + aabb.bottom_right_coord();
+        let mirror = |v: Vector2<N>| match axis {
+            MirrorAxis::Horizontal => vec2(sum.x - v.x, v.y),
+            MirrorAxis::Vertical => vec2(v.x, sum.y - v.y),
+        };
+        match self {
+            Shape::LineSegment(line_segment) => Shape::LineSegment(
+                LineSegment::new(mirror(line_segment.start), mirror(line_segment.end)),
+            ),
+            Shape::ConvexPolygon(polygon) => Shape::ConvexPolygon(ConvexPolygon::new(
+                polygon.vertices().iter().map(|&v| mirror(v)).collect(),
+            )),
+            Shape::Chain(chain) => Shape::Chain(Chain::new(
+                chain.points().iter().rev().map(|&p| mirror(p)).collect(),
+            )),
+            Shape::Heightfield(heightfield) => {
+                let heights = heightfield.heights();
+                let heights = match axis {
+                    MirrorAxis::Horizontal => heights.iter().rev().cloned().collect(),
+                    MirrorAxis::Vertical => {
+                        heights.iter().map(|&height| -sum.y - height).collect()
+                    }
+                };
+                Shape::Heightfield(Heightfield::new(heightfield.column_width(), heights))
+            }
+            Shape::AxisAlignedRect(_) | Shape::OrientedRect(_) => unreachable!(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use trig::Angle;
+
+    fn rect(width: i64, height: i64) -> AxisAlignedRect<i64> {
+        AxisAlignedRect::new(vec2(width, height))
+    }
+
+    fn corner_test(
+        position: Vector2<i64>,
+        stationary_position: Vector2<i64>,
+        movement: Vector2<i64>,
+    ) -> CollisionInfo<i64> {
+        rect(4, 4)
+            .movement_collision_test(
+                position,
+                &rect(4, 4),
+                stationary_position,
+                movement,
+                Default::default(),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn convex_polygons_collide_with_every_shape() {
+        let wedge = Shape::ConvexPolygon(ConvexPolygon::new(vec![
+            vec2(0, 0),
+            vec2(8, 4),
+            vec2(0, 8),
+        ]));
+        assert!(wedge.can_move());
+        let wall = Shape::AxisAlignedRect(rect(4, 16));
+        let tilted = Shape::OrientedRect(OrientedRect::new(vec2(2, 8), Angle::ZERO));
+        let stationaries = [(wall, vec2(20, -4)), (tilted, vec2(22, 4))];
+        for &(ref stationary, position) in stationaries.iter() {
+            let info = wedge
+                .movement_collision_test(
+                    vec2(0, 0),
+                    stationary,
+                    position,
+                    vec2(24, 0),
+                    Default::default(),
+                )
+                .unwrap();
+            assert_eq!(info.allowed_movement, vec2(11, 0));
+            assert_eq!(info.line_segment.vector().x, 0);
+            let moving = stationary.movement_collision_test(
+                position,
+                &wedge,
+                vec2(0, 0),
+                vec2(-24, 0),
+                Default::default(),
+            );
+            assert_eq!(moving.unwrap().allowed_movement, vec2(-11, 0));
+        }
+    }
+
+    #[test]
+    fn corner_meets_corner_prefers_horizontal_edge() {
+        let info = corner_test(vec2(0, 0), vec2(6, 6), vec2(4, 4));
+        assert_eq!(info.allowed_movement, vec2(1, 1));
+        assert_eq!(info.line_segment.vector().y, 0);
+    }
+
+    #[test]
+    fn corner_tie_break_is_symmetric() {
+        let down_right = corner_test(vec2(0, 0), vec2(6, 6), vec2(4, 4));
+        let up_left = corner_test(vec2(6, 6), vec2(0, 0), vec2(-4, -4));
+        assert_eq!(down_right.allowed_movement, -up_left.allowed_movement);
+        assert_eq!(up_left.line_segment.vector().y, 0);
+    }
+
+    #[test]
+    fn corner_tie_break_prefers_most_opposed_edge() {
+        let info = corner_test(vec2(0, 0), vec2(6, 8), vec2(4, 8));
+        assert_eq!(info.line_segment.vector().y, 0);
+        let info = corner_test(vec2(0, 0), vec2(8, 6), vec2(8, 4));
+        assert_eq!(info.line_segment.vector().x, 0);
+    }
+
+    #[test]
+    fn start_inside_edge_policies() {
+        let floor = LineSegment::new(vec2(-10, 4), vec2(10, 4));
+        let floor_test = |movement, policy| {
+            rect(4, 4)
+                .movement_collision_test(vec2(0, 0), &floor, vec2(0, 0), movement, policy)
+        };
+        let along = vec2(2, 0);
+        let into = vec2(0, 2);
+        assert!(floor_test(along, StartInsideEdgePolicy::AllowSeparating).is_none());
+        let info = floor_test(into, StartInsideEdgePolicy::AllowSeparating).unwrap();
+        assert_eq!(info.allowed_movement, vec2(0, 0));
+        assert_eq!(info.start_inside_normal, Some(vec2(0, -20)));
+        let info = floor_test(along, StartInsideEdgePolicy::BlockAll).unwrap();
+        assert_eq!(info.allowed_movement, vec2(0, 0));
+    }
+
+    fn shape_rect(width: i64, height: i64) -> Shape<i64> {
+        Shape::AxisAlignedRect(rect(width, height))
+    }
+
+    #[test]
+    fn touching_rects_do_not_overlap() {
+        assert!(!shape_rect(4, 4).overlaps(vec2(0, 0), &shape_rect(4, 4), vec2(4, 0), 0));
+        assert!(shape_rect(4, 4).overlaps(vec2(0, 0), &shape_rect(4, 4), vec2(3, 0), 0));
+        assert!(!shape_rect(4, 4).overlaps(vec2(0, 0), &shape_rect(4, 4), vec2(3, 0), 1));
+    }
+
+    #[test]
+    fn segment_through_rect() {
+        let diagonal = Shape::LineSegment(LineSegment::new(vec2(0, 0), vec2(10, 10)));
+        assert!(shape_rect(4, 4).overlaps(vec2(3, 0), &diagonal, vec2(0, 0), 0));
+        assert!(!shape_rect(4, 4).overlaps(vec2(6, 0), &diagonal, vec2(0, 0), 0));
+        let floor = Shape::LineSegment(LineSegment::new(vec2(-10, 4), vec2(10, 4)));
+        assert!(!floor.overlaps(vec2(0, 0), &shape_rect(4, 4), vec2(0, 0), 0));
+    }
+
+    #[test]
+    fn raycast_hits_nearest_edge() {
+        let rect = shape_rect(10, 10);
+        let hit = rect.raycast(vec2(20, 0), vec2(0, 5), vec2(100, 0)).unwrap();
+        assert_eq!(hit.allowed_movement, vec2(19, 0));
+        assert_eq!(hit.line_segment.vector().x, 0);
+        assert!(rect.raycast(vec2(20, 0), vec2(0, 15), vec2(100, 0)).is_none());
+        assert!(rect.raycast(vec2(20, 0), vec2(0, 5), vec2(10, 0)).is_none());
+        let floor = Shape::LineSegment(LineSegment::new(vec2(-10, 4), vec2(10, 4)));
+        let hit = floor.raycast(vec2(0, 0), vec2(0, -4), vec2(0, 20)).unwrap();
+        assert_eq!(hit.allowed_movement, vec2(0, 7));
+    }
+
+    #[test]
+    fn time_of_impact_is_not_rounded() {
+        let hit = rect(4, 4)
+            .movement_collision_test(
+                vec2(0, 0),
+                &rect(4, 4),
+                vec2(5, 0),
+                vec2(3, 0),
+                Default::default(),
+            )
+            .unwrap();
+        assert_eq!(hit.allowed_movement, vec2(0, 0));
+        assert_eq!(hit.time_of_impact, TimeOfImpact::from_wide(1, 3).unwrap());
+        assert!(hit.time_of_impact > TimeOfImpact::zero());
+    }
+
+    #[test]
+    fn oriented_rects_use_separating_axes() {
+        let square = Shape::OrientedRect(OrientedRect::new(vec2(2, 2), Angle::ZERO));
+        let test = |stationary_position, movement, policy| {
+            square.movement_collision_test(
+                vec2(0, 0),
+                &square,
+                stationary_position,
+                movement,
+                policy,
+            )
+        };
+        let policy = StartInsideEdgePolicy::AllowSeparating;
+        let hit = test(vec2(10, 0), vec2(12, 0), policy).unwrap();
+        assert_eq!(hit.allowed_movement, vec2(5, 0));
+        assert_eq!(hit.time_of_impact, TimeOfImpact::from_wide(1, 2).unwrap());
+        assert!(hit.start_inside_normal.is_none());
+        assert!(test(vec2(4, 0), vec2(0, 5), policy).is_none());
+        let inside = test(vec2(3, 0), vec2(2, 0), policy).unwrap();
+        assert_eq!(inside.allowed_movement, vec2(0, 0));
+        assert_eq!(inside.time_of_impact, TimeOfImpact::zero());
+        let normal = inside.start_inside_normal.unwrap();
+        assert!(normal.x < 0 && normal.y == 0);
+        assert!(test(vec2(3, 0), vec2(-2, 0), policy).is_none());
+        let blocked = test(vec2(3, 0), vec2(-2, 0), StartInsideEdgePolicy::BlockAll);
+        assert_eq!(blocked.unwrap().allowed_movement, vec2(0, 0));
+    }
+
+    #[test]
+    fn transforms() {
+        let slope = Shape::LineSegment(LineSegment::new(vec2(2, 0), vec2(6, 4)));
+        match slope.mirrored(MirrorAxis::Horizontal) {
+            Shape::LineSegment(segment) => {
+                assert_eq!((segment.start, segment.end), (vec2(6, 0), vec2(2, 4)))
+            }
+            _ => panic!(),
+        }
+        match slope.translated(vec2(1, 1)) {
+            (Shape::LineSegment(segment), offset) => {
+                assert_eq!(segment.start, vec2(3, 1));
+                assert_eq!(offset, vec2(0, 0));
+            }
+            _ => panic!(),
         }
+        let rect = shape_rect(4, 6);
+        assert_eq!(rect.scaled(3, 2).unwrap().aabb(vec2(0, 0)).size(), vec2(6, 9));
+        assert_eq!(rect.translated(vec2(5, 0)).1, vec2(5, 0));
+    }
+
+    fn every_shape() -> Vec<Shape<i64>> {
+        vec![
+            shape_rect(4, 6),
+            Shape::LineSegment(LineSegment::new(vec2(2, 0), vec2(6, 4))),
+            Shape::OrientedRect(OrientedRect::new(vec2(8, 3), Angle::from_degrees(30))),
+            Shape::Chain(Chain::new(vec![vec2(0, 0), vec2(5, -3), vec2(9, 2)])),
+            Shape::Heightfield(Heightfield::new(4, vec![1, 6, 3, 0])),
+        ]
+    }
+
+    #[test]
+    fn scaling_every_shape() {
+        for shape in every_shape() {
+            let size = shape.aabb(vec2(0, 0)).size();
+            let doubled = shape.scaled(2, 1).unwrap();
+            let doubled_size = doubled.aabb(vec2(0, 0)).size();
+            assert!((doubled_size.x - size.x * 2).abs() <= 2, "{:?}", shape);
+            assert!((doubled_size.y - size.y * 2).abs() <= 2, "{:?}", shape);
+            assert_eq!(shape.scaled(3, 3).unwrap(), shape);
+        }
+        match every_shape()[4].scaled(1, 2).unwrap() {
+            Shape::Heightfield(heightfield) => {
+                assert_eq!(heightfield.column_width(), 2);
+                assert_eq!(heightfield.heights(), &[0, 3, 1, 0]);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn invalid_scales_are_errors() {
+        let rect = shape_rect(4, 6);
+        assert_eq!(rect.scaled(1, 0), Err(PhysicsError::InvalidScale));
+        assert_eq!(rect.scaled(-1, 2), Err(PhysicsError::InvalidScale));
+        assert_eq!(rect.scaled(0, 2), Err(PhysicsError::InvalidScale));
+        assert_eq!(rect.scaled(i64::MAX, 1), Err(PhysicsError::Overflow));
+        let heightfield = &every_shape()[4];
+        assert_eq!(heightfield.scaled(1, 8), Err(PhysicsError::InvalidScale));
+    }
+
+    #[test]
+    fn mirroring_keeps_the_aabb() {
+        for shape in every_shape() {
+            let aabb = shape.aabb(vec2(0, 0));
+            for &axis in [MirrorAxis::Horizontal, MirrorAxis::Vertical].iter() {
+                let mirrored = shape.mirrored(axis);
+                assert_eq!(mirrored.aabb(vec2(0, 0)), aabb, "{:?} {:?}", shape, axis);
+                match shape {
+                    Shape::OrientedRect(_) => (),
+                    _ => assert_eq!(mirrored.mirrored(axis), shape),
+                }
+            }
+        }
+        assert_eq!(every_shape()[0].mirrored(MirrorAxis::Vertical), every_shape()[0]);
+        match every_shape()[4].mirrored(MirrorAxis::Vertical) {
+            Shape::Heightfield(heightfield) => {
+                assert_eq!(heightfield.heights(), &[5, 0, 3, 6])
+            }
+            _ => panic!(),
+        }
+        match every_shape()[2].mirrored(MirrorAxis::Horizontal) {
+            Shape::OrientedRect(oriented_rect) => {
+                assert_eq!(oriented_rect.angle(), -Angle::from_degrees(30))
+            }
+            _ => panic!(),
+        }
+    }
+
+    fn overlap_area(a: &Aabb<i64>, b: &Aabb<i64>) -> bool {
+        a.bottom_right_coord().x > b.top_left().x
+            && b.bottom_right_coord().x > a.top_left().x
+            && a.bottom_right_coord().y > b.top_left().y
+            && b.bottom_right_coord().y > a.top_left().y
+    }
+
+    fn for_each_rect_case<F>(mut f: F)
+    where
+        F: FnMut(
+            &AxisAlignedRect<i64>,
+            Vector2<i64>,
+            Vector2<i64>,
+            StartInsideEdgePolicy,
+        ),
+    {
+        let policies = [
+            StartInsideEdgePolicy::BlockAll,
+            StartInsideEdgePolicy::AllowSeparating,
+            StartInsideEdgePolicy::PushOut,
+        ];
+        for &(width, height) in [(3, 2), (1, 5)].iter() {
+            for x in -6..7 {
+                for y in -6..7 {
+                    for movement_x in -4..5 {
+                        for movement_y in -4..5 {
+                            for &policy in policies.iter() {
+                                f(
+                                    &rect(width, height),
+                                    vec2(x, y),
+                                    vec2(movement_x, movement_y),
+                                    policy,
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rect_fast_path_matches_generic_when_separated() {
+        for_each_rect_case(|stationary, stationary_position, movement, policy| {
+            let moving = rect(3, 3);
+            let moving_aabb = moving.aabb(vec2(0, 0)).expand(1);
+            if overlap_area(&moving_aabb, &stationary.aabb(stationary_position))
+                || movement == vec2(0, 0)
+            {
+                return;
+            }
+            let test = |info: Option<CollisionInfo<i64>>| {
+                info.map(|info| {
+                    (
+                        info.allowed_movement,
+                        info.time_of_impact,
+                        info.start_inside_normal,
+                    )
+                })
+            };
+            let generic = moving.movement_collision_test(
+                vec2(0, 0),
+                stationary,
+                stationary_position,
+                movement,
+                policy,
+            );
+            let fast = rect_movement_collision_test(
+                &moving,
+                vec2(0, 0),
+                stationary,
+                stationary_position,
+                movement,
+                policy,
+            );
+            assert_eq!(test(generic), test(fast));
+        });
+    }
+
+    #[test]
+    fn rect_fast_path_never_moves_into_overlap() {
+        for_each_rect_case(|stationary, stationary_position, movement, policy| {
+            let moving = rect(3, 3);
+            let stationary_aabb = stationary.aabb(stationary_position);
+            if overlap_area(&moving.aabb(vec2(0, 0)), &stationary_aabb) {
+                return;
+            }
+            let allowed_movement = rect_movement_collision_test(
+                &moving,
+                vec2(0, 0),
+                stationary,
+                stationary_position,
+                movement,
+                policy,
+            ).map(|info| info.allowed_movement)
+                .unwrap_or(movement);
+            assert!(!overlap_area(&moving.aabb(allowed_movement), &stationary_aabb));
+        });
     }
 }
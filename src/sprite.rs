@@ -0,0 +1,26 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SourceRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Sprite {
+    pub texture_id: u32,
+    pub source: SourceRect,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+}
+
+impl Sprite {
+    pub fn new(texture_id: u32, source: SourceRect) -> Self {
+        Self {
+            texture_id,
+            source,
+            flip_horizontal: false,
+            flip_vertical: false,
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use cgmath::{Vector2, vec2};
+use physics_num;
+use pixel_num::SubPixelI64;
+
+fn dot_wide(v: Vector2<SubPixelI64>, w: Vector2<SubPixelI64>) -> i128 {
+    physics_num::widen(v.x) * physics_num::widen(w.x)
+        + physics_num::widen(v.y) * physics_num::widen(w.y)
+}
+
+pub fn supports(normal: Vector2<SubPixelI64>, gravity: Vector2<SubPixelI64>) -> bool {
+    dot_wide(normal, gravity) > 0
+}
+
+pub fn is_resting(
+    relative_velocity: Vector2<SubPixelI64>,
+    normal: Vector2<SubPixelI64>,
+    gravity: Vector2<SubPixelI64>,
+) -> bool {
+    let approach = dot_wide(relative_velocity, normal);
+    let gravity_len2 = physics_num::magnitude2_wide(gravity);
+    let normal_len2 = physics_num::magnitude2_wide(normal);
+    approach <= 0 || approach * approach <= gravity_len2 * normal_len2
+}
+
+pub fn resting_velocity(
+    velocity: Vector2<SubPixelI64>,
+    support_velocity: Vector2<SubPixelI64>,
+    normal: Vector2<SubPixelI64>,
+) -> Vector2<SubPixelI64> {
+    let approach = dot_wide(velocity - support_velocity, normal);
+    let normal_len2 = physics_num::magnitude2_wide(normal);
+    if approach <= 0 || normal_len2 == 0 {
+        return velocity;
+    }
+    velocity
+        - vec2(
+            physics_num::saturating_narrow(physics_num::widen(normal.x) * approach / normal_len2),
+            physics_num::saturating_narrow(physics_num::widen(normal.y) * approach / normal_len2),
+        )
+}
+
+pub fn depth_along_gravity(
+    position: Vector2<SubPixelI64>,
+    gravity: Vector2<SubPixelI64>,
+) -> i128 {
+    dot_wide(position, gravity)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axis_aligned_rect::AxisAlignedRect;
+    use game::{EntityId, GameState, InputModel};
+    use physics_config::PhysicsConfig;
+    use shape::Shape;
+
+    fn v(x: i64, y: i64) -> Vector2<SubPixelI64> {
+        vec2(SubPixelI64::new(x), SubPixelI64::new(y))
+    }
+
+    fn px(pixels: i64) -> SubPixelI64 {
+        SubPixelI64::new_pixels(pixels)
+    }
+
+    fn rect(width: i64, height: i64) -> Shape<SubPixelI64> {
+        Shape::AxisAlignedRect(AxisAlignedRect::new(vec2(px(width), px(height))))
+    }
+
+    fn stack(
+        num_boxes: i64,
+        gravity: Vector2<SubPixelI64>,
+    ) -> (GameState, Vec<EntityId>) {
+        let config = PhysicsConfig {
+            gravity,
+            ..Default::default()
+        };
+        let mut game_state = GameState::new(vec2(640., 640.), config);
+        let player = game_state
+            .spawn()
+            .position(vec2(px(600), px(0)))
+            .shape(rect(8, 8))
+            .velocity(v(0, 0))
+            .build()
+            .unwrap();
+        game_state.set_player_id(Some(player));
+        game_state
+            .spawn()
+            .position(vec2(px(0), px(400)))
+            .shape(rect(400, 16))
+            .build()
+            .unwrap();
+        let boxes = (0..num_boxes)
+            .map(|i| {
+                game_state
+                    .spawn()
+                    .position(vec2(px(100), px(400 - 17 * (i + 1))))
+                    .shape(rect(16, 16))
+                    .velocity(v(0, 0))
+                    .mass(1)
+                    .build()
+                    .unwrap()
+            })
+            .collect();
+        (game_state, boxes)
+    }
+
+    fn positions(
+        game_state: &GameState,
+        boxes: &[EntityId],
+    ) -> Vec<Vector2<SubPixelI64>> {
+        boxes
+            .iter()
+            .map(|&id| game_state.position(id).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn resting_contact_removes_approach_only() {
+        let normal = v(0, 512);
+        let gravity = v(0, 16);
+        assert!(supports(normal, gravity));
+        assert!(!supports(-normal, gravity));
+        assert!(is_resting(v(40, 16), normal, gravity));
+        assert!(!is_resting(v(0, 17), normal, gravity));
+        assert!(is_resting(v(0, -300), normal, gravity));
+        assert_eq!(resting_velocity(v(40, 16), v(0, 0), normal), v(40, 0));
+        assert_eq!(resting_velocity(v(40, 16), v(0, 10), normal), v(40, 10));
+        assert_eq!(resting_velocity(v(40, -16), v(0, 0), normal), v(40, -16));
+    }
+
+    #[test]
+    fn column_of_boxes_stays_put() {
+        let (mut game_state, boxes) = stack(6, v(0, 24));
+        for _ in 0..120 {
+            game_state.update(&InputModel::default());
+        }
+        let settled = positions(&game_state, &boxes);
+        for _ in 0..600 {
+            game_state.update(&InputModel::default());
+            assert_eq!(positions(&game_state, &boxes), settled);
+        }
+        assert!(boxes.iter().all(|&id| game_state.is_asleep(id)));
+        for (below, above) in settled.iter().zip(settled.iter().skip(1)) {
+            assert_eq!(below.x, px(100));
+            assert!(above.y + px(16) <= below.y);
+            assert!(below.y - (above.y + px(16)) < px(1));
+        }
+        assert!(settled[0].y + px(16) <= px(400));
+    }
+}
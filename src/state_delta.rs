@@ -0,0 +1,344 @@
+use aabb::Aabb;
+use cgmath::Vector2;
+use fnv::FnvHashMap;
+use animation::AnimationState;
+use attractor::Attractor;
+use bounce_pad::BouncePad;
+use colour_tween::{ColourTween, Flash};
+use cow_table::{CowSet, CowTable};
+use drag::Drag;
+use game::{EntityId, ScaleRemainder};
+use gravity::GravityOverride;
+use hazard::{Hazard, HazardCooldowns};
+use health::Health;
+use homing::Homing;
+use impulse::Restitution;
+use lifetime::Lifetime;
+use material::MaterialId;
+use out_of_bounds::OutOfBoundsPolicy;
+use path_follower::PathFollower;
+use physics_config::MovementMode;
+use portal::PortalPair;
+use particles::ParticleSystem;
+use pickup::Pickup;
+use pixel_num::SubPixelI64;
+use render::BlendHint;
+use resources::Resources;
+use rope::Rope;
+use scheduler::Scheduler;
+use shape::{Shape, StartInsideEdgePolicy};
+use sprite::Sprite;
+use state_machine::StateMachine;
+use verlet::VerletSystem;
+
+pub trait ComponentTable<T> {
+    fn component(&self, id: EntityId) -> Option<&T>;
+    fn insert_component(&mut self, id: EntityId, value: T);
+    fn remove_component(&mut self, id: EntityId);
+}
+
+impl<T> ComponentTable<T> for FnvHashMap<EntityId, T> {
+    fn component(&self, id: EntityId) -> Option<&T> {
+        self.get(&id)
+    }
+    fn insert_component(&mut self, id: EntityId, value: T) {
+        self.insert(id, value);
+    }
+    fn remove_component(&mut self, id: EntityId) {
+        self.remove(&id);
+    }
+}
+
+impl<T: Clone> ComponentTable<T> for CowTable<T> {
+    fn component(&self, id: EntityId) -> Option<&T> {
+        self.get(&id)
+    }
+    fn insert_component(&mut self, id: EntityId, value: T) {
+        self.insert(id, value);
+    }
+    fn remove_component(&mut self, id: EntityId) {
+        self.remove(&id);
+    }
+}
+
+/// Values set on, and components removed from, entities that exist on both
+/// sides of a diff (or were just spawned).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableDelta<T> {
+    pub set: Vec<(EntityId, T)>,
+    pub removed: Vec<EntityId>,
+}
+
+impl<T> Default for TableDelta<T> {
+    fn default() -> Self {
+        Self {
+            set: Vec::new(),
+            removed: Vec::new(),
+        }
+    }
+}
+
+impl<T: Clone + PartialEq> TableDelta<T> {
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty() && self.removed.is_empty()
+    }
+    pub fn first_entity(&self) -> Option<EntityId> {
+        let set = self.set.first().map(|&(id, _)| id);
+        let removed = self.removed.first().cloned();
+        set.into_iter().chain(removed).min()
+    }
+    pub fn record<A: ComponentTable<T>>(
+        &mut self,
+        id: EntityId,
+        spawned: bool,
+        before: &A,
+        after: &A,
+    ) {
+        let previous = if spawned { None } else { before.component(id) };
+        match (previous, after.component(id)) {
+            (Some(previous), Some(value)) if previous == value => (),
+            (_, Some(value)) => self.set.push((id, value.clone())),
+            (Some(_), None) => self.removed.push(id),
+            (None, None) => (),
+        }
+    }
+    pub fn apply<A: ComponentTable<T>>(&self, table: &mut A) {
+        for &id in self.removed.iter() {
+            table.remove_component(id);
+        }
+        for &(id, ref value) in self.set.iter() {
+            table.insert_component(id, value.clone());
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SetDelta {
+    pub inserted: Vec<EntityId>,
+    pub removed: Vec<EntityId>,
+}
+
+impl SetDelta {
+    pub fn is_empty(&self) -> bool {
+        self.inserted.is_empty() && self.removed.is_empty()
+    }
+    pub fn first_entity(&self) -> Option<EntityId> {
+        let inserted = self.inserted.first().cloned();
+        let removed = self.removed.first().cloned();
+        inserted.into_iter().chain(removed).min()
+    }
+    pub fn record(
+        &mut self,
+        id: EntityId,
+        spawned: bool,
+        before: &CowSet,
+        after: &CowSet,
+    ) {
+        match (!spawned && before.contains(&id), after.contains(&id)) {
+            (false, true) => self.inserted.push(id),
+            (true, false) => self.removed.push(id),
+            _ => (),
+        }
+    }
+    pub fn apply(&self, set: &mut CowSet) {
+        for id in self.removed.iter() {
+            set.remove(id);
+        }
+        set.extend(self.inserted.iter().cloned());
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Spawn {
+    pub id: EntityId,
+    pub position: Vector2<SubPixelI64>,
+    pub shape: Shape<SubPixelI64>,
+}
+
+/// One change set per replicated component table. Spawned entities carry
+/// their position and shape in `spawns`; everything else they have arrives
+/// through the tables below.
+#[derive(Debug, Clone, Default)]
+pub struct ComponentDeltas {
+    pub positions: TableDelta<Vector2<SubPixelI64>>,
+    pub shapes: TableDelta<Shape<SubPixelI64>>,
+    pub velocities: TableDelta<Vector2<SubPixelI64>>,
+    pub colours: TableDelta<[f32; 4]>,
+    pub z_indices: TableDelta<i32>,
+    pub sprites: TableDelta<Sprite>,
+    pub animations: TableDelta<AnimationState>,
+    pub colour_tweens: TableDelta<ColourTween>,
+    pub flashes: TableDelta<Flash>,
+    pub blends: TableDelta<BlendHint>,
+    pub masses: TableDelta<i64>,
+    pub restitutions: TableDelta<Restitution>,
+    pub ropes: TableDelta<Rope>,
+    pub attractors: TableDelta<Attractor>,
+    pub forces: TableDelta<Vector2<SubPixelI64>>,
+    pub gravities: TableDelta<GravityOverride>,
+    pub drags: TableDelta<Drag>,
+    pub materials: TableDelta<MaterialId>,
+    pub bounce_pads: TableDelta<BouncePad>,
+    pub hazards: TableDelta<Hazard>,
+    pub healths: TableDelta<Health>,
+    pub pickups: TableDelta<Pickup>,
+    pub lifetimes: TableDelta<Lifetime>,
+    pub state_machines: TableDelta<StateMachine>,
+    pub path_followers: TableDelta<PathFollower>,
+    pub homings: TableDelta<Homing>,
+    pub start_inside_edge_policies: TableDelta<StartInsideEdgePolicy>,
+    pub skins: TableDelta<SubPixelI64>,
+    pub movement_modes: TableDelta<MovementMode>,
+    pub collision_layers: TableDelta<u32>,
+    pub out_of_bounds_policies: TableDelta<OutOfBoundsPolicy>,
+    pub hidden: SetDelta,
+    pub pushable: SetDelta,
+    pub sensors: SetDelta,
+    pub collectors: SetDelta,
+    pub movement_remainders: TableDelta<ScaleRemainder>,
+    pub low_velocity_streaks: TableDelta<u32>,
+    pub resting: SetDelta,
+    pub asleep: SetDelta,
+    pub out_of_bounds: SetDelta,
+    pub portal_suppressed: SetDelta,
+}
+
+impl ComponentDeltas {
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty() && self.shapes.is_empty() && self.velocities.is_empty()
+            && self.colours.is_empty() && self.z_indices.is_empty()
+            && self.sprites.is_empty() && self.animations.is_empty()
+            && self.colour_tweens.is_empty() && self.flashes.is_empty()
+            && self.blends.is_empty() && self.masses.is_empty()
+            && self.restitutions.is_empty() && self.ropes.is_empty()
+            && self.attractors.is_empty() && self.forces.is_empty()
+            && self.gravities.is_empty() && self.drags.is_empty()
+            && self.materials.is_empty() && self.bounce_pads.is_empty()
+            && self.hazards.is_empty() && self.healths.is_empty()
+            && self.pickups.is_empty() && self.lifetimes.is_empty()
+            && self.state_machines.is_empty() && self.path_followers.is_empty()
+            && self.homings.is_empty() && self.start_inside_edge_policies.is_empty()
+            && self.skins.is_empty() && self.movement_modes.is_empty()
+            && self.collision_layers.is_empty()
+            && self.out_of_bounds_policies.is_empty() && self.hidden.is_empty()
+            && self.pushable.is_empty() && self.sensors.is_empty()
+            && self.collectors.is_empty() && self.movement_remainders.is_empty()
+            && self.low_velocity_streaks.is_empty() && self.resting.is_empty()
+            && self.asleep.is_empty() && self.out_of_bounds.is_empty()
+            && self.portal_suppressed.is_empty()
+    }
+    /// The first table, in declaration order, that has a change, paired with
+    /// the lowest entity it changes.
+    pub fn first_change(&self) -> Option<(&'static str, EntityId)> {
+        let tables = [
+            ("positions", self.positions.first_entity()),
+            ("shapes", self.shapes.first_entity()),
+            ("velocities", self.velocities.first_entity()),
+            ("colours", self.colours.first_entity()),
+            ("z_indices", self.z_indices.first_entity()),
+            ("sprites", self.sprites.first_entity()),
+            ("animations", self.animations.first_entity()),
+            ("colour_tweens", self.colour_tweens.first_entity()),
+            ("flashes", self.flashes.first_entity()),
+            ("blends", self.blends.first_entity()),
+            ("masses", self.masses.first_entity()),
+            ("restitutions", self.restitutions.first_entity()),
+            ("ropes", self.ropes.first_entity()),
+            ("attractors", self.attractors.first_entity()),
+            ("forces", self.forces.first_entity()),
+            ("gravities", self.gravities.first_entity()),
+            ("drags", self.drags.first_entity()),
+            ("materials", self.materials.first_entity()),
+            ("bounce_pads", self.bounce_pads.first_entity()),
+            ("hazards", self.hazards.first_entity()),
+            ("healths", self.healths.first_entity()),
+            ("pickups", self.pickups.first_entity()),
+            ("lifetimes", self.lifetimes.first_entity()),
+            ("state_machines", self.state_machines.first_entity()),
+            ("path_followers", self.path_followers.first_entity()),
+            ("homings", self.homings.first_entity()),
+            (
+                "start_inside_edge_policies",
+                self.start_inside_edge_policies.first_entity(),
+            ),
+            ("skins", self.skins.first_entity()),
+            ("movement_modes", self.movement_modes.first_entity()),
+            ("collision_layers", self.collision_layers.first_entity()),
+            ("out_of_bounds_policies", self.out_of_bounds_policies.first_entity()),
+            ("hidden", self.hidden.first_entity()),
+            ("pushable", self.pushable.first_entity()),
+            ("sensors", self.sensors.first_entity()),
+            ("collectors", self.collectors.first_entity()),
+            ("movement_remainders", self.movement_remainders.first_entity()),
+            ("low_velocity_streaks", self.low_velocity_streaks.first_entity()),
+            ("resting", self.resting.first_entity()),
+            ("asleep", self.asleep.first_entity()),
+            ("out_of_bounds", self.out_of_bounds.first_entity()),
+            ("portal_suppressed", self.portal_suppressed.first_entity()),
+        ];
+        tables
+            .iter()
+            .filter_map(|&(name, id)| id.map(|id| (name, id)))
+            .next()
+    }
+}
+
+/// Simulation state that is not stored per entity. Each field is set when the
+/// two sides of a diff differ.
+#[derive(Debug, Clone, Default)]
+pub struct SimulationDelta {
+    pub next_entity_id: Option<EntityId>,
+    pub world_bounds: Option<Aabb<SubPixelI64>>,
+    pub time_remainder: Option<i64>,
+    /// Sorted pairs of entities in contact at the end of the last update.
+    pub touching: Option<Vec<(EntityId, EntityId)>>,
+    pub hazard_cooldowns: Option<HazardCooldowns>,
+    pub portals: Option<Vec<PortalPair>>,
+    pub signals: Option<Vec<(EntityId, String)>>,
+    pub verlet: Option<Vec<VerletSystem>>,
+    pub particles: Option<ParticleSystem>,
+}
+
+impl SimulationDelta {
+    pub fn is_empty(&self) -> bool {
+        self.first_change().is_none()
+    }
+    /// The first field, in declaration order, that is set.
+    pub fn first_change(&self) -> Option<&'static str> {
+        let fields = [
+            ("next_entity_id", self.next_entity_id.is_some()),
+            ("world_bounds", self.world_bounds.is_some()),
+            ("time_remainder", self.time_remainder.is_some()),
+            ("touching", self.touching.is_some()),
+            ("hazard_cooldowns", self.hazard_cooldowns.is_some()),
+            ("portals", self.portals.is_some()),
+            ("signals", self.signals.is_some()),
+            ("verlet", self.verlet.is_some()),
+            ("particles", self.particles.is_some()),
+        ];
+        fields
+            .iter()
+            .find(|&&(_, changed)| changed)
+            .map(|&(name, _)| name)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StateDelta {
+    pub player_id: Option<EntityId>,
+    pub spawns: Vec<Spawn>,
+    pub despawns: Vec<EntityId>,
+    pub components: ComponentDeltas,
+    pub rng_state: Option<u64>,
+    pub scheduler: Option<Scheduler>,
+    pub simulation: SimulationDelta,
+    pub resources: Option<Resources>,
+}
+
+impl StateDelta {
+    pub fn is_empty(&self) -> bool {
+        self.spawns.is_empty() && self.despawns.is_empty() && self.components.is_empty()
+            && self.rng_state.is_none() && self.scheduler.is_none()
+            && self.simulation.is_empty() && self.resources.is_none()
+    }
+}
@@ -0,0 +1,108 @@
+use game::EntityId;
+use pixel_num::SubPixelI64;
+
+pub type StateId = u32;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Trigger {
+    After(u32),
+    Event(String),
+    Contact,
+    PlayerWithin(SubPixelI64),
+    PlayerBeyond(SubPixelI64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Transition {
+    from: StateId,
+    trigger: Trigger,
+    to: StateId,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StateMachine {
+    state: StateId,
+    elapsed: u32,
+    transitions: Vec<Transition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateChangeEvent {
+    pub entity_id: EntityId,
+    pub from: StateId,
+    pub to: StateId,
+}
+
+impl StateMachine {
+    pub fn new(initial: StateId) -> Self {
+        Self {
+            state: initial,
+            elapsed: 0,
+            transitions: Vec::new(),
+        }
+    }
+    pub fn with_transition(
+        mut self,
+        from: StateId,
+        trigger: Trigger,
+        to: StateId,
+    ) -> Self {
+        self.transitions.push(Transition { from, trigger, to });
+        self
+    }
+    pub fn state(&self) -> StateId {
+        self.state
+    }
+    pub fn elapsed(&self) -> u32 {
+        self.elapsed
+    }
+    pub fn set_state(&mut self, state: StateId) {
+        self.state = state;
+        self.elapsed = 0;
+    }
+    pub fn step<F>(&mut self, mut is_triggered: F) -> Option<StateId>
+    where
+        F: FnMut(&Trigger) -> bool,
+    {
+        self.elapsed = self.elapsed.saturating_add(1);
+        let state = self.state;
+        let elapsed = self.elapsed;
+        let to = self.transitions
+            .iter()
+            .filter(|transition| transition.from == state)
+            .find(|transition| match transition.trigger {
+                Trigger::After(updates) => elapsed >= updates,
+                ref trigger => is_triggered(trigger),
+            })
+            .map(|transition| transition.to)?;
+        self.set_state(to);
+        Some(to)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const PATROL: StateId = 0;
+    const CHASE: StateId = 1;
+    const ATTACK: StateId = 2;
+
+    #[test]
+    fn transitions_fire_in_declaration_order() {
+        let mut machine = StateMachine::new(PATROL)
+            .with_transition(PATROL, Trigger::Event("alarm".to_string()), CHASE)
+            .with_transition(PATROL, Trigger::After(3), CHASE)
+            .with_transition(CHASE, Trigger::Contact, ATTACK)
+            .with_transition(ATTACK, Trigger::After(1), PATROL);
+        assert_eq!(machine.step(|_| false), None);
+        assert_eq!(machine.step(|_| false), None);
+        assert_eq!(machine.step(|_| false), Some(CHASE));
+        assert_eq!(machine.step(|_| false), None);
+        assert_eq!(machine.step(|trigger| *trigger == Trigger::Contact), Some(ATTACK));
+        assert_eq!(machine.step(|_| false), Some(PATROL));
+        let alarm = Trigger::Event("alarm".to_string());
+        assert_eq!(machine.step(|trigger| *trigger == alarm), Some(CHASE));
+        assert_eq!((machine.state(), machine.elapsed()), (CHASE, 0));
+    }
+}
@@ -0,0 +1,163 @@
+use aabb::Aabb;
+use cgmath::vec2;
+use chunked_index::ChunkedIndex;
+use game::EntityId;
+use line_segment::LineSegment;
+use physics_num::PhysicsNum;
+
+fn merge_runs<N, F>(rects: Vec<Aabb<N>>, mergeable: F) -> Vec<Aabb<N>>
+where
+    N: PhysicsNum,
+    F: Fn(&Aabb<N>, &Aabb<N>) -> bool,
+{
+    let mut merged: Vec<Aabb<N>> = Vec::with_capacity(rects.len());
+    for rect in rects {
+        if let Some(last) = merged.last_mut() {
+            if mergeable(last, &rect) {
+                *last = last.union(&rect);
+                continue;
+            }
+        }
+        merged.push(rect);
+    }
+    merged
+}
+
+pub fn merge_rects<N: PhysicsNum>(mut rects: Vec<Aabb<N>>) -> Vec<Aabb<N>> {
+    rects.sort_by_key(|rect| {
+        let (top_left, bottom_right) = (rect.top_left(), rect.bottom_right_coord());
+        (top_left.y, bottom_right.y, top_left.x)
+    });
+    let mut rows = merge_runs(rects, |a, b| {
+        a.top_left().y == b.top_left().y
+            && a.bottom_right_coord().y == b.bottom_right_coord().y
+            && b.top_left().x <= a.bottom_right_coord().x
+    });
+    rows.sort_by_key(|rect| {
+        let (top_left, bottom_right) = (rect.top_left(), rect.bottom_right_coord());
+        (top_left.x, bottom_right.x, top_left.y)
+    });
+    merge_runs(rows, |a, b| {
+        a.top_left().x == b.top_left().x
+            && a.bottom_right_coord().x == b.bottom_right_coord().x
+            && b.top_left().y <= a.bottom_right_coord().y
+    })
+}
+
+pub fn edges<N: PhysicsNum>(aabb: &Aabb<N>) -> [LineSegment<N>; 4] {
+    let top_left = aabb.top_left();
+    let bottom_right = aabb.bottom_right_coord();
+    let top_right = vec2(bottom_right.x, top_left.y);
+    let bottom_left = vec2(top_left.x, bottom_right.y);
+    [
+        LineSegment::new(top_left, top_right),
+        LineSegment::new(top_right, bottom_right),
+        LineSegment::new(bottom_right, bottom_left),
+        LineSegment::new(bottom_left, top_left),
+    ]
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BakedCollider<N> {
+    pub entity_id: EntityId,
+    pub aabb: Aabb<N>,
+    pub edges: [LineSegment<N>; 4],
+}
+
+#[derive(Debug, Clone)]
+pub struct StaticGeometry<N: PhysicsNum> {
+    colliders: Vec<BakedCollider<N>>,
+    index: ChunkedIndex<usize, N>,
+}
+
+impl<N: PhysicsNum> StaticGeometry<N> {
+    pub fn new<I>(colliders: I, cell_size: N) -> Self
+    where
+        I: IntoIterator<Item = (EntityId, Aabb<N>)>,
+    {
+        let mut colliders = colliders
+            .into_iter()
+            .map(|(entity_id, aabb)| BakedCollider {
+                entity_id,
+                aabb,
+                edges: edges(&aabb),
+            })
+            .collect::<Vec<_>>();
+        colliders.sort_by_key(|collider| collider.entity_id);
+        let mut index = ChunkedIndex::new(cell_size);
+        for (i, collider) in colliders.iter().enumerate() {
+            index.insert(collider.aabb, i);
+        }
+        Self { colliders, index }
+    }
+    pub fn len(&self) -> usize {
+        self.colliders.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.colliders.is_empty()
+    }
+    pub fn colliders(&self) -> &[BakedCollider<N>] {
+        &self.colliders
+    }
+    pub fn get(&self, id: EntityId) -> Option<&BakedCollider<N>> {
+        self.colliders
+            .binary_search_by_key(&id, |collider| collider.entity_id)
+            .ok()
+            .map(|index| &self.colliders[index])
+    }
+    pub fn contains(&self, id: EntityId) -> bool {
+        self.get(id).is_some()
+    }
+    pub fn for_each_intersection<F>(&self, aabb: &Aabb<N>, mut f: F) -> usize
+    where
+        F: FnMut(&BakedCollider<N>),
+    {
+        let colliders = &self.colliders;
+        self.index
+            .for_each_intersection(aabb, |_aabb, &index| f(&colliders[index]))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rect(x: i64, y: i64, width: i64, height: i64) -> Aabb<i64> {
+        Aabb::new(vec2(x, y), vec2(width, height))
+    }
+
+    #[test]
+    fn merges_tile_rows_then_columns() {
+        let mut tiles = Vec::new();
+        for y in 0..3 {
+            for x in 0..4 {
+                tiles.push(rect(x * 8, y * 8, 8, 8));
+            }
+        }
+        tiles.push(rect(40, 0, 8, 8));
+        tiles.push(rect(0, 24, 8, 4));
+        let merged = merge_rects(tiles);
+        assert_eq!(
+            merged,
+            vec![rect(0, 24, 8, 4), rect(0, 0, 32, 24), rect(40, 0, 8, 8)]
+        );
+    }
+
+    #[test]
+    fn finds_colliders_by_area_and_id() {
+        let geometry = StaticGeometry::new(
+            vec![(7, rect(0, 0, 32, 8)), (3, rect(100, 100, 8, 8))],
+            16,
+        );
+        let wall = geometry.get(7).map(|collider| collider.aabb);
+        assert_eq!(wall, Some(rect(0, 0, 32, 8)));
+        assert!(!geometry.contains(4));
+        let mut hits = Vec::new();
+        geometry.for_each_intersection(&rect(20, 4, 4, 4), |collider| {
+            hits.push(collider.entity_id)
+        });
+        assert_eq!(hits, vec![7]);
+        let bottom = geometry.get(7).unwrap().edges[2];
+        assert_eq!((bottom.start, bottom.end), (vec2(32, 8), vec2(0, 8)));
+    }
+}
@@ -0,0 +1,9 @@
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UpdateStats {
+    pub broadphase_candidates: usize,
+    pub narrowphase_tests: usize,
+    pub slide_iterations: usize,
+    pub max_slide_iterations_hit: usize,
+    pub broadphase_visits: usize,
+    pub broadphase_rebuilds: usize,
+}
@@ -0,0 +1,70 @@
+use cgmath::Vector2;
+use physics_num::{self, PhysicsNum};
+use trig::Angle;
+
+pub fn seek<N: PhysicsNum>(
+    position: Vector2<N>,
+    target: Vector2<N>,
+    max_speed: N,
+) -> Vector2<N> {
+    physics_num::normalize_to_length(target - position, max_speed)
+}
+
+pub fn flee<N: PhysicsNum>(
+    position: Vector2<N>,
+    threat: Vector2<N>,
+    max_speed: N,
+) -> Vector2<N> {
+    physics_num::normalize_to_length(position - threat, max_speed)
+}
+
+pub fn arrive<N: PhysicsNum>(
+    position: Vector2<N>,
+    target: Vector2<N>,
+    max_speed: N,
+    slowing_radius: N,
+) -> Vector2<N> {
+    let offset = target - position;
+    let distance = physics_num::magnitude(offset);
+    let speed = if distance < slowing_radius {
+        physics_num::saturating_narrow(
+            physics_num::widen(max_speed) * physics_num::widen(distance)
+                / physics_num::widen(slowing_radius),
+        )
+    } else {
+        max_speed
+    };
+    physics_num::normalize_to_length(offset, speed.min(distance))
+}
+
+pub fn wander<N: PhysicsNum>(heading: &mut Angle, turn: Angle, speed: N) -> Vector2<N> {
+    *heading = *heading + turn;
+    heading.unit_vector(speed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cgmath::vec2;
+
+    #[test]
+    fn seek_and_flee() {
+        assert_eq!(seek(vec2(0i64, 0), vec2(100, 0), 8), vec2(8, 0));
+        assert_eq!(flee(vec2(0i64, 0), vec2(0, 100), 8), vec2(0, -8));
+    }
+
+    #[test]
+    fn arrive_slows_and_stops() {
+        assert_eq!(arrive(vec2(0i64, 0), vec2(1000, 0), 10, 100), vec2(10, 0));
+        assert_eq!(arrive(vec2(0i64, 0), vec2(50, 0), 10, 100), vec2(5, 0));
+        assert_eq!(arrive(vec2(0i64, 0), vec2(3, 0), 10, 4), vec2(3, 0));
+        assert_eq!(arrive(vec2(7i64, 7), vec2(7, 7), 10, 100), vec2(0, 0));
+    }
+
+    #[test]
+    fn wander_turns_heading() {
+        let mut heading = Angle::ZERO;
+        assert_eq!(wander(&mut heading, Angle::QUARTER_TURN, 16i64), vec2(0, 16));
+        assert_eq!(heading, Angle::QUARTER_TURN);
+    }
+}
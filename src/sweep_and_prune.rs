@@ -0,0 +1,171 @@
+use std::collections::BTreeMap;
+use aabb::Aabb;
+use physics_num::PhysicsNum;
+use num::Zero;
+
+/// Items are kept ordered by the left edge of their AABB so a query only
+/// visits items whose left edge lies within `max_width` of the query.
+/// `max_width` only ever grows between clears, which keeps queries correct
+/// after removals at the cost of visiting a few extra items.
+#[derive(Debug, Clone)]
+pub struct SweepAndPrune<T, N: PhysicsNum> {
+    items: BTreeMap<(N, u64), (Aabb<N>, T)>,
+    next_seq: u64,
+    max_width: N,
+}
+
+impl<T, N: PhysicsNum> Default for SweepAndPrune<T, N> {
+    fn default() -> Self {
+        Self {
+            items: BTreeMap::new(),
+            next_seq: 0,
+            max_width: Zero::zero(),
+        }
+    }
+}
+
+impl<T, N: PhysicsNum> SweepAndPrune<T, N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.next_seq = 0;
+        self.max_width = Zero::zero();
+    }
+
+    pub fn insert(&mut self, aabb: Aabb<N>, t: T) {
+        self.max_width = self.max_width.max(aabb.size().x);
+        self.items.insert((aabb.top_left().x, self.next_seq), (aabb, t));
+        self.next_seq += 1;
+    }
+
+    pub fn remove(&mut self, aabb: &Aabb<N>, t: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        let x = aabb.top_left().x;
+        let key = self.items
+            .range((x, 0)..=(x, u64::MAX))
+            .find(|&(_, (item_aabb, item))| item_aabb == aabb && item == t)
+            .map(|(&key, _)| key);
+        match key {
+            Some(key) => {
+                self.items.remove(&key);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn for_each_intersection<F: FnMut(&Aabb<N>, &T)>(
+        &self,
+        aabb: &Aabb<N>,
+        mut f: F,
+    ) -> usize {
+        let start = aabb.top_left().x - self.max_width;
+        let end = aabb.bottom_right_coord().x;
+        if start > end {
+            return 0;
+        }
+        let mut visits = 0;
+        let range = self.items.range((start, 0)..=(end, u64::MAX));
+        for (item_aabb, t) in range.map(|(_, item)| item) {
+            visits += 1;
+            if item_aabb.is_intersecting(aabb) {
+                f(item_aabb, t);
+            }
+        }
+        visits
+    }
+
+    pub fn for_each_item<F: FnMut(&Aabb<N>, &T)>(&self, mut f: F) {
+        for (aabb, t) in self.items.values() {
+            f(aabb, t);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cgmath::vec2;
+
+    #[test]
+    fn finds_intersections() {
+        let mut sap = SweepAndPrune::new();
+        sap.insert(Aabb::new(vec2(0i64, 0), vec2(100, 10)), 0);
+        sap.insert(Aabb::new(vec2(50, 50), vec2(10, 10)), 1);
+        sap.insert(Aabb::new(vec2(200, 0), vec2(10, 10)), 2);
+        sap.insert(Aabb::new(vec2(30, 0), vec2(10, 10)), 3);
+        let query = |top_left, size| {
+            let mut found = Vec::new();
+            sap.for_each_intersection(&Aabb::new(top_left, size), |_, &t| found.push(t));
+            found.sort();
+            found
+        };
+        assert_eq!(query(vec2(90, 5), vec2(5, 5)), vec![0]);
+        assert_eq!(query(vec2(35, 5), vec2(20, 50)), vec![0, 1, 3]);
+        assert_eq!(query(vec2(150, 0), vec2(20, 20)), Vec::<i32>::new());
+        assert_eq!(query(vec2(205, 5), vec2(1, 1)), vec![2]);
+    }
+
+    #[test]
+    fn removal_keeps_queries_exact() {
+        let items = (0..120)
+            .map(|i| {
+                let size = vec2(1 + (i * 13) % 50, 1 + (i * 7) % 30);
+                (Aabb::new(vec2((i * 37) % 400, (i * 59) % 400), size), i)
+            })
+            .collect::<Vec<_>>();
+        let mut sap = SweepAndPrune::default();
+        for &(aabb, i) in items.iter() {
+            sap.insert(aabb, i);
+        }
+        for &(aabb, i) in items.iter().filter(|&&(_, i)| i % 3 == 0) {
+            assert!(sap.remove(&aabb, &i));
+            assert!(!sap.remove(&aabb, &i));
+        }
+        assert_eq!(sap.len(), items.len() - 40);
+        for query in 0..40 {
+            let top_left = vec2((query * 41) % 400, (query * 23) % 400);
+            let aabb = Aabb::new(top_left, vec2(30, 30));
+            let mut expected = items
+                .iter()
+                .filter(|&&(item, i)| i % 3 != 0 && item.is_intersecting(&aabb))
+                .map(|&(_, i)| i)
+                .collect::<Vec<_>>();
+            let mut actual = Vec::new();
+            sap.for_each_intersection(&aabb, |_, &i| actual.push(i));
+            expected.sort();
+            actual.sort();
+            assert_eq!(expected, actual);
+        }
+    }
+
+    #[test]
+    fn items_sharing_an_edge_are_removed_individually() {
+        let mut sap = SweepAndPrune::new();
+        let aabb = Aabb::new(vec2(10i64, 0), vec2(5, 5));
+        sap.insert(aabb, 'a');
+        sap.insert(aabb, 'b');
+        sap.insert(Aabb::new(vec2(10, 20), vec2(5, 5)), 'c');
+        assert!(!sap.remove(&Aabb::new(vec2(10, 1), vec2(5, 5)), &'a'));
+        assert!(sap.remove(&aabb, &'b'));
+        let mut found = Vec::new();
+        sap.for_each_item(|_, &t| found.push(t));
+        found.sort();
+        assert_eq!(found, vec!['a', 'c']);
+        sap.clear();
+        assert!(sap.is_empty());
+    }
+}
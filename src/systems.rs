@@ -0,0 +1,186 @@
+use game::{World, WorldView};
+use hooks::{Hook, UpdateStage};
+
+/// The phases of `GameState::update`, each of which can be run on its own with
+/// `World::run_system`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum System {
+    Integrate,
+    Broadphase,
+    Narrowphase,
+    Resolve,
+    Events,
+}
+
+pub const SYSTEMS: [System; 5] = [
+    System::Integrate,
+    System::Broadphase,
+    System::Narrowphase,
+    System::Resolve,
+    System::Events,
+];
+
+/// A system added to a schedule. Cloning a schedule clones its systems, so
+/// state captured by a closure is not shared between a world and its branches.
+pub trait CustomSystem: FnMut(&mut World) {
+    fn clone_system(&self) -> Box<dyn CustomSystem>;
+}
+
+impl<F> CustomSystem for F
+where
+    F: 'static + Clone + FnMut(&mut World),
+{
+    fn clone_system(&self) -> Box<dyn CustomSystem> {
+        Box::new(self.clone())
+    }
+}
+
+/// What `Schedule::remove` did with a system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Removal {
+    Removed,
+    NotScheduled,
+}
+
+enum Step {
+    Builtin(System),
+    Custom(Box<dyn CustomSystem>),
+    Hook(Box<dyn Hook>),
+}
+
+impl Clone for Step {
+    fn clone(&self) -> Self {
+        match *self {
+            Step::Builtin(system) => Step::Builtin(system),
+            Step::Custom(ref f) => Step::Custom(f.clone_system()),
+            Step::Hook(ref hook) => Step::Hook(hook.clone_hook()),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Placement {
+    End,
+    Before(System),
+    After(System),
+}
+
+enum Edit {
+    Insert(Placement, Step),
+    Remove(System),
+    ClearHooks,
+}
+
+#[derive(Clone)]
+pub struct Schedule {
+    steps: Vec<Step>,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self {
+            steps: SYSTEMS.iter().map(|&system| Step::Builtin(system)).collect(),
+        }
+    }
+}
+
+impl Schedule {
+    pub fn empty() -> Self {
+        Self { steps: Vec::new() }
+    }
+    fn edit(&mut self, edit: Edit) {
+        match edit {
+            Edit::Insert(placement, step) => {
+                let index = match placement {
+                    Placement::End => None,
+                    Placement::Before(system) => self.position(system),
+                    Placement::After(system) => {
+                        self.position(system).map(|index| index + 1)
+                    }
+                };
+                let index = index.unwrap_or(self.steps.len());
+                self.steps.insert(index, step);
+            }
+            Edit::Remove(system) => {
+                if let Some(index) = self.position(system) {
+                    self.steps.remove(index);
+                }
+            }
+            Edit::ClearHooks => self.steps.retain(|step| match *step {
+                Step::Hook(_) => false,
+                Step::Builtin(_) | Step::Custom(_) => true,
+            }),
+        }
+    }
+    fn position(&self, system: System) -> Option<usize> {
+        self.steps.iter().position(|step| match *step {
+            Step::Builtin(builtin) => builtin == system,
+            Step::Custom(_) | Step::Hook(_) => false,
+        })
+    }
+    pub fn contains(&self, system: System) -> bool {
+        self.position(system).is_some()
+    }
+    pub fn add_system<F>(&mut self, f: F)
+    where
+        F: 'static + Clone + FnMut(&mut World),
+    {
+        self.edit(Edit::Insert(Placement::End, Step::Custom(Box::new(f))));
+    }
+    pub fn add_before<F>(&mut self, system: System, f: F)
+    where
+        F: 'static + Clone + FnMut(&mut World),
+    {
+        let placement = Placement::Before(system);
+        self.edit(Edit::Insert(placement, Step::Custom(Box::new(f))));
+    }
+    pub fn add_after<F>(&mut self, system: System, f: F)
+    where
+        F: 'static + Clone + FnMut(&mut World),
+    {
+        let placement = Placement::After(system);
+        self.edit(Edit::Insert(placement, Step::Custom(Box::new(f))));
+    }
+    pub fn add_hook<F>(&mut self, stage: UpdateStage, f: F)
+    where
+        F: 'static + Clone + FnMut(&mut WorldView),
+    {
+        let placement = match stage {
+            UpdateStage::BeforeIntegration => Placement::Before(System::Integrate),
+            UpdateStage::AfterCollisionResolution => Placement::Before(System::Events),
+        };
+        let hook = Step::Hook(Box::new(f));
+        self.edit(Edit::Insert(placement, hook));
+    }
+    pub fn clear_hooks(&mut self) {
+        self.edit(Edit::ClearHooks);
+    }
+    pub fn remove(&mut self, system: System) -> Removal {
+        let removal = if self.contains(system) {
+            Removal::Removed
+        } else {
+            Removal::NotScheduled
+        };
+        self.edit(Edit::Remove(system));
+        removal
+    }
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+    pub fn run(&mut self, world: &mut World) {
+        for step in self.steps.iter_mut() {
+            match *step {
+                Step::Builtin(system) => world.run_system(system),
+                Step::Custom(ref mut f) => f(world),
+                Step::Hook(ref mut hook) => world.run_hook(&mut **hook),
+            }
+        }
+    }
+}
+
+pub trait Plugin {
+    fn build(&self, world: &mut World, schedule: &mut Schedule);
+}
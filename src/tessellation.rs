@@ -0,0 +1,105 @@
+use cgmath::{Vector2, vec2};
+use pixel_num::sub_pixel_i64::{self, SubPixelI64};
+use shape::Shape;
+
+pub type Triangle = [Vector2<f32>; 3];
+pub type Line = [Vector2<f32>; 2];
+
+fn pixel(position: Vector2<SubPixelI64>, v: Vector2<SubPixelI64>) -> Vector2<f32> {
+    sub_pixel_i64::vector_to_f32_pixel(v + position)
+}
+
+fn closed_loop(
+    position: Vector2<SubPixelI64>,
+    vertices: &[Vector2<SubPixelI64>],
+) -> Vec<Line> {
+    (0..vertices.len())
+        .map(|i| {
+            let next = vertices[(i + 1) % vertices.len()];
+            [pixel(position, vertices[i]), pixel(position, next)]
+        })
+        .collect()
+}
+
+fn fan(
+    position: Vector2<SubPixelI64>,
+    vertices: &[Vector2<SubPixelI64>],
+) -> Vec<Triangle> {
+    (1..vertices.len().saturating_sub(1))
+        .map(|i| {
+            [
+                pixel(position, vertices[0]),
+                pixel(position, vertices[i]),
+                pixel(position, vertices[i + 1]),
+            ]
+        })
+        .collect()
+}
+
+pub fn lines(shape: &Shape<SubPixelI64>, position: Vector2<SubPixelI64>) -> Vec<Line> {
+    match shape {
+        &Shape::AxisAlignedRect(_)
+        | &Shape::OrientedRect(_)
+        | &Shape::ConvexPolygon(_) => closed_loop(position, &shape.vertices()),
+        &Shape::LineSegment(_) | &Shape::Chain(_) | &Shape::Heightfield(_) => shape
+            .vertices()
+            .windows(2)
+            .map(|pair| [pixel(position, pair[0]), pixel(position, pair[1])])
+            .collect(),
+    }
+}
+
+pub fn triangles(
+    shape: &Shape<SubPixelI64>,
+    position: Vector2<SubPixelI64>,
+) -> Vec<Triangle> {
+    match shape {
+        &Shape::AxisAlignedRect(_)
+        | &Shape::OrientedRect(_)
+        | &Shape::ConvexPolygon(_) => fan(position, &shape.vertices()),
+        &Shape::LineSegment(_) | &Shape::Chain(_) => Vec::new(),
+        Shape::Heightfield(heightfield) => {
+            let zero = SubPixelI64::new(0);
+            heightfield
+                .chain()
+                .points()
+                .windows(2)
+                .flat_map(|pair| {
+                    let base = (vec2(pair[1].x, zero), vec2(pair[0].x, zero));
+                    fan(position, &[pair[0], pair[1], base.0, base.1])
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axis_aligned_rect::AxisAlignedRect;
+    use heightfield::Heightfield;
+
+    fn pixels(x: i64, y: i64) -> Vector2<SubPixelI64> {
+        vec2(SubPixelI64::new_pixels(x), SubPixelI64::new_pixels(y))
+    }
+
+    #[test]
+    fn rect_and_heightfield() {
+        let rect = Shape::AxisAlignedRect(AxisAlignedRect::new(pixels(4, 2)));
+        let position = pixels(10, 20);
+        let rect_triangles = triangles(&rect, position);
+        assert_eq!(rect_triangles.len(), 2);
+        let area: f32 = rect_triangles
+            .iter()
+            .map(|t| ((t[1] - t[0]).perp_dot(t[2] - t[0])).abs() / 2.)
+            .sum();
+        assert_eq!(area, 8.);
+        assert_eq!(lines(&rect, position).len(), 4);
+        let heights = vec![SubPixelI64::new_pixels(1), SubPixelI64::new_pixels(3)];
+        let heightfield =
+            Shape::Heightfield(Heightfield::new(SubPixelI64::new_pixels(2), heights));
+        assert_eq!(triangles(&heightfield, position).len(), 2);
+        let expected_lines = vec![[vec2(10., 19.), vec2(12., 17.)]];
+        assert_eq!(lines(&heightfield, position), expected_lines);
+    }
+}
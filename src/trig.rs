@@ -0,0 +1,203 @@
+use cgmath::{Vector2, vec2};
+use physics_num::{self, PhysicsNum};
+use core::ops::{Add, Neg, Sub};
+
+pub const TRIG_ONE: i64 = 65536;
+const TABLE_STEPS: u32 = 256;
+const QUARTER_TURN_RAW: u32 = 16384;
+const RAW_PER_STEP: u32 = QUARTER_TURN_RAW / TABLE_STEPS;
+
+const QUARTER_SIN: [i64; 257] = [
+    0, 402, 804, 1206, 1608, 2010, 2412, 2814, 3216, 3617,
+    4019, 4420, 4821, 5222, 5623, 6023, 6424, 6824, 7224, 7623,
+    8022, 8421, 8820, 9218, 9616, 10014, 10411, 10808, 11204, 11600,
+    11996, 12391, 12785, 13180, 13573, 13966, 14359, 14751, 15143, 15534,
+    15924, 16314, 16703, 17091, 17479, 17867, 18253, 18639, 19024, 19409,
+    19792, 20175, 20557, 20939, 21320, 21699, 22078, 22457, 22834, 23210,
+    23586, 23961, 24335, 24708, 25080, 25451, 25821, 26190, 26558, 26925,
+    27291, 27656, 28020, 28383, 28745, 29106, 29466, 29824, 30182, 30538,
+    30893, 31248, 31600, 31952, 32303, 32652, 33000, 33347, 33692, 34037,
+    34380, 34721, 35062, 35401, 35738, 36075, 36410, 36744, 37076, 37407,
+    37736, 38064, 38391, 38716, 39040, 39362, 39683, 40002, 40320, 40636,
+    40951, 41264, 41576, 41886, 42194, 42501, 42806, 43110, 43412, 43713,
+    44011, 44308, 44604, 44898, 45190, 45480, 45769, 46056, 46341, 46624,
+    46906, 47186, 47464, 47741, 48015, 48288, 48559, 48828, 49095, 49361,
+    49624, 49886, 50146, 50404, 50660, 50914, 51166, 51417, 51665, 51911,
+    52156, 52398, 52639, 52878, 53114, 53349, 53581, 53812, 54040, 54267,
+    54491, 54714, 54934, 55152, 55368, 55582, 55794, 56004, 56212, 56418,
+    56621, 56823, 57022, 57219, 57414, 57607, 57798, 57986, 58172, 58356,
+    58538, 58718, 58896, 59071, 59244, 59415, 59583, 59750, 59914, 60075,
+    60235, 60392, 60547, 60700, 60851, 60999, 61145, 61288, 61429, 61568,
+    61705, 61839, 61971, 62101, 62228, 62353, 62476, 62596, 62714, 62830,
+    62943, 63054, 63162, 63268, 63372, 63473, 63572, 63668, 63763, 63854,
+    63944, 64031, 64115, 64197, 64277, 64354, 64429, 64501, 64571, 64639,
+    64704, 64766, 64827, 64884, 64940, 64993, 65043, 65091, 65137, 65180,
+    65220, 65259, 65294, 65328, 65358, 65387, 65413, 65436, 65457, 65476,
+    65492, 65505, 65516, 65525, 65531, 65535, 65536,
+];
+
+fn quarter_sin(offset: u32) -> i64 {
+    let step = (offset / RAW_PER_STEP) as usize;
+    let fraction = (offset % RAW_PER_STEP) as i64;
+    if fraction == 0 {
+        return QUARTER_SIN[step];
+    }
+    let start = QUARTER_SIN[step];
+    let end = QUARTER_SIN[step + 1];
+    start + (end - start) * fraction / RAW_PER_STEP as i64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Angle(u16);
+
+impl Angle {
+    pub const ZERO: Angle = Angle(0);
+    pub const QUARTER_TURN: Angle = Angle(QUARTER_TURN_RAW as u16);
+    pub const HALF_TURN: Angle = Angle(2 * QUARTER_TURN_RAW as u16);
+
+    pub fn from_raw(raw: u16) -> Self {
+        Angle(raw)
+    }
+    pub fn raw(self) -> u16 {
+        self.0
+    }
+    pub fn from_turn_fraction(numerator: i64, denominator: i64) -> Self {
+        assert!(denominator != 0, "zero denominator");
+        let raw =
+            numerator as i128 * (4 * QUARTER_TURN_RAW) as i128 / denominator as i128;
+        Angle(raw as i64 as u16)
+    }
+    pub fn from_degrees(degrees: i64) -> Self {
+        Self::from_turn_fraction(degrees % 360, 360)
+    }
+    pub fn sin(self) -> i64 {
+        let raw = self.0 as u32;
+        let offset = raw % QUARTER_TURN_RAW;
+        match raw / QUARTER_TURN_RAW {
+            0 => quarter_sin(offset),
+            1 => quarter_sin(QUARTER_TURN_RAW - offset),
+            2 => -quarter_sin(offset),
+            _ => -quarter_sin(QUARTER_TURN_RAW - offset),
+        }
+    }
+    pub fn cos(self) -> i64 {
+        (self + Self::QUARTER_TURN).sin()
+    }
+    pub fn unit_vector<N: PhysicsNum>(self, length: N) -> Vector2<N> {
+        let length = physics_num::widen(length);
+        vec2(
+            physics_num::saturating_narrow(length * self.cos() as i128 / TRIG_ONE as i128),
+            physics_num::saturating_narrow(length * self.sin() as i128 / TRIG_ONE as i128),
+        )
+    }
+    pub fn rotate<N: PhysicsNum>(self, v: Vector2<N>) -> Vector2<N> {
+        let x = physics_num::widen(v.x);
+        let y = physics_num::widen(v.y);
+        let sin = self.sin() as i128;
+        let cos = self.cos() as i128;
+        vec2(
+            physics_num::saturating_narrow((x * cos - y * sin) / TRIG_ONE as i128),
+            physics_num::saturating_narrow((x * sin + y * cos) / TRIG_ONE as i128),
+        )
+    }
+    pub fn of_vector<N: PhysicsNum>(v: Vector2<N>) -> Option<Self> {
+        let x = physics_num::widen(v.x);
+        let y = physics_num::widen(v.y);
+        if x == 0 && y == 0 {
+            return None;
+        }
+        let (abs_x, abs_y) = (x.abs(), y.abs());
+        let mut low = 0;
+        let mut high = QUARTER_TURN_RAW;
+        while low < high {
+            let mid = (low + high) / 2;
+            let sin = quarter_sin(mid) as i128;
+            let cos = quarter_sin(QUARTER_TURN_RAW - mid) as i128;
+            if sin * abs_x >= cos * abs_y {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        let offset = Angle(low as u16);
+        let angle = match (x >= 0, y >= 0) {
+            (true, true) => offset,
+            (false, true) => Self::HALF_TURN - offset,
+            (false, false) => Self::HALF_TURN + offset,
+            (true, false) => -offset,
+        };
+        Some(angle)
+    }
+    pub fn distance(self, other: Angle) -> Angle {
+        let difference = self.0.wrapping_sub(other.0);
+        Angle(difference.min(difference.wrapping_neg()))
+    }
+}
+
+impl Add for Angle {
+    type Output = Angle;
+    fn add(self, other: Angle) -> Angle {
+        Angle(self.0.wrapping_add(other.0))
+    }
+}
+
+impl Sub for Angle {
+    type Output = Angle;
+    fn sub(self, other: Angle) -> Angle {
+        Angle(self.0.wrapping_sub(other.0))
+    }
+}
+
+impl Neg for Angle {
+    type Output = Angle;
+    fn neg(self) -> Angle {
+        Angle(self.0.wrapping_neg())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cardinal_directions() {
+        assert_eq!(Angle::ZERO.sin(), 0);
+        assert_eq!(Angle::ZERO.cos(), TRIG_ONE);
+        assert_eq!(Angle::from_degrees(90).sin(), TRIG_ONE);
+        assert_eq!(Angle::from_degrees(180).cos(), -TRIG_ONE);
+        assert_eq!(Angle::from_degrees(270).sin(), -TRIG_ONE);
+        assert_eq!(Angle::from_degrees(-90), Angle::from_degrees(270));
+        assert!((Angle::from_degrees(30).sin() - TRIG_ONE / 2).abs() <= 8);
+    }
+
+    #[test]
+    fn rotation() {
+        assert_eq!(Angle::QUARTER_TURN.rotate(vec2(100i64, 0)), vec2(0, 100));
+        assert_eq!(Angle::HALF_TURN.rotate(vec2(100i64, 50)), vec2(-100, -50));
+        let v = Angle::from_degrees(60).unit_vector(1000i64);
+        assert!((v.x - 500).abs() <= 1 && (v.y - 866).abs() <= 1);
+    }
+
+    #[test]
+    fn angle_of_vector() {
+        assert_eq!(Angle::of_vector(vec2(0i64, 0)), None);
+        assert_eq!(Angle::of_vector(vec2(5i64, 0)), Some(Angle::ZERO));
+        assert_eq!(Angle::of_vector(vec2(0i64, 5)), Some(Angle::QUARTER_TURN));
+        assert_eq!(Angle::of_vector(vec2(-5i64, 0)), Some(Angle::HALF_TURN));
+        assert_eq!(Angle::of_vector(vec2(3i64, 3)), Some(Angle::from_degrees(45)));
+        assert_eq!(
+            Angle::of_vector(vec2(-3i64, -3)),
+            Some(Angle::from_degrees(225))
+        );
+    }
+
+    #[test]
+    fn angular_distance_wraps() {
+        let (near_end, near_start) = (Angle::from_raw(65000), Angle::from_raw(500));
+        assert_eq!(near_end.distance(near_start), Angle::from_raw(1036));
+        assert_eq!(near_start.distance(near_end), Angle::from_raw(1036));
+        let (up, down) = (Angle::from_degrees(90), Angle::from_degrees(270));
+        assert_eq!(up.distance(down), Angle::HALF_TURN);
+        assert_eq!(up.distance(up), Angle::ZERO);
+    }
+}
@@ -0,0 +1,161 @@
+use std::any::{Any, TypeId};
+use fnv::FnvHashMap;
+use cow_table::CowTable;
+use game::EntityId;
+
+trait Table: Any {
+    fn contains_entity(&self, id: EntityId) -> bool;
+    fn remove_entity(&mut self, id: EntityId);
+    fn clear(&mut self);
+    fn clone_table(&self) -> Box<dyn Table>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: 'static + Clone> Table for CowTable<T> {
+    fn contains_entity(&self, id: EntityId) -> bool {
+        self.contains_key(&id)
+    }
+    fn remove_entity(&mut self, id: EntityId) {
+        self.remove(&id);
+    }
+    fn clear(&mut self) {
+        CowTable::clear(self);
+    }
+    fn clone_table(&self) -> Box<dyn Table> {
+        Box::new(self.clone())
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Per-entity values keyed by type. Cloning keeps every table, sharing
+/// unchanged chunks with the original like the built-in components do.
+#[derive(Default)]
+pub struct UserData {
+    tables: FnvHashMap<TypeId, Box<dyn Table>>,
+}
+
+impl UserData {
+    fn table<T: 'static + Clone>(&self) -> Option<&CowTable<T>> {
+        self.tables
+            .get(&TypeId::of::<T>())
+            .and_then(|table| table.as_any().downcast_ref())
+    }
+    fn table_mut<T: 'static + Clone>(&mut self) -> Option<&mut CowTable<T>> {
+        self.tables
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|table| table.as_any_mut().downcast_mut())
+    }
+    pub fn insert<T: 'static + Clone>(&mut self, id: EntityId, value: T) -> Option<T> {
+        self.tables
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(CowTable::<T>::default()))
+            .as_any_mut()
+            .downcast_mut::<CowTable<T>>()
+            .expect("user data table has unexpected type")
+            .insert(id, value)
+    }
+    pub fn get<T: 'static + Clone>(&self, id: EntityId) -> Option<&T> {
+        self.table().and_then(|table| table.get(&id))
+    }
+    pub fn get_mut<T: 'static + Clone>(&mut self, id: EntityId) -> Option<&mut T> {
+        self.table_mut().and_then(|table| table.get_mut(&id))
+    }
+    pub fn contains<T: 'static + Clone>(&self, id: EntityId) -> bool {
+        self.get::<T>(id).is_some()
+    }
+    pub fn remove<T: 'static + Clone>(&mut self, id: EntityId) -> Option<T> {
+        self.table_mut().and_then(|table| table.remove(&id))
+    }
+    pub fn iter<T: 'static + Clone>(&self) -> impl Iterator<Item = (EntityId, &T)> {
+        self.table::<T>()
+            .into_iter()
+            .flat_map(|table| table.iter().map(|(id, value)| (*id, value)))
+    }
+    pub fn contains_entity(&self, id: EntityId) -> bool {
+        self.tables.values().any(|table| table.contains_entity(id))
+    }
+    pub fn remove_entity(&mut self, id: EntityId) {
+        for table in self.tables.values_mut() {
+            table.remove_entity(id);
+        }
+    }
+    pub fn clear(&mut self) {
+        for table in self.tables.values_mut() {
+            table.clear();
+        }
+    }
+}
+
+impl Clone for UserData {
+    fn clone(&self) -> Self {
+        let tables = self.tables
+            .iter()
+            .map(|(&type_id, table)| (type_id, table.clone_table()))
+            .collect();
+        UserData { tables }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Tag(&'static str);
+
+    #[test]
+    fn tables_are_keyed_by_type() {
+        let mut user_data = UserData::default();
+        assert_eq!(user_data.insert(1, Tag("player")), None);
+        assert_eq!(user_data.insert(1, 7u32), None);
+        assert_eq!(user_data.insert(2, Tag("enemy")), None);
+        assert_eq!(user_data.get::<Tag>(1), Some(&Tag("player")));
+        assert_eq!(user_data.get::<u32>(1), Some(&7));
+        assert_eq!(user_data.get::<u32>(2), None);
+        assert_eq!(user_data.get::<i64>(1), None);
+        *user_data.get_mut::<u32>(1).unwrap() += 1;
+        assert_eq!(user_data.insert(1, 0u32), Some(8));
+        let mut tags = user_data.iter::<Tag>().map(|(id, _)| id).collect::<Vec<_>>();
+        tags.sort();
+        assert_eq!(tags, vec![1, 2]);
+        assert_eq!(user_data.iter::<i64>().count(), 0);
+    }
+
+    #[test]
+    fn removing_an_entity_clears_every_table() {
+        let mut user_data = UserData::default();
+        user_data.insert(1, Tag("player"));
+        user_data.insert(1, 7u32);
+        user_data.insert(2, 3u32);
+        assert!(user_data.contains_entity(1));
+        user_data.remove_entity(1);
+        assert!(!user_data.contains_entity(1));
+        assert!(!user_data.contains::<Tag>(1));
+        assert_eq!(user_data.remove::<u32>(2), Some(3));
+        assert!(!user_data.contains_entity(2));
+        user_data.insert(3, Tag("pickup"));
+        user_data.clear();
+        assert_eq!(user_data.get::<Tag>(3), None);
+    }
+
+    #[test]
+    fn clones_keep_every_table() {
+        let mut user_data = UserData::default();
+        user_data.insert(1, "player");
+        user_data.insert(1, 7u32);
+        user_data.insert(2, 3u32);
+        let mut clone = user_data.clone();
+        assert_eq!(clone.get::<&str>(1), Some(&"player"));
+        assert_eq!(clone.get::<u32>(1), Some(&7));
+        assert_eq!(clone.get::<u32>(2), Some(&3));
+        *clone.get_mut::<u32>(2).unwrap() += 1;
+        assert_eq!(user_data.get::<u32>(2), Some(&3));
+        assert_eq!(clone.get::<u32>(2), Some(&4));
+    }
+}
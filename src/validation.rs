@@ -0,0 +1,10 @@
+use entity_id::EntityId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationError {
+    Overlap(EntityId, EntityId),
+    MissingProxy(EntityId),
+    StaleProxy(EntityId),
+    /// A broadphase or baked proxy for an entity that is gone or dynamic.
+    OrphanProxy(EntityId),
+}
@@ -0,0 +1,242 @@
+use cgmath::{Vector2, vec2};
+use line_segment::LineSegment;
+use num::Zero;
+use physics_num;
+use pixel_num::SubPixelI64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Particle {
+    pub position: Vector2<SubPixelI64>,
+    pub previous: Vector2<SubPixelI64>,
+    pub pinned: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Link {
+    pub a: usize,
+    pub b: usize,
+    pub length: SubPixelI64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VerletSystem {
+    particles: Vec<Particle>,
+    links: Vec<Link>,
+    gravity: Vector2<SubPixelI64>,
+    iterations: usize,
+    damping: (i64, i64),
+}
+
+fn scale(
+    v: Vector2<SubPixelI64>,
+    numerator: i128,
+    denominator: i128,
+) -> Vector2<SubPixelI64> {
+    vec2(
+        physics_num::saturating_narrow(physics_num::widen(v.x) * numerator / denominator),
+        physics_num::saturating_narrow(physics_num::widen(v.y) * numerator / denominator),
+    )
+}
+
+impl VerletSystem {
+    pub fn new(gravity: Vector2<SubPixelI64>, iterations: usize) -> Self {
+        assert!(iterations > 0, "verlet system needs at least one iteration");
+        Self {
+            particles: Vec::new(),
+            links: Vec::new(),
+            gravity,
+            iterations,
+            damping: (1, 1),
+        }
+    }
+    pub fn set_damping(&mut self, numerator: i64, denominator: i64) {
+        assert!(denominator > 0, "damping denominator must be positive");
+        assert!(
+            numerator >= 0 && numerator <= denominator,
+            "damping must be between zero and one"
+        );
+        self.damping = (numerator, denominator);
+    }
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+    pub fn add_particle(&mut self, position: Vector2<SubPixelI64>) -> usize {
+        self.particles.push(Particle {
+            position,
+            previous: position,
+            pinned: false,
+        });
+        self.particles.len() - 1
+    }
+    pub fn set_pinned(&mut self, index: usize, pinned: bool) {
+        self.particles[index].pinned = pinned;
+    }
+    pub fn move_particle(&mut self, index: usize, position: Vector2<SubPixelI64>) {
+        let particle = &mut self.particles[index];
+        particle.position = position;
+        particle.previous = position;
+    }
+    pub fn add_link(&mut self, a: usize, b: usize) -> usize {
+        let delta = self.particles[b].position - self.particles[a].position;
+        let length = physics_num::magnitude(delta);
+        self.links.push(Link { a, b, length });
+        self.links.len() - 1
+    }
+    pub fn add_chain(
+        &mut self,
+        start: Vector2<SubPixelI64>,
+        end: Vector2<SubPixelI64>,
+        segments: usize,
+    ) -> Vec<usize> {
+        assert!(segments > 0, "chain needs at least one segment");
+        let indices = (0..(segments + 1))
+            .map(|i| {
+                let position = start + scale(end - start, i as i128, segments as i128);
+                self.add_particle(position)
+            })
+            .collect::<Vec<_>>();
+        for pair in indices.windows(2) {
+            self.add_link(pair[0], pair[1]);
+        }
+        indices
+    }
+    pub fn segments<'a>(&'a self) -> impl Iterator<Item = LineSegment<SubPixelI64>> + 'a {
+        self.links.iter().map(move |link| {
+            LineSegment::new(
+                self.particles[link.a].position,
+                self.particles[link.b].position,
+            )
+        })
+    }
+    fn integrate(&mut self) {
+        let gravity = self.gravity;
+        let (numerator, denominator) = self.damping;
+        for particle in self.particles.iter_mut().filter(|particle| !particle.pinned) {
+            let velocity = scale(
+                particle.position - particle.previous,
+                numerator as i128,
+                denominator as i128,
+            );
+            particle.previous = particle.position;
+            particle.position = particle.position + velocity + gravity;
+        }
+    }
+    fn satisfy_link(&mut self, link: Link) {
+        let a = self.particles[link.a];
+        let b = self.particles[link.b];
+        if a.pinned && b.pinned {
+            return;
+        }
+        let delta = b.position - a.position;
+        let distance = physics_num::isqrt_wide(physics_num::magnitude2_wide(delta));
+        if distance == 0 {
+            return;
+        }
+        let stretch = distance - physics_num::widen(link.length);
+        let correction = scale(delta, stretch, distance);
+        if a.pinned {
+            self.particles[link.b].position = b.position - correction;
+        } else if b.pinned {
+            self.particles[link.a].position = a.position + correction;
+        } else {
+            let half = scale(correction, 1, 2);
+            self.particles[link.a].position = a.position + half;
+            self.particles[link.b].position = b.position - (correction - half);
+        }
+    }
+    pub fn step<F>(&mut self, mut collide: F)
+    where
+        F: FnMut(Vector2<SubPixelI64>, Vector2<SubPixelI64>)
+            -> Option<Vector2<SubPixelI64>>,
+    {
+        let starts = self.particles
+            .iter()
+            .map(|particle| particle.position)
+            .collect::<Vec<_>>();
+        self.integrate();
+        for _ in 0..self.iterations {
+            for i in 0..self.links.len() {
+                let link = self.links[i];
+                self.satisfy_link(link);
+            }
+        }
+        for (particle, &start) in self.particles.iter_mut().zip(starts.iter()) {
+            if particle.pinned {
+                continue;
+            }
+            let movement = particle.position - start;
+            if movement.x.is_zero() && movement.y.is_zero() {
+                continue;
+            }
+            if let Some(stop) = collide(start, movement) {
+                particle.position = stop;
+                particle.previous = stop;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn px(x: i64, y: i64) -> Vector2<SubPixelI64> {
+        vec2(SubPixelI64::new_pixels(x), SubPixelI64::new_pixels(y))
+    }
+
+    fn hanging_chain() -> VerletSystem {
+        let gravity = vec2(SubPixelI64::new(0), SubPixelI64::new(16));
+        let mut system = VerletSystem::new(gravity, 8);
+        system.set_damping(31, 32);
+        let chain = system.add_chain(px(0, 0), px(80, 0), 8);
+        system.set_pinned(chain[0], true);
+        system
+    }
+
+    #[test]
+    fn pinned_chain_hangs_below_pin() {
+        let mut system = hanging_chain();
+        for _ in 0..600 {
+            system.step(|_, _| None);
+        }
+        assert_eq!(system.particles()[0].position, px(0, 0));
+        let end = system.particles()[8].position;
+        assert!(end.y > SubPixelI64::new_pixels(60));
+        assert!(end.x.raw().abs() < SubPixelI64::new_pixels(20).raw());
+        for segment in system.segments() {
+            let length = physics_num::magnitude(segment.vector());
+            assert!((length - SubPixelI64::new_pixels(10)).raw().abs() < 64);
+        }
+    }
+
+    #[test]
+    fn collisions_stop_particles() {
+        let mut system = hanging_chain();
+        let floor = SubPixelI64::new_pixels(30);
+        for _ in 0..300 {
+            system.step(|start, movement| {
+                let end = start + movement;
+                if end.y > floor {
+                    Some(vec2(end.x, floor))
+                } else {
+                    None
+                }
+            });
+        }
+        assert!(system.particles().iter().all(|particle| particle.position.y <= floor));
+    }
+
+    #[test]
+    fn deterministic() {
+        let mut a = hanging_chain();
+        let mut b = hanging_chain();
+        for _ in 0..100 {
+            a.step(|_, _| None);
+            b.step(|_, _| None);
+        }
+        assert_eq!(a, b);
+    }
+}
@@ -0,0 +1,100 @@
+use cgmath::{Vector2, vec2};
+use pixel_num::SubPixelI64;
+
+#[derive(Debug, Clone)]
+pub struct WalkabilityGrid {
+    top_left: Vector2<SubPixelI64>,
+    cell_size: SubPixelI64,
+    width: usize,
+    height: usize,
+    blocked: Vec<bool>,
+}
+
+impl WalkabilityGrid {
+    pub fn new<F>(
+        top_left: Vector2<SubPixelI64>,
+        cell_size: SubPixelI64,
+        width: usize,
+        height: usize,
+        mut is_blocked: F,
+    ) -> Self
+    where
+        F: FnMut(Vector2<SubPixelI64>) -> bool,
+    {
+        assert!(cell_size > SubPixelI64::new(0), "cell size must be positive");
+        let mut grid = Self {
+            top_left,
+            cell_size,
+            width,
+            height,
+            blocked: Vec::with_capacity(width * height),
+        };
+        for y in 0..height {
+            for x in 0..width {
+                let centre = grid.cell_centre(x, y);
+                grid.blocked.push(is_blocked(centre));
+            }
+        }
+        grid
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    pub fn cell_size(&self) -> SubPixelI64 {
+        self.cell_size
+    }
+    pub fn cell_centre(&self, x: usize, y: usize) -> Vector2<SubPixelI64> {
+        let half = self.cell_size / SubPixelI64::new(2);
+        self.top_left
+            + vec2(
+                self.cell_size * x as i64 + half,
+                self.cell_size * y as i64 + half,
+            )
+    }
+    pub fn cell_containing(
+        &self,
+        position: Vector2<SubPixelI64>,
+    ) -> Option<(usize, usize)> {
+        let offset = position - self.top_left;
+        if offset.x < SubPixelI64::new(0) || offset.y < SubPixelI64::new(0) {
+            return None;
+        }
+        let x = (offset.x / self.cell_size).raw() as usize;
+        let y = (offset.y / self.cell_size).raw() as usize;
+        if x < self.width && y < self.height {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+    pub fn is_walkable(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && !self.blocked[y * self.width + x]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rasterizes_cells() {
+        let grid = WalkabilityGrid::new(
+            vec2(SubPixelI64::new(100), SubPixelI64::new(0)),
+            SubPixelI64::new(10),
+            3,
+            2,
+            |centre| centre.x == SubPixelI64::new(115),
+        );
+        assert!(grid.is_walkable(0, 0));
+        assert!(!grid.is_walkable(1, 0));
+        assert!(!grid.is_walkable(1, 1));
+        assert!(!grid.is_walkable(3, 0));
+        let position = vec2(SubPixelI64::new(129), SubPixelI64::new(10));
+        assert_eq!(grid.cell_containing(position), Some((2, 1)));
+        let position = vec2(SubPixelI64::new(99), SubPixelI64::new(10));
+        assert_eq!(grid.cell_containing(position), None);
+    }
+}
@@ -0,0 +1,50 @@
+//! wasm-bindgen facade over `flat::Simulation`. Input and read-back go through
+//! typed arrays owned by the caller, so a browser game can reuse the same
+//! `Float32Array`s every frame.
+
+use flat;
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+pub struct Simulation {
+    inner: flat::Simulation,
+}
+
+#[wasm_bindgen]
+impl Simulation {
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: f32, height: f32) -> Simulation {
+        Simulation {
+            inner: flat::Simulation::new(width, height),
+        }
+    }
+    pub fn demo(width: f32, height: f32) -> Result<Simulation, JsValue> {
+        match flat::Simulation::demo(width, height) {
+            Ok(inner) => Ok(Simulation { inner }),
+            Err(error) => Err(JsValue::from_str(&format!("{:?}", error))),
+        }
+    }
+    /// `input` holds `flat::INPUT_LEN` floats: left, right, up, down. Returns
+    /// false if it has the wrong length.
+    pub fn step(&mut self, input: &[f32]) -> bool {
+        self.inner.step(input).is_ok()
+    }
+    pub fn frame(&self) -> u32 {
+        self.inner.frame()
+    }
+    pub fn state_hash(&self) -> u64 {
+        self.inner.state_hash()
+    }
+    pub fn write_entity_ids(&self, out: &mut [u32]) -> usize {
+        self.inner.write_entity_ids(out)
+    }
+    pub fn write_positions(&self, out: &mut [f32]) -> usize {
+        self.inner.write_positions(out)
+    }
+    pub fn write_rects(&self, out: &mut [f32]) -> usize {
+        self.inner.write_rects(out)
+    }
+    pub fn write_colours(&self, out: &mut [f32]) -> usize {
+        self.inner.write_colours(out)
+    }
+}